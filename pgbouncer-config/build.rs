@@ -0,0 +1,35 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    compile_grpc_service();
+}
+
+/// Generates the tonic client/server stubs of `grpc::ConfigService`.
+///
+/// The messages are plain prost structs in `src/grpc.rs`, so the service is
+/// described here instead of a `.proto` file and no `protoc` is needed.
+#[cfg(feature = "grpc")]
+fn compile_grpc_service() {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    let method = |name: &str, route_name: &str, message: &str| {
+        Method::builder()
+            .name(name)
+            .route_name(route_name)
+            .input_type(format!("crate::grpc::{}Request", message))
+            .output_type(format!("crate::grpc::{}Response", message))
+            .codec_path("tonic_prost::ProstCodec")
+            .build()
+    };
+
+    let service = Service::builder()
+        .name("ConfigService")
+        .package("pgbouncer_config")
+        .method(method("get_config", "GetConfig", "GetConfig"))
+        .method(method("apply_patch", "ApplyPatch", "ApplyPatch"))
+        .method(method("diff", "Diff", "Diff"))
+        .method(method("generate", "Generate", "Generate"))
+        .method(method("reload", "Reload", "Reload"))
+        .build();
+
+    Builder::new().compile(&[service]);
+}
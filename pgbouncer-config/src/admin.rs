@@ -0,0 +1,526 @@
+//! Client for the admin console of a running PgBouncer.
+//!
+//! Enabled with the `admin` feature. PgBouncer exposes its runtime state
+//! through the virtual `pgbouncer` database; [`AdminClient`] connects to it
+//! and returns typed rows for the `SHOW` commands:
+//!
+//! - [`AdminClient::show_pools`]: `SHOW POOLS` as [`PoolStats`].
+//! - [`AdminClient::show_databases`]: `SHOW DATABASES` as [`DatabaseStatus`].
+//! - [`AdminClient::show_config`]: `SHOW CONFIG` as [`ConfigEntry`].
+//! - [`AdminClient::show_clients`]: `SHOW CLIENTS` as [`ClientInfo`].
+//!
+//! It also runs the process control commands `RELOAD`, `PAUSE`, `RESUME`
+//! and `SUSPEND`, so a new configuration can be applied without psql.
+//!
+//! The admin console only understands the simple query protocol, so every
+//! command is sent with [`sqlx::raw_sql`] and values are read as text.
+//! Columns added by newer PgBouncer releases are optional.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use sqlx::{Column, Connection, PgConnection, Row, ValueRef};
+use sqlx::postgres::{PgConnectOptions, PgRow};
+use crate::error::PgBouncerError;
+use crate::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+
+/// Name of the virtual database serving the admin console.
+pub const ADMIN_DATABASE: &str = "pgbouncer";
+
+/// Keys [`PgBouncerSetting`] always needs, even when they hold the default value.
+const REQUIRED_CONFIG_KEYS: [&str; 6] = [
+    "listen_addr", "listen_port", "auth_type", "max_client_conn", "default_pool_size", "pool_mode",
+];
+
+/// Row of an admin console result, keyed by column name; `None` for SQL NULL.
+type AdminRow = HashMap<String, Option<String>>;
+
+/// Connection to the admin console of a PgBouncer instance.
+///
+/// # Examples
+/// ```rust,no_run
+/// use pgbouncer_config::admin::AdminClient;
+///
+/// # async fn run() -> pgbouncer_config::error::Result<()> {
+/// let mut client = AdminClient::connect("127.0.0.1", 6432, "pgbouncer", "secret").await?;
+/// for pool in client.show_pools().await? {
+///     println!("{}/{}: {} waiting", pool.database, pool.user, pool.cl_waiting);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct AdminClient {
+    connection: PgConnection,
+}
+
+impl AdminClient {
+    /// Connects to the admin console.
+    ///
+    /// # Parameters
+    /// - host: Host name or address PgBouncer listens on.
+    /// - port: PgBouncer port (`listen_port`).
+    /// - user: A user listed in `admin_users` or `stats_users`.
+    /// - password: Password of `user`.
+    ///
+    /// # Returns
+    /// The connected AdminClient.
+    ///
+    /// # Errors
+    /// Returns an error if the connection or authentication fails.
+    pub async fn connect(host: &str, port: u16, user: &str, password: &str) -> crate::error::Result<Self> {
+        let options = PgConnectOptions::new()
+            .host(host)
+            .port(port)
+            .username(user)
+            .password(password)
+            .database(ADMIN_DATABASE)
+            // The admin console rejects startup parameters it does not track.
+            .extra_float_digits(None)
+            .statement_cache_capacity(0);
+
+        Self::connect_with(&options).await
+    }
+
+    /// Connects to the admin console with explicit connection options.
+    ///
+    /// # Parameters
+    /// - options: Connection options; the database should be [`ADMIN_DATABASE`].
+    ///
+    /// # Returns
+    /// The connected AdminClient.
+    ///
+    /// # Errors
+    /// Returns an error if the connection or authentication fails.
+    pub async fn connect_with(options: &PgConnectOptions) -> crate::error::Result<Self> {
+        let connection = PgConnection::connect_with(options).await?;
+
+        Ok(Self { connection })
+    }
+
+    /// Runs `SHOW POOLS`.
+    ///
+    /// # Returns
+    /// One entry per database/user pool.
+    ///
+    /// # Errors
+    /// Returns an error if the command fails or a row cannot be read.
+    pub async fn show_pools(&mut self) -> crate::error::Result<Vec<PoolStats>> {
+        self.show("POOLS").await?.iter().map(PoolStats::from_row).collect()
+    }
+
+    /// Runs `SHOW DATABASES`.
+    ///
+    /// # Returns
+    /// One entry per configured (or automatically created) database.
+    ///
+    /// # Errors
+    /// Returns an error if the command fails or a row cannot be read.
+    pub async fn show_databases(&mut self) -> crate::error::Result<Vec<DatabaseStatus>> {
+        self.show("DATABASES").await?.iter().map(DatabaseStatus::from_row).collect()
+    }
+
+    /// Runs `SHOW CONFIG`.
+    ///
+    /// # Returns
+    /// One entry per setting with its effective value.
+    ///
+    /// # Errors
+    /// Returns an error if the command fails or a row cannot be read.
+    pub async fn show_config(&mut self) -> crate::error::Result<Vec<ConfigEntry>> {
+        self.show("CONFIG").await?.iter().map(ConfigEntry::from_row).collect()
+    }
+
+    /// Reads the effective `[pgbouncer]` settings of the running instance.
+    ///
+    /// Settings still at their built-in default are left out, except the keys
+    /// [`PgBouncerSetting`] requires, so comparing the result with a definition
+    /// only reports values that were configured explicitly.
+    ///
+    /// # Returns
+    /// The setting built with [`PgBouncerSetting::from_show_config`].
+    ///
+    /// # Errors
+    /// Returns an error if the command fails or the rows cannot be parsed.
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// use pgbouncer_config::admin::AdminClient;
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    /// use pgbouncer_config::utils::diff::compute_diff;
+    ///
+    /// # async fn run(desired: PgBouncerSetting) -> pgbouncer_config::error::Result<()> {
+    /// let mut client = AdminClient::connect("127.0.0.1", 6432, "pgbouncer", "secret").await?;
+    /// let running = client.show_pgbouncer_setting().await?;
+    /// let diff = compute_diff(&running, &desired)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn show_pgbouncer_setting(&mut self) -> crate::error::Result<PgBouncerSetting> {
+        let entries = self.show_config().await?;
+        let rows = entries
+            .iter()
+            .filter(|entry| {
+                REQUIRED_CONFIG_KEYS.contains(&entry.key.as_str())
+                    || entry.default.as_deref() != Some(entry.value.as_str())
+            })
+            .map(|entry| (entry.key.as_str(), entry.value.as_str()));
+
+        PgBouncerSetting::from_show_config(rows)
+    }
+
+    /// Runs `SHOW CLIENTS`.
+    ///
+    /// # Returns
+    /// One entry per client connection.
+    ///
+    /// # Errors
+    /// Returns an error if the command fails or a row cannot be read.
+    pub async fn show_clients(&mut self) -> crate::error::Result<Vec<ClientInfo>> {
+        self.show("CLIENTS").await?.iter().map(ClientInfo::from_row).collect()
+    }
+
+    /// Runs `RELOAD` so PgBouncer rereads its configuration file.
+    ///
+    /// # Errors
+    /// Returns an error if the command fails (e.g. the user is not in `admin_users`).
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// use pgbouncer_config::admin::AdminClient;
+    ///
+    /// # async fn run() -> pgbouncer_config::error::Result<()> {
+    /// let mut client = AdminClient::connect("127.0.0.1", 6432, "pgbouncer", "secret").await?;
+    /// client.pause(Some("app")).await?;
+    /// client.reload().await?;
+    /// client.resume(Some("app")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn reload(&mut self) -> crate::error::Result<()> {
+        self.execute("RELOAD").await
+    }
+
+    /// Runs `PAUSE`, waiting until the server connections are released.
+    ///
+    /// # Parameters
+    /// - database: Database to pause; `None` pauses every database.
+    ///
+    /// # Errors
+    /// Returns an error if the database name is invalid or the command fails.
+    pub async fn pause(&mut self, database: Option<&str>) -> crate::error::Result<()> {
+        self.execute(&with_database("PAUSE", database)?).await
+    }
+
+    /// Runs `RESUME` after [`AdminClient::pause`] or [`AdminClient::suspend`].
+    ///
+    /// # Parameters
+    /// - database: Database to resume; `None` resumes every database.
+    ///
+    /// # Errors
+    /// Returns an error if the database name is invalid or the command fails.
+    pub async fn resume(&mut self, database: Option<&str>) -> crate::error::Result<()> {
+        self.execute(&with_database("RESUME", database)?).await
+    }
+
+    /// Runs `SUSPEND`, flushing socket buffers and stopping all network activity.
+    ///
+    /// # Errors
+    /// Returns an error if the command fails.
+    ///
+    /// # Notes
+    /// - Intended for online restarts; new client connections wait until
+    ///   [`AdminClient::resume`] is called.
+    pub async fn suspend(&mut self) -> crate::error::Result<()> {
+        self.execute("SUSPEND").await
+    }
+
+    /// Closes the connection.
+    ///
+    /// # Errors
+    /// Returns an error if the connection cannot be closed cleanly.
+    pub async fn close(self) -> crate::error::Result<()> {
+        self.connection.close().await?;
+        Ok(())
+    }
+
+    async fn execute(&mut self, command: &str) -> crate::error::Result<()> {
+        sqlx::raw_sql(command).execute(&mut self.connection).await?;
+        Ok(())
+    }
+
+    async fn show(&mut self, subject: &str) -> crate::error::Result<Vec<AdminRow>> {
+        let rows = sqlx::raw_sql(&format!("SHOW {}", subject))
+            .fetch_all(&mut self.connection)
+            .await?;
+
+        rows.iter().map(row_to_map).collect()
+    }
+}
+
+/// Appends a database name to an admin command, rejecting names that would alter the command.
+fn with_database(command: &str, database: Option<&str>) -> crate::error::Result<String> {
+    let Some(database) = database else {
+        return Ok(command.to_string());
+    };
+    let valid = !database.is_empty()
+        && database.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '$'));
+    if !valid {
+        return Err(PgBouncerError::PgBouncer(format!("Invalid database name for {}: {}", command, database)));
+    }
+
+    Ok(format!("{} {}", command, database))
+}
+
+fn row_to_map(row: &PgRow) -> crate::error::Result<AdminRow> {
+    let mut map = AdminRow::new();
+    for column in row.columns() {
+        let raw = row.try_get_raw(column.ordinal())?;
+        let value = if raw.is_null() {
+            None
+        } else {
+            let text = raw.as_str().map_err(|e| PgBouncerError::PgBouncer(format!(
+                "column {} is not readable as text: {}", column.name(), e
+            )))?;
+            Some(text.to_string())
+        };
+        map.insert(column.name().to_string(), value);
+    }
+
+    Ok(map)
+}
+
+fn text(row: &AdminRow, column: &str) -> crate::error::Result<String> {
+    optional_text(row, column)
+        .ok_or(PgBouncerError::PgBouncer(format!("admin console row has no {} column", column)))
+}
+
+fn optional_text(row: &AdminRow, column: &str) -> Option<String> {
+    row.get(column).cloned().flatten()
+}
+
+fn number<T: FromStr>(row: &AdminRow, column: &str) -> crate::error::Result<T> {
+    optional_number(row, column)?
+        .ok_or(PgBouncerError::PgBouncer(format!("admin console row has no {} column", column)))
+}
+
+fn optional_number<T: FromStr>(row: &AdminRow, column: &str) -> crate::error::Result<Option<T>> {
+    optional_text(row, column)
+        .map(|value| value.parse::<T>().map_err(|_| PgBouncerError::PgBouncer(format!(
+            "{} must be a number: {}", column, value
+        ))))
+        .transpose()
+}
+
+/// A row of `SHOW POOLS`.
+///
+/// # Fields
+/// - database: Database name.
+/// - user: User name.
+/// - cl_active: Client connections linked to a server connection or idle.
+/// - cl_waiting: Client connections waiting for a server connection.
+/// - sv_active: Server connections linked to a client.
+/// - sv_idle: Server connections unused and immediately usable.
+/// - sv_used: Server connections idle longer than `server_check_delay`.
+/// - sv_tested: Server connections running `server_reset_query` or `server_check_query`.
+/// - sv_login: Server connections in the process of logging in.
+/// - maxwait: Seconds the oldest waiting client has waited.
+/// - pool_mode: Pooling mode in use.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PoolStats {
+    pub database: String,
+    pub user: String,
+    pub cl_active: i64,
+    pub cl_waiting: i64,
+    pub sv_active: i64,
+    pub sv_idle: i64,
+    pub sv_used: i64,
+    pub sv_tested: i64,
+    pub sv_login: i64,
+    pub maxwait: i64,
+    pub pool_mode: String,
+}
+
+impl PoolStats {
+    fn from_row(row: &AdminRow) -> crate::error::Result<Self> {
+        Ok(Self {
+            database: text(row, "database")?,
+            user: text(row, "user")?,
+            cl_active: number(row, "cl_active")?,
+            cl_waiting: number(row, "cl_waiting")?,
+            sv_active: number(row, "sv_active")?,
+            sv_idle: number(row, "sv_idle")?,
+            sv_used: number(row, "sv_used")?,
+            sv_tested: number(row, "sv_tested")?,
+            sv_login: number(row, "sv_login")?,
+            maxwait: number(row, "maxwait")?,
+            pool_mode: text(row, "pool_mode")?,
+        })
+    }
+}
+
+/// A row of `SHOW DATABASES`.
+///
+/// # Fields
+/// - name: Exposed database name.
+/// - host: Host PgBouncer connects to; `None` for Unix sockets.
+/// - port: Port PgBouncer connects to.
+/// - database: Actual database name on the server.
+/// - force_user: User forced by the entry, if any.
+/// - pool_size: Maximum number of server connections.
+/// - min_pool_size: Minimum number of server connections (1.18+).
+/// - reserve_pool: Additional connections allowed when the pool is exhausted.
+/// - pool_mode: Pooling mode overriding the global one, if any.
+/// - max_connections: Maximum number of server connections to the database.
+/// - current_connections: Current number of server connections to the database.
+/// - paused: Whether the database is paused.
+/// - disabled: Whether the database is disabled.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseStatus {
+    pub name: String,
+    pub host: Option<String>,
+    pub port: u16,
+    pub database: String,
+    pub force_user: Option<String>,
+    pub pool_size: i64,
+    pub min_pool_size: Option<i64>,
+    pub reserve_pool: i64,
+    pub pool_mode: Option<String>,
+    pub max_connections: i64,
+    pub current_connections: i64,
+    pub paused: bool,
+    pub disabled: bool,
+}
+
+impl DatabaseStatus {
+    fn from_row(row: &AdminRow) -> crate::error::Result<Self> {
+        Ok(Self {
+            name: text(row, "name")?,
+            host: optional_text(row, "host"),
+            port: number(row, "port")?,
+            database: text(row, "database")?,
+            force_user: optional_text(row, "force_user"),
+            pool_size: number(row, "pool_size")?,
+            min_pool_size: optional_number(row, "min_pool_size")?,
+            reserve_pool: number(row, "reserve_pool")?,
+            pool_mode: optional_text(row, "pool_mode"),
+            max_connections: number(row, "max_connections")?,
+            current_connections: number(row, "current_connections")?,
+            paused: number::<i64>(row, "paused")? != 0,
+            disabled: number::<i64>(row, "disabled")? != 0,
+        })
+    }
+}
+
+/// A row of `SHOW CONFIG`.
+///
+/// # Fields
+/// - key: Setting name.
+/// - value: Effective value.
+/// - default: Built-in default value (1.18+).
+/// - changeable: Whether the setting can be changed with `SET` or `RELOAD`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ConfigEntry {
+    pub key: String,
+    pub value: String,
+    pub default: Option<String>,
+    pub changeable: bool,
+}
+
+impl ConfigEntry {
+    fn from_row(row: &AdminRow) -> crate::error::Result<Self> {
+        Ok(Self {
+            key: text(row, "key")?,
+            value: optional_text(row, "value").unwrap_or_default(),
+            default: optional_text(row, "default"),
+            changeable: optional_text(row, "changeable").is_some_and(|value| value == "yes"),
+        })
+    }
+}
+
+/// A row of `SHOW CLIENTS`.
+///
+/// # Fields
+/// - user: User the client connected as.
+/// - database: Database the client connected to.
+/// - state: Connection state (`active`, `waiting`, `idle`, ...).
+/// - addr: Client address; `unix` for Unix sockets.
+/// - port: Client port.
+/// - connect_time: When the client connected.
+/// - request_time: When the client sent its latest request.
+/// - application_name: `application_name` of the client, if reported (1.18+).
+/// - tls: TLS details of the connection; empty when not encrypted.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ClientInfo {
+    pub user: String,
+    pub database: String,
+    pub state: String,
+    pub addr: String,
+    pub port: u16,
+    pub connect_time: String,
+    pub request_time: String,
+    pub application_name: Option<String>,
+    pub tls: Option<String>,
+}
+
+impl ClientInfo {
+    fn from_row(row: &AdminRow) -> crate::error::Result<Self> {
+        Ok(Self {
+            user: text(row, "user")?,
+            database: text(row, "database")?,
+            state: text(row, "state")?,
+            addr: text(row, "addr")?,
+            port: number(row, "port")?,
+            connect_time: text(row, "connect_time")?,
+            request_time: text(row, "request_time")?,
+            application_name: optional_text(row, "application_name").filter(|name| !name.is_empty()),
+            tls: optional_text(row, "tls").filter(|tls| !tls.is_empty()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(columns: &[(&str, Option<&str>)]) -> AdminRow {
+        columns
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.map(|v| v.to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn database_status_reads_optional_columns() {
+        let mut columns = vec![
+            ("name", Some("app")), ("host", None), ("port", Some("5432")), ("database", Some("app")),
+            ("force_user", None), ("pool_size", Some("20")), ("reserve_pool", Some("0")),
+            ("pool_mode", None), ("max_connections", Some("0")), ("current_connections", Some("3")),
+            ("paused", Some("1")), ("disabled", Some("0")),
+        ];
+        let status = DatabaseStatus::from_row(&row(&columns)).unwrap();
+        assert_eq!(status.host, None);
+        assert_eq!(status.min_pool_size, None);
+        assert!(status.paused && !status.disabled);
+
+        columns.push(("min_pool_size", Some("5")));
+        assert_eq!(DatabaseStatus::from_row(&row(&columns)).unwrap().min_pool_size, Some(5));
+    }
+
+    #[test]
+    fn with_database_rejects_command_injection() {
+        assert_eq!(with_database("PAUSE", None).unwrap(), "PAUSE");
+        assert_eq!(with_database("RESUME", Some("app_db")).unwrap(), "RESUME app_db");
+        assert!(with_database("PAUSE", Some("app; SHUTDOWN")).is_err());
+        assert!(with_database("PAUSE", Some("")).is_err());
+    }
+
+    #[test]
+    fn rows_with_missing_or_invalid_columns_are_rejected() {
+        let config = ConfigEntry::from_row(&row(&[("key", Some("pool_mode")), ("value", Some("session")), ("changeable", Some("yes"))])).unwrap();
+        assert!(config.changeable);
+        assert_eq!(config.default, None);
+
+        assert!(ConfigEntry::from_row(&row(&[("value", Some("session"))])).is_err());
+        assert!(PoolStats::from_row(&row(&[("database", Some("app")), ("user", Some("u")), ("cl_active", Some("many"))])).is_err());
+    }
+}
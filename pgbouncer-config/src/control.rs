@@ -0,0 +1,145 @@
+//! Operations shared by the HTTP (`server`) and gRPC (`grpc`) front-ends.
+//!
+//! Both APIs accept definitions in the JSON representation of
+//! [`PgBouncerConfig`], validate them by rendering and by
+//! [`PgBouncerConfig::validate`], and persist or reload
+//! through the paths and commands in [`ServiceOptions`].
+
+use std::path::{Path, PathBuf};
+use serde_json::Value;
+use crate::error::PgBouncerError;
+use crate::io::ConfigFileFormat;
+use crate::io::write::{Writer, Writers};
+use crate::pgbouncer_config::PgBouncerConfig;
+
+/// Options of the config management APIs.
+///
+/// # Fields
+/// - definition: Definition file (TOML) rewritten whenever the definition is updated.
+/// - output: pgbouncer.ini path written by the generate operation.
+/// - reload_command: Program and arguments run by the reload operation
+///   (e.g. `["systemctl", "reload", "pgbouncer"]`).
+#[derive(Debug, Clone, Default)]
+pub struct ServiceOptions {
+    pub definition: Option<PathBuf>,
+    pub output: Option<PathBuf>,
+    pub reload_command: Option<Vec<String>>,
+}
+
+/// Parses a JSON definition and renders it to make sure it can produce a pgbouncer.ini.
+pub(crate) fn parse_definition(json: &str) -> crate::error::Result<PgBouncerConfig> {
+    let config: PgBouncerConfig = serde_json::from_str(json)?;
+    validate(&config)?;
+
+    Ok(config)
+}
+
+/// Applies a JSON merge patch (RFC 7386) to a configuration.
+///
+/// Objects are merged recursively, `null` removes a key and any other value
+/// replaces the target. The patched configuration is validated before it is returned.
+pub(crate) fn apply_merge_patch(config: &PgBouncerConfig, patch: &str) -> crate::error::Result<PgBouncerConfig> {
+    let patch: Value = serde_json::from_str(patch)?;
+    let mut target = serde_json::to_value(config)?;
+    merge_patch(&mut target, &patch);
+
+    let patched: PgBouncerConfig = serde_json::from_value(target)?;
+    validate(&patched)?;
+
+    Ok(patched)
+}
+
+/// Writes the definition file as TOML.
+pub(crate) fn save_definition(path: &Path, config: &PgBouncerConfig) -> crate::error::Result<()> {
+    let mut writer = Writer::try_from(Writers::File(path))?;
+    writer.write_config(config, ConfigFileFormat::TOML)
+}
+
+/// Writes the rendered pgbouncer.ini.
+pub(crate) fn generate(output: &Path, config: &PgBouncerConfig) -> crate::error::Result<()> {
+    let mut writer = Writer::try_from(Writers::File(output))?;
+    writer.write(config)
+}
+
+/// Runs the reload command and fails when it exits unsuccessfully.
+pub(crate) async fn run_reload_command(command: &[String]) -> crate::error::Result<()> {
+    let (program, args) = command
+        .split_first()
+        .ok_or(PgBouncerError::PgBouncer("the reload command is empty".to_string()))?;
+
+    let status = tokio::process::Command::new(program)
+        .args(args)
+        .status()
+        .await?;
+
+    if !status.success() {
+        return Err(PgBouncerError::PgBouncer(format!("reload command exited with {}", status)));
+    }
+
+    Ok(())
+}
+
+fn validate(config: &PgBouncerConfig) -> crate::error::Result<()> {
+    config.expr()?;
+
+    let errors = config
+        .validate()
+        .into_iter()
+        .filter(|issue| issue.is_error())
+        .map(|issue| issue.to_string())
+        .collect::<Vec<_>>();
+    if !errors.is_empty() {
+        return Err(PgBouncerError::PgBouncer(format!("invalid configuration:\n{}", errors.join("\n"))));
+    }
+
+    Ok(())
+}
+
+fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(serde_json::Map::new());
+    }
+    // SAFETY: target has just been made an object
+    let target = target.as_object_mut().unwrap();
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::PgBouncerConfigBuilder;
+    use crate::pgbouncer_config::databases_setting::DatabasesSetting;
+    use crate::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+
+    #[test]
+    fn merge_patch_follows_rfc_7386() {
+        let mut target = serde_json::json!({"a": "b", "c": {"d": "e", "f": "g"}});
+        let patch = serde_json::json!({"a": "z", "c": {"f": null}});
+        merge_patch(&mut target, &patch);
+        assert_eq!(target, serde_json::json!({"a": "z", "c": {"d": "e"}}));
+    }
+
+    #[test]
+    fn apply_merge_patch_updates_a_single_setting() {
+        let config = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new())
+            .unwrap()
+            .build();
+        let patch = r#"{"pgbouncer": {"PgBouncerSetting": {"listen_port": 7432}}}"#;
+
+        let patched = apply_merge_patch(&config, patch).unwrap();
+        let text = patched.expr().unwrap();
+        assert!(text.contains("listen_port = 7432"));
+        assert!(text.contains("listen_addr = 127.0.0.1"));
+    }
+}
@@ -0,0 +1,370 @@
+//! Rich diagnostics for parse and validation errors (`diagnostics` feature).
+//!
+//! [`ConfigDiagnostic`] implements [`miette::Diagnostic`], so an error or a
+//! validation issue can be rendered against the text it comes from, with the
+//! offending key or value underlined and a hint such as
+//! "did you mean `pool_mode`?".
+
+use std::fmt::{Display, Formatter};
+use miette::{Diagnostic, LabeledSpan, NamedSource, SourceCode, SourceSpan};
+use crate::error::PgBouncerError;
+use crate::pgbouncer_config::lint::LintFinding;
+use crate::pgbouncer_config::settings_catalog::{find_deprecated_setting, find_section_setting, settings};
+use crate::pgbouncer_config::validation::{Severity, ValidationIssue};
+
+/// A parse error, validation issue or unknown setting located in the text of
+/// pgbouncer.ini or of a definition file.
+#[derive(Debug)]
+pub struct ConfigDiagnostic {
+    severity: miette::Severity,
+    code: &'static str,
+    message: String,
+    source: NamedSource<String>,
+    span: Option<SourceSpan>,
+    label: String,
+    help: Option<String>,
+}
+
+impl ConfigDiagnostic {
+    /// Creates the diagnostic of an error returned while parsing `text`.
+    ///
+    /// A [`PgBouncerError::ParseError`] is pointed at its line and column, or
+    /// at the line of its key; its help tells the value type of the key.
+    /// Other errors are reported without location.
+    ///
+    /// # Parameters
+    /// - error: Error returned by the parser.
+    /// - name: Name of the parsed file, shown above the snippet.
+    /// - text: The parsed text.
+    ///
+    /// # Returns
+    /// The diagnostic with error severity.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::diagnostic::ConfigDiagnostic;
+    /// use pgbouncer_config::pgbouncer_config::PgBouncerConfig;
+    /// use pgbouncer_config::utils::parser::ParserIniFromStr;
+    /// use miette::Diagnostic;
+    ///
+    /// let ini = "[pgbouncer]\nlisten_addr = 127.0.0.1\nlisten_port = many\n";
+    /// let error = PgBouncerConfig::parse_from_str(ini).unwrap_err();
+    /// let diagnostic = ConfigDiagnostic::from_error(&error, "pgbouncer.ini", ini);
+    /// assert_eq!(diagnostic.to_string(), "listen_port must be a number");
+    /// assert_eq!(diagnostic.help().unwrap().to_string(), "`listen_port` takes a value of type integer");
+    /// ```
+    pub fn from_error(error: &PgBouncerError, name: &str, text: &str) -> Self {
+        let PgBouncerError::ParseError { section, key, line, column, reason } = error else {
+            return Self::new(miette::Severity::Error, "pgbouncer_config::error", error.to_string(), name, text);
+        };
+
+        let mut diagnostic = Self::new(miette::Severity::Error, "pgbouncer_config::parse", reason.clone(), name, text);
+        diagnostic.span = match (line, column, section, key) {
+            (Some(line), Some(column), _, _) => value_span(text, *line, *column),
+            (_, _, Some(section), Some(key)) => key_span(text, section, key),
+            _ => None,
+        };
+        diagnostic.label = "invalid value".to_string();
+        diagnostic.help = section
+            .as_deref()
+            .zip(key.as_deref())
+            .and_then(|(section, key)| find_section_setting(section, key))
+            .map(|info| format!("`{}` takes a value of type {}", info.name, info.value_type));
+
+        diagnostic
+    }
+
+    /// Creates the diagnostic of a validation issue found in the config parsed from `text`.
+    ///
+    /// The issue is pointed at the line defining its key, if any.
+    ///
+    /// # Parameters
+    /// - issue: Issue returned by `PgBouncerConfig::validate`.
+    /// - name: Name of the parsed file, shown above the snippet.
+    /// - text: Text of pgbouncer.ini or of the TOML definition the config was parsed from.
+    ///
+    /// # Returns
+    /// The diagnostic with the severity of the issue.
+    pub fn from_issue(issue: &ValidationIssue, name: &str, text: &str) -> Self {
+        let severity = match issue.severity() {
+            Severity::Error => miette::Severity::Error,
+            Severity::Warning => miette::Severity::Warning,
+        };
+
+        let mut diagnostic = Self::new(severity, "pgbouncer_config::validation", issue.message().to_string(), name, text);
+        diagnostic.span = issue.key().and_then(|key| key_span(text, issue.section(), key));
+        diagnostic
+    }
+
+    /// Creates the diagnostic of a lint finding in the config parsed from `text`.
+    ///
+    /// The finding is pointed at the line defining its key, like a validation
+    /// issue; its help names the rule so it can be disabled.
+    ///
+    /// # Parameters
+    /// - finding: Finding returned by `Linter::lint`.
+    /// - name: Name of the parsed file, shown above the snippet.
+    /// - text: Text of pgbouncer.ini or of the TOML definition the config was parsed from.
+    ///
+    /// # Returns
+    /// The diagnostic with the severity of the finding.
+    pub fn from_finding(finding: &LintFinding, name: &str, text: &str) -> Self {
+        let mut diagnostic = Self::from_issue(finding.issue(), name, text);
+        diagnostic.code = "pgbouncer_config::lint";
+        diagnostic.help = Some(format!("{} (rule `{}`)", finding.rule().description(), finding.rule()));
+        diagnostic
+    }
+
+    fn new(severity: miette::Severity, code: &'static str, message: String, name: &str, text: &str) -> Self {
+        Self {
+            severity,
+            code,
+            message,
+            source: NamedSource::new(name, text.to_string()),
+            span: None,
+            label: "here".to_string(),
+            help: None,
+        }
+    }
+}
+
+impl Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ConfigDiagnostic {}
+
+impl Diagnostic for ConfigDiagnostic {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code))
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        Some(self.severity)
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.help.as_ref().map(|help| Box::new(help) as Box<dyn Display + 'a>)
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        self.span.map(|span| {
+            let label = LabeledSpan::new_with_span(Some(self.label.clone()), span);
+            Box::new(std::iter::once(label)) as Box<dyn Iterator<Item = LabeledSpan>>
+        })
+    }
+}
+
+/// Reports the keys of the [pgbouncer] section of pgbouncer.ini text that
+/// PgBouncer does not know, suggesting the closest known key.
+///
+/// Unknown keys are kept by the parser, so a typo like `pool_mod` would
+/// otherwise go unnoticed until PgBouncer refuses to start.
+///
+/// # Parameters
+/// - name: Name of the file, shown above the snippet.
+/// - text: pgbouncer.ini text.
+///
+/// # Returns
+/// One warning per unknown key, in order of appearance.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::diagnostic::unknown_settings;
+/// use miette::Diagnostic;
+///
+/// let diagnostics = unknown_settings("pgbouncer.ini", "[pgbouncer]\npool_mod = transaction\n");
+/// assert_eq!(diagnostics[0].to_string(), "unknown setting `pool_mod` in [pgbouncer]");
+/// assert_eq!(diagnostics[0].help().unwrap().to_string(), "did you mean `pool_mode`?");
+/// ```
+pub fn unknown_settings(name: &str, text: &str) -> Vec<ConfigDiagnostic> {
+    key_lines(text)
+        .filter(|entry| entry.section == "pgbouncer" && find_section_setting("pgbouncer", entry.key).is_none())
+        // Deprecated keys are reported by `deprecations`
+        .filter(|entry| find_deprecated_setting("pgbouncer", entry.key, entry.value).is_none())
+        .map(|entry| {
+            let message = format!("unknown setting `{}` in [pgbouncer]", entry.key);
+            let mut diagnostic = ConfigDiagnostic::new(miette::Severity::Warning, "pgbouncer_config::unknown_setting", message, name, text);
+            diagnostic.span = Some(SourceSpan::new(entry.offset.into(), entry.key.len()));
+            diagnostic.label = "unknown setting".to_string();
+            diagnostic.help = suggest_setting("pgbouncer", entry.key).map(|known| format!("did you mean `{}`?", known));
+            diagnostic
+        })
+        .collect()
+}
+
+/// Reports the deprecated keys and values of pgbouncer.ini text.
+///
+/// # Parameters
+/// - name: Name of the file, shown above the snippet.
+/// - text: pgbouncer.ini text.
+///
+/// # Returns
+/// One warning per deprecated key or value, in order of appearance, with the
+/// replacement as help.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::diagnostic::deprecations;
+/// use miette::Diagnostic;
+///
+/// let diagnostics = deprecations("pgbouncer.ini", "[pgbouncer]\nauth_type = plain\n");
+/// assert_eq!(diagnostics[0].help().unwrap().to_string(), "use auth_type = scram-sha-256 instead");
+/// ```
+pub fn deprecations(name: &str, text: &str) -> Vec<ConfigDiagnostic> {
+    key_lines(text)
+        .filter_map(|entry| find_deprecated_setting(&entry.section, entry.key, entry.value).map(|deprecated| (entry, deprecated)))
+        .map(|(entry, deprecated)| {
+            let message = format!("{} in [{}]", deprecated.message(), entry.section);
+            let mut diagnostic = ConfigDiagnostic::new(miette::Severity::Warning, "pgbouncer_config::deprecated", message, name, text);
+            diagnostic.span = Some(SourceSpan::new(entry.offset.into(), entry.key.len()));
+            diagnostic.label = "deprecated".to_string();
+            diagnostic.help = deprecated.replacement.map(|replacement| format!("use {} instead", replacement));
+            diagnostic
+        })
+        .collect()
+}
+
+/// Returns the known key of `section` closest to `key`, if it is close enough
+/// to be a typo.
+///
+/// # Parameters
+/// - section: Section of the key, without brackets.
+/// - key: The unknown key.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::diagnostic::suggest_setting;
+///
+/// assert_eq!(suggest_setting("pgbouncer", "listen_prot"), Some("listen_port"));
+/// assert_eq!(suggest_setting("pgbouncer", "completely_different"), None);
+/// ```
+pub fn suggest_setting(section: &str, key: &str) -> Option<&'static str> {
+    let key = key.to_ascii_lowercase();
+    let max_distance = (key.len() / 3).max(2);
+
+    settings()
+        .iter()
+        .filter(|info| info.section == section)
+        .map(|info| (edit_distance(&key, info.name), info.name))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name)
+}
+
+/// A `key = value` line of pgbouncer.ini or TOML text.
+struct KeyLine<'a> {
+    /// Section of the line; for TOML the first part of the table header.
+    section: String,
+    key: &'a str,
+    value: &'a str,
+    /// Byte offset of the key in the text.
+    offset: usize,
+}
+
+fn key_lines(text: &str) -> impl Iterator<Item = KeyLine<'_>> {
+    let mut section = String::new();
+    let mut offset = 0;
+
+    text.split_inclusive('\n').filter_map(move |line| {
+        let line_offset = offset;
+        offset += line.len();
+
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            section = trimmed
+                .trim_matches(|c| c == '[' || c == ']')
+                .split('.')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .trim_matches('"')
+                .to_string();
+            return None;
+        }
+        if trimmed.starts_with(['#', ';']) {
+            return None;
+        }
+
+        let (key, value) = line.split_once('=')?;
+        let key = key.trim().trim_matches('"');
+        let value = value.trim().trim_matches('"');
+        let key_offset = line_offset + line.find(key)?;
+        (!key.is_empty()).then(|| KeyLine { section: section.clone(), key, value, offset: key_offset })
+    })
+}
+
+/// Span of `key` on the line defining it in `section`.
+fn key_span(text: &str, section: &str, key: &str) -> Option<SourceSpan> {
+    key_lines(text)
+        .find(|entry| entry.section == section && entry.key == key)
+        .map(|entry| SourceSpan::new(entry.offset.into(), entry.key.len()))
+}
+
+/// Span from a 1-based line and column to the end of the line, trailing spaces excluded.
+fn value_span(text: &str, line: usize, column: usize) -> Option<SourceSpan> {
+    let line_offset = text.split_inclusive('\n').take(line.checked_sub(1)?).map(str::len).sum::<usize>();
+    let line_text = text[line_offset..].lines().next()?;
+    let start = line_text.char_indices().nth(column.saturating_sub(1)).map_or(line_text.len(), |(index, _)| index);
+    let length = line_text[start..].trim_end().len();
+
+    Some(SourceSpan::new((line_offset + start).into(), length))
+}
+
+/// Levenshtein distance between two keys.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgbouncer_config::PgBouncerConfig;
+    use crate::utils::parser::ParserIniFromStr;
+
+    const INI: &str = "[pgbouncer]\nlisten_addr = 127.0.0.1\nlisten_port = many \n";
+
+    #[test]
+    fn parse_errors_underline_the_value() {
+        let error = PgBouncerConfig::parse_from_str(INI).unwrap_err();
+        let diagnostic = ConfigDiagnostic::from_error(&error, "pgbouncer.ini", INI);
+
+        let label = diagnostic.labels().unwrap().next().unwrap();
+        assert_eq!(&INI[label.offset()..label.offset() + label.len()], "many");
+    }
+
+    #[test]
+    fn issues_point_at_their_key_in_ini_and_toml_text() {
+        let issue = ValidationIssue::error("pgbouncer", Some("listen_port"), "listen_port is in use");
+        for text in [INI, "[pgbouncer.PgBouncerSetting]\nlisten_port = 6432\n"] {
+            let diagnostic = ConfigDiagnostic::from_issue(&issue, "config", text);
+            let label = diagnostic.labels().unwrap().next().unwrap();
+            assert_eq!(&text[label.offset()..label.offset() + label.len()], "listen_port");
+        }
+    }
+
+    #[test]
+    fn edit_distance_counts_single_character_edits() {
+        assert_eq!(edit_distance("pool_mod", "pool_mode"), 1);
+        assert_eq!(edit_distance("listen_prot", "listen_port"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}
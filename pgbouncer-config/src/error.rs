@@ -9,6 +9,19 @@ pub enum PgBouncerError {
     Sqlx(#[from] sqlx::Error),
     #[error("PgBouncer Error: {0}")]
     PgBouncer(String),
+    /// A value of pgbouncer.ini or of a definition file could not be parsed.
+    ///
+    /// The location is filled in as far as it is known: section and key for
+    /// errors of a single setting, line and column (both 1-based) once the
+    /// error is matched against the parsed text.
+    #[error("Parse Error{}: {reason}", describe_location(.section, .line, .column))]
+    ParseError {
+        section: Option<String>,
+        key: Option<String>,
+        line: Option<usize>,
+        column: Option<usize>,
+        reason: String,
+    },
     #[error("I/O Error: {0}")]
     Io(#[from] std::io::Error),
     #[error("Regex Error: {0}")]
@@ -23,6 +36,8 @@ pub enum PgBouncerError {
     SshAuth(String),
     #[error("SSH error: {0}")]
     Connection(String),
+    #[error("Secret Error: {0}")]
+    Secret(String),
     #[cfg(feature = "io")]
     #[error("Serialize Error: {0}")]
     Serialize(#[from] toml::ser::Error),
@@ -32,6 +47,15 @@ pub enum PgBouncerError {
     #[cfg(feature = "io")]
     #[error("Deserialize Error: {0}")]
     Deserialize(#[from] toml::de::Error),
+    #[cfg(feature = "git")]
+    #[error("Git Error: {0}")]
+    Git(#[from] git2::Error),
+    #[cfg(feature = "grpc")]
+    #[error("gRPC transport Error: {0}")]
+    GrpcTransport(#[from] tonic::transport::Error),
+    #[cfg(feature = "test-support")]
+    #[error("Container Error: {0}")]
+    Container(#[from] testcontainers_modules::testcontainers::TestcontainersError),
 }
 
 impl Into<PgBouncerError> for String {
@@ -44,4 +68,129 @@ impl Into<PgBouncerError> for &str {
     fn into(self) -> PgBouncerError {
         PgBouncerError::PgBouncer(self.to_string())
     }
-}
\ No newline at end of file
+}
+
+impl PgBouncerError {
+    /// Creates a [`PgBouncerError::ParseError`] of a key, without location.
+    ///
+    /// # Parameters
+    /// - section: Section of the key, without brackets.
+    /// - key: The key whose value could not be parsed.
+    /// - reason: What is wrong with the value.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::error::PgBouncerError;
+    ///
+    /// let err = PgBouncerError::parse_error("pgbouncer", "listen_port", "listen_port must be a number");
+    /// assert_eq!(err.to_string(), "Parse Error in [pgbouncer]: listen_port must be a number");
+    /// ```
+    pub fn parse_error(section: &str, key: &str, reason: impl Into<String>) -> Self {
+        PgBouncerError::ParseError {
+            section: Some(section.to_string()),
+            key: Some(key.to_string()),
+            line: None,
+            column: None,
+            reason: reason.into(),
+        }
+    }
+
+    /// Attributes a parse failure to `section`.
+    ///
+    /// A [`PgBouncerError::PgBouncer`] message becomes the reason of a
+    /// [`PgBouncerError::ParseError`]; a `ParseError` keeps the section it
+    /// already has. Other errors are returned unchanged.
+    ///
+    /// # Parameters
+    /// - section: Section being parsed, without brackets.
+    pub fn in_section(self, section: &str) -> Self {
+        match self {
+            PgBouncerError::PgBouncer(reason) => PgBouncerError::ParseError {
+                section: Some(section.to_string()),
+                key: None,
+                line: None,
+                column: None,
+                reason,
+            },
+            PgBouncerError::ParseError { section: found_section, key, line, column, reason } => PgBouncerError::ParseError {
+                section: found_section.or_else(|| Some(section.to_string())),
+                key,
+                line,
+                column,
+                reason,
+            },
+            other => other,
+        }
+    }
+
+    /// Attributes a parse failure to `key` in `section`.
+    ///
+    /// Like [`PgBouncerError::in_section`], and additionally sets the key of a
+    /// `ParseError` that has none yet.
+    ///
+    /// # Parameters
+    /// - section: Section being parsed, without brackets.
+    /// - key: Key being parsed.
+    pub fn at_key(self, section: &str, key: &str) -> Self {
+        match self.in_section(section) {
+            PgBouncerError::ParseError { section, key: found_key, line, column, reason } => PgBouncerError::ParseError {
+                section,
+                key: found_key.or_else(|| Some(key.to_string())),
+                line,
+                column,
+                reason,
+            },
+            other => other,
+        }
+    }
+
+    /// Sets the line and column of a parse failure that has none yet.
+    ///
+    /// A [`PgBouncerError::PgBouncer`] message becomes the reason of a
+    /// [`PgBouncerError::ParseError`]. Other errors are returned unchanged.
+    ///
+    /// # Parameters
+    /// - line: 1-based line number in the parsed text.
+    /// - column: 1-based column number in the line.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::error::PgBouncerError;
+    ///
+    /// let err = PgBouncerError::parse_error("pgbouncer", "listen_port", "listen_port must be a number").at_location(3, 15);
+    /// assert_eq!(err.to_string(), "Parse Error in [pgbouncer] at line 3, column 15: listen_port must be a number");
+    /// ```
+    pub fn at_location(self, line: usize, column: usize) -> Self {
+        match self {
+            PgBouncerError::PgBouncer(reason) => PgBouncerError::ParseError {
+                section: None,
+                key: None,
+                line: Some(line),
+                column: Some(column),
+                reason,
+            },
+            PgBouncerError::ParseError { section, key, line: found_line, column: found_column, reason } => PgBouncerError::ParseError {
+                section,
+                key,
+                line: found_line.or(Some(line)),
+                column: found_column.or(Some(column)),
+                reason,
+            },
+            other => other,
+        }
+    }
+}
+
+fn describe_location(section: &Option<String>, line: &Option<usize>, column: &Option<usize>) -> String {
+    let mut location = String::new();
+    if let Some(section) = section {
+        location.push_str(&format!(" in [{}]", section));
+    }
+    match (line, column) {
+        (Some(line), Some(column)) => location.push_str(&format!(" at line {}, column {}", line, column)),
+        (Some(line), None) => location.push_str(&format!(" at line {}", line)),
+        _ => {},
+    }
+
+    location
+}
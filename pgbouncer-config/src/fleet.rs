@@ -0,0 +1,382 @@
+//! Inventory-driven management of many PgBouncer instances.
+//!
+//! Enabled with the `fleet` feature. An inventory (TOML) lists the instances
+//! of a fleet; each one refers to a profile definition and may override
+//! individual `[pgbouncer]` settings:
+//!
+//! ```toml
+//! # Directory holding `<profile>.toml` definitions, relative to the inventory.
+//! profiles_dir = "profiles"
+//!
+//! [[instances]]
+//! name = "pgb-tokyo-1"
+//! host = "10.0.0.11"
+//! profile = "prod"
+//!
+//! [instances.overrides]
+//! listen_port = 6433
+//! pool_mode = "transaction"
+//! ```
+//!
+//! [`Fleet::from_inventory_file`] resolves every entry into a full
+//! [`PgBouncerConfig`], [`Fleet::validate`] checks them all at once,
+//! [`Fleet::render_to`] writes one pgbouncer.ini per instance and
+//! [`Fleet::diff_against`] reports how instances differ from each other.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::error::PgBouncerError;
+use crate::io::ConfigFileFormat;
+use crate::io::read::{Reader, Readers};
+use crate::io::write::{Writer, Writers};
+use crate::pgbouncer_config::PgBouncerConfig;
+use crate::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+use crate::utils::diff::{compute_diff_pg_config, Diff};
+
+/// Default profiles directory, relative to the inventory file.
+pub const DEFAULT_PROFILES_DIR: &str = "profiles";
+
+/// File name of the configuration rendered for each instance.
+pub const OUTPUT_FILE_NAME: &str = "pgbouncer.ini";
+
+/// Inventory of the instances in a fleet.
+///
+/// # Fields
+/// - profiles_dir: Directory holding `<profile>.toml` definitions. Relative paths are
+///   resolved against the inventory file's directory. Defaults to [`DEFAULT_PROFILES_DIR`].
+/// - instances: Instances of the fleet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Inventory {
+    #[serde(default)]
+    pub profiles_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub instances: Vec<InstanceSpec>,
+}
+
+/// One instance entry of an [`Inventory`].
+///
+/// # Fields
+/// - name: Unique instance name; also the name of its output directory.
+/// - host: Address of the machine running the instance; recorded in reports.
+/// - profile: Name of the profile definition the instance is based on.
+/// - overrides: `[pgbouncer]` settings replacing the profile's values, keyed by setting name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstanceSpec {
+    pub name: String,
+    pub host: String,
+    pub profile: String,
+    #[serde(default)]
+    pub overrides: BTreeMap<String, serde_json::Value>,
+}
+
+/// An instance resolved into a full configuration.
+///
+/// # Fields
+/// - name: Instance name.
+/// - host: Address of the machine running the instance.
+/// - profile: Profile the configuration is based on.
+/// - config: Profile configuration with the instance overrides applied.
+#[derive(Debug, Clone)]
+pub struct FleetInstance {
+    pub name: String,
+    pub host: String,
+    pub profile: String,
+    pub config: PgBouncerConfig,
+}
+
+/// A pgbouncer.ini written for an instance.
+///
+/// # Fields
+/// - name: Instance name.
+/// - output: Path of the written file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedInstance {
+    pub name: String,
+    pub output: PathBuf,
+}
+
+/// Resolved configurations of every instance in an inventory.
+///
+/// # Fields
+/// - instances: Resolved instances in inventory order.
+#[derive(Debug, Clone)]
+pub struct Fleet {
+    instances: Vec<FleetInstance>,
+}
+
+impl Fleet {
+    /// Reads an inventory file and resolves every instance.
+    ///
+    /// # Parameters
+    /// - path: Path of the inventory (TOML).
+    ///
+    /// # Returns
+    /// The resolved `Fleet`.
+    ///
+    /// # Errors
+    /// Returns an error if the inventory cannot be read, a profile cannot be
+    /// loaded or an override cannot be applied.
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// use std::path::Path;
+    /// use pgbouncer_config::fleet::Fleet;
+    ///
+    /// let fleet = Fleet::from_inventory_file(Path::new("fleet/inventory.toml")).unwrap();
+    /// fleet.validate().unwrap();
+    /// fleet.render_to(Path::new("generated/fleet")).unwrap();
+    /// ```
+    pub fn from_inventory_file(path: &Path) -> crate::error::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let inventory: Inventory = toml::from_str(&text)?;
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+
+        Self::resolve(&inventory, base_dir)
+    }
+
+    /// Resolves every instance of an inventory.
+    ///
+    /// Each profile definition is loaded once and shared by the instances using it.
+    ///
+    /// # Parameters
+    /// - inventory: Inventory to resolve.
+    /// - base_dir: Directory relative profile paths are resolved against.
+    ///
+    /// # Returns
+    /// The resolved `Fleet`.
+    ///
+    /// # Errors
+    /// Returns an error if instance names are not unique, a profile cannot be
+    /// loaded or an override names an unknown setting or has an invalid value.
+    pub fn resolve(inventory: &Inventory, base_dir: &Path) -> crate::error::Result<Self> {
+        let profiles_dir = base_dir.join(
+            inventory.profiles_dir.as_deref().unwrap_or(Path::new(DEFAULT_PROFILES_DIR))
+        );
+
+        let mut names = HashSet::new();
+        let mut profiles: HashMap<&str, PgBouncerConfig> = HashMap::new();
+        let mut instances = Vec::new();
+        for spec in &inventory.instances {
+            if !names.insert(spec.name.as_str()) {
+                return Err(PgBouncerError::PgBouncer(format!("instance {} is defined more than once", spec.name)));
+            }
+
+            if !profiles.contains_key(spec.profile.as_str()) {
+                let definition = profiles_dir.join(format!("{}.toml", spec.profile));
+                let config = Reader::try_from(Readers::File(&definition))?
+                    .read_config(ConfigFileFormat::TOML)
+                    .map_err(|e| PgBouncerError::PgBouncer(format!("failed to load profile {}: {}", spec.profile, e)))?;
+                profiles.insert(spec.profile.as_str(), config);
+            }
+
+            let mut config = profiles[spec.profile.as_str()].clone();
+            apply_overrides(&mut config, &spec.overrides)
+                .map_err(|e| PgBouncerError::PgBouncer(format!("instance {}: {}", spec.name, e)))?;
+
+            instances.push(FleetInstance {
+                name: spec.name.clone(),
+                host: spec.host.clone(),
+                profile: spec.profile.clone(),
+                config,
+            });
+        }
+
+        Ok(Self { instances })
+    }
+
+    /// Returns the resolved instances in inventory order.
+    pub fn instances(&self) -> &[FleetInstance] {
+        &self.instances
+    }
+
+    /// Returns the instance with the given name.
+    ///
+    /// # Parameters
+    /// - name: Instance name.
+    ///
+    /// # Returns
+    /// The instance, or `None` if the fleet has no instance with that name.
+    pub fn instance(&self, name: &str) -> Option<&FleetInstance> {
+        self.instances.iter().find(|instance| instance.name == name)
+    }
+
+    /// Checks that every instance renders to a pgbouncer.ini.
+    ///
+    /// # Returns
+    /// Unit when every instance is valid.
+    ///
+    /// # Errors
+    /// Returns a single error listing every failing instance, so one run
+    /// reports all problems of the fleet.
+    pub fn validate(&self) -> crate::error::Result<()> {
+        let failures = self.instances
+            .iter()
+            .filter_map(|instance| instance.config.expr().err().map(|e| format!("{}: {}", instance.name, e)))
+            .collect::<Vec<_>>();
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(PgBouncerError::PgBouncer(format!("invalid fleet instances:\n{}", failures.join("\n"))))
+        }
+    }
+
+    /// Writes one pgbouncer.ini per instance to `out_dir/<instance>/pgbouncer.ini`.
+    ///
+    /// The whole fleet is validated first so nothing is written when any instance is invalid.
+    ///
+    /// # Parameters
+    /// - out_dir: Output directory.
+    ///
+    /// # Returns
+    /// The written files in inventory order.
+    ///
+    /// # Errors
+    /// Returns an error if validation fails or a file cannot be written.
+    pub fn render_to(&self, out_dir: &Path) -> crate::error::Result<Vec<RenderedInstance>> {
+        self.validate()?;
+
+        let mut rendered = Vec::new();
+        for instance in &self.instances {
+            let output = out_dir.join(&instance.name).join(OUTPUT_FILE_NAME);
+            let mut writer = Writer::try_from(Writers::File(&output))?;
+            writer.write(&instance.config)?;
+            rendered.push(RenderedInstance { name: instance.name.clone(), output });
+        }
+
+        Ok(rendered)
+    }
+
+    /// Diffs every other instance against a reference instance.
+    ///
+    /// # Parameters
+    /// - reference: Name of the instance used as the old side of each diff.
+    ///
+    /// # Returns
+    /// A map of instance name to its diff from the reference, excluding the reference itself.
+    ///
+    /// # Errors
+    /// Returns an error if the reference instance does not exist or a diff cannot be computed.
+    pub fn diff_against(&self, reference: &str) -> crate::error::Result<BTreeMap<String, Diff>> {
+        let base = self
+            .instance(reference)
+            .ok_or(PgBouncerError::PgBouncer(format!("instance {} is not in the fleet", reference)))?;
+
+        let mut report = BTreeMap::new();
+        for instance in self.instances.iter().filter(|instance| instance.name != reference) {
+            report.insert(instance.name.clone(), compute_diff_pg_config(&base.config, &instance.config)?);
+        }
+
+        Ok(report)
+    }
+}
+
+fn apply_overrides(
+    config: &mut PgBouncerConfig,
+    overrides: &BTreeMap<String, serde_json::Value>,
+) -> crate::error::Result<()> {
+    if overrides.is_empty() {
+        return Ok(());
+    }
+
+    let setting = config.get_config_mut::<PgBouncerSetting>()?;
+    let mut value = serde_json::to_value(&*setting)?;
+    // SAFETY: PgBouncerSetting is a struct and always serializes to an object
+    let fields = value.as_object_mut().unwrap();
+    for (key, override_value) in overrides {
+        if !fields.contains_key(key) {
+            return Err(PgBouncerError::PgBouncer(format!("unknown [pgbouncer] setting: {}", key)));
+        }
+        fields.insert(key.clone(), override_value.clone());
+    }
+
+    *setting = serde_json::from_value(value)
+        .map_err(|e| PgBouncerError::PgBouncer(format!("invalid override: {}", e)))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::PgBouncerConfigBuilder;
+    use crate::pgbouncer_config::databases_setting::DatabasesSetting;
+
+    fn fleet_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pgbouncer-config-fleet-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join(DEFAULT_PROFILES_DIR)).unwrap();
+
+        let config = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new())
+            .unwrap()
+            .build();
+        let mut writer = Writer::try_from(Writers::File(&dir.join(DEFAULT_PROFILES_DIR).join("prod.toml"))).unwrap();
+        writer.write_config(&config, ConfigFileFormat::TOML).unwrap();
+
+        dir
+    }
+
+    fn write_inventory(dir: &Path, text: &str) -> PathBuf {
+        let path = dir.join("inventory.toml");
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn overrides_are_applied_per_instance() {
+        let dir = fleet_dir("overrides");
+        let inventory = write_inventory(&dir, r#"
+            [[instances]]
+            name = "a"
+            host = "10.0.0.1"
+            profile = "prod"
+
+            [[instances]]
+            name = "b"
+            host = "10.0.0.2"
+            profile = "prod"
+            overrides = { listen_port = 7432, pool_mode = "transaction" }
+        "#);
+
+        let fleet = Fleet::from_inventory_file(&inventory).unwrap();
+        let b = fleet.instance("b").unwrap().config.expr().unwrap();
+        assert!(b.contains("listen_port = 7432"));
+        assert!(b.contains("pool_mode = transaction"));
+        assert!(fleet.instance("a").unwrap().config.expr().unwrap().contains("listen_port = 6432"));
+
+        let report = fleet.diff_against("a").unwrap();
+        assert_eq!(report.keys().collect::<Vec<_>>(), vec!["b"]);
+        assert_ne!(report["b"], Diff::Same { value: String::new() });
+
+        let rendered = fleet.render_to(&dir.join("out")).unwrap();
+        assert_eq!(rendered.len(), 2);
+        assert!(rendered.iter().all(|r| r.output.exists()));
+    }
+
+    #[test]
+    fn unknown_override_and_duplicate_names_are_rejected() {
+        let dir = fleet_dir("invalid");
+        let unknown = write_inventory(&dir, r#"
+            [[instances]]
+            name = "a"
+            host = "10.0.0.1"
+            profile = "prod"
+            overrides = { no_such_setting = 1 }
+        "#);
+        assert!(Fleet::from_inventory_file(&unknown).is_err());
+
+        let duplicated = write_inventory(&dir, r#"
+            [[instances]]
+            name = "a"
+            host = "10.0.0.1"
+            profile = "prod"
+
+            [[instances]]
+            name = "a"
+            host = "10.0.0.2"
+            profile = "prod"
+        "#);
+        assert!(Fleet::from_inventory_file(&duplicated).is_err());
+    }
+}
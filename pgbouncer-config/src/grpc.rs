@@ -0,0 +1,344 @@
+//! gRPC API for managing a configuration, mirroring the HTTP API.
+//!
+//! Enabled with the `grpc` feature. The `pgbouncer_config.ConfigService`
+//! service exposes `GetConfig`, `ApplyPatch`, `Diff`, `Generate` and `Reload`
+//! over a [`SharedPgBouncerConfig`]. Definitions, patches and diffs travel as
+//! JSON strings (the same representations the HTTP API uses), so the messages
+//! stay small and independent of the configuration model.
+//!
+//! Client and server stubs are generated at build time into
+//! [`config_service_client`] and [`config_service_server`].
+
+use std::net::SocketAddr;
+use tonic::{Request, Response, Status};
+use crate::control::{self, ServiceOptions};
+use crate::error::PgBouncerError;
+use crate::pgbouncer_config::shared::SharedPgBouncerConfig;
+use crate::utils::diff::compute_diff_pg_config;
+
+include!(concat!(env!("OUT_DIR"), "/pgbouncer_config.ConfigService.rs"));
+
+/// Request of `GetConfig`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetConfigRequest {}
+
+/// Response of `GetConfig`.
+///
+/// # Fields
+/// - definition_json: Current definition as JSON.
+/// - ini: Current configuration rendered as pgbouncer.ini.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetConfigResponse {
+    #[prost(string, tag = "1")]
+    pub definition_json: String,
+    #[prost(string, tag = "2")]
+    pub ini: String,
+}
+
+/// Request of `ApplyPatch`.
+///
+/// # Fields
+/// - patch_json: JSON merge patch (RFC 7386) applied to the current definition.
+/// - dry_run: Only compute the resulting diff without applying it.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ApplyPatchRequest {
+    #[prost(string, tag = "1")]
+    pub patch_json: String,
+    #[prost(bool, tag = "2")]
+    pub dry_run: bool,
+}
+
+/// Response of `ApplyPatch`.
+///
+/// # Fields
+/// - diff_json: Diff between the previous and the patched definition as JSON.
+/// - applied: Whether the patched definition has been applied.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ApplyPatchResponse {
+    #[prost(string, tag = "1")]
+    pub diff_json: String,
+    #[prost(bool, tag = "2")]
+    pub applied: bool,
+}
+
+/// Request of `Diff`.
+///
+/// # Fields
+/// - definition_json: Definition compared against the current one.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct DiffRequest {
+    #[prost(string, tag = "1")]
+    pub definition_json: String,
+}
+
+/// Response of `Diff`.
+///
+/// # Fields
+/// - diff_json: Diff between the current and the given definition as JSON.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct DiffResponse {
+    #[prost(string, tag = "1")]
+    pub diff_json: String,
+}
+
+/// Request of `Generate`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GenerateRequest {}
+
+/// Response of `Generate`.
+///
+/// # Fields
+/// - output: Path of the written pgbouncer.ini.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GenerateResponse {
+    #[prost(string, tag = "1")]
+    pub output: String,
+}
+
+/// Request of `Reload`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ReloadRequest {}
+
+/// Response of `Reload`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ReloadResponse {}
+
+/// Implementation of `pgbouncer_config.ConfigService`.
+///
+/// # Fields
+/// - config: Shared configuration served and updated by the service.
+/// - options: Paths and commands used by the persisting operations.
+#[derive(Debug, Clone)]
+pub struct ConfigServer {
+    config: SharedPgBouncerConfig,
+    options: ServiceOptions,
+}
+
+impl ConfigServer {
+    /// Creates the service implementation.
+    ///
+    /// # Parameters
+    /// - config: Shared configuration served and updated by the service.
+    /// - options: Paths and commands used by the persisting operations.
+    ///
+    /// # Returns
+    /// A new `ConfigServer`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+    /// use pgbouncer_config::control::ServiceOptions;
+    /// use pgbouncer_config::grpc::ConfigServer;
+    /// use pgbouncer_config::pgbouncer_config::shared::SharedPgBouncerConfig;
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::DatabasesSetting;
+    ///
+    /// let cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new())
+    ///     .unwrap()
+    ///     .build();
+    /// let _service = ConfigServer::new(SharedPgBouncerConfig::new(cfg), ServiceOptions::default())
+    ///     .into_service();
+    /// ```
+    pub fn new(config: SharedPgBouncerConfig, options: ServiceOptions) -> Self {
+        Self { config, options }
+    }
+
+    /// Wraps the implementation into a tonic service ready to be added to a server.
+    ///
+    /// # Returns
+    /// A `ConfigServiceServer` for `tonic::transport::Server::add_service`.
+    pub fn into_service(self) -> config_service_server::ConfigServiceServer<Self> {
+        config_service_server::ConfigServiceServer::new(self)
+    }
+}
+
+/// Serves the gRPC API until the process is terminated.
+///
+/// # Parameters
+/// - addr: Socket address to listen on.
+/// - config: Shared configuration served and updated by the service.
+/// - options: Paths and commands used by the persisting operations.
+///
+/// # Returns
+/// Unit when the server stops.
+///
+/// # Errors
+/// Returns an error if the address cannot be bound or serving fails.
+pub async fn serve(
+    addr: SocketAddr,
+    config: SharedPgBouncerConfig,
+    options: ServiceOptions,
+) -> crate::error::Result<()> {
+    tonic::transport::Server::builder()
+        .add_service(ConfigServer::new(config, options).into_service())
+        .serve(addr)
+        .await?;
+
+    Ok(())
+}
+
+fn to_status(e: PgBouncerError) -> Status {
+    match e {
+        PgBouncerError::PgBouncer(_)
+        | PgBouncerError::ParseError { .. }
+        | PgBouncerError::SerdeJson(_)
+        | PgBouncerError::Deserialize(_)
+        | PgBouncerError::Regex(_) => Status::invalid_argument(e.to_string()),
+        _ => Status::internal(e.to_string()),
+    }
+}
+
+#[tonic::async_trait]
+impl config_service_server::ConfigService for ConfigServer {
+    async fn get_config(
+        &self,
+        _request: Request<GetConfigRequest>,
+    ) -> Result<Response<GetConfigResponse>, Status> {
+        let config = self.config.snapshot().await;
+        let definition_json = serde_json::to_string(&config).map_err(|e| to_status(e.into()))?;
+        let ini = config.expr().map_err(to_status)?;
+
+        Ok(Response::new(GetConfigResponse { definition_json, ini }))
+    }
+
+    async fn apply_patch(
+        &self,
+        request: Request<ApplyPatchRequest>,
+    ) -> Result<Response<ApplyPatchResponse>, Status> {
+        let request = request.into_inner();
+        if request.dry_run {
+            let current = self.config.snapshot().await;
+            let new = control::apply_merge_patch(&current, &request.patch_json).map_err(to_status)?;
+            let diff = compute_diff_pg_config(&current, &new).map_err(to_status)?;
+            let diff_json = serde_json::to_string(&diff).map_err(|e| to_status(e.into()))?;
+
+            return Ok(Response::new(ApplyPatchResponse { diff_json, applied: false }));
+        }
+
+        // Patched, persisted and committed under the write lock so concurrent patches are not lost
+        let diff_json = self.config.update(|config| {
+            let new = control::apply_merge_patch(config, &request.patch_json)?;
+            let diff_json = serde_json::to_string(&compute_diff_pg_config(config, &new)?)?;
+            if let Some(definition) = &self.options.definition {
+                control::save_definition(definition, &new)?;
+            }
+            *config = new;
+
+            Ok(diff_json)
+        }).await.map_err(to_status)?;
+
+        Ok(Response::new(ApplyPatchResponse { diff_json, applied: true }))
+    }
+
+    async fn diff(
+        &self,
+        request: Request<DiffRequest>,
+    ) -> Result<Response<DiffResponse>, Status> {
+        let new = control::parse_definition(&request.into_inner().definition_json).map_err(to_status)?;
+        let current = self.config.snapshot().await;
+        let diff = compute_diff_pg_config(&current, &new).map_err(to_status)?;
+        let diff_json = serde_json::to_string(&diff).map_err(|e| to_status(e.into()))?;
+
+        Ok(Response::new(DiffResponse { diff_json }))
+    }
+
+    async fn generate(
+        &self,
+        _request: Request<GenerateRequest>,
+    ) -> Result<Response<GenerateResponse>, Status> {
+        let output = self.options.output
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition("no output path is configured"))?;
+        control::generate(output, &self.config.snapshot().await).map_err(to_status)?;
+
+        Ok(Response::new(GenerateResponse { output: output.display().to_string() }))
+    }
+
+    async fn reload(
+        &self,
+        _request: Request<ReloadRequest>,
+    ) -> Result<Response<ReloadResponse>, Status> {
+        let command = self.options.reload_command
+            .as_deref()
+            .ok_or_else(|| Status::failed_precondition("no reload command is configured"))?;
+        control::run_reload_command(command).await.map_err(to_status)?;
+
+        Ok(Response::new(ReloadResponse {}))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::config_service_server::ConfigService;
+    use crate::builder::PgBouncerConfigBuilder;
+    use crate::pgbouncer_config::databases_setting::DatabasesSetting;
+    use crate::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+
+    fn server() -> (ConfigServer, SharedPgBouncerConfig) {
+        let cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new())
+            .unwrap()
+            .build();
+        let shared = SharedPgBouncerConfig::new(cfg);
+
+        (ConfigServer::new(shared.clone(), ServiceOptions::default()), shared)
+    }
+
+    #[tokio::test]
+    async fn apply_patch_dry_run_does_not_change_the_config() {
+        let (server, shared) = server();
+        let request = ApplyPatchRequest {
+            patch_json: r#"{"pgbouncer": {"PgBouncerSetting": {"listen_port": 7432}}}"#.to_string(),
+            dry_run: true,
+        };
+
+        let response = server.apply_patch(Request::new(request.clone())).await.unwrap().into_inner();
+        assert!(!response.applied);
+        assert!(response.diff_json.contains("7432"));
+        assert!(shared.read().await.expr().unwrap().contains("listen_port = 6432"));
+
+        let request = ApplyPatchRequest { dry_run: false, ..request };
+        assert!(server.apply_patch(Request::new(request)).await.unwrap().into_inner().applied);
+        assert!(shared.read().await.expr().unwrap().contains("listen_port = 7432"));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_patches_are_all_applied() {
+        let (server, shared) = server();
+        let keys = ["server_check_delay", "server_idle_timeout", "server_lifetime", "server_connect_timeout"];
+
+        let tasks: Vec<_> = keys.iter().enumerate().map(|(i, key)| {
+            let server = server.clone();
+            let request = ApplyPatchRequest {
+                patch_json: format!(r#"{{"pgbouncer": {{"PgBouncerSetting": {{"{}": {}}}}}}}"#, key, i + 10),
+                dry_run: false,
+            };
+            tokio::spawn(async move { server.apply_patch(Request::new(request)).await })
+        }).collect();
+        for task in tasks {
+            assert!(task.await.unwrap().unwrap().into_inner().applied);
+        }
+
+        let ini = shared.read().await.expr().unwrap();
+        for (i, key) in keys.iter().enumerate() {
+            assert!(ini.contains(&format!("{} = {}", key, i + 10)), "{} is missing:\n{}", key, ini);
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_definition_maps_to_invalid_argument() {
+        let (server, _) = server();
+        let request = DiffRequest { definition_json: "{not json".to_string() };
+
+        let status = server.diff(Request::new(request)).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn generate_requires_an_output_path() {
+        let (server, _) = server();
+
+        let status = server.generate(Request::new(GenerateRequest {})).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+    }
+}
@@ -0,0 +1,253 @@
+//! docker-compose and Dockerfile snippets for local development.
+//!
+//! [`ComposeService`] renders a compose service block that runs PgBouncer
+//! with the generated pgbouncer.ini (and userlist.txt) mounted, publishing
+//! the port given by `listen_addr`/`listen_port`. The same mounts are
+//! available as a Dockerfile snippet for images that bake the files in.
+
+use crate::io::yaml::quote;
+use crate::pgbouncer_config::PgBouncerConfig;
+use crate::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+
+/// Path of pgbouncer.ini inside the container.
+pub const CONTAINER_CONFIG_PATH: &str = "/etc/pgbouncer/pgbouncer.ini";
+/// Path of userlist.txt inside the container when `auth_file` is not set.
+pub const CONTAINER_AUTH_FILE_PATH: &str = "/etc/pgbouncer/userlist.txt";
+
+/// Addresses that make PgBouncer listen on every interface.
+const WILDCARD_ADDRESSES: [&str; 3] = ["*", "0.0.0.0", "::"];
+
+/// Settings of the generated compose service.
+///
+/// # Fields
+/// - service_name: Name of the service in the compose file.
+/// - image: Image running PgBouncer with its configuration at [`CONTAINER_CONFIG_PATH`].
+/// - config_source: Host path of the generated pgbouncer.ini, relative to the compose file.
+/// - auth_file_source: Optional host path of the generated userlist.txt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComposeService {
+    service_name: String,
+    image: String,
+    config_source: String,
+    auth_file_source: Option<String>,
+}
+
+impl ComposeService {
+    /// Creates a service mounting `./pgbouncer.ini` into the `edoburu/pgbouncer` image.
+    ///
+    /// # Parameters
+    /// - service_name: Name of the service in the compose file.
+    ///
+    /// # Returns
+    /// Service settings without a mounted userlist.txt.
+    pub fn new(service_name: &str) -> Self {
+        Self {
+            service_name: service_name.to_string(),
+            image: "edoburu/pgbouncer:latest".to_string(),
+            config_source: "./pgbouncer.ini".to_string(),
+            auth_file_source: None,
+        }
+    }
+
+    /// Sets the image.
+    ///
+    /// # Parameters
+    /// - image: Image reference that reads its configuration from [`CONTAINER_CONFIG_PATH`].
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated image.
+    pub fn set_image(&mut self, image: &str) -> &mut Self {
+        self.image = image.to_string();
+        self
+    }
+
+    /// Sets the host path of pgbouncer.ini.
+    ///
+    /// # Parameters
+    /// - config_source: Path relative to the compose file (or the Docker build context).
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated path.
+    pub fn set_config_source(&mut self, config_source: &str) -> &mut Self {
+        self.config_source = config_source.to_string();
+        self
+    }
+
+    /// Sets the host path of userlist.txt.
+    ///
+    /// The file is mounted at the `auth_file` path of the [pgbouncer] section,
+    /// or at [`CONTAINER_AUTH_FILE_PATH`] when `auth_file` is not set.
+    ///
+    /// # Parameters
+    /// - auth_file_source: Path relative to the compose file, or `None` to mount no userlist.txt.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated path.
+    pub fn set_auth_file_source(&mut self, auth_file_source: Option<&str>) -> &mut Self {
+        self.auth_file_source = auth_file_source.map(ToString::to_string);
+        self
+    }
+
+    /// Renders a compose file containing the PgBouncer service.
+    ///
+    /// A wildcard `listen_addr` publishes `listen_port` on every host
+    /// interface; a specific address publishes it on that host address only.
+    ///
+    /// # Parameters
+    /// - config: Configuration providing `listen_addr`, `listen_port` and `auth_file`.
+    ///
+    /// # Returns
+    /// The compose file as YAML.
+    ///
+    /// # Errors
+    /// Returns an error if the configuration has no [pgbouncer] section.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+    /// use pgbouncer_config::io::compose::ComposeService;
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::DatabasesSetting;
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut pgbouncer_setting = PgBouncerSetting::default();
+    /// pgbouncer_setting.set_listen_addr("*");
+    /// let cfg = PgBouncerConfigBuilder::new(pgbouncer_setting, DatabasesSetting::new())
+    ///     .unwrap()
+    ///     .build();
+    /// let compose = ComposeService::new("pgbouncer").render(&cfg).unwrap();
+    /// assert!(compose.contains("    ports:\n      - \"6432:6432\"\n"));
+    /// assert!(compose.contains("      - \"./pgbouncer.ini:/etc/pgbouncer/pgbouncer.ini:ro\"\n"));
+    /// ```
+    pub fn render(&self, config: &PgBouncerConfig) -> crate::error::Result<String> {
+        let setting = config.get_config::<PgBouncerSetting>()?;
+
+        let mut compose = format!(
+            "services:\n  {}:\n    image: {}\n    ports:\n      - {}\n    volumes:\n",
+            self.service_name,
+            quote(&self.image),
+            quote(&published_port(setting)),
+        );
+        for (source, target) in self.mounts(setting) {
+            compose.push_str(&format!("      - {}\n", quote(&format!("{}:{}:ro", source, target))));
+        }
+
+        Ok(compose)
+    }
+
+    /// Renders a Dockerfile snippet copying the generated files into the image.
+    ///
+    /// # Parameters
+    /// - config: Configuration providing `listen_port` and `auth_file`.
+    ///
+    /// # Returns
+    /// The Dockerfile instructions.
+    ///
+    /// # Errors
+    /// Returns an error if the configuration has no [pgbouncer] section.
+    pub fn dockerfile(&self, config: &PgBouncerConfig) -> crate::error::Result<String> {
+        let setting = config.get_config::<PgBouncerSetting>()?;
+
+        let mut dockerfile = format!("FROM {}\n", self.image);
+        for (source, target) in self.mounts(setting) {
+            dockerfile.push_str(&format!("COPY {} {}\n", source, target));
+        }
+        dockerfile.push_str(&format!("EXPOSE {}\n", setting.listen_port()));
+
+        Ok(dockerfile)
+    }
+
+    /// Host paths and the container paths they are mounted or copied to.
+    fn mounts<'a>(&'a self, setting: &'a PgBouncerSetting) -> Vec<(&'a str, &'a str)> {
+        let mut mounts = vec![(self.config_source.as_str(), CONTAINER_CONFIG_PATH)];
+        if let Some(auth_file_source) = &self.auth_file_source {
+            let target = setting.auth_file().unwrap_or(CONTAINER_AUTH_FILE_PATH);
+            mounts.push((auth_file_source.as_str(), target));
+        }
+
+        mounts
+    }
+}
+
+/// Returns whether `listen_addr` accepts connections forwarded into the container.
+///
+/// PgBouncer must listen on every interface (`*`, `0.0.0.0` or `::`) for a
+/// published port to reach it; any other address only accepts connections
+/// from inside the container.
+pub fn listens_on_all_interfaces(setting: &PgBouncerSetting) -> bool {
+    setting
+        .listen_addr()
+        .split(',')
+        .map(str::trim)
+        .any(|address| WILDCARD_ADDRESSES.contains(&address))
+}
+
+fn published_port(setting: &PgBouncerSetting) -> String {
+    let port = setting.listen_port();
+    if listens_on_all_interfaces(setting) {
+        return format!("{}:{}", port, port);
+    }
+
+    let address = setting.listen_addr().split(',').next().unwrap_or_default().trim();
+    if address.contains(':') {
+        format!("[{}]:{}:{}", address, port, port)
+    } else {
+        format!("{}:{}:{}", address, port, port)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::PgBouncerConfigBuilder;
+    use crate::pgbouncer_config::databases_setting::DatabasesSetting;
+
+    fn config(listen_addr: &str, auth_file: Option<&str>) -> PgBouncerConfig {
+        let mut pgbouncer = PgBouncerSetting::default();
+        pgbouncer.set_listen_addr(listen_addr);
+        pgbouncer.set_listen_port(6433);
+        if let Some(auth_file) = auth_file {
+            pgbouncer.set_auth_file(auth_file);
+        }
+        PgBouncerConfigBuilder::new(pgbouncer, DatabasesSetting::new()).unwrap().build()
+    }
+
+    #[test]
+    fn render_publishes_listen_port_and_mounts_files() {
+        let mut service = ComposeService::new("pooler");
+        service
+            .set_image("edoburu/pgbouncer:v1.24.1-p1")
+            .set_config_source("./generated/pgbouncer.ini")
+            .set_auth_file_source(Some("./generated/userlist.txt"));
+
+        let compose = service.render(&config("0.0.0.0", Some("/etc/pgbouncer/users.txt"))).unwrap();
+        assert_eq!(compose, "\
+services:
+  pooler:
+    image: \"edoburu/pgbouncer:v1.24.1-p1\"
+    ports:
+      - \"6433:6433\"
+    volumes:
+      - \"./generated/pgbouncer.ini:/etc/pgbouncer/pgbouncer.ini:ro\"
+      - \"./generated/userlist.txt:/etc/pgbouncer/users.txt:ro\"
+");
+
+        let local = service.render(&config("127.0.0.1", None)).unwrap();
+        assert!(local.contains("      - \"127.0.0.1:6433:6433\"\n"));
+        assert!(local.contains("./generated/userlist.txt:/etc/pgbouncer-config/userlist.txt:ro"));
+        assert!(!listens_on_all_interfaces(config("127.0.0.1", None).get_config::<PgBouncerSetting>().unwrap()));
+    }
+
+    #[test]
+    fn dockerfile_copies_files_and_exposes_port() {
+        let dockerfile = ComposeService::new("pgbouncer")
+            .set_auth_file_source(Some("userlist.txt"))
+            .dockerfile(&config("*", None))
+            .unwrap();
+        assert_eq!(dockerfile, "\
+FROM edoburu/pgbouncer:latest
+COPY ./pgbouncer.ini /etc/pgbouncer/pgbouncer.ini
+COPY userlist.txt /etc/pgbouncer-config/userlist.txt
+EXPOSE 6433
+");
+    }
+}
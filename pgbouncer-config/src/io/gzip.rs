@@ -0,0 +1,55 @@
+//! Transparent gzip support for readers and writers (`gzip` feature).
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use flate2::Compression;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+
+/// First two bytes of every gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Returns whether `path` has a `.gz` extension.
+pub(crate) fn is_gzip_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("gz"))
+}
+
+/// Wraps `reader` in a gzip decoder when its content starts with the gzip magic bytes.
+pub(crate) fn decompress<R: Read + 'static>(reader: R) -> std::io::Result<Box<dyn Read>> {
+    let mut reader = BufReader::new(reader);
+    if reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        return Ok(Box::new(MultiGzDecoder::new(reader)));
+    }
+
+    Ok(Box::new(reader))
+}
+
+/// Wraps `writer` in a gzip encoder; the stream is finished when it is dropped.
+pub(crate) fn compress<W: Write + 'static>(writer: W) -> Box<dyn Write> {
+    Box::new(GzEncoder::new(writer, Compression::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn decompress_detects_gzip_by_content() {
+        let mut compressed = Vec::new();
+        {
+            let mut encoder = GzEncoder::new(&mut compressed, Compression::default());
+            encoder.write_all(b"[pgbouncer]\n").unwrap();
+        }
+
+        for input in [compressed, b"[pgbouncer]\n".to_vec()] {
+            let mut text = String::new();
+            decompress(Cursor::new(input)).unwrap().read_to_string(&mut text).unwrap();
+            assert_eq!(text, "[pgbouncer]\n");
+        }
+        assert!(is_gzip_path(Path::new("archive/pgbouncer.ini.GZ")));
+        assert!(!is_gzip_path(Path::new("pgbouncer.ini")));
+    }
+}
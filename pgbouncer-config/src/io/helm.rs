@@ -0,0 +1,256 @@
+//! Helm values for the Bitnami pgbouncer chart.
+//!
+//! The Bitnami pgbouncer image is configured through `PGBOUNCER_*`
+//! environment variables rather than a pgbouncer.ini file. [`HelmValues`]
+//! maps the [pgbouncer] section onto those variables (passed to the chart as
+//! `extraEnvVars`) and every [databases] entry onto a `PGBOUNCER_DSN_<n>`
+//! variable, so one definition drives both raw ini generation and chart
+//! deployments.
+
+use crate::io::yaml::quote;
+use crate::pgbouncer_config::{Expression, PgBouncerConfig};
+use crate::pgbouncer_config::databases_setting::DatabasesSetting;
+use crate::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+
+/// [pgbouncer] keys and the environment variables of the Bitnami image that set them.
+const ENV_VARS: [(&str, &str); 30] = [
+    ("listen_addr", "PGBOUNCER_BIND_ADDRESS"),
+    ("listen_port", "PGBOUNCER_PORT"),
+    ("auth_type", "PGBOUNCER_AUTH_TYPE"),
+    ("auth_user", "PGBOUNCER_AUTH_USER"),
+    ("auth_query", "PGBOUNCER_AUTH_QUERY"),
+    ("pool_mode", "PGBOUNCER_POOL_MODE"),
+    ("max_client_conn", "PGBOUNCER_MAX_CLIENT_CONN"),
+    ("max_db_connections", "PGBOUNCER_MAX_DB_CONNECTIONS"),
+    ("default_pool_size", "PGBOUNCER_DEFAULT_POOL_SIZE"),
+    ("min_pool_size", "PGBOUNCER_MIN_POOL_SIZE"),
+    ("reserve_pool_size", "PGBOUNCER_RESERVE_POOL_SIZE"),
+    ("ignore_startup_parameters", "PGBOUNCER_IGNORE_STARTUP_PARAMETERS"),
+    ("stats_users", "PGBOUNCER_STATS_USERS"),
+    ("server_reset_query", "PGBOUNCER_SERVER_RESET_QUERY"),
+    ("server_idle_timeout", "PGBOUNCER_SERVER_IDLE_TIMEOUT"),
+    ("server_lifetime", "PGBOUNCER_SERVER_LIFETIME"),
+    ("query_wait_timeout", "PGBOUNCER_QUERY_WAIT_TIMEOUT"),
+    ("client_idle_timeout", "PGBOUNCER_CLIENT_IDLE_TIMEOUT"),
+    ("idle_transaction_timeout", "PGBOUNCER_IDLE_TRANSACTION_TIMEOUT"),
+    ("client_tls_sslmode", "PGBOUNCER_CLIENT_TLS_SSLMODE"),
+    ("client_tls_ca_file", "PGBOUNCER_CLIENT_TLS_CA_FILE"),
+    ("client_tls_cert_file", "PGBOUNCER_CLIENT_TLS_CERT_FILE"),
+    ("client_tls_key_file", "PGBOUNCER_CLIENT_TLS_KEY_FILE"),
+    ("client_tls_ciphers", "PGBOUNCER_CLIENT_TLS_CIPHERS"),
+    ("server_tls_sslmode", "PGBOUNCER_SERVER_TLS_SSLMODE"),
+    ("server_tls_ca_file", "PGBOUNCER_SERVER_TLS_CA_FILE"),
+    ("server_tls_cert_file", "PGBOUNCER_SERVER_TLS_CERT_FILE"),
+    ("server_tls_key_file", "PGBOUNCER_SERVER_TLS_KEY_FILE"),
+    ("server_tls_protocols", "PGBOUNCER_SERVER_TLS_PROTOCOLS"),
+    ("server_tls_ciphers", "PGBOUNCER_SERVER_TLS_CIPHERS"),
+];
+
+/// Image and secret settings of the exported values.yaml.
+///
+/// # Fields
+/// - image_registry: Registry of the pgbouncer image.
+/// - image_repository: Repository of the pgbouncer image.
+/// - image_tag: Optional image tag; the chart default is used when unset.
+/// - extra_env_vars_secret: Optional Secret whose keys are added as
+///   environment variables, e.g. holding `POSTGRESQL_PASSWORD`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HelmValues {
+    image_registry: String,
+    image_repository: String,
+    image_tag: Option<String>,
+    extra_env_vars_secret: Option<String>,
+}
+
+impl HelmValues {
+    /// Creates values for the `docker.io/bitnami/pgbouncer` image.
+    ///
+    /// # Returns
+    /// Values using the chart's default image tag and no extra Secret.
+    pub fn new() -> Self {
+        Self {
+            image_registry: "docker.io".to_string(),
+            image_repository: "bitnami/pgbouncer".to_string(),
+            image_tag: None,
+            extra_env_vars_secret: None,
+        }
+    }
+
+    /// Sets the image tag.
+    ///
+    /// # Parameters
+    /// - image_tag: Tag to deploy, or `None` for the chart default.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated image tag.
+    pub fn set_image_tag(&mut self, image_tag: Option<&str>) -> &mut Self {
+        self.image_tag = image_tag.map(ToString::to_string);
+        self
+    }
+
+    /// Sets the Secret whose keys are added as environment variables.
+    ///
+    /// # Parameters
+    /// - secret: Name of an existing Secret, or `None` to omit `extraEnvVarsSecret`.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated Secret name.
+    pub fn set_extra_env_vars_secret(&mut self, secret: Option<&str>) -> &mut Self {
+        self.extra_env_vars_secret = secret.map(ToString::to_string);
+        self
+    }
+
+    /// Renders the values.yaml for a configuration.
+    ///
+    /// The container and service ports follow `listen_port`; every mapped
+    /// [pgbouncer] key becomes an `extraEnvVars` entry and every [databases]
+    /// line a `PGBOUNCER_DSN_<n>` entry. Credentials embedded in [databases]
+    /// lines end up in the values; keep them in the Secret given to
+    /// [`HelmValues::set_extra_env_vars_secret`] instead.
+    ///
+    /// # Parameters
+    /// - config: Configuration to export.
+    ///
+    /// # Returns
+    /// The values as a YAML document.
+    ///
+    /// # Errors
+    /// Returns an error if the configuration has no [pgbouncer] section or
+    /// the [databases] section cannot be rendered.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+    /// use pgbouncer_config::io::helm::HelmValues;
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::DatabasesSetting;
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new())
+    ///     .unwrap()
+    ///     .build();
+    /// let values = HelmValues::new().render(&cfg).unwrap();
+    /// assert!(values.contains("containerPorts:\n  pgbouncer: 6432\n"));
+    /// assert!(values.contains("  - name: PGBOUNCER_POOL_MODE\n    value: \"session\"\n"));
+    /// ```
+    pub fn render(&self, config: &PgBouncerConfig) -> crate::error::Result<String> {
+        let setting = config.get_config::<PgBouncerSetting>()?;
+
+        let mut values = String::new();
+        values.push_str(&format!(
+            "image:\n  registry: {}\n  repository: {}\n",
+            self.image_registry, self.image_repository
+        ));
+        if let Some(tag) = &self.image_tag {
+            values.push_str(&format!("  tag: {}\n", quote(tag)));
+        }
+        values.push_str(&format!("containerPorts:\n  pgbouncer: {}\n", setting.listen_port()));
+        values.push_str(&format!("service:\n  ports:\n    pgbouncer: {}\n", setting.listen_port()));
+
+        values.push_str("extraEnvVars:\n");
+        for (name, value) in env_vars(config)? {
+            values.push_str(&format!("  - name: {}\n    value: {}\n", name, quote(&value)));
+        }
+        if let Some(secret) = &self.extra_env_vars_secret {
+            values.push_str(&format!("extraEnvVarsSecret: {}\n", secret));
+        }
+
+        Ok(values)
+    }
+
+    /// Lists the [pgbouncer] keys that have no Bitnami environment variable.
+    ///
+    /// These keys are not part of the exported values; set them through the
+    /// chart's own options or mount a full pgbouncer.ini instead.
+    ///
+    /// # Parameters
+    /// - config: Configuration to export.
+    ///
+    /// # Returns
+    /// The unmapped keys in output order; empty without a [pgbouncer] section.
+    pub fn unmapped_keys(&self, config: &PgBouncerConfig) -> Vec<String> {
+        config.get_config::<PgBouncerSetting>()
+            .map(|setting| setting
+                .configured_keys()
+                .into_iter()
+                .filter(|key| !ENV_VARS.iter().any(|(mapped, _)| mapped == key))
+                .collect())
+            .unwrap_or_default()
+    }
+}
+
+impl Default for HelmValues {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Environment variables of the Bitnami image for the [pgbouncer] and [databases] sections.
+fn env_vars(config: &PgBouncerConfig) -> crate::error::Result<Vec<(String, String)>> {
+    let setting = config.get_config::<PgBouncerSetting>()?;
+    let mut env_vars = setting
+        .configured_values()
+        .into_iter()
+        .filter_map(|(key, value)| ENV_VARS
+            .iter()
+            .find(|(mapped, _)| *mapped == key)
+            .map(|(_, name)| (name.to_string(), value)))
+        .collect::<Vec<_>>();
+
+    if let Ok(databases) = config.get_config::<DatabasesSetting>() {
+        let dsns = databases
+            .expr()?
+            .lines()
+            .filter_map(|line| line.split_once(" = "))
+            .map(|(name, connection)| format!("{}={}", name, connection))
+            .collect::<Vec<_>>();
+        for (index, dsn) in dsns.into_iter().enumerate() {
+            env_vars.push((format!("PGBOUNCER_DSN_{}", index), dsn));
+        }
+    }
+
+    Ok(env_vars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::PgBouncerConfigBuilder;
+    use crate::pgbouncer_config::databases_setting::{Database, FallbackDatabase};
+
+    #[test]
+    fn render_maps_settings_and_databases() {
+        let mut pgbouncer = PgBouncerSetting::default();
+        pgbouncer.set_listen_addr("0.0.0.0");
+        pgbouncer.set_listen_port(5433);
+        let mut databases = DatabasesSetting::new();
+        databases.add_database(Database::new("10.0.0.10", 5432, "app", "secret", Some(&["app", "billing"])));
+        databases.set_fallback(Some(FallbackDatabase::new("10.0.0.20", 5432)));
+        let cfg = PgBouncerConfigBuilder::new(pgbouncer, databases).unwrap().build();
+
+        let values = HelmValues::new()
+            .set_image_tag(Some("1.24.1"))
+            .set_extra_env_vars_secret(Some("pgbouncer-credentials"))
+            .render(&cfg)
+            .unwrap();
+        assert!(values.starts_with("image:\n  registry: docker.io\n  repository: bitnami/pgbouncer\n  tag: \"1.24.1\"\n"));
+        assert!(values.contains("service:\n  ports:\n    pgbouncer: 5433\n"));
+        assert!(values.contains("  - name: PGBOUNCER_BIND_ADDRESS\n    value: \"0.0.0.0\"\n"));
+        assert!(values.contains("  - name: PGBOUNCER_PORT\n    value: \"5433\"\n"));
+        assert!(values.contains("  - name: PGBOUNCER_DSN_0\n    value: \"app=dbname=app host=10.0.0.10 port=5432\"\n"));
+        assert!(values.contains("  - name: PGBOUNCER_DSN_1\n    value: \"billing=dbname=billing host=10.0.0.10 port=5432\"\n"));
+        assert!(values.contains("  - name: PGBOUNCER_DSN_2\n    value: \"*=host=10.0.0.20 port=5432\"\n"));
+        assert!(values.ends_with("extraEnvVarsSecret: pgbouncer-credentials\n"));
+    }
+
+    #[test]
+    fn unmapped_keys_lists_settings_without_env_var() {
+        let mut pgbouncer = PgBouncerSetting::default();
+        pgbouncer.set_auth_hba_file(Some("/etc/pgbouncer/pg_hba.conf")).unwrap();
+        let cfg = PgBouncerConfigBuilder::new(pgbouncer, DatabasesSetting::new()).unwrap().build();
+
+        let unmapped = HelmValues::new().unmapped_keys(&cfg);
+        assert!(unmapped.contains(&"auth_file".to_string()));
+        assert!(unmapped.contains(&"auth_hba_file".to_string()));
+        assert!(!unmapped.contains(&"pool_mode".to_string()));
+    }
+}
@@ -0,0 +1,260 @@
+//! Kubernetes manifests wrapping the rendered configuration.
+//!
+//! [`KubernetesManifests`] puts the rendered pgbouncer.ini into a ConfigMap
+//! and, optionally, a userlist.txt into a Secret, so the output can be
+//! committed to a GitOps repository and applied with `kubectl apply -f`.
+
+use std::collections::BTreeMap;
+use crate::error::PgBouncerError;
+use crate::io::yaml::{push_block_scalar, quote};
+use crate::pgbouncer_config::PgBouncerConfig;
+use crate::pgbouncer_config::auth_file::AuthFile;
+
+/// Key of the pgbouncer.ini entry in the ConfigMap.
+pub const CONFIG_MAP_INI_KEY: &str = "pgbouncer.ini";
+/// Key of the userlist.txt entry in the Secret.
+pub const SECRET_AUTH_FILE_KEY: &str = "userlist.txt";
+
+/// Names and metadata of the generated ConfigMap/Secret manifests.
+///
+/// # Fields
+/// - config_map_name: Name of the ConfigMap holding pgbouncer.ini.
+/// - secret_name: Name of the Secret holding userlist.txt.
+/// - namespace: Namespace of both objects; omitted from the manifests when unset.
+/// - labels: Labels added to both objects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KubernetesManifests {
+    config_map_name: String,
+    secret_name: String,
+    namespace: Option<String>,
+    labels: BTreeMap<String, String>,
+}
+
+impl KubernetesManifests {
+    /// Creates manifest settings named after an application.
+    ///
+    /// # Parameters
+    /// - name: Application name; the ConfigMap is named `<name>-config` and the
+    ///   Secret `<name>-userlist`, and both get the `app.kubernetes.io/name` label.
+    ///
+    /// # Returns
+    /// Manifest settings without a namespace.
+    pub fn new(name: &str) -> Self {
+        let mut labels = BTreeMap::new();
+        labels.insert("app.kubernetes.io/name".to_string(), name.to_string());
+
+        Self {
+            config_map_name: format!("{}-config", name),
+            secret_name: format!("{}-userlist", name),
+            namespace: None,
+            labels,
+        }
+    }
+
+    /// Sets the ConfigMap name.
+    ///
+    /// # Parameters
+    /// - config_map_name: Name of the ConfigMap holding pgbouncer.ini.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated ConfigMap name.
+    pub fn set_config_map_name(&mut self, config_map_name: &str) -> &mut Self {
+        self.config_map_name = config_map_name.to_string();
+        self
+    }
+
+    /// Sets the Secret name.
+    ///
+    /// # Parameters
+    /// - secret_name: Name of the Secret holding userlist.txt.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated Secret name.
+    pub fn set_secret_name(&mut self, secret_name: &str) -> &mut Self {
+        self.secret_name = secret_name.to_string();
+        self
+    }
+
+    /// Sets the namespace of both objects.
+    ///
+    /// # Parameters
+    /// - namespace: Target namespace, or `None` to use the namespace of the `kubectl` context.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated namespace.
+    pub fn set_namespace(&mut self, namespace: Option<&str>) -> &mut Self {
+        self.namespace = namespace.map(ToString::to_string);
+        self
+    }
+
+    /// Adds a label to both objects, replacing any label with the same key.
+    ///
+    /// # Parameters
+    /// - key: Label key, e.g. `app.kubernetes.io/part-of`.
+    /// - value: Label value.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the label added.
+    pub fn add_label(&mut self, key: &str, value: &str) -> &mut Self {
+        self.labels.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Renders the ConfigMap holding pgbouncer.ini.
+    ///
+    /// # Parameters
+    /// - config: Configuration rendered into the `pgbouncer.ini` key.
+    ///
+    /// # Returns
+    /// The ConfigMap as a YAML document.
+    ///
+    /// # Errors
+    /// Returns an error if a name or the namespace is not a valid Kubernetes
+    /// object name, or the configuration cannot be rendered.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+    /// use pgbouncer_config::io::k8s::KubernetesManifests;
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::DatabasesSetting;
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new())
+    ///     .unwrap()
+    ///     .build();
+    /// let manifest = KubernetesManifests::new("pgbouncer")
+    ///     .set_namespace(Some("db"))
+    ///     .config_map(&cfg)
+    ///     .unwrap();
+    /// assert!(manifest.contains("kind: ConfigMap"));
+    /// assert!(manifest.contains("  namespace: db\n"));
+    /// assert!(manifest.contains("  pgbouncer.ini: |\n    [databases]\n"));
+    /// ```
+    pub fn config_map(&self, config: &PgBouncerConfig) -> crate::error::Result<String> {
+        let mut manifest = self.header("ConfigMap", &self.config_map_name)?;
+        manifest.push_str("data:\n");
+        push_block_scalar(&mut manifest, 2, CONFIG_MAP_INI_KEY, &config.expr()?);
+
+        Ok(manifest)
+    }
+
+    /// Renders the Secret holding userlist.txt.
+    ///
+    /// The file is stored under `stringData`, so Kubernetes base64-encodes it
+    /// on apply; encrypt the manifest (e.g. with Sealed Secrets or SOPS)
+    /// before committing it.
+    ///
+    /// # Parameters
+    /// - auth_file: Users rendered into the `userlist.txt` key.
+    ///
+    /// # Returns
+    /// The Secret as a YAML document.
+    ///
+    /// # Errors
+    /// Returns an error if a name or the namespace is not a valid Kubernetes object name.
+    pub fn secret(&self, auth_file: &AuthFile) -> crate::error::Result<String> {
+        let mut manifest = self.header("Secret", &self.secret_name)?;
+        manifest.push_str("type: Opaque\nstringData:\n");
+        push_block_scalar(&mut manifest, 2, SECRET_AUTH_FILE_KEY, &auth_file.expr());
+
+        Ok(manifest)
+    }
+
+    /// Renders the ConfigMap and, when `auth_file` is given, the Secret as one multi-document YAML stream.
+    ///
+    /// # Parameters
+    /// - config: Configuration rendered into the ConfigMap.
+    /// - auth_file: Users rendered into the Secret, or `None` to skip the Secret.
+    ///
+    /// # Returns
+    /// The manifests separated by `---`.
+    ///
+    /// # Errors
+    /// Returns an error if any manifest cannot be rendered.
+    pub fn render(&self, config: &PgBouncerConfig, auth_file: Option<&AuthFile>) -> crate::error::Result<String> {
+        let mut manifests = vec![self.config_map(config)?];
+        if let Some(auth_file) = auth_file {
+            manifests.push(self.secret(auth_file)?);
+        }
+
+        Ok(manifests.join("---\n"))
+    }
+
+    fn header(&self, kind: &str, name: &str) -> crate::error::Result<String> {
+        validate_object_name(name)?;
+        let mut header = format!("apiVersion: v1\nkind: {}\nmetadata:\n  name: {}\n", kind, name);
+        if let Some(namespace) = &self.namespace {
+            validate_object_name(namespace)?;
+            header.push_str(&format!("  namespace: {}\n", namespace));
+        }
+        if !self.labels.is_empty() {
+            header.push_str("  labels:\n");
+            for (key, value) in &self.labels {
+                header.push_str(&format!("    {}: {}\n", quote(key), quote(value)));
+            }
+        }
+
+        Ok(header)
+    }
+}
+
+/// Checks a DNS-1123 subdomain, the format of ConfigMap/Secret names and namespaces.
+fn validate_object_name(name: &str) -> crate::error::Result<()> {
+    let is_valid = !name.is_empty()
+        && name.len() <= 253
+        && name.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'.')
+        && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name.ends_with(|c: char| c.is_ascii_alphanumeric());
+    if !is_valid {
+        return Err(PgBouncerError::PgBouncer(format!(
+            "{:?} is not a valid Kubernetes name (lowercase letters, digits, '-' and '.')", name
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::PgBouncerConfigBuilder;
+    use crate::pgbouncer_config::databases_setting::{Database, DatabasesSetting};
+    use crate::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+
+    fn config() -> PgBouncerConfig {
+        let mut databases = DatabasesSetting::new();
+        databases.add_database(Database::new("10.0.0.10", 5432, "app", "secret", Some(&["app"])));
+        PgBouncerConfigBuilder::new(PgBouncerSetting::default(), databases)
+            .unwrap()
+            .build()
+    }
+
+    #[test]
+    fn render_wraps_ini_and_userlist() {
+        let mut auth_file = AuthFile::new();
+        auth_file.add_user("app", "secret");
+        let manifests = KubernetesManifests::new("pgbouncer")
+            .set_namespace(Some("db"))
+            .add_label("app.kubernetes.io/part-of", "billing")
+            .render(&config(), Some(&auth_file))
+            .unwrap();
+
+        let documents = manifests.split("---\n").collect::<Vec<_>>();
+        assert_eq!(documents.len(), 2);
+        assert!(documents[0].starts_with("apiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: pgbouncer-config\n  namespace: db\n"));
+        assert!(documents[0].contains("    \"app.kubernetes.io/part-of\": \"billing\"\n"));
+        for line in config().expr().unwrap().lines().filter(|line| !line.is_empty()) {
+            assert!(documents[0].contains(&format!("    {}\n", line)), "missing {}", line);
+        }
+        assert!(documents[1].contains("kind: Secret\n"));
+        assert!(documents[1].contains("  name: pgbouncer-userlist\n"));
+        assert!(documents[1].ends_with("stringData:\n  userlist.txt: |\n    \"app\" \"secret\"\n"));
+    }
+
+    #[test]
+    fn invalid_names_are_rejected() {
+        assert!(KubernetesManifests::new("PgBouncer").config_map(&config()).is_err());
+        assert!(KubernetesManifests::new("pgbouncer").set_namespace(Some("db_prod")).config_map(&config()).is_err());
+        assert!(KubernetesManifests::new("pgbouncer").set_secret_name("-x").secret(&AuthFile::new()).is_err());
+    }
+}
@@ -3,19 +3,150 @@
 //! This module provides generic readers and writers that operate on any
 //! `std::io::Read`/`std::io::Write` implementation, along with a simple
 //! file-format switch for serialization.
+//!
+//! [`read::Reader`] and [`write::Writer`] are synchronous and never need an
+//! async runtime; [`read::AsyncReader`] and [`write::AsyncWriter`] mirror them
+//! for tokio. The `blocking` feature adds synchronous variants of the database
+//! import APIs.
+//!
+//! With the `gzip` feature, readers decompress gzip content transparently and
+//! file writers compress files with a `.gz` extension.
 
 pub mod write;
 pub mod read;
+pub mod k8s;
+pub mod helm;
+pub mod compose;
+mod yaml;
+#[cfg(feature = "gzip")]
+mod gzip;
+
+use std::path::Path;
+use crate::error::PgBouncerError;
+use crate::utils::parser::{is_comment, INCLUDE_DIRECTIVE};
+
+/// Section names that appear in pgbouncer.ini, used to tell it apart from TOML.
+const INI_SECTIONS: [&str; 4] = ["pgbouncer", "databases", "peers", "users"];
 
 /// Configuration file formats supported by this crate when serializing/deserializing
 /// a `PgBouncerConfig` from/to text.
 ///
 /// Use this together with:
-/// - `io::read::Reader::read_config` to deserialize JSON/TOML into `PgBouncerConfig`.
-/// - `io::write::Writer::write_config` to serialize `PgBouncerConfig` into JSON/TOML.
+/// - `io::read::Reader::read_config` to deserialize JSON/TOML/INI into `PgBouncerConfig`.
+/// - `io::write::Writer::write_config` to serialize `PgBouncerConfig` into JSON/TOML/INI.
+///
+/// YAML is not supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ConfigFileFormat {
     /// TOML representation of `PgBouncerConfig`
     TOML,
     /// JSON representation of `PgBouncerConfig`
     JSON,
-}
\ No newline at end of file
+    /// PgBouncer INI (pgbouncer.ini)
+    INI,
+    /// Detected from the content when reading (see [`ConfigFileFormat::detect`]);
+    /// written as TOML, the default definition format.
+    Auto,
+}
+
+impl ConfigFileFormat {
+    /// Picks the format from a file extension.
+    ///
+    /// `.toml`, `.json` and `.ini`/`.conf` map to their formats; any other
+    /// extension (or none) gives [`ConfigFileFormat::Auto`]. A trailing `.gz`
+    /// is skipped, so `pgbouncer.ini.gz` is INI.
+    ///
+    /// # Parameters
+    /// - path: Path of the configuration or definition file.
+    ///
+    /// # Returns
+    /// The format implied by the extension.
+    ///
+    /// # Errors
+    /// Returns an error for `.yaml`/`.yml` files, which are not supported.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use std::path::Path;
+    /// use pgbouncer_config::io::ConfigFileFormat;
+    ///
+    /// assert_eq!(ConfigFileFormat::from_path(Path::new("def.json")).unwrap(), ConfigFileFormat::JSON);
+    /// assert_eq!(ConfigFileFormat::from_path(Path::new("definition")).unwrap(), ConfigFileFormat::Auto);
+    /// assert_eq!(ConfigFileFormat::from_path(Path::new("pgbouncer.ini.gz")).unwrap(), ConfigFileFormat::INI);
+    /// assert!(ConfigFileFormat::from_path(Path::new("def.yaml")).is_err());
+    /// ```
+    pub fn from_path(path: &Path) -> crate::error::Result<Self> {
+        let is_gzip = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("gz"));
+        let path = match path.file_stem() {
+            Some(stem) if is_gzip => Path::new(stem),
+            _ => path,
+        };
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase());
+
+        let format = match extension.as_deref() {
+            Some("toml") => ConfigFileFormat::TOML,
+            Some("json") => ConfigFileFormat::JSON,
+            Some("ini") | Some("conf") => ConfigFileFormat::INI,
+            Some("yaml") | Some("yml") => {
+                return Err(PgBouncerError::PgBouncer(format!(
+                    "YAML is not supported, use TOML or JSON: {}", path.display()
+                )));
+            },
+            _ => ConfigFileFormat::Auto,
+        };
+
+        Ok(format)
+    }
+
+    /// Detects the format of configuration text.
+    ///
+    /// Comments and blank lines are skipped. Text starting with `{` is JSON.
+    /// Text starting with `%include` or a plain section header such as
+    /// `[pgbouncer]` is INI; anything else, including the dotted headers of a
+    /// definition (`[pgbouncer.PgBouncerSetting]`), is TOML.
+    ///
+    /// # Parameters
+    /// - text: Content of the configuration or definition file.
+    ///
+    /// # Returns
+    /// The detected format; never [`ConfigFileFormat::Auto`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::io::ConfigFileFormat;
+    ///
+    /// assert_eq!(ConfigFileFormat::detect("{\"settings\": {}}"), ConfigFileFormat::JSON);
+    /// assert_eq!(ConfigFileFormat::detect("; managed\n[pgbouncer]\nlisten_port = 6432\n"), ConfigFileFormat::INI);
+    /// assert_eq!(ConfigFileFormat::detect("[pgbouncer.PgBouncerSetting]\nlisten_port = 6432\n"), ConfigFileFormat::TOML);
+    /// ```
+    pub fn detect(text: &str) -> Self {
+        let first_line = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !is_comment(line));
+        let Some(first_line) = first_line else {
+            return ConfigFileFormat::TOML;
+        };
+
+        if first_line.starts_with('{') {
+            return ConfigFileFormat::JSON;
+        }
+        if first_line.starts_with(INCLUDE_DIRECTIVE) {
+            return ConfigFileFormat::INI;
+        }
+
+        let section = first_line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+            .map(str::trim);
+        match section {
+            Some(section) if INI_SECTIONS.contains(&section.to_lowercase().as_str()) => ConfigFileFormat::INI,
+            _ => ConfigFileFormat::TOML,
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use tokio::io::{AsyncRead, AsyncReadExt};
 use crate::error::PgBouncerError;
 use crate::io::ConfigFileFormat;
 use crate::pgbouncer_config::PgBouncerConfig;
@@ -8,13 +9,14 @@ use crate::utils::parser::ParserIniFromStr;
 /// # Notes
 /// - This wrapper does not know about file paths; it simply reads text from
 ///   the inner reader and parses it as PgBouncer INI (via [`Reader::read`])
-///   or as JSON/TOML (via [`Reader::read_config`]).
+///   or as JSON/TOML/INI (via [`Reader::read_config`]).
 pub struct Reader<R: std::io::Read>(R);
 
 /// Input sources that can be converted into a [`Reader`].
 ///
 /// This enum is a convenience for constructing a `Reader` from common
-/// sources like standard input or a file path via `From`.
+/// sources like standard input or a file path via `From`. With the `gzip`
+/// feature, gzip content is detected by its magic bytes and decompressed.
 ///
 /// # Notes
 /// - Constructing a [`Reader`] via `From<Readers>` will panic if the file
@@ -24,6 +26,13 @@ pub enum Readers<'a> {
     Stdin,
     /// Read configuration text from a file at the given path.
     File(&'a std::path::Path),
+    /// Read configuration text from an `http://` or `https://` URL, e.g. an
+    /// object storage or config service endpoint (`url` feature).
+    ///
+    /// The body is downloaded when the [`Reader`] is created; HTTPS trusts the
+    /// platform's root certificates.
+    #[cfg(feature = "url")]
+    Url(&'a str),
 }
 
 impl <R: std::io::Read> Reader<R> {
@@ -90,14 +99,35 @@ impl <R: std::io::Read> Reader<R> {
         self.0.read_to_string(&mut text)?;
         Ok(PgBouncerConfig::parse_from_str(&text)?)
     }
+
+    /// Reads all text as PgBouncer INI, resolving `%include` directives against `base_dir`.
+    ///
+    /// [`Reader::read`] resolves includes against the current working
+    /// directory; use this method when the text comes from a file elsewhere.
+    ///
+    /// # Parameters
+    /// - base_dir: Directory used to resolve relative `%include` paths.
+    ///
+    /// # Returns
+    /// Parsed `PgBouncerConfig` on success.
+    ///
+    /// # Errors
+    /// Returns an error if reading fails, an include cannot be resolved or the
+    /// text cannot be parsed as PgBouncer INI.
+    pub fn read_with_base_dir(&mut self, base_dir: &std::path::Path) -> crate::error::Result<PgBouncerConfig> {
+        let mut text = String::new();
+        self.0.read_to_string(&mut text)?;
+        PgBouncerConfig::parse_from_str_with_base_dir(&text, base_dir)
+    }
     
-    /// Reads all text and deserializes a `PgBouncerConfig` from JSON or TOML.
+    /// Reads all text and deserializes a `PgBouncerConfig` from JSON, TOML or INI.
     ///
-    /// Use [`ConfigFileFormat::JSON`] or [`ConfigFileFormat::TOML`] to choose the
-    /// decoder. For PgBouncer INI format, use [`Reader::read`] instead.
+    /// Use [`ConfigFileFormat::JSON`], [`ConfigFileFormat::TOML`] or
+    /// [`ConfigFileFormat::INI`] to choose the decoder, or
+    /// [`ConfigFileFormat::Auto`] to detect it from the content.
     ///
     /// # Parameters
-    /// - format: Which structured format to use for deserialization.
+    /// - format: Which format to use for deserialization.
     ///
     /// # Returns
     /// Parsed `PgBouncerConfig` on success.
@@ -105,20 +135,176 @@ impl <R: std::io::Read> Reader<R> {
     /// # Errors
     /// Returns an error if reading fails or if the content cannot be
     /// deserialized from the selected format.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::io::{read::Reader, ConfigFileFormat};
+    /// use std::io::Cursor;
+    ///
+    /// let ini = "[pgbouncer]\nlisten_addr = 127.0.0.1\nlisten_port = 6432\nauth_type = md5\nmax_client_conn = 100\ndefault_pool_size = 20\npool_mode = session\n";
+    /// let cfg = Reader::new(Cursor::new(ini.as_bytes())).read_config(ConfigFileFormat::Auto).unwrap();
+    /// assert!(cfg.to_string().contains("listen_port = 6432"));
+    /// ```
     pub fn read_config(&mut self, format: ConfigFileFormat) -> crate::error::Result<PgBouncerConfig> {
         let mut text = String::new();
         self.0.read_to_string(&mut text)?;
-        
-        let file_content = match format {
-            ConfigFileFormat::JSON => {
-                serde_json::from_str::<PgBouncerConfig>(&text)?
-            },
-            ConfigFileFormat::TOML => {
-                toml::from_str::<PgBouncerConfig>(&text)?
-            }
-        };
-        
-        Ok(file_content)
+
+        parse_config(&text, format)
+    }
+}
+
+/// Asynchronous counterpart of [`Reader`] for any `tokio::io::AsyncRead`.
+///
+/// The methods mirror [`Reader`]; only reading is asynchronous, so async
+/// services can read configurations without `spawn_blocking`.
+pub struct AsyncReader<R: AsyncRead + Unpin>(R);
+
+impl<R: AsyncRead + Unpin> AsyncReader<R> {
+    /// Wraps an asynchronous reader.
+    ///
+    /// # Parameters
+    /// - reader: Any type implementing `tokio::io::AsyncRead` (e.g. `tokio::fs::File`,
+    ///   a `TcpStream`, or an in-memory `&[u8]`).
+    ///
+    /// # Returns
+    /// A new `AsyncReader` that will read from the given source.
+    pub fn new(reader: R) -> Self {
+        Self(reader)
+    }
+
+    /// Reads all text as PgBouncer INI, see [`Reader::read`].
+    ///
+    /// # Returns
+    /// Parsed `PgBouncerConfig` on success.
+    ///
+    /// # Errors
+    /// Returns an error if reading fails or the text cannot be parsed as PgBouncer INI.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::io::read::AsyncReader;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let ini = "[pgbouncer]\nlisten_addr = 127.0.0.1\nlisten_port = 6432\nauth_type = md5\nmax_client_conn = 100\ndefault_pool_size = 20\npool_mode = session\n";
+    /// let cfg = AsyncReader::new(ini.as_bytes()).read().await.unwrap();
+    /// assert!(cfg.to_string().contains("listen_port = 6432"));
+    /// # }
+    /// ```
+    pub async fn read(&mut self) -> crate::error::Result<PgBouncerConfig> {
+        let text = self.read_text().await?;
+        PgBouncerConfig::parse_from_str(&text)
+    }
+
+    /// Reads all text as PgBouncer INI, resolving `%include` directives against
+    /// `base_dir`, see [`Reader::read_with_base_dir`].
+    ///
+    /// # Parameters
+    /// - base_dir: Directory used to resolve relative `%include` paths.
+    ///
+    /// # Returns
+    /// Parsed `PgBouncerConfig` on success.
+    ///
+    /// # Errors
+    /// Returns an error if reading fails, an include cannot be resolved or the
+    /// text cannot be parsed as PgBouncer INI.
+    pub async fn read_with_base_dir(&mut self, base_dir: &std::path::Path) -> crate::error::Result<PgBouncerConfig> {
+        let text = self.read_text().await?;
+        PgBouncerConfig::parse_from_str_with_base_dir(&text, base_dir)
+    }
+
+    /// Reads all text and deserializes it from JSON, TOML or INI, see [`Reader::read_config`].
+    ///
+    /// # Parameters
+    /// - format: Which format to use for deserialization.
+    ///
+    /// # Returns
+    /// Parsed `PgBouncerConfig` on success.
+    ///
+    /// # Errors
+    /// Returns an error if reading fails or if the content cannot be
+    /// deserialized from the selected format.
+    pub async fn read_config(&mut self, format: ConfigFileFormat) -> crate::error::Result<PgBouncerConfig> {
+        let text = self.read_text().await?;
+        parse_config(&text, format)
+    }
+
+    async fn read_text(&mut self) -> crate::error::Result<String> {
+        let mut text = String::new();
+        self.0.read_to_string(&mut text).await?;
+        Ok(text)
+    }
+}
+
+/// Deserializes configuration text in `format`, detecting it for [`ConfigFileFormat::Auto`].
+fn parse_config(text: &str, format: ConfigFileFormat) -> crate::error::Result<PgBouncerConfig> {
+    let format = match format {
+        ConfigFileFormat::Auto => ConfigFileFormat::detect(text),
+        format => format,
+    };
+    let file_content = match format {
+        ConfigFileFormat::JSON => {
+            serde_json::from_str::<PgBouncerConfig>(text).map_err(json_parse_error)?
+        },
+        ConfigFileFormat::TOML | ConfigFileFormat::Auto => {
+            toml::from_str::<PgBouncerConfig>(text).map_err(|e| toml_parse_error(text, e))?
+        },
+        ConfigFileFormat::INI => {
+            PgBouncerConfig::parse_from_str(text)?
+        },
+    };
+
+    Ok(file_content)
+}
+
+/// Turns a JSON definition error into a [`PgBouncerError::ParseError`] at its line and column.
+fn json_parse_error(e: serde_json::Error) -> PgBouncerError {
+    if e.line() == 0 {
+        return e.into();
+    }
+
+    let message = e.to_string();
+    let suffix = format!(" at line {} column {}", e.line(), e.column());
+    PgBouncerError::ParseError {
+        section: None,
+        key: None,
+        line: Some(e.line()),
+        column: Some(e.column()),
+        reason: message.strip_suffix(&suffix).unwrap_or(&message).to_string(),
+    }
+}
+
+/// Turns a TOML definition error into a [`PgBouncerError::ParseError`].
+///
+/// The section is the first part of the closest table header above the
+/// error (`[pgbouncer.PgBouncerSetting]` is `pgbouncer`), and the key the
+/// one assigned on the line of the error, if any.
+fn toml_parse_error(text: &str, e: toml::de::Error) -> PgBouncerError {
+    let Some(span) = e.span() else {
+        return e.into();
+    };
+
+    let before = &text[..span.start.min(text.len())];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line_text = text[line_start..].lines().next().unwrap_or_default();
+    let section = text[..line_start + line_text.len()]
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|line| line.starts_with('['))
+        .and_then(|header| header.trim_matches(|c| c == '[' || c == ']').split('.').next())
+        .map(|section| section.trim().trim_matches('"').to_string());
+    let key = line_text
+        .split_once('=')
+        .map(|(key, _)| key.trim().trim_matches('"').to_string())
+        .filter(|key| !key.starts_with('['));
+
+    PgBouncerError::ParseError {
+        section,
+        key,
+        line: Some(before.matches('\n').count() + 1),
+        column: Some(before[line_start..].chars().count() + 1),
+        reason: e.message().to_string(),
     }
 }
 
@@ -126,16 +312,50 @@ impl<'a> TryFrom<Readers<'a>> for Reader<Box<dyn std::io::Read>> {
     type Error = PgBouncerError;
 
     fn try_from(value: Readers<'a>) -> Result<Self, Self::Error> {
-        match value {
-            Readers::Stdin => Ok(Self::new(Box::new(std::io::stdin()))),
+        let reader: Box<dyn std::io::Read> = match value {
+            Readers::Stdin => Box::new(std::io::stdin()),
             Readers::File(path) => {
                 if !path.exists() {
                     return Err(PgBouncerError::PgBouncer(format!("File not found: {}", path.display())));
                 }
-                Ok(Self::new(Box::new(std::fs::File::open(path)?)))
-            }
-        }
+                Box::new(std::fs::File::open(path)?)
+            },
+            #[cfg(feature = "url")]
+            Readers::Url(url) => Box::new(std::io::Cursor::new(fetch_url(url)?)),
+        };
+
+        #[cfg(feature = "gzip")]
+        let reader = crate::io::gzip::decompress(reader)?;
+        Ok(Self::new(reader))
+    }
+}
+
+/// Downloads the body of an `http://` or `https://` URL.
+#[cfg(feature = "url")]
+fn fetch_url(url: &str) -> crate::error::Result<Vec<u8>> {
+    if !is_url(url) {
+        return Err(PgBouncerError::PgBouncer(format!("Only http:// and https:// URLs can be read: {}", url)));
     }
+
+    crate::utils::http::build_agent(Vec::new())
+        .get(url)
+        .call()
+        .and_then(|mut response| response.body_mut().read_to_vec())
+        .map_err(|e| PgBouncerError::PgBouncer(format!("Failed to read {}: {}", url, e)))
+}
+
+/// Returns whether `location` is an `http://` or `https://` URL rather than a path.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::io::read::is_url;
+///
+/// assert!(is_url("https://config.example.com/pgbouncer.toml"));
+/// assert!(!is_url("/etc/pgbouncer/pgbouncer.ini"));
+/// ```
+pub fn is_url(location: &str) -> bool {
+    let lower = location.to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
 }
 
 
@@ -186,4 +406,70 @@ pool_mode = session\n\
         let cfg_toml = reader_toml.read_config(ConfigFileFormat::TOML).expect("from toml");
         assert_eq!(toml::to_string(&cfg).unwrap(), toml::to_string(&cfg_toml).unwrap());
     }
+
+    #[test]
+    fn definition_errors_carry_their_line_and_column() {
+        let cfg = Reader::new(Cursor::new(minimal_ini().as_bytes())).read().expect("parse ini");
+
+        let toml_s = toml::to_string_pretty(&cfg).expect("to toml").replace("listen_port = 6432", "listen_port = \"many\"");
+        let err = Reader::new(Cursor::new(toml_s.as_bytes())).read_config(ConfigFileFormat::TOML).unwrap_err();
+        assert!(matches!(err, PgBouncerError::ParseError { line: Some(_), column: Some(_), .. }), "{}", err);
+
+        let json = serde_json::to_string_pretty(&cfg).expect("to json").replace("6432", "\"many\"");
+        let err = Reader::new(Cursor::new(json.as_bytes())).read_config(ConfigFileFormat::JSON).unwrap_err();
+        assert!(matches!(err, PgBouncerError::ParseError { line: Some(_), column: Some(_), .. }), "{}", err);
+    }
+
+    #[cfg(feature = "url")]
+    #[test]
+    fn reader_from_url_reads_the_response_body() {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let url = format!("http://{}/pgbouncer.ini", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let body = minimal_ini();
+            write!(reader.get_mut(), "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body).unwrap();
+        });
+
+        let cfg = Reader::try_from(Readers::Url(&url)).expect("fetch").read().expect("parse ini");
+        assert!(cfg.to_string().contains("listen_port = 6432"));
+        server.join().unwrap();
+
+        assert!(Reader::try_from(Readers::Url("ftp://example.com/pgbouncer.ini")).is_err());
+    }
+
+    #[tokio::test]
+    async fn async_reader_matches_reader() {
+        let cfg = Reader::new(Cursor::new(minimal_ini().as_bytes())).read().expect("parse ini");
+        let json = serde_json::to_string_pretty(&cfg).expect("to json");
+
+        let from_ini = AsyncReader::new(minimal_ini().as_bytes()).read().await.expect("async ini");
+        let from_json = AsyncReader::new(json.as_bytes()).read_config(ConfigFileFormat::Auto).await.expect("async json");
+        assert_eq!(from_ini.to_string(), cfg.to_string());
+        assert_eq!(from_json.to_string(), cfg.to_string());
+    }
+
+    #[test]
+    fn reader_read_config_auto_detects_each_format() {
+        let cfg = Reader::new(Cursor::new(minimal_ini().as_bytes())).read().expect("parse ini");
+        let expected = cfg.to_string();
+
+        for text in [
+            minimal_ini(),
+            serde_json::to_string_pretty(&cfg).expect("to json"),
+            format!("# definition\n{}", toml::to_string_pretty(&cfg).expect("to toml")),
+        ] {
+            let detected = Reader::new(Cursor::new(text.as_bytes()))
+                .read_config(ConfigFileFormat::Auto)
+                .expect("auto");
+            assert_eq!(detected.to_string(), expected);
+        }
+    }
 }
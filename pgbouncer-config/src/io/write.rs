@@ -1,13 +1,20 @@
 use std::fs::create_dir_all;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use crate::error::PgBouncerError;
 use crate::io::ConfigFileFormat;
 use crate::pgbouncer_config::{PgBouncerConfig};
+use crate::pgbouncer_config::render::RenderOptions;
+use crate::pgbouncer_config::auth_file::AuthFile;
+use crate::pgbouncer_config::hba_file::HbaFile;
+use crate::io::k8s::KubernetesManifests;
+use crate::utils::parser::INCLUDE_DIRECTIVE;
 
 /// Generic writer for emitting a `PgBouncerConfig` to any `std::io::Write`.
 ///
 /// # Notes
 /// - This wrapper does not own any path. It writes configuration text to the
-///   inner writer as PgBouncer INI (via [`Writer::write`]) or as JSON/TOML
+///   inner writer as PgBouncer INI (via [`Writer::write`]) or as JSON/TOML/INI
 ///   (via [`Writer::write_config`]).
 pub struct Writer<W: std::io::Write>(W);
 
@@ -23,9 +30,65 @@ pub enum Writers<'a> {
     /// Write configuration text to standard output (stdout).
     Stdout,
     /// Write configuration text to a file at the given path (create/truncate).
+    /// With the `gzip` feature, a path ending in `.gz` is written gzip-compressed.
     File(&'a Path),
     /// Write configuration text to standard error (stderr).
     Stderr,
+    /// Write configuration text to a file at the given path (create/truncate),
+    /// first keeping the previous content as `<path>.bak.1`, see [`rotate_backups`].
+    /// The second field is the number of backups to keep.
+    FileWithBackups(&'a Path, usize),
+}
+
+/// Keeps the current content of `path` as `<path>.bak.1` before it is overwritten.
+///
+/// Existing backups are shifted (`.bak.1` becomes `.bak.2`, ...) and the ones
+/// beyond `keep` are deleted, so `.bak.1` is always the most recent.
+///
+/// # Parameters
+/// - path: File about to be overwritten.
+/// - keep: Number of backups to keep; 0 disables backups.
+///
+/// # Returns
+/// The path of the new backup, or `None` when `path` does not exist or `keep` is 0.
+///
+/// # Errors
+/// Returns an error if a backup cannot be renamed, deleted or copied.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::io::write::rotate_backups;
+///
+/// let path = std::env::temp_dir().join("pgbouncer-config-rotate-doc.ini");
+/// std::fs::write(&path, "[pgbouncer]\n").unwrap();
+/// let backup = rotate_backups(&path, 3).unwrap().unwrap();
+/// assert!(backup.display().to_string().ends_with("pgbouncer-config-rotate-doc.ini.bak.1"));
+/// ```
+pub fn rotate_backups(path: &Path, keep: usize) -> crate::error::Result<Option<PathBuf>> {
+    if keep == 0 || !path.exists() {
+        return Ok(None);
+    }
+
+    let backup = |n: usize| {
+        let mut name = path.as_os_str().to_os_string();
+        name.push(format!(".bak.{}", n));
+        PathBuf::from(name)
+    };
+
+    let oldest = backup(keep);
+    if oldest.exists() {
+        std::fs::remove_file(&oldest)?;
+    }
+    for n in (1..keep).rev() {
+        let from = backup(n);
+        if from.exists() {
+            std::fs::rename(&from, backup(n + 1))?;
+        }
+    }
+
+    let latest = backup(1);
+    std::fs::copy(path, &latest)?;
+    Ok(Some(latest))
 }
 
 impl<W: std::io::Write> Writer<W> {
@@ -85,13 +148,183 @@ impl<W: std::io::Write> Writer<W> {
     /// assert!(!buf.is_empty());
     /// ```
     pub fn write(&mut self, config: &PgBouncerConfig) -> crate::error::Result<()> {
-        writeln!(self.0, "{}", config.expr()?)?;
+        config.expr_to(&mut self.0)?;
+        writeln!(self.0)?;
+        Ok(())
+    }
+
+    /// Writes the configuration in PgBouncer INI format with the given layout options.
+    ///
+    /// # Parameters
+    /// - config: Configuration to be written.
+    /// - options: Key order, `=` alignment, defaults, spacing, provenance header and
+    ///   checksum comment of the output.
+    ///
+    /// # Returns
+    /// Unit on success.
+    ///
+    /// # Errors
+    /// Returns an error if a section cannot be rendered or writing fails.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+    /// use pgbouncer_config::io::write::Writer;
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::DatabasesSetting;
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    /// use pgbouncer_config::pgbouncer_config::render::{KeyOrder, RenderOptions};
+    ///
+    /// let cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new())
+    ///     .unwrap()
+    ///     .build();
+    /// let mut options = RenderOptions::new();
+    /// options.set_key_order(KeyOrder::Alphabetical);
+    /// let mut buf: Vec<u8> = Vec::new();
+    /// Writer::new(&mut buf).write_with_options(&cfg, &options).unwrap();
+    /// assert!(String::from_utf8(buf).unwrap().contains("[pgbouncer]\nauth_file = "));
+    /// ```
+    pub fn write_with_options(&mut self, config: &PgBouncerConfig, options: &RenderOptions) -> crate::error::Result<()> {
+        self.0.write_all(config.expr_with(options)?.as_bytes())?;
+        writeln!(self.0)?;
         Ok(())
     }
 
-    /// Writes the configuration serialized as JSON or TOML.
+    /// Writes the configuration in PgBouncer INI format, moving selected sections
+    /// into separate files referenced with `%include`.
+    ///
+    /// Every section listed in `includes` is rendered into its file (parent
+    /// directories are created) and replaced by an `%include <path>` line in the
+    /// main output. The paths are written as given, so relative paths are
+    /// resolved by PgBouncer against its working directory.
+    ///
+    /// # Parameters
+    /// - config: Configuration to be written.
+    /// - includes: Pairs of section name (e.g. `databases`) and the file receiving it.
+    ///
+    /// # Returns
+    /// Unit on success.
+    ///
+    /// # Errors
+    /// Returns an error if a listed section does not exist in `config`, or if
+    /// writing the main output or an included file fails.
     ///
-    /// Select the output format via [`ConfigFileFormat`].
+    /// # Examples
+    /// ```rust,no_run
+    /// use std::path::Path;
+    /// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+    /// use pgbouncer_config::io::write::Writer;
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::DatabasesSetting;
+    ///
+    /// let cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new())
+    ///     .unwrap()
+    ///     .build();
+    /// let mut buf: Vec<u8> = Vec::new();
+    /// Writer::new(&mut buf)
+    ///     .write_with_includes(&cfg, &[("databases", Path::new("/etc/pgbouncer/databases.ini"))])
+    ///     .unwrap();
+    /// assert!(String::from_utf8(buf).unwrap().contains("%include /etc/pgbouncer/databases.ini"));
+    /// ```
+    pub fn write_with_includes(
+        &mut self,
+        config: &PgBouncerConfig,
+        includes: &[(&str, &Path)],
+    ) -> crate::error::Result<()> {
+        let (text, included) = render_with_includes(config, includes)?;
+        for (path, expr) in included {
+            if let Some(parent) = path.parent() {
+                create_dir_all(parent)?;
+            }
+            std::fs::write(path, expr)?;
+        }
+
+        self.0.write_all(text.as_bytes())?;
+        Ok(())
+    }
+
+    /// Writes an auth file (userlist.txt).
+    ///
+    /// # Parameters
+    /// - auth_file: Users and passwords to be written.
+    ///
+    /// # Returns
+    /// Unit on success.
+    ///
+    /// # Errors
+    /// Returns an error if writing to the underlying writer fails.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::io::write::Writer;
+    /// use pgbouncer_config::pgbouncer_config::auth_file::AuthFile;
+    ///
+    /// let mut auth_file = AuthFile::new();
+    /// auth_file.add_user("app", "secret");
+    /// let mut buf: Vec<u8> = Vec::new();
+    /// Writer::new(&mut buf).write_auth_file(&auth_file).unwrap();
+    /// assert_eq!(String::from_utf8(buf).unwrap(), "\"app\" \"secret\"\n");
+    /// ```
+    pub fn write_auth_file(&mut self, auth_file: &AuthFile) -> crate::error::Result<()> {
+        self.0.write_all(auth_file.expr().as_bytes())?;
+        Ok(())
+    }
+
+    /// Writes an HBA file (pg_hba.conf).
+    ///
+    /// # Parameters
+    /// - hba_file: Rules to be written, in evaluation order.
+    ///
+    /// # Returns
+    /// Unit on success.
+    ///
+    /// # Errors
+    /// Returns an error if writing to the underlying writer fails.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::io::write::Writer;
+    /// use pgbouncer_config::pgbouncer_config::hba_file::{HbaConnectionType, HbaFile, HbaMethod, HbaRule};
+    ///
+    /// let mut hba_file = HbaFile::new();
+    /// hba_file.add_rule(HbaRule::new(HbaConnectionType::Local, &["all"], &["all"], None, HbaMethod::Peer));
+    /// let mut buf: Vec<u8> = Vec::new();
+    /// Writer::new(&mut buf).write_hba_file(&hba_file).unwrap();
+    /// assert_eq!(String::from_utf8(buf).unwrap(), "local all all peer\n");
+    /// ```
+    pub fn write_hba_file(&mut self, hba_file: &HbaFile) -> crate::error::Result<()> {
+        self.0.write_all(hba_file.expr().as_bytes())?;
+        Ok(())
+    }
+
+    /// Writes the configuration as Kubernetes manifests.
+    ///
+    /// Emits a ConfigMap holding pgbouncer.ini and, when `auth_file` is given,
+    /// a Secret holding userlist.txt (see [`KubernetesManifests::render`]).
+    ///
+    /// # Parameters
+    /// - config: Configuration rendered into the ConfigMap.
+    /// - auth_file: Users rendered into the Secret, or `None` to skip the Secret.
+    /// - manifests: Names, namespace and labels of the objects.
+    ///
+    /// # Returns
+    /// Unit on success.
+    ///
+    /// # Errors
+    /// Returns an error if a manifest cannot be rendered or writing fails.
+    pub fn write_kubernetes(
+        &mut self,
+        config: &PgBouncerConfig,
+        auth_file: Option<&AuthFile>,
+        manifests: &KubernetesManifests,
+    ) -> crate::error::Result<()> {
+        self.0.write_all(manifests.render(config, auth_file)?.as_bytes())?;
+        Ok(())
+    }
+
+    /// Writes the configuration serialized as JSON, TOML or INI.
+    ///
+    /// Select the output format via [`ConfigFileFormat`]; [`ConfigFileFormat::Auto`]
+    /// writes TOML.
     ///
     /// # Parameters
     /// - config: Configuration to be serialized.
@@ -125,20 +358,211 @@ impl<W: std::io::Write> Writer<W> {
     /// assert!(String::from_utf8(buf).unwrap().contains("PgBouncerSetting"));
     /// ```
     pub fn write_config(&mut self, config: &PgBouncerConfig, format: ConfigFileFormat) -> crate::error::Result<()> {
-        let file_content = match format {
-            ConfigFileFormat::JSON => {
-                serde_json::to_string_pretty(config)?
-            },
-            ConfigFileFormat::TOML => {
-                toml::to_string_pretty(config)?
+        if format == ConfigFileFormat::INI {
+            return self.write(config);
+        }
+
+        self.0.write_all(render_config(config, format)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Asynchronous counterpart of [`Writer`] for any `tokio::io::AsyncWrite`.
+///
+/// The methods mirror [`Writer`] and flush the inner writer once the output
+/// is written, so async services can write configurations without `spawn_blocking`.
+pub struct AsyncWriter<W: AsyncWrite + Unpin>(W);
+
+impl<W: AsyncWrite + Unpin> AsyncWriter<W> {
+    /// Wraps an asynchronous writer.
+    ///
+    /// # Parameters
+    /// - writer: Any type implementing `tokio::io::AsyncWrite` (e.g. `tokio::fs::File`,
+    ///   `tokio::io::Stdout`, or an in-memory `Vec<u8>`).
+    ///
+    /// # Returns
+    /// A new `AsyncWriter` that will write to the given destination.
+    pub fn new(writer: W) -> Self {
+        Self(writer)
+    }
+
+    /// Writes the configuration in PgBouncer INI format, see [`Writer::write`].
+    ///
+    /// # Parameters
+    /// - config: Configuration to be written.
+    ///
+    /// # Returns
+    /// Unit on success.
+    ///
+    /// # Errors
+    /// Returns an error if a section cannot be rendered or writing fails.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+    /// use pgbouncer_config::io::write::AsyncWriter;
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::DatabasesSetting;
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new())
+    ///     .unwrap()
+    ///     .build();
+    /// let mut buf: Vec<u8> = Vec::new();
+    /// AsyncWriter::new(&mut buf).write(&cfg).await.unwrap();
+    /// assert!(String::from_utf8(buf).unwrap().contains("[pgbouncer]"));
+    /// # }
+    /// ```
+    pub async fn write(&mut self, config: &PgBouncerConfig) -> crate::error::Result<()> {
+        self.write_text(&render_config(config, ConfigFileFormat::INI)?).await
+    }
+
+    /// Writes the configuration in PgBouncer INI format, moving selected sections
+    /// into separate files referenced with `%include`, see [`Writer::write_with_includes`].
+    ///
+    /// # Parameters
+    /// - config: Configuration to be written.
+    /// - includes: Pairs of section name (e.g. `databases`) and the file receiving it.
+    ///
+    /// # Returns
+    /// Unit on success.
+    ///
+    /// # Errors
+    /// Returns an error if a listed section does not exist in `config`, or if
+    /// writing the main output or an included file fails.
+    pub async fn write_with_includes(
+        &mut self,
+        config: &PgBouncerConfig,
+        includes: &[(&str, &Path)],
+    ) -> crate::error::Result<()> {
+        let (text, included) = render_with_includes(config, includes)?;
+        for (path, expr) in included {
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
             }
-        };
+            tokio::fs::write(path, expr).await?;
+        }
+
+        self.write_text(&text).await
+    }
+
+    /// Writes an auth file (userlist.txt), see [`Writer::write_auth_file`].
+    ///
+    /// # Parameters
+    /// - auth_file: Users and passwords to be written.
+    ///
+    /// # Returns
+    /// Unit on success.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    pub async fn write_auth_file(&mut self, auth_file: &AuthFile) -> crate::error::Result<()> {
+        self.write_text(&auth_file.expr()).await
+    }
+
+    /// Writes an HBA file (pg_hba.conf), see [`Writer::write_hba_file`].
+    ///
+    /// # Parameters
+    /// - hba_file: Rules to be written, in evaluation order.
+    ///
+    /// # Returns
+    /// Unit on success.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    pub async fn write_hba_file(&mut self, hba_file: &HbaFile) -> crate::error::Result<()> {
+        self.write_text(&hba_file.expr()).await
+    }
 
-        writeln!(self.0, "{}", file_content)?;
+    /// Writes the configuration as Kubernetes manifests, see [`Writer::write_kubernetes`].
+    ///
+    /// # Parameters
+    /// - config: Configuration rendered into the ConfigMap.
+    /// - auth_file: Users rendered into the Secret, or `None` to skip the Secret.
+    /// - manifests: Names, namespace and labels of the objects.
+    ///
+    /// # Returns
+    /// Unit on success.
+    ///
+    /// # Errors
+    /// Returns an error if a manifest cannot be rendered or writing fails.
+    pub async fn write_kubernetes(
+        &mut self,
+        config: &PgBouncerConfig,
+        auth_file: Option<&AuthFile>,
+        manifests: &KubernetesManifests,
+    ) -> crate::error::Result<()> {
+        self.write_text(&manifests.render(config, auth_file)?).await
+    }
+
+    /// Writes the configuration serialized as JSON, TOML or INI, see [`Writer::write_config`].
+    ///
+    /// # Parameters
+    /// - config: Configuration to be serialized.
+    /// - format: Target serialization format.
+    ///
+    /// # Returns
+    /// Unit on success.
+    ///
+    /// # Errors
+    /// Returns an error if serialization fails or if writing fails.
+    pub async fn write_config(&mut self, config: &PgBouncerConfig, format: ConfigFileFormat) -> crate::error::Result<()> {
+        self.write_text(&render_config(config, format)?).await
+    }
+
+    async fn write_text(&mut self, text: &str) -> crate::error::Result<()> {
+        self.0.write_all(text.as_bytes()).await?;
+        self.0.flush().await?;
         Ok(())
     }
 }
 
+/// Renders the configuration as written by [`Writer::write_config`].
+fn render_config(config: &PgBouncerConfig, format: ConfigFileFormat) -> crate::error::Result<String> {
+    let file_content = match format {
+        ConfigFileFormat::JSON => {
+            serde_json::to_string_pretty(config)?
+        },
+        ConfigFileFormat::TOML | ConfigFileFormat::Auto => {
+            toml::to_string_pretty(config)?
+        },
+        ConfigFileFormat::INI => {
+            config.expr()?
+        },
+    };
+
+    Ok(format!("{}\n", file_content))
+}
+
+/// Renders the main output of [`Writer::write_with_includes`] together with
+/// the content of every included file.
+fn render_with_includes<'p>(
+    config: &PgBouncerConfig,
+    includes: &[(&str, &'p Path)],
+) -> crate::error::Result<(String, Vec<(&'p Path, String)>)> {
+    if let Some((section, _)) = includes.iter().find(|(section, _)| !config.settings.contains_key(*section)) {
+        return Err(PgBouncerError::PgBouncer(format!("section {} does not exist", section)));
+    }
+
+    let mut text = String::new();
+    let mut included = Vec::new();
+    for (section_name, setting) in &config.settings {
+        let expr = setting.expr()?;
+        match includes.iter().find(|(section, _)| section == section_name) {
+            Some((_, path)) => {
+                text.push_str(&format!("{} {}\n", INCLUDE_DIRECTIVE, path.display()));
+                included.push((*path, expr));
+            },
+            None => text.push_str(&expr),
+        }
+        text.push('\n');
+    }
+    text.push('\n');
+
+    Ok((text, included))
+}
+
 impl<'a> TryFrom<Writers<'a>> for Writer<Box<dyn std::io::Write>> {
     type Error = crate::error::PgBouncerError;
 
@@ -152,8 +576,16 @@ impl<'a> TryFrom<Writers<'a>> for Writer<Box<dyn std::io::Write>> {
 
                 }
                 let file = std::fs::File::create(path)?;
+                #[cfg(feature = "gzip")]
+                if crate::io::gzip::is_gzip_path(path) {
+                    return Ok(Self::new(crate::io::gzip::compress(file)));
+                }
                 Ok(Self::new(Box::new(file)))
             },
+            Writers::FileWithBackups(path, keep) => {
+                rotate_backups(path, keep)?;
+                Self::try_from(Writers::File(path))
+            },
         }
     }
 }
@@ -215,4 +647,78 @@ pool_mode = session\n\
         let cfg_toml: crate::pgbouncer_config::PgBouncerConfig = toml::from_str(&out_toml).expect("valid toml");
         assert_eq!(toml::to_string(&cfg).unwrap(), toml::to_string(&cfg_toml).unwrap());
     }
+
+    #[test]
+    fn rotate_backups_keeps_the_most_recent_files() {
+        use std::io::Write;
+
+        let dir = std::env::temp_dir().join(format!("pgbouncer-config-write-backups-{}", std::process::id()));
+        let path = dir.join("pgbouncer.ini");
+        let read_backup = |n: usize| std::fs::read_to_string(dir.join(format!("pgbouncer.ini.bak.{}", n))).ok();
+        assert_eq!(rotate_backups(&path, 2).expect("missing file"), None);
+
+        for version in 1..=4 {
+            let mut writer = Writer::try_from(Writers::FileWithBackups(&path, 2)).expect("open");
+            writer.0.write_all(format!("version {}\n", version).as_bytes()).expect("write");
+        }
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "version 4\n");
+        assert_eq!(read_backup(1).as_deref(), Some("version 3\n"));
+        assert_eq!(read_backup(2).as_deref(), Some("version 2\n"));
+        assert_eq!(read_backup(3), None);
+
+        assert_eq!(rotate_backups(&path, 0).expect("disabled"), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn writer_compresses_gz_files_and_reader_detects_them() {
+        let cfg = crate::io::read::Reader::new(Cursor::new(minimal_ini().as_bytes())).read().expect("parse ini");
+        let path = std::env::temp_dir().join(format!("pgbouncer-config-write-gzip-{}.ini.gz", std::process::id()));
+
+        Writer::try_from(Writers::File(&path)).expect("create").write(&cfg).expect("write ini");
+        assert_eq!(std::fs::read(&path).unwrap()[..2], [0x1f, 0x8b]);
+
+        let reread = crate::io::read::Reader::try_from(crate::io::read::Readers::File(&path))
+            .expect("open")
+            .read_config(ConfigFileFormat::from_path(&path).unwrap())
+            .expect("parse gzip");
+        assert_eq!(reread.expr().unwrap(), cfg.expr().unwrap());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn async_writer_matches_writer() {
+        let cfg = crate::io::read::Reader::new(Cursor::new(minimal_ini().as_bytes())).read().expect("parse ini");
+
+        for format in [ConfigFileFormat::INI, ConfigFileFormat::JSON, ConfigFileFormat::TOML] {
+            let mut expected: Vec<u8> = Vec::new();
+            Writer::new(&mut expected).write_config(&cfg, format).expect("write");
+            let mut actual: Vec<u8> = Vec::new();
+            AsyncWriter::new(&mut actual).write_config(&cfg, format).await.expect("async write");
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn writer_write_with_includes_round_trips() {
+        let ini = format!("{}[databases]\napp = dbname=app host=127.0.0.1 port=5432\n", minimal_ini());
+        let cfg = crate::io::read::Reader::new(Cursor::new(ini.as_bytes())).read().expect("parse ini");
+
+        let dir = std::env::temp_dir().join(format!("pgbouncer-config-write-include-{}", std::process::id()));
+        let include = dir.join("databases.ini");
+        let mut buf: Vec<u8> = Vec::new();
+        Writer::new(&mut buf).write_with_includes(&cfg, &[("databases", &include)]).expect("write ini");
+        let text = String::from_utf8(buf).expect("utf8");
+        assert!(text.contains(&format!("%include {}", include.display())));
+        assert!(!text.contains("dbname=app"));
+
+        let reparsed = crate::io::read::Reader::new(Cursor::new(text.as_bytes()))
+            .read_with_base_dir(&dir)
+            .expect("parse with includes");
+        assert_eq!(reparsed.expr().unwrap(), cfg.expr().unwrap());
+
+        let mut buf: Vec<u8> = Vec::new();
+        assert!(Writer::new(&mut buf).write_with_includes(&cfg, &[("peers", &include)]).is_err());
+    }
 }
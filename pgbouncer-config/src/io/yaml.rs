@@ -0,0 +1,26 @@
+//! Minimal YAML emitting helpers shared by the manifest exporters.
+
+/// Appends `key: |` at `indent` spaces followed by `text` as a YAML literal block.
+pub(crate) fn push_block_scalar(out: &mut String, indent: usize, key: &str, text: &str) {
+    // An indentation indicator is required when the first line starts with a space.
+    let indicator = if text.starts_with(' ') { "2" } else { "" };
+    out.push_str(&format!("{:indent$}{}: |{}\n", "", key, indicator, indent = indent));
+    for line in text.lines() {
+        if line.is_empty() {
+            out.push('\n');
+        } else {
+            out.push_str(&format!("{:indent$}{}\n", "", line, indent = indent + 2));
+        }
+    }
+}
+
+/// Quotes a string as a YAML double-quoted scalar.
+pub(crate) fn quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t");
+
+    format!("\"{}\"", escaped)
+}
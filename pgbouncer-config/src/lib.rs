@@ -11,6 +11,15 @@
 //!   formatted TOML/JSON
 //! - **Import databases from PostgreSQL host** - Import all databases from Postgres host
 //! - **Return difference between two config/setting** - Retrieves the difference between 2 configs/settings
+//! - **Golden-snapshot test helpers** - Compare rendered configs with fixture files (`testing` feature)
+//! - **HTTP API** - Serve, validate and update a config over HTTP (`server` feature)
+//! - **gRPC API** - The same operations as a tonic service (`grpc` feature)
+//! - **Fleet management** - Render and compare configs for many instances from one inventory (`fleet` feature)
+//! - **Admin console client** - Read `SHOW` results from a running PgBouncer (`admin` feature)
+//! - **Secret references** - Resolve `env://` and `vault://` passwords when rendering (`vault` feature for Vault)
+//! - **Remote definitions** - Read definitions and pgbouncer.ini files from HTTP(S) URLs (`url` feature)
+//! - **Definition schema** - JSON Schema of the TOML/JSON definition format for editors (`schema` feature)
+//! - **Rich diagnostics** - Render parse and validation errors with source snippets and hints (`diagnostics` feature)
 //!
 //! ## Quick Start
 //! Add this crate to your `Cargo.toml`:
@@ -27,8 +36,7 @@
 //! use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
 //! use pgbouncer_config::pgbouncer_config::{PgBouncerConfig, Expression};
 //!
-//! #[tokio::main]
-//! async fn main() {
+//! fn main() {
 //!     let mut db_setting = DatabasesSetting::new();
 //!     let mut db = Database::new("localhost", 5432, "postgres", "postgres", None);
 //!
@@ -52,11 +60,27 @@
 
 pub mod pgbouncer_config;
 pub mod error;
-pub(crate) mod pg_client;
+pub mod pg_client;
 pub mod builder;
 pub mod utils;
 #[cfg(feature = "io")]
 pub mod io;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "test-support")]
+pub mod test_support;
+#[cfg(any(feature = "server", feature = "grpc"))]
+pub mod control;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "fleet")]
+pub mod fleet;
+#[cfg(feature = "admin")]
+pub mod admin;
+#[cfg(feature = "diagnostics")]
+pub mod diagnostic;
 
 #[cfg(feature = "derive")]
 pub use pgbouncer_config_derive::Expression;
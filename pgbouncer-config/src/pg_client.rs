@@ -1,38 +1,229 @@
-use sqlx::PgPool;
-use sqlx::postgres::PgPoolOptions;
+//! Client for the PostgreSQL hosts behind the [databases] entries.
+//!
+//! The import connects to every backend host with a [`PgClient`] to list its
+//! databases. The client is public so the same connection settings (Unix
+//! sockets, TLS, `application_name` and timeouts) can be used for discovery
+//! queries of your own, e.g. listing the schemas of a database.
 
+use sqlx::{Decode, PgPool, Postgres, Type};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgSslMode};
+use crate::pgbouncer_config::databases_setting::{ClientTls, ConnectionOptions};
+use crate::pgbouncer_config::pgbouncer_setting::SslMode;
+
+/// A database listed by [`PgClient::get_databases`].
+///
+/// # Fields
+/// - name: Name of the database.
+/// - owner: Role owning the database.
+/// - is_template: Whether the database is a template (`datistemplate`).
+/// - encoding: Character encoding, read only with metadata.
+/// - size: Size in bytes, read only with metadata and for databases the user may connect to.
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct DatabaseInfo {
+    pub name: String,
+    pub owner: String,
+    pub is_template: bool,
+    pub encoding: Option<String>,
+    pub size: Option<i64>,
+}
+
+/// A role listed by [`PgClient::get_roles`].
+///
+/// # Fields
+/// - name: Name of the role.
+/// - can_login: Whether the role may log in (`rolcanlogin`).
+/// - is_superuser: Whether the role is a superuser (`rolsuper`).
+/// - password: Stored password verifier (`md5...` or `SCRAM-SHA-256$...`), read
+///   only with passwords; `None` for roles without a password.
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct RoleInfo {
+    pub name: String,
+    pub can_login: bool,
+    pub is_superuser: bool,
+    pub password: Option<String>,
+}
+
+/// Connection pool to a PostgreSQL host.
+///
+/// # Examples
+/// ```rust,no_run
+/// use pgbouncer_config::pg_client::PgClient;
+/// use pgbouncer_config::pgbouncer_config::databases_setting::ConnectionOptions;
+///
+/// # async fn run() -> pgbouncer_config::error::Result<()> {
+/// let client = PgClient::new("127.0.0.1", 5432, "postgres", "postgres", "app", None, &ConnectionOptions::new()).await?;
+/// let schemas: Vec<String> = client
+///     .query_scalar("SELECT nspname::text FROM pg_namespace WHERE nspname !~ '^pg_' ORDER BY nspname")
+///     .await?;
+/// client.close().await;
+/// # Ok(())
+/// # }
+/// ```
 pub struct PgClient {
     pool: PgPool,
 }
 
 impl PgClient {
+    /// Connects to a database of a PostgreSQL host.
+    ///
+    /// # Parameters
+    /// - host: Host name or address; a `host` starting with `/` is the directory
+    ///   of the server's Unix socket, as in libpq and PgBouncer.
+    /// - port: Port of the server.
+    /// - user: User to connect as.
+    /// - password: Password of `user`.
+    /// - database: Database to connect to.
+    /// - tls: sslmode and certificates, see [`ClientTls`]. Without it the
+    ///   connection uses the libpq default, `prefer`.
+    /// - connection: `application_name` and timeouts, see [`ConnectionOptions`].
+    ///
+    /// # Returns
+    /// The connected PgClient.
+    ///
+    /// # Errors
+    /// Returns an error if the connection or authentication fails, or the
+    /// connect timeout expires.
     pub async fn new(
         host: &str,
         port: u16,
         user: &str,
         password: &str,
         database: &str,
+        tls: Option<&ClientTls>,
+        connection: &ConnectionOptions,
     ) -> crate::error::Result<Self> {
-        let database_url = format!(
-            "postgres://{}:{}@{}:{}/{}",
-            user, password, host, port, database
-        );
-        
+        let options = PgConnectOptions::new()
+            .port(port)
+            .username(user)
+            .password(password)
+            .database(database)
+            .application_name(connection.application_name());
+        let options = if host.starts_with('/') {
+            options.socket(host)
+        } else {
+            options.host(host)
+        };
+        let options = match tls {
+            Some(tls) => apply_tls(options, tls),
+            None => options,
+        };
+        let options = match connection.statement_timeout() {
+            Some(timeout) => options.options([("statement_timeout", timeout.as_millis().to_string())]),
+            None => options,
+        };
+
         let pool = PgPoolOptions::new()
             .max_connections(5)
-            .connect(&database_url)
+            .acquire_timeout(connection.connect_timeout())
+            .connect_with(options)
             .await?;
         
         Ok(Self { pool })
     }
     
-    pub async fn get_databases(&self) -> crate::error::Result<Vec<String>> {
-        let db_names = sqlx::query_scalar(
-            "SELECT datname FROM pg_database WHERE datallowconn = true ORDER BY datname",
+    /// Closes every connection of the client.
+    pub async fn close(self) {
+        self.pool.close().await;
+    }
+
+    /// Reads the databases accepting connections, ordered by name.
+    ///
+    /// The encoding and size are only read when `with_metadata` is set; the
+    /// size is left out for databases the user may not connect to.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub async fn get_databases(&self, with_metadata: bool) -> crate::error::Result<Vec<DatabaseInfo>> {
+        let databases = sqlx::query_as(
+            "SELECT datname::text AS name, pg_get_userbyid(datdba)::text AS owner, datistemplate AS is_template, \
+             CASE WHEN $1 THEN pg_encoding_to_char(encoding)::text END AS encoding, \
+             CASE WHEN $1 AND has_database_privilege(oid, 'CONNECT') THEN pg_database_size(oid) END AS size \
+             FROM pg_database WHERE datallowconn = true ORDER BY datname",
         )
+            .bind(with_metadata)
             .fetch_all(&self.pool)
             .await?;
-        
-        Ok(db_names)
+
+        Ok(databases)
+    }
+
+    /// Runs a query and returns the first column of every row.
+    ///
+    /// # Parameters
+    /// - sql: Query to run, without bind parameters.
+    ///
+    /// # Returns
+    /// The value of the first column of each row, in the order of the rows.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails or the first column cannot be decoded as `T`.
+    pub async fn query_scalar<T>(&self, sql: &str) -> crate::error::Result<Vec<T>>
+    where
+        T: for<'r> Decode<'r, Postgres> + Type<Postgres> + Send + Unpin,
+    {
+        Ok(sqlx::query_scalar(sql).fetch_all(&self.pool).await?)
     }
-}
\ No newline at end of file
+
+    /// Reads the roles, ordered by name.
+    ///
+    /// With `with_passwords`, the stored password verifiers are read as well:
+    /// `pg_authid` is read first and servers that hide it fall back to the
+    /// `pg_shadow` view. Both require superuser privileges. Without it the
+    /// roles are read from `pg_roles`, which any user may read.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails, or neither `pg_authid` nor
+    /// `pg_shadow` can be read when `with_passwords` is set.
+    pub async fn get_roles(&self, with_passwords: bool) -> crate::error::Result<Vec<RoleInfo>> {
+        if !with_passwords {
+            return Ok(sqlx::query_as(
+                "SELECT rolname::text AS name, rolcanlogin AS can_login, rolsuper AS is_superuser, \
+                 NULL::text AS password FROM pg_roles ORDER BY rolname",
+            )
+                .fetch_all(&self.pool)
+                .await?);
+        }
+
+        let roles = sqlx::query_as(
+            "SELECT rolname::text AS name, rolcanlogin AS can_login, rolsuper AS is_superuser, \
+             rolpassword AS password FROM pg_authid ORDER BY rolname",
+        )
+            .fetch_all(&self.pool)
+            .await;
+
+        match roles {
+            Ok(roles) => Ok(roles),
+            Err(_) => Ok(sqlx::query_as(
+                "SELECT r.rolname::text AS name, r.rolcanlogin AS can_login, r.rolsuper AS is_superuser, \
+                 s.passwd AS password FROM pg_roles r LEFT JOIN pg_shadow s ON s.usesysid = r.oid \
+                 ORDER BY r.rolname",
+            )
+                .fetch_all(&self.pool)
+                .await?),
+        }
+    }
+}
+
+/// Applies the TLS settings of an entry to the connection options.
+fn apply_tls(options: PgConnectOptions, tls: &ClientTls) -> PgConnectOptions {
+    let mode = match tls.sslmode() {
+        SslMode::Disable => PgSslMode::Disable,
+        SslMode::Allow => PgSslMode::Allow,
+        SslMode::Prefer => PgSslMode::Prefer,
+        SslMode::Require => PgSslMode::Require,
+        SslMode::VerifyCa => PgSslMode::VerifyCa,
+        SslMode::VerifyFull => PgSslMode::VerifyFull,
+    };
+    let mut options = options.ssl_mode(mode);
+    if let Some(root_cert) = tls.root_cert() {
+        options = options.ssl_root_cert(root_cert);
+    }
+    if let Some(client_cert) = tls.client_cert() {
+        options = options.ssl_client_cert(client_cert);
+    }
+    if let Some(client_key) = tls.client_key() {
+        options = options.ssl_client_key(client_key);
+    }
+
+    options
+}
@@ -0,0 +1,484 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use crate::error::PgBouncerError;
+use crate::pg_client::RoleInfo;
+use crate::pgbouncer_config::databases_setting::DatabasesSetting;
+use crate::pgbouncer_config::pgbouncer_setting::AuthType;
+use crate::utils::password::{is_verifier, password_verifier, verifier_matches};
+#[cfg(feature = "io")]
+use crate::utils::parser::{is_comment, ParserIniFromStr};
+
+/// Contents of the file referenced by `auth_file` (commonly userlist.txt).
+///
+/// Each entry maps a user name to the password PgBouncer uses to authenticate
+/// it. Users are kept ordered by name so the rendered file is stable.
+///
+/// # Fields
+/// - users: Map from user name to password.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::pgbouncer_config::auth_file::AuthFile;
+///
+/// let mut auth_file = AuthFile::new();
+/// auth_file.add_user("app", "s3cr\"t");
+/// assert_eq!(auth_file.expr(), "\"app\" \"s3cr\"\"t\"\n");
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct AuthFile {
+    users: BTreeMap<String, String>,
+}
+
+impl AuthFile {
+    /// Creates an empty AuthFile.
+    ///
+    /// # Returns
+    /// The initialized AuthFile without users.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collects the credentials of every `Database` entry.
+    ///
+    /// # Parameters
+    /// - databases_setting: The [databases] section to read `user`/`password` from.
+    ///
+    /// # Returns
+    /// An AuthFile with one entry per distinct user; entries with an empty user are skipped.
+    ///
+    /// # Errors
+    /// Returns an error if the same user appears with different passwords.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::auth_file::AuthFile;
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::{Database, DatabasesSetting};
+    ///
+    /// let mut settings = DatabasesSetting::new();
+    /// settings.add_database(Database::new("10.0.0.1", 5432, "app", "secret", Some(&["app"][..])));
+    /// let auth_file = AuthFile::from_databases(&settings).unwrap();
+    /// assert_eq!(auth_file.password("app"), Some("secret"));
+    /// ```
+    pub fn from_databases(databases_setting: &DatabasesSetting) -> crate::error::Result<Self> {
+        let mut auth_file = Self::new();
+        for database in databases_setting.entries() {
+            if database.user().is_empty() {
+                continue;
+            }
+
+            match auth_file.password(database.user()) {
+                Some(password) if password != database.password() => {
+                    return Err(PgBouncerError::PgBouncer(format!(
+                        "user {} is configured with different passwords", database.user()
+                    )));
+                },
+                _ => {
+                    auth_file.add_user(database.user(), database.password());
+                },
+            }
+        }
+
+        Ok(auth_file)
+    }
+
+    /// Reads the login roles and their password verifiers from every Postgres host.
+    ///
+    /// Each distinct host/port/user of the [databases] section is queried once
+    /// (through its SSH tunnel, if any) as that user, which must be a superuser
+    /// to read `pg_authid`. Roles without a password are skipped.
+    ///
+    /// # Parameters
+    /// - databases_setting: The [databases] section listing the hosts.
+    /// - filter: Selects the roles to import.
+    ///
+    /// # Returns
+    /// An AuthFile holding the stored verifiers (`md5...` or `SCRAM-SHA-256$...`).
+    ///
+    /// # Errors
+    /// Returns an error if a host cannot be queried or the same role has
+    /// different verifiers on two hosts.
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// use pgbouncer_config::pgbouncer_config::auth_file::{AuthFile, RoleFilter};
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::{Database, DatabasesSetting};
+    ///
+    /// let mut settings = DatabasesSetting::new();
+    /// settings.add_database(Database::new("127.0.0.1", 5432, "postgres", "postgres", None));
+    /// let mut filter = RoleFilter::new();
+    /// filter.set_exclude(Some("^(postgres|replicator)$")).unwrap();
+    ///
+    /// let rt = tokio::runtime::Runtime::new().unwrap();
+    /// let auth_file = rt.block_on(AuthFile::import_roles(&settings, &filter)).unwrap();
+    /// ```
+    pub async fn import_roles(databases_setting: &DatabasesSetting, filter: &RoleFilter) -> crate::error::Result<Self> {
+        let mut auth_file = Self::new();
+        let mut queried = Vec::new();
+        for database in databases_setting.entries() {
+            let target = (database.host(), database.port(), database.user());
+            if queried.contains(&target) {
+                continue;
+            }
+            queried.push(target);
+
+            for role in database.get_roles_from_host(None).await? {
+                if filter.accepts(&role) {
+                    auth_file.add_imported_role(&role)?;
+                }
+            }
+        }
+
+        Ok(auth_file)
+    }
+
+    /// Adds a role read from a host with its password verifier.
+    ///
+    /// # Errors
+    /// Returns an error if the role is already present with a different verifier.
+    pub(crate) fn add_imported_role(&mut self, role: &RoleInfo) -> crate::error::Result<()> {
+        let verifier = role.password.as_deref().unwrap_or_default();
+        match self.password(&role.name) {
+            Some(stored) if stored != verifier => Err(PgBouncerError::PgBouncer(format!(
+                "role {} has different passwords on different hosts", role.name
+            ))),
+            _ => {
+                self.add_user(&role.name, verifier);
+                Ok(())
+            },
+        }
+    }
+
+    /// Adds a user, replacing the password of an existing entry.
+    ///
+    /// # Parameters
+    /// - user: User name.
+    /// - password: Password (plain text or a verifier such as `md5...` or `SCRAM-SHA-256$...`).
+    ///
+    /// # Returns
+    /// A mutable reference to self with the new entry.
+    pub fn add_user(&mut self, user: &str, password: &str) -> &mut Self {
+        self.users.insert(user.to_string(), password.to_string());
+        self
+    }
+
+    /// Removes a user.
+    ///
+    /// # Parameters
+    /// - user: User name to remove; unknown users are ignored.
+    ///
+    /// # Returns
+    /// A mutable reference to self without the user.
+    pub fn remove_user(&mut self, user: &str) -> &mut Self {
+        self.users.remove(user);
+        self
+    }
+
+    /// Returns a copy whose passwords are replaced by verifiers for `auth_type`.
+    ///
+    /// `md5` stores `md5` hashes and `scram-sha-256` stores SCRAM-SHA-256
+    /// verifiers; other methods keep the passwords as they are. Passwords that
+    /// already are verifiers are kept.
+    ///
+    /// # Parameters
+    /// - auth_type: Authentication method the auth file is used with.
+    ///
+    /// # Returns
+    /// The AuthFile with hashed passwords.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::auth_file::AuthFile;
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::AuthType;
+    ///
+    /// let mut auth_file = AuthFile::new();
+    /// auth_file.add_user("postgres", "postgres");
+    /// let hashed = auth_file.with_verifiers(AuthType::Md5);
+    /// assert_eq!(hashed.password("postgres"), Some("md53175bce1d3201d16594cebf9d7eb3f9d"));
+    /// ```
+    pub fn with_verifiers(&self, auth_type: AuthType) -> Self {
+        let users = self.users
+            .iter()
+            .map(|(user, password)| (user.clone(), password_verifier(auth_type, user, password)))
+            .collect();
+
+        Self { users }
+    }
+
+    /// Returns a copy that keeps the verifiers of `existing` which still match.
+    ///
+    /// SCRAM-SHA-256 verifiers use a random salt, so hashing the same password
+    /// twice gives different text. Reusing the verifier already stored for a
+    /// user whose plain-text password did not change keeps the rendered file
+    /// stable between runs.
+    ///
+    /// # Parameters
+    /// - existing: The auth file currently deployed (e.g. parsed from userlist.txt).
+    ///
+    /// # Returns
+    /// The AuthFile with matching verifiers taken from `existing`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::auth_file::AuthFile;
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::AuthType;
+    ///
+    /// let mut desired = AuthFile::new();
+    /// desired.add_user("app", "secret");
+    /// let deployed = desired.with_verifiers(AuthType::ScramSha256);
+    /// let next = desired.reuse_verifiers(&deployed).with_verifiers(AuthType::ScramSha256);
+    /// assert_eq!(next, deployed);
+    /// ```
+    pub fn reuse_verifiers(&self, existing: &AuthFile) -> Self {
+        let users = self.users
+            .iter()
+            .map(|(user, password)| {
+                let password = match existing.password(user) {
+                    Some(stored) if !is_verifier(password)
+                        && is_verifier(stored)
+                        && verifier_matches(stored, user, password) => stored.to_string(),
+                    _ => password.clone(),
+                };
+                (user.clone(), password)
+            })
+            .collect();
+
+        Self { users }
+    }
+
+    /// Returns the password of a user, if present.
+    pub fn password(&self, user: &str) -> Option<&str> {
+        self.users.get(user).map(String::as_str)
+    }
+
+    /// Returns the users and their passwords ordered by user name.
+    pub fn users(&self) -> &BTreeMap<String, String> {
+        &self.users
+    }
+
+    /// Returns true when no user is registered.
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+
+    /// Renders the userlist.txt content.
+    ///
+    /// # Returns
+    /// One `"user" "password"` line per user; double quotes inside a field are doubled.
+    pub fn expr(&self) -> String {
+        self.users
+            .iter()
+            .map(|(user, password)| format!("{} {}\n", quote_field(user), quote_field(password)))
+            .collect()
+    }
+}
+
+/// Selects the roles imported by [`AuthFile::import_roles`] and
+/// [`DatabasesSetting::add_database_and_roles_from_hosts_with_progress`].
+///
+/// # Fields
+/// - include: Only roles whose name matches this pattern are imported; every role when unset.
+/// - exclude: Roles whose name matches this pattern are skipped.
+#[derive(Debug, Clone, Default)]
+pub struct RoleFilter {
+    include: Option<Regex>,
+    exclude: Option<Regex>,
+}
+
+impl RoleFilter {
+    /// Creates a filter accepting every role.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the pattern of the roles to import.
+    ///
+    /// # Parameters
+    /// - pattern: Regular expression matched against the role name, or `None` to accept every role.
+    ///
+    /// # Returns
+    /// If successful, a mutable reference to self with the updated pattern.
+    ///
+    /// # Errors
+    /// Returns an error if the pattern is not a valid regular expression.
+    pub fn set_include(&mut self, pattern: Option<&str>) -> crate::error::Result<&mut Self> {
+        self.include = pattern.map(Regex::new).transpose()?;
+        Ok(self)
+    }
+
+    /// Sets the pattern of the roles to skip.
+    ///
+    /// # Parameters
+    /// - pattern: Regular expression matched against the role name, or `None` to skip no role.
+    ///
+    /// # Returns
+    /// If successful, a mutable reference to self with the updated pattern.
+    ///
+    /// # Errors
+    /// Returns an error if the pattern is not a valid regular expression.
+    pub fn set_exclude(&mut self, pattern: Option<&str>) -> crate::error::Result<&mut Self> {
+        self.exclude = pattern.map(Regex::new).transpose()?;
+        Ok(self)
+    }
+
+    /// Returns true when a role passes the filter.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::auth_file::RoleFilter;
+    ///
+    /// let mut filter = RoleFilter::new();
+    /// filter
+    ///     .set_include(Some("^app_")).unwrap()
+    ///     .set_exclude(Some("_admin$")).unwrap();
+    /// assert!(filter.matches("app_reader"));
+    /// assert!(!filter.matches("app_admin"));
+    /// assert!(!filter.matches("postgres"));
+    /// ```
+    pub fn matches(&self, role: &str) -> bool {
+        self.include.as_ref().is_none_or(|include| include.is_match(role))
+            && !self.exclude.as_ref().is_some_and(|exclude| exclude.is_match(role))
+    }
+
+    /// Returns true when a role read from a host is imported: it may log in,
+    /// has a password and passes the filter.
+    pub(crate) fn accepts(&self, role: &RoleInfo) -> bool {
+        role.can_login && role.password.is_some() && self.matches(&role.name)
+    }
+}
+
+impl Display for AuthFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expr())
+    }
+}
+
+fn quote_field(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+#[cfg(feature = "io")]
+impl ParserIniFromStr for AuthFile {
+    type Error = PgBouncerError;
+
+    /// Parses userlist.txt content.
+    ///
+    /// Every non-empty line that is not a comment must hold two double-quoted
+    /// fields, user and password, where `""` stands for a literal quote.
+    fn parse_from_str(value: &str) -> Result<Self, Self::Error> {
+        let mut auth_file = AuthFile::new();
+        for line in value.lines() {
+            let line = line.trim();
+            if line.is_empty() || is_comment(line) {
+                continue;
+            }
+
+            let invalid = || PgBouncerError::PgBouncer(format!("Invalid auth file line: {}", line));
+            let (user, rest) = unquote_field(line).ok_or_else(invalid)?;
+            let (password, rest) = unquote_field(rest.trim_start()).ok_or_else(invalid)?;
+            if !rest.trim().is_empty() && !is_comment(rest.trim()) {
+                return Err(invalid());
+            }
+
+            auth_file.add_user(&user, &password);
+        }
+
+        Ok(auth_file)
+    }
+}
+
+/// Reads one double-quoted field and returns it with the remaining text.
+#[cfg(feature = "io")]
+fn unquote_field(value: &str) -> Option<(String, &str)> {
+    let body = value.strip_prefix('"')?;
+    let mut field = String::new();
+    let mut chars = body.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        if c != '"' {
+            field.push(c);
+            continue;
+        }
+        if let Some((_, '"')) = chars.peek() {
+            field.push('"');
+            chars.next();
+            continue;
+        }
+
+        return Some((field, &body[index + 1..]));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgbouncer_config::databases_setting::Database;
+
+    #[test]
+    fn expr_quotes_fields_and_orders_users() {
+        let mut auth_file = AuthFile::new();
+        auth_file.add_user("zoe", "p w");
+        auth_file.add_user("al\"ice", "x");
+        assert_eq!(auth_file.expr(), "\"al\"\"ice\" \"x\"\n\"zoe\" \"p w\"\n");
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn parse_from_str_round_trips_quoting() {
+        let mut auth_file = AuthFile::new();
+        auth_file.add_user("al\"ice", "p w");
+        auth_file.add_user("bob", "md53175bce1d3201d16594cebf9d7eb3f9d");
+        let text = format!(";; managed by pgbouncer-generator\n\n{}", auth_file.expr());
+        assert_eq!(AuthFile::parse_from_str(&text).unwrap(), auth_file);
+
+        assert!(AuthFile::parse_from_str("\"bob\" secret").is_err());
+        assert!(AuthFile::parse_from_str("\"bob\" \"unterminated").is_err());
+    }
+
+    #[test]
+    fn from_databases_rejects_conflicting_passwords() {
+        let mut settings = DatabasesSetting::new();
+        settings.add_database(Database::new("10.0.0.1", 5432, "app", "one", Some(&["a"][..])));
+        settings.add_database(Database::new("10.0.0.2", 5432, "app", "one", Some(&["b"][..])));
+        assert_eq!(AuthFile::from_databases(&settings).unwrap().users().len(), 1);
+
+        settings.add_database(Database::new("10.0.0.3", 5432, "app", "two", Some(&["c"][..])));
+        assert!(AuthFile::from_databases(&settings).is_err());
+    }
+
+    #[test]
+    fn role_filter_accepts_everything_by_default_and_rejects_bad_patterns() {
+        assert!(RoleFilter::new().matches("postgres"));
+
+        let mut filter = RoleFilter::new();
+        filter.set_exclude(Some("^(postgres|rds.*)$")).unwrap();
+        assert!(filter.matches("app"));
+        assert!(!filter.matches("rdsadmin"));
+
+        assert!(RoleFilter::new().set_include(Some("app(")).is_err());
+    }
+
+    #[test]
+    fn imported_roles_need_login_and_matching_verifiers() {
+        let role = |name: &str, can_login: bool, password: Option<&str>| RoleInfo {
+            name: name.to_string(),
+            can_login,
+            is_superuser: false,
+            password: password.map(ToString::to_string),
+        };
+        let mut filter = RoleFilter::new();
+        filter.set_exclude(Some("^postgres$")).unwrap();
+        assert!(filter.accepts(&role("app", true, Some("SCRAM-SHA-256$a"))));
+        assert!(!filter.accepts(&role("app", false, Some("SCRAM-SHA-256$a"))));
+        assert!(!filter.accepts(&role("app", true, None)));
+        assert!(!filter.accepts(&role("postgres", true, Some("SCRAM-SHA-256$a"))));
+
+        let mut auth_file = AuthFile::new();
+        auth_file.add_imported_role(&role("app", true, Some("SCRAM-SHA-256$a"))).unwrap();
+        auth_file.add_imported_role(&role("app", true, Some("SCRAM-SHA-256$a"))).unwrap();
+        assert!(auth_file.add_imported_role(&role("app", true, Some("SCRAM-SHA-256$b"))).is_err());
+        assert_eq!(auth_file.password("app"), Some("SCRAM-SHA-256$a"));
+    }
+}
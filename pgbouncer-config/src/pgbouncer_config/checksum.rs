@@ -0,0 +1,118 @@
+//! Checksum comments for detecting manual edits of generated files.
+//!
+//! [`embed_checksum`] puts a `; pgbouncer-config sha256: <hex>` line at the
+//! top of a generated file, hashing everything after it. Trailing blank lines
+//! are not covered, so an editor adding a final newline is not a drift.
+//! [`verify_checksum`] recomputes the hash to tell whether the file was
+//! edited (drifted) since generation.
+
+use sha2::{Digest, Sha256};
+
+/// Marker of the checksum comment, following the comment character.
+pub const CHECKSUM_MARKER: &str = "pgbouncer-config sha256:";
+
+/// Result of [`verify_checksum`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChecksumStatus {
+    /// The content matches its embedded checksum.
+    Verified,
+    /// The content was edited after the checksum was embedded.
+    Modified {
+        /// Checksum embedded in the file.
+        expected: String,
+        /// Checksum of the current content.
+        actual: String,
+    },
+    /// The file has no checksum comment.
+    Missing,
+}
+
+/// Adds a checksum comment as the first line of `text`.
+///
+/// An existing checksum comment is replaced, so the function can be applied
+/// to a file that is regenerated in place.
+///
+/// # Parameters
+/// - text: Generated file content.
+///
+/// # Returns
+/// The content prefixed with `; pgbouncer-config sha256: <hex>`.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::pgbouncer_config::checksum::{embed_checksum, verify_checksum, ChecksumStatus};
+///
+/// let text = embed_checksum("[pgbouncer]\nlisten_port = 6432\n");
+/// assert!(text.starts_with("; pgbouncer-config sha256: "));
+/// assert_eq!(verify_checksum(&text), ChecksumStatus::Verified);
+/// assert!(matches!(verify_checksum(&text.replace("6432", "6433")), ChecksumStatus::Modified { .. }));
+/// ```
+pub fn embed_checksum(text: &str) -> String {
+    let body = split_checksum(text).map_or(text, |(_, body)| body);
+
+    format!("; {} {}\n{}", CHECKSUM_MARKER, sha256_hex(body.trim_end()), body)
+}
+
+/// Checks the content of a generated file against its embedded checksum.
+///
+/// The checksum comment may start with `;` or `#`.
+///
+/// # Parameters
+/// - text: Content of the file, including the checksum comment.
+///
+/// # Returns
+/// Whether the content is unchanged, modified, or has no checksum.
+pub fn verify_checksum(text: &str) -> ChecksumStatus {
+    match split_checksum(text) {
+        Some((expected, body)) => {
+            let actual = sha256_hex(body.trim_end());
+            match actual.eq_ignore_ascii_case(expected) {
+                true => ChecksumStatus::Verified,
+                false => ChecksumStatus::Modified { expected: expected.to_string(), actual },
+            }
+        },
+        None => ChecksumStatus::Missing,
+    }
+}
+
+/// Splits `text` into the embedded checksum and the content it covers.
+fn split_checksum(text: &str) -> Option<(&str, &str)> {
+    let (first_line, body) = text.split_once('\n').unwrap_or((text, ""));
+    let checksum = first_line
+        .trim()
+        .strip_prefix([';', '#'])?
+        .trim_start()
+        .strip_prefix(CHECKSUM_MARKER)?
+        .trim();
+
+    Some((checksum, body))
+}
+
+fn sha256_hex(text: &str) -> String {
+    Sha256::digest(text.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedding_twice_replaces_the_checksum() {
+        let text = embed_checksum("[pgbouncer]\n");
+        assert_eq!(embed_checksum(&text), text);
+        assert_eq!(text.lines().count(), 2);
+    }
+
+    #[test]
+    fn trailing_blank_lines_are_not_a_drift() {
+        let text = embed_checksum("[pgbouncer]\n");
+        assert_eq!(verify_checksum(&format!("{}\n\n", text)), ChecksumStatus::Verified);
+    }
+
+    #[test]
+    fn hash_comments_and_missing_checksums_are_recognized() {
+        let text = embed_checksum("[pgbouncer]\n").replacen(';', "#", 1);
+        assert_eq!(verify_checksum(&text), ChecksumStatus::Verified);
+        assert_eq!(verify_checksum("[pgbouncer]\n"), ChecksumStatus::Missing);
+    }
+}
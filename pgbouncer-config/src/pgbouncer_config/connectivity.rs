@@ -0,0 +1,173 @@
+//! Connectivity checks of the [databases] routes.
+//!
+//! [`DatabasesSetting::check_connectivity`](crate::pgbouncer_config::databases_setting::DatabasesSetting::check_connectivity)
+//! connects to the backend of every rendered [databases] line with the
+//! credentials of its entry and reports a [`RouteCheck`] per line, so
+//! unreachable hosts, rejected credentials and missing databases are found
+//! before the configuration is deployed.
+
+use std::fmt::{Display, Formatter};
+use crate::error::PgBouncerError;
+use crate::pgbouncer_config::databases_setting::Database;
+
+/// Outcome of connecting to the backend of one [databases] line.
+///
+/// # Variants
+/// - Ok: The connection was established and authenticated.
+/// - Unreachable: The host, port or SSH tunnel could not be reached.
+/// - AuthenticationFailed: The server rejected the user or password.
+/// - DatabaseNotFound: The backend `dbname` does not exist.
+/// - Failed: Any other error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteStatus {
+    Ok,
+    Unreachable(String),
+    AuthenticationFailed(String),
+    DatabaseNotFound(String),
+    Failed(String),
+}
+
+impl RouteStatus {
+    /// Classifies the error returned while connecting.
+    pub(crate) fn from_error(error: &PgBouncerError) -> Self {
+        match error {
+            PgBouncerError::Sqlx(sqlx::Error::Database(e)) => match e.code().as_deref() {
+                // invalid_password / invalid_authorization_specification
+                Some("28P01") | Some("28000") => Self::AuthenticationFailed(e.message().to_string()),
+                // invalid_catalog_name
+                Some("3D000") => Self::DatabaseNotFound(e.message().to_string()),
+                _ => Self::Failed(e.message().to_string()),
+            },
+            PgBouncerError::Sqlx(sqlx::Error::Io(e)) => Self::Unreachable(e.to_string()),
+            PgBouncerError::Sqlx(sqlx::Error::PoolTimedOut) => Self::Unreachable("connection timed out".to_string()),
+            PgBouncerError::SshConnection(_)
+            | PgBouncerError::SshKey(_)
+            | PgBouncerError::SshAuth(_)
+            | PgBouncerError::Connection(_) => Self::Unreachable(error.to_string()),
+            _ => Self::Failed(error.to_string()),
+        }
+    }
+}
+
+impl Display for RouteStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RouteStatus::Ok => write!(f, "ok"),
+            RouteStatus::Unreachable(message) => write!(f, "unreachable: {}", message),
+            RouteStatus::AuthenticationFailed(message) => write!(f, "authentication failed: {}", message),
+            RouteStatus::DatabaseNotFound(message) => write!(f, "database not found: {}", message),
+            RouteStatus::Failed(message) => write!(f, "failed: {}", message),
+        }
+    }
+}
+
+/// Result of checking one [databases] line.
+///
+/// # Fields
+/// - name: Database name clients connect to.
+/// - dbname: Backend database the line points at.
+/// - host: Backend PostgreSQL host.
+/// - port: Backend PostgreSQL port.
+/// - user: User the connection was attempted as.
+/// - via_ssh_tunnel: Whether the connection went through the entry's SSH tunnel.
+/// - status: Outcome of the connection attempt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteCheck {
+    name: String,
+    dbname: String,
+    host: String,
+    port: u16,
+    user: String,
+    via_ssh_tunnel: bool,
+    status: RouteStatus,
+}
+
+impl RouteCheck {
+    pub(crate) fn new(name: &str, dbname: &str, database: &Database, via_ssh_tunnel: bool, status: RouteStatus) -> Self {
+        Self {
+            name: name.to_string(),
+            dbname: dbname.to_string(),
+            host: database.host().to_string(),
+            port: database.port(),
+            user: database.user().to_string(),
+            via_ssh_tunnel,
+            status,
+        }
+    }
+
+    /// Returns the database name clients connect to.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the backend database the line points at.
+    pub fn dbname(&self) -> &str {
+        &self.dbname
+    }
+
+    /// Returns the backend PostgreSQL host.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Returns the backend PostgreSQL port.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Returns the user the connection was attempted as.
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    /// Returns whether the connection went through an SSH tunnel.
+    pub fn via_ssh_tunnel(&self) -> bool {
+        self.via_ssh_tunnel
+    }
+
+    /// Returns the outcome of the connection attempt.
+    pub fn status(&self) -> &RouteStatus {
+        &self.status
+    }
+
+    /// Returns true when the backend accepted the connection.
+    pub fn is_ok(&self) -> bool {
+        self.status == RouteStatus::Ok
+    }
+}
+
+impl Display for RouteCheck {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} -> dbname={} host={} port={} user={}", self.name, self.dbname, self.host, self.port, self.user)?;
+        if self.via_ssh_tunnel {
+            write!(f, " (via SSH tunnel)")?;
+        }
+
+        write!(f, ": {}", self.status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_error_classifies_connection_failures() {
+        let refused = std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "connection refused");
+        assert!(matches!(RouteStatus::from_error(&sqlx::Error::Io(refused).into()), RouteStatus::Unreachable(_)));
+        assert!(matches!(RouteStatus::from_error(&PgBouncerError::SshAuth("denied".to_string())), RouteStatus::Unreachable(_)));
+        assert!(matches!(RouteStatus::from_error(&PgBouncerError::PgBouncer("x".to_string())), RouteStatus::Failed(_)));
+    }
+
+    #[test]
+    fn route_check_display_names_the_line_and_status() {
+        let database = Database::new("10.0.0.10", 5432, "app", "secret", Some(&["app"]));
+        let check = RouteCheck::new("app", "app", &database, false, RouteStatus::DatabaseNotFound("database \"app\" does not exist".to_string()));
+
+        assert!(!check.is_ok());
+        assert_eq!(
+            check.to_string(),
+            "app -> dbname=app host=10.0.0.10 port=5432 user=app: database not found: database \"app\" does not exist",
+        );
+    }
+}
@@ -0,0 +1,401 @@
+//! Lossless representation of a pgbouncer.ini file.
+//!
+//! [`PgBouncerConfig`] models the settings but not their layout: parsing a
+//! file and rendering it again drops comments and reorders keys. [`Document`]
+//! keeps every line as written (comments, blank lines, `%include` directives
+//! and key order) so that applying a new configuration only rewrites the
+//! entries whose rendered value actually changed.
+
+use std::fmt::Display;
+use crate::error::PgBouncerError;
+use crate::pgbouncer_config::PgBouncerConfig;
+use crate::utils::parser::{is_comment, parse_key_value, ParserIniFromStr};
+
+/// A pgbouncer.ini file kept line by line.
+///
+/// Rendering a `Document` with [`Display`] reproduces the parsed text exactly
+/// until it is modified.
+///
+/// # Fields
+/// - lines: Lines of the file, including their line endings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    lines: Vec<Line>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Line {
+    raw: String,
+    kind: LineKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LineKind {
+    /// Empty line, comment or directive such as `%include`.
+    Verbatim,
+    /// Section header; holds the section name.
+    Section(String),
+    /// `key = value` entry; holds the key, the value and the byte offset of the value in `raw`.
+    Entry { key: String, value: String, value_start: usize },
+}
+
+impl Document {
+    /// Parses pgbouncer.ini text without losing any line.
+    ///
+    /// `%include` directives are kept as written and not expanded.
+    ///
+    /// # Parameters
+    /// - value: pgbouncer.ini text.
+    ///
+    /// # Returns
+    /// The parsed document.
+    ///
+    /// # Errors
+    /// Returns an error if a line inside a section is neither empty, a comment,
+    /// a directive nor a `key = value` entry.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::document::Document;
+    ///
+    /// let text = "; main settings\n[pgbouncer]\nlisten_port = 6432\n";
+    /// let doc = Document::parse(text).unwrap();
+    /// assert_eq!(doc.to_string(), text);
+    /// assert_eq!(doc.get("pgbouncer", "listen_port"), Some("6432"));
+    /// ```
+    pub fn parse(value: &str) -> crate::error::Result<Self> {
+        let mut lines = Vec::new();
+        for raw in value.split_inclusive('\n') {
+            lines.push(Line { raw: raw.to_string(), kind: classify(raw)? });
+        }
+
+        Ok(Self { lines })
+    }
+
+    /// Returns the section names in the order they appear.
+    ///
+    /// # Returns
+    /// Section names; a section defined twice is listed twice.
+    pub fn sections(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match &line.kind {
+                LineKind::Section(name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns every `key = value` entry in the order it appears.
+    ///
+    /// # Returns
+    /// `(section, key, value)` tuples; entries before the first section header
+    /// have an empty section name.
+    pub fn entries(&self) -> Vec<(&str, &str, &str)> {
+        let mut section = "";
+        let mut entries = Vec::new();
+        for line in &self.lines {
+            match &line.kind {
+                LineKind::Section(name) => section = name,
+                LineKind::Entry { key, value, .. } => entries.push((section, key.as_str(), value.as_str())),
+                LineKind::Verbatim => (),
+            }
+        }
+
+        entries
+    }
+
+    /// Returns the value of a key.
+    ///
+    /// # Parameters
+    /// - section: Section name without brackets.
+    /// - key: Key name.
+    ///
+    /// # Returns
+    /// The value of the last matching entry, or `None` if the key is not set.
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.entry_indices(section, key)
+            .last()
+            .and_then(|&index| match &self.lines[index].kind {
+                LineKind::Entry { value, .. } => Some(value.as_str()),
+                _ => None,
+            })
+    }
+
+    /// Sets the value of a key, rewriting only the affected line.
+    ///
+    /// An existing entry keeps its key spelling and spacing and only has its
+    /// value replaced. A new entry is inserted after the last entry of the
+    /// section; a missing section is appended at the end of the document.
+    ///
+    /// # Parameters
+    /// - section: Section name without brackets.
+    /// - key: Key name.
+    /// - value: New value.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::document::Document;
+    ///
+    /// let mut doc = Document::parse("[pgbouncer]\nlisten_port   = 6432\n").unwrap();
+    /// doc.set("pgbouncer", "listen_port", "7432");
+    /// assert_eq!(doc.to_string(), "[pgbouncer]\nlisten_port   = 7432\n");
+    /// ```
+    pub fn set(&mut self, section: &str, key: &str, value: &str) {
+        if let Some(&index) = self.entry_indices(section, key).last() {
+            let Line { raw, kind } = &mut self.lines[index];
+            if let LineKind::Entry { value: current, value_start, .. } = kind {
+                *raw = format!("{}{}{}", &raw[..*value_start], value, line_ending(raw));
+                *current = value.to_string();
+            }
+            return;
+        }
+
+        let new_line = entry_line(key, value);
+        match self.section_range(section) {
+            Some((header, end)) => {
+                let insert_at = (header + 1..end)
+                    .rev()
+                    .find(|&i| matches!(self.lines[i].kind, LineKind::Entry { .. }))
+                    .map_or(header + 1, |i| i + 1);
+                self.ensure_trailing_newline(insert_at);
+                self.lines.insert(insert_at, new_line);
+            },
+            None => {
+                let len = self.lines.len();
+                self.ensure_trailing_newline(len);
+                if self.lines.last().is_some_and(|line| !line.raw.trim().is_empty()) {
+                    self.lines.push(Line { raw: "\n".to_string(), kind: LineKind::Verbatim });
+                }
+                self.lines.push(Line {
+                    raw: format!("[{}]\n", section),
+                    kind: LineKind::Section(section.to_string()),
+                });
+                self.lines.push(new_line);
+            },
+        }
+    }
+
+    /// Removes every entry of a key.
+    ///
+    /// # Parameters
+    /// - section: Section name without brackets.
+    /// - key: Key name.
+    ///
+    /// # Returns
+    /// `true` if at least one entry was removed.
+    pub fn remove(&mut self, section: &str, key: &str) -> bool {
+        let indices = self.entry_indices(section, key);
+        for &index in indices.iter().rev() {
+            self.lines.remove(index);
+        }
+
+        !indices.is_empty()
+    }
+
+    /// Converts the document into a [`PgBouncerConfig`].
+    ///
+    /// # Returns
+    /// The configuration described by the document.
+    ///
+    /// # Errors
+    /// Returns an error if the document cannot be parsed as a configuration;
+    /// `%include` directives are resolved against the current working directory.
+    pub fn to_config(&self) -> crate::error::Result<PgBouncerConfig> {
+        PgBouncerConfig::parse_from_str(&self.to_string())
+    }
+
+    /// Applies a configuration, rewriting only the entries that change.
+    ///
+    /// Both the current document and `config` are rendered with
+    /// [`PgBouncerConfig::expr`]; entries whose rendered value differs are set,
+    /// entries that disappear are removed and everything else (comments, blank
+    /// lines, keys not modeled by the crate, unchanged entries) is left as is.
+    ///
+    /// # Parameters
+    /// - config: Configuration to apply.
+    ///
+    /// # Returns
+    /// Unit on success.
+    ///
+    /// # Errors
+    /// Returns an error if the document cannot be converted into a configuration
+    /// or either side cannot be rendered.
+    pub fn apply_config(&mut self, config: &PgBouncerConfig) -> crate::error::Result<()> {
+        let old = rendered_entries(&self.to_config()?.expr()?)?;
+        let new = rendered_entries(&config.expr()?)?;
+
+        for (section, key, value) in &new {
+            let unchanged = old.iter().any(|(s, k, v)| s == section && k == key && v == value);
+            if !unchanged {
+                self.set(section, key, value);
+            }
+        }
+        for (section, key, _) in &old {
+            if !new.iter().any(|(s, k, _)| s == section && k == key) {
+                self.remove(section, key);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn entry_indices(&self, section: &str, key: &str) -> Vec<usize> {
+        let mut current = None;
+        let mut indices = Vec::new();
+        for (index, line) in self.lines.iter().enumerate() {
+            match &line.kind {
+                LineKind::Section(name) => current = Some(name.as_str()),
+                LineKind::Entry { key: k, .. } if current == Some(section) && k == key => indices.push(index),
+                _ => (),
+            }
+        }
+
+        indices
+    }
+
+    /// Returns the header index and the end (exclusive) of the last definition of `section`.
+    fn section_range(&self, section: &str) -> Option<(usize, usize)> {
+        let header = self.lines
+            .iter()
+            .rposition(|line| matches!(&line.kind, LineKind::Section(name) if name == section))?;
+        let end = self.lines[header + 1..]
+            .iter()
+            .position(|line| matches!(line.kind, LineKind::Section(_)))
+            .map_or(self.lines.len(), |offset| header + 1 + offset);
+
+        Some((header, end))
+    }
+
+    /// Makes sure the line before `index` is terminated so a line can be inserted at `index`.
+    fn ensure_trailing_newline(&mut self, index: usize) {
+        if let Some(line) = index.checked_sub(1).and_then(|i| self.lines.get_mut(i))
+            && !line.raw.ends_with('\n')
+        {
+            line.raw.push('\n');
+        }
+    }
+}
+
+impl Display for Document {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            f.write_str(&line.raw)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ParserIniFromStr for Document {
+    type Error = PgBouncerError;
+
+    fn parse_from_str(value: &str) -> Result<Self, Self::Error> {
+        Self::parse(value)
+    }
+}
+
+fn classify(raw: &str) -> crate::error::Result<LineKind> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() || is_comment(trimmed) || trimmed.starts_with('%') {
+        return Ok(LineKind::Verbatim);
+    }
+    if let Some(name) = trimmed.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+        return Ok(LineKind::Section(name.trim().to_string()));
+    }
+
+    let (key, value) = parse_key_value(raw.trim_end_matches(['\r', '\n']))?;
+    let equals = raw
+        .find('=')
+        .ok_or(PgBouncerError::PgBouncer(format!("Invalid format key=value: {}", raw)))?;
+    let value_start = equals + 1 + raw[equals + 1..].len() - raw[equals + 1..].trim_start().len();
+
+    Ok(LineKind::Entry { key, value, value_start })
+}
+
+fn line_ending(raw: &str) -> &'static str {
+    if raw.ends_with("\r\n") {
+        "\r\n"
+    } else if raw.ends_with('\n') {
+        "\n"
+    } else {
+        ""
+    }
+}
+
+fn entry_line(key: &str, value: &str) -> Line {
+    let raw = format!("{} = {}\n", key, value);
+    let value_start = key.len() + 3;
+
+    Line {
+        raw,
+        kind: LineKind::Entry { key: key.to_string(), value: value.to_string(), value_start },
+    }
+}
+
+fn rendered_entries(text: &str) -> crate::error::Result<Vec<(String, String, String)>> {
+    let mut section = String::new();
+    let mut entries = Vec::new();
+    for raw in text.split_inclusive('\n') {
+        match classify(raw)? {
+            LineKind::Section(name) => section = name,
+            LineKind::Entry { key, value, .. } => entries.push((section.clone(), key, value)),
+            LineKind::Verbatim => (),
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INI: &str = "\
+;; PgBouncer configuration\n\
+[databases]\n\
+app = dbname=app host=127.0.0.1 port=5432\n\
+\n\
+[pgbouncer]\n\
+; network\n\
+listen_port = 6432\n\
+listen_addr = 127.0.0.1\n\
+auth_type = md5\n\
+max_client_conn = 100\n\
+default_pool_size = 20\n\
+pool_mode = session\n\
+";
+
+    #[test]
+    fn parse_and_display_are_lossless() {
+        let doc = Document::parse(INI).unwrap();
+        assert_eq!(doc.to_string(), INI);
+        assert_eq!(doc.sections(), vec!["databases", "pgbouncer"]);
+    }
+
+    #[test]
+    fn apply_config_rewrites_only_changed_entries() {
+        let mut doc = Document::parse(INI).unwrap();
+        let mut config = doc.to_config().unwrap();
+        let setting = config
+            .get_config_mut::<crate::pgbouncer_config::pgbouncer_setting::PgBouncerSetting>()
+            .unwrap();
+        setting.set_listen_port(7432);
+
+        doc.apply_config(&config).unwrap();
+        assert_eq!(doc.to_string(), INI.replace("listen_port = 6432", "listen_port = 7432"));
+    }
+
+    #[test]
+    fn set_appends_missing_keys_and_sections() {
+        let mut doc = Document::parse("[pgbouncer]\nlisten_port = 6432\n\n; trailing comment\n").unwrap();
+        doc.set("pgbouncer", "logfile", "/var/log/pgbouncer.log");
+        doc.set("peers", "1", "host=/tmp/pgbouncer1");
+        assert_eq!(
+            doc.to_string(),
+            "[pgbouncer]\nlisten_port = 6432\nlogfile = /var/log/pgbouncer.log\n\n; trailing comment\n\n[peers]\n1 = host=/tmp/pgbouncer1\n"
+        );
+        assert!(doc.remove("pgbouncer", "logfile"));
+        assert_eq!(doc.get("pgbouncer", "logfile"), None);
+    }
+}
@@ -0,0 +1,456 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use crate::error::PgBouncerError;
+use crate::pgbouncer_config::PgBouncerConfig;
+use crate::pgbouncer_config::databases_setting::DatabasesSetting;
+use crate::pgbouncer_config::pgbouncer_setting::{AuthType, PgBouncerSetting};
+use crate::pgbouncer_config::validation::ValidationIssue;
+#[cfg(feature = "io")]
+use crate::utils::parser::ParserIniFromStr;
+
+/// Database and user keywords that do not name a single database or user.
+const HBA_KEYWORDS: [&str; 4] = ["all", "sameuser", "samerole", "replication"];
+
+/// Contents of the file referenced by `auth_hba_file` (pg_hba.conf format).
+///
+/// Rules are evaluated by PgBouncer from top to bottom and the first matching
+/// rule decides how the client is authenticated, so their order is kept.
+///
+/// # Fields
+/// - rules: Rules in evaluation order.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::pgbouncer_config::hba_file::{HbaConnectionType, HbaFile, HbaMethod, HbaRule};
+///
+/// let mut hba_file = HbaFile::new();
+/// hba_file.add_rule(HbaRule::new(HbaConnectionType::HostSsl, &["app"], &["all"], Some("10.0.0.0/8"), HbaMethod::ScramSha256));
+/// assert_eq!(hba_file.expr(), "hostssl app all 10.0.0.0/8 scram-sha-256\n");
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct HbaFile {
+    rules: Vec<HbaRule>,
+}
+
+impl HbaFile {
+    /// Creates an HbaFile without rules.
+    ///
+    /// # Returns
+    /// The initialized HbaFile.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a rule; it is evaluated after the existing rules.
+    ///
+    /// # Parameters
+    /// - rule: The rule to append.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the rule appended.
+    pub fn add_rule(&mut self, rule: HbaRule) -> &mut Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Returns the rules in evaluation order.
+    pub fn rules(&self) -> &[HbaRule] {
+        &self.rules
+    }
+
+    /// Renders the file content, one rule per line.
+    ///
+    /// # Returns
+    /// The rendered rules.
+    pub fn expr(&self) -> String {
+        self.rules.iter().map(|rule| format!("{}\n", rule.expr())).collect()
+    }
+
+    /// Checks that the rules fit the configuration that references this file.
+    ///
+    /// Reported issues:
+    /// - `auth_type` is not `hba` or `auth_hba_file` is not set, so the file is ignored (warning).
+    /// - A rule names a database that is not defined in [databases] while no `*`
+    ///   fallback exists (warning).
+    /// - A database defined in [databases] is not matched by any rule, so every
+    ///   client connecting to it is rejected (error).
+    ///
+    /// # Parameters
+    /// - config: Configuration whose `auth_hba_file` points to this file.
+    ///
+    /// # Returns
+    /// Every issue found; empty when the rules and the configuration agree.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::{Database, DatabasesSetting};
+    /// use pgbouncer_config::pgbouncer_config::hba_file::{HbaConnectionType, HbaFile, HbaMethod, HbaRule};
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::{AuthType, PgBouncerSetting};
+    ///
+    /// let mut pgbouncer_setting = PgBouncerSetting::default();
+    /// pgbouncer_setting.set_auth_type(AuthType::Hba);
+    /// pgbouncer_setting.set_auth_hba_file(Some("/etc/pgbouncer/pg_hba.conf")).unwrap();
+    /// let mut databases_setting = DatabasesSetting::new();
+    /// databases_setting.add_database(Database::new("10.0.0.1", 5432, "u", "p", Some(&["app", "report"][..])));
+    /// let cfg = PgBouncerConfigBuilder::new(pgbouncer_setting, databases_setting).unwrap().build();
+    ///
+    /// let mut hba_file = HbaFile::new();
+    /// hba_file.add_rule(HbaRule::new(HbaConnectionType::Host, &["app"], &["all"], Some("0.0.0.0/0"), HbaMethod::Md5));
+    /// let issues = hba_file.validate_against(&cfg);
+    /// assert_eq!(issues.len(), 1);
+    /// assert_eq!(issues[0].key(), Some("report"));
+    /// ```
+    pub fn validate_against(&self, config: &PgBouncerConfig) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        if let Ok(setting) = config.get_config::<PgBouncerSetting>()
+            && (setting.auth_type() != AuthType::Hba || setting.auth_hba_file().is_none())
+        {
+            issues.push(ValidationIssue::warning(
+                "pgbouncer",
+                Some("auth_hba_file"),
+                "the HBA file is only used when auth_type is 'hba' and auth_hba_file is set",
+            ));
+        }
+
+        let Ok(databases_setting) = config.get_config::<DatabasesSetting>() else {
+            return issues;
+        };
+        let exposed = databases_setting.exposed_names();
+
+        if databases_setting.fallback().is_none() {
+            let unknown = self.rules
+                .iter()
+                .flat_map(|rule| rule.databases.iter())
+                .filter(|database| is_name(database) && !exposed.contains(database.as_str()));
+            for database in unknown {
+                issues.push(ValidationIssue::warning(
+                    "hba",
+                    Some(database),
+                    &format!("database '{}' is not defined in [databases]", database),
+                ));
+            }
+        }
+
+        for database in exposed {
+            if !self.rules.iter().any(|rule| rule.matches_database(database)) {
+                issues.push(ValidationIssue::error(
+                    "hba",
+                    Some(database),
+                    &format!("no rule matches database '{}', so its clients are rejected", database),
+                ));
+            }
+        }
+
+        issues
+    }
+}
+
+impl Display for HbaFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expr())
+    }
+}
+
+#[cfg(feature = "io")]
+impl ParserIniFromStr for HbaFile {
+    type Error = PgBouncerError;
+
+    /// Parses pg_hba.conf content; blank lines and `#` comments are skipped.
+    fn parse_from_str(value: &str) -> Result<Self, Self::Error> {
+        let mut hba_file = HbaFile::new();
+        for line in value.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            hba_file.add_rule(HbaRule::parse_from_str(line)?);
+        }
+
+        Ok(hba_file)
+    }
+}
+
+/// A single line of an HBA file.
+///
+/// # Fields
+/// - connection_type: Kind of client connection the rule applies to.
+/// - databases: Database names or keywords such as `all`.
+/// - users: User names or keywords such as `all`.
+/// - address: Client address (CIDR or `all`); `None` for `local` rules.
+/// - method: Authentication method used when the rule matches.
+/// - options: Method options written as `name=value` after the method.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HbaRule {
+    connection_type: HbaConnectionType,
+    databases: Vec<String>,
+    users: Vec<String>,
+    address: Option<String>,
+    method: HbaMethod,
+    #[serde(default)]
+    options: BTreeMap<String, String>,
+}
+
+impl HbaRule {
+    /// Creates a new HbaRule.
+    ///
+    /// # Parameters
+    /// - connection_type: Kind of client connection the rule applies to.
+    /// - databases: Database names or keywords such as `all`.
+    /// - users: User names or keywords such as `all`.
+    /// - address: Client address (CIDR or `all`); ignored for `local` rules.
+    /// - method: Authentication method used when the rule matches.
+    ///
+    /// # Returns
+    /// The initialized HbaRule without options.
+    pub fn new(
+        connection_type: HbaConnectionType,
+        databases: &[&str],
+        users: &[&str],
+        address: Option<&str>,
+        method: HbaMethod,
+    ) -> Self {
+        let address = match connection_type {
+            HbaConnectionType::Local => None,
+            _ => address.map(|address| address.to_string()),
+        };
+
+        Self {
+            connection_type,
+            databases: databases.iter().map(|database| database.to_string()).collect(),
+            users: users.iter().map(|user| user.to_string()).collect(),
+            address,
+            method,
+            options: BTreeMap::new(),
+        }
+    }
+
+    /// Sets a method option such as `map`.
+    ///
+    /// # Parameters
+    /// - name: Option name.
+    /// - value: Option value.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the option set.
+    pub fn set_option(&mut self, name: &str, value: &str) -> &mut Self {
+        self.options.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Returns the connection type.
+    pub fn connection_type(&self) -> HbaConnectionType {
+        self.connection_type
+    }
+
+    /// Returns the database names or keywords.
+    pub fn databases(&self) -> &[String] {
+        &self.databases
+    }
+
+    /// Returns the user names or keywords.
+    pub fn users(&self) -> &[String] {
+        &self.users
+    }
+
+    /// Returns the client address, if any.
+    pub fn address(&self) -> Option<&str> {
+        self.address.as_deref()
+    }
+
+    /// Returns the authentication method.
+    pub fn method(&self) -> HbaMethod {
+        self.method
+    }
+
+    /// Renders this rule as one line without the trailing newline.
+    pub fn expr(&self) -> String {
+        let mut columns = vec![
+            self.connection_type.to_string(),
+            self.databases.join(","),
+            self.users.join(","),
+        ];
+        if let Some(address) = &self.address {
+            columns.push(address.clone());
+        }
+        columns.push(self.method.to_string());
+        columns.extend(self.options.iter().map(|(name, value)| format!("{}={}", name, value)));
+
+        columns.join(" ")
+    }
+
+    fn matches_database(&self, database: &str) -> bool {
+        self.databases
+            .iter()
+            .any(|entry| entry == "all" || entry == "sameuser" || entry.starts_with('@') || entry == database)
+    }
+}
+
+#[cfg(feature = "io")]
+impl ParserIniFromStr for HbaRule {
+    type Error = PgBouncerError;
+
+    fn parse_from_str(value: &str) -> Result<Self, Self::Error> {
+        let invalid = || PgBouncerError::PgBouncer(format!("Invalid HBA rule: {}", value));
+        let mut tokens = value.split_whitespace();
+
+        let connection_type = HbaConnectionType::try_from(tokens.next().ok_or_else(invalid)?)?;
+        let databases = tokens.next().ok_or_else(invalid)?.split(',').collect::<Vec<_>>();
+        let users = tokens.next().ok_or_else(invalid)?.split(',').collect::<Vec<_>>();
+        let address = match connection_type {
+            HbaConnectionType::Local => None,
+            _ => Some(tokens.next().ok_or_else(invalid)?),
+        };
+        let method = HbaMethod::try_from(tokens.next().ok_or_else(invalid)?)?;
+
+        let mut rule = HbaRule::new(connection_type, &databases, &users, address, method);
+        for option in tokens {
+            let (name, value) = option.split_once('=').ok_or_else(invalid)?;
+            rule.set_option(name, value);
+        }
+
+        Ok(rule)
+    }
+}
+
+/// Kind of client connection an [`HbaRule`] applies to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum HbaConnectionType {
+    /// Unix-domain socket connections.
+    Local,
+    /// TCP/IP connections, with or without TLS.
+    Host,
+    /// TCP/IP connections using TLS.
+    HostSsl,
+    /// TCP/IP connections not using TLS.
+    HostNoSsl,
+}
+
+impl TryFrom<&str> for HbaConnectionType {
+    type Error = PgBouncerError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let connection_type = match value.to_lowercase().as_str() {
+            "local" => HbaConnectionType::Local,
+            "host" => HbaConnectionType::Host,
+            "hostssl" => HbaConnectionType::HostSsl,
+            "hostnossl" => HbaConnectionType::HostNoSsl,
+            _ => return Err(PgBouncerError::PgBouncer(format!("Invalid HBA connection type: {}", value))),
+        };
+
+        Ok(connection_type)
+    }
+}
+
+impl Display for HbaConnectionType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HbaConnectionType::Local => write!(f, "local"),
+            HbaConnectionType::Host => write!(f, "host"),
+            HbaConnectionType::HostSsl => write!(f, "hostssl"),
+            HbaConnectionType::HostNoSsl => write!(f, "hostnossl"),
+        }
+    }
+}
+
+/// Authentication method of an [`HbaRule`], limited to the methods PgBouncer supports.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum HbaMethod {
+    #[serde(rename = "trust")]
+    Trust,
+    #[serde(rename = "reject")]
+    Reject,
+    #[serde(rename = "md5")]
+    Md5,
+    #[serde(rename = "password")]
+    Password,
+    #[serde(rename = "scram-sha-256")]
+    ScramSha256,
+    #[serde(rename = "cert")]
+    Cert,
+    #[serde(rename = "peer")]
+    Peer,
+}
+
+impl TryFrom<&str> for HbaMethod {
+    type Error = PgBouncerError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let method = match value.to_lowercase().as_str() {
+            "trust" => HbaMethod::Trust,
+            "reject" => HbaMethod::Reject,
+            "md5" => HbaMethod::Md5,
+            "password" => HbaMethod::Password,
+            "scram-sha-256" => HbaMethod::ScramSha256,
+            "cert" => HbaMethod::Cert,
+            "peer" => HbaMethod::Peer,
+            _ => return Err(PgBouncerError::PgBouncer(format!("Invalid HBA method: {}", value))),
+        };
+
+        Ok(method)
+    }
+}
+
+impl Display for HbaMethod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HbaMethod::Trust => write!(f, "trust"),
+            HbaMethod::Reject => write!(f, "reject"),
+            HbaMethod::Md5 => write!(f, "md5"),
+            HbaMethod::Password => write!(f, "password"),
+            HbaMethod::ScramSha256 => write!(f, "scram-sha-256"),
+            HbaMethod::Cert => write!(f, "cert"),
+            HbaMethod::Peer => write!(f, "peer"),
+        }
+    }
+}
+
+/// Returns true when a database or user column entry names a single object.
+fn is_name(entry: &str) -> bool {
+    !HBA_KEYWORDS.contains(&entry) && !entry.starts_with('@')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn parse_from_str_round_trips() {
+        let text = "local all postgres peer\nhostssl app,report +admins 10.0.0.0/8 cert map=certmap\nhost all all 0.0.0.0/0 reject\n";
+        let hba_file = HbaFile::parse_from_str(&format!("# managed\n\n{}", text)).unwrap();
+        assert_eq!(hba_file.rules().len(), 3);
+        assert_eq!(hba_file.rules()[1].databases(), ["app", "report"]);
+        assert_eq!(hba_file.expr(), text);
+
+        assert!(HbaFile::parse_from_str("host all all md5").is_err());
+        assert!(HbaFile::parse_from_str("host all all 0.0.0.0/0 gss").is_err());
+    }
+
+    #[test]
+    fn local_rules_have_no_address() {
+        let rule = HbaRule::new(HbaConnectionType::Local, &["all"], &["all"], Some("0.0.0.0/0"), HbaMethod::Trust);
+        assert_eq!(rule.address(), None);
+        assert_eq!(rule.expr(), "local all all trust");
+    }
+
+    #[test]
+    fn validate_against_reports_unknown_and_unmatched_databases() {
+        use crate::builder::PgBouncerConfigBuilder;
+        use crate::pgbouncer_config::databases_setting::Database;
+
+        let mut databases_setting = DatabasesSetting::new();
+        databases_setting.add_database(Database::new("10.0.0.1", 5432, "u", "p", Some(&["app"][..])));
+        let cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), databases_setting).unwrap().build();
+
+        let mut hba_file = HbaFile::new();
+        hba_file.add_rule(HbaRule::new(HbaConnectionType::Host, &["legacy", "all"], &["all"], Some("all"), HbaMethod::Md5));
+        let issues = hba_file.validate_against(&cfg);
+        let keys = issues.iter().map(|issue| issue.key()).collect::<Vec<_>>();
+        assert_eq!(keys, [Some("auth_hba_file"), Some("legacy")]);
+        assert!(issues.iter().all(|issue| !issue.is_error()));
+    }
+}
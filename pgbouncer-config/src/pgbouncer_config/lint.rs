@@ -0,0 +1,553 @@
+//! Best-practice checks of a configuration.
+//!
+//! [`validation`](crate::pgbouncer_config::validation) reports configurations
+//! PgBouncer rejects or cannot serve with; the [`Linter`] reports ones that
+//! work but are risky, such as passwords written into pgbouncer.ini or
+//! `auth_type = trust` on a public address. Each rule can be disabled, and
+//! every finding names the [`LintRule`] that produced it.
+
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use crate::error::PgBouncerError;
+use crate::pgbouncer_config::PgBouncerConfig;
+use crate::pgbouncer_config::databases_setting::DatabasesSetting;
+use crate::pgbouncer_config::peers_setting::PeersSetting;
+use crate::pgbouncer_config::pgbouncer_setting::{AuthType, PgBouncerSetting, PoolMode, SslMode};
+use crate::pgbouncer_config::validation::{TargetVersion, ValidationIssue};
+use crate::utils::secret::SecretReference;
+
+/// A best-practice rule checked by the [`Linter`].
+///
+/// # Variants
+/// - PlaintextCredentials: A [databases] entry writes a password into pgbouncer.ini
+///   instead of a `vault://`/`env://` reference or auth_file.
+/// - TrustOnNetwork: `auth_type` is `trust` or `any` while PgBouncer listens on
+///   a non-loopback address.
+/// - WildcardWithoutTls: PgBouncer listens on every interface without client TLS.
+/// - MissingAdminUsers: No `admin_users` are set, so nobody can run admin
+///   commands such as `RELOAD` or `PAUSE` through the admin console.
+/// - TransactionPooling: `pool_mode = transaction` without the settings most
+///   clients need under it (`ignore_startup_parameters = extra_float_digits`,
+///   and `max_prepared_statements` from PgBouncer 1.21).
+/// - PoolSizing: `min_pool_size` or `reserve_pool_size` larger than
+///   `default_pool_size`, or a reserve pool disabled by `reserve_pool_timeout = 0`.
+/// - Peering: `so_reuseport` or a [peers] section without a `peer_id`, or a
+///   `peer_id` missing from [peers], so cancel requests reaching another
+///   process are not forwarded.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintRule {
+    PlaintextCredentials,
+    TrustOnNetwork,
+    WildcardWithoutTls,
+    MissingAdminUsers,
+    TransactionPooling,
+    PoolSizing,
+    Peering,
+}
+
+impl LintRule {
+    /// Returns every rule, in the order findings are reported.
+    pub fn all() -> &'static [LintRule] {
+        &[
+            LintRule::PlaintextCredentials,
+            LintRule::TrustOnNetwork,
+            LintRule::WildcardWithoutTls,
+            LintRule::MissingAdminUsers,
+            LintRule::TransactionPooling,
+            LintRule::PoolSizing,
+            LintRule::Peering,
+        ]
+    }
+
+    /// Returns the name of the rule used on the command line, e.g. `plaintext-credentials`.
+    pub fn name(self) -> &'static str {
+        match self {
+            LintRule::PlaintextCredentials => "plaintext-credentials",
+            LintRule::TrustOnNetwork => "trust-on-network",
+            LintRule::WildcardWithoutTls => "wildcard-without-tls",
+            LintRule::MissingAdminUsers => "missing-admin-users",
+            LintRule::TransactionPooling => "transaction-pooling",
+            LintRule::PoolSizing => "pool-sizing",
+            LintRule::Peering => "peering",
+        }
+    }
+
+    /// Returns a one-line description of what the rule checks.
+    pub fn description(self) -> &'static str {
+        match self {
+            LintRule::PlaintextCredentials => "Database entries must not embed plaintext passwords",
+            LintRule::TrustOnNetwork => "auth_type trust/any is only safe on loopback addresses",
+            LintRule::WildcardWithoutTls => "Listening on every interface requires client TLS",
+            LintRule::MissingAdminUsers => "admin_users should name at least one user",
+            LintRule::TransactionPooling => "Transaction pooling needs settings common clients rely on",
+            LintRule::PoolSizing => "min_pool_size and reserve_pool_size should fit within default_pool_size",
+            LintRule::Peering => "so_reuseport, peer_id and [peers] should describe the same peering group",
+        }
+    }
+}
+
+impl Display for LintRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl TryFrom<&str> for LintRule {
+    type Error = PgBouncerError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        LintRule::all()
+            .iter()
+            .copied()
+            .find(|rule| rule.name() == value)
+            .ok_or_else(|| {
+                let names = LintRule::all().iter().map(|rule| rule.name()).collect::<Vec<_>>();
+                PgBouncerError::PgBouncer(format!("Unknown lint rule {}, expected one of {}", value, names.join(", ")))
+            })
+    }
+}
+
+/// A single finding of the [`Linter`].
+///
+/// # Fields
+/// - rule: Rule that produced the finding.
+/// - issue: Location, severity and message of the finding.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LintFinding {
+    rule: LintRule,
+    issue: ValidationIssue,
+}
+
+impl LintFinding {
+    /// Returns the rule that produced the finding.
+    pub fn rule(&self) -> LintRule {
+        self.rule
+    }
+
+    /// Returns the location, severity and message of the finding.
+    pub fn issue(&self) -> &ValidationIssue {
+        &self.issue
+    }
+}
+
+impl Display for LintFinding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.issue, self.rule)
+    }
+}
+
+/// Checks a configuration against the enabled [`LintRule`]s.
+///
+/// Every rule is enabled by default, and version-dependent rules assume the
+/// latest PgBouncer unless a target version is set.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+/// use pgbouncer_config::pgbouncer_config::databases_setting::DatabasesSetting;
+/// use pgbouncer_config::pgbouncer_config::lint::{LintRule, Linter};
+/// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+///
+/// let cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new())
+///     .unwrap()
+///     .build();
+///
+/// let findings = Linter::new().lint(&cfg);
+/// assert!(findings.iter().any(|finding| finding.rule() == LintRule::MissingAdminUsers));
+///
+/// let findings = Linter::new().disable(LintRule::MissingAdminUsers).lint(&cfg);
+/// assert!(findings.iter().all(|finding| finding.rule() != LintRule::MissingAdminUsers));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Linter {
+    disabled: BTreeSet<LintRule>,
+    target_version: Option<TargetVersion>,
+}
+
+impl Linter {
+    /// Creates a linter with every rule enabled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enables a rule.
+    ///
+    /// # Parameters
+    /// - rule: The rule to check.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the rule enabled.
+    pub fn enable(&mut self, rule: LintRule) -> &mut Self {
+        self.disabled.remove(&rule);
+        self
+    }
+
+    /// Disables a rule.
+    ///
+    /// # Parameters
+    /// - rule: The rule to skip.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the rule disabled.
+    pub fn disable(&mut self, rule: LintRule) -> &mut Self {
+        self.disabled.insert(rule);
+        self
+    }
+
+    /// Set the PgBouncer release the configuration is deployed to.
+    ///
+    /// # Parameters
+    /// - target_version: The release, or `None` for the latest one.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated target version.
+    pub fn set_target_version(&mut self, target_version: Option<TargetVersion>) -> &mut Self {
+        self.target_version = target_version;
+        self
+    }
+
+    /// Returns whether a rule is checked.
+    pub fn is_enabled(&self, rule: LintRule) -> bool {
+        !self.disabled.contains(&rule)
+    }
+
+    /// Checks a configuration against the enabled rules.
+    ///
+    /// # Parameters
+    /// - config: The configuration to check.
+    ///
+    /// # Returns
+    /// The findings of every enabled rule, grouped by rule in the order of
+    /// [`LintRule::all`]; empty when the configuration follows them all.
+    pub fn lint(&self, config: &PgBouncerConfig) -> Vec<LintFinding> {
+        let pgbouncer = config.get_config::<PgBouncerSetting>().ok();
+        let databases = config.get_config::<DatabasesSetting>().ok();
+        let peers = config.get_config::<PeersSetting>().ok();
+        let target_version = self.target_version.unwrap_or_else(TargetVersion::latest);
+
+        LintRule::all()
+            .iter()
+            .copied()
+            .filter(|rule| self.is_enabled(*rule))
+            .flat_map(|rule| {
+                let issues = match rule {
+                    LintRule::PlaintextCredentials => databases.map(plaintext_credentials).unwrap_or_default(),
+                    LintRule::TrustOnNetwork => pgbouncer.map(trust_on_network).unwrap_or_default(),
+                    LintRule::WildcardWithoutTls => pgbouncer.map(wildcard_without_tls).unwrap_or_default(),
+                    LintRule::MissingAdminUsers => pgbouncer.map(missing_admin_users).unwrap_or_default(),
+                    LintRule::TransactionPooling => pgbouncer
+                        .map(|setting| transaction_pooling(setting, target_version))
+                        .unwrap_or_default(),
+                    LintRule::PoolSizing => pgbouncer.map(pool_sizing).unwrap_or_default(),
+                    LintRule::Peering => pgbouncer.map(|setting| peering(setting, peers)).unwrap_or_default(),
+                };
+                issues.into_iter().map(move |issue| LintFinding { rule, issue })
+            })
+            .collect()
+    }
+}
+
+impl PgBouncerConfig {
+    /// Checks the configuration against every [`LintRule`].
+    ///
+    /// Use a [`Linter`] to disable rules.
+    ///
+    /// # Returns
+    /// Every finding; empty when the configuration follows all rules.
+    pub fn lint(&self) -> Vec<LintFinding> {
+        Linter::new().lint(self)
+    }
+}
+
+fn plaintext_credentials(databases: &DatabasesSetting) -> Vec<ValidationIssue> {
+    databases
+        .iter()
+        .filter(|database| database.is_output_credentials_to_config() && !database.password().is_empty())
+        .filter(|database| SecretReference::parse(database.password()).is_none())
+        .map(|database| {
+            let name = database.databases().first().map(String::as_str);
+            ValidationIssue::warning(
+                "databases",
+                name,
+                "the password is written into pgbouncer.ini in plaintext; use a vault:// or env:// reference or auth_file",
+            )
+        })
+        .collect()
+}
+
+fn trust_on_network(setting: &PgBouncerSetting) -> Vec<ValidationIssue> {
+    let unauthenticated = matches!(setting.auth_type(), AuthType::Trust | AuthType::Any);
+    match listen_addrs(setting).find(|addr| !is_loopback(addr)) {
+        Some(addr) if unauthenticated => vec![ValidationIssue::warning(
+            "pgbouncer",
+            Some("auth_type"),
+            &format!("auth_type = {} lets anyone reaching {} connect without a password", setting.auth_type(), addr),
+        )],
+        _ => Vec::new(),
+    }
+}
+
+fn wildcard_without_tls(setting: &PgBouncerSetting) -> Vec<ValidationIssue> {
+    let tls_enabled = setting.client_tls_sslmode().is_some_and(|mode| mode != SslMode::Disable);
+    match listen_addrs(setting).any(|addr| matches!(addr, "*" | "0.0.0.0" | "::")) {
+        true if !tls_enabled => vec![ValidationIssue::warning(
+            "pgbouncer",
+            Some("listen_addr"),
+            "PgBouncer listens on every interface without client TLS; set client_tls_sslmode and a certificate",
+        )],
+        _ => Vec::new(),
+    }
+}
+
+fn missing_admin_users(setting: &PgBouncerSetting) -> Vec<ValidationIssue> {
+    match setting.admin_users().is_empty() {
+        true => vec![ValidationIssue::warning(
+            "pgbouncer",
+            Some("admin_users"),
+            "no admin_users are set, so the admin console cannot run RELOAD, PAUSE or SHUTDOWN",
+        )],
+        false => Vec::new(),
+    }
+}
+
+fn transaction_pooling(setting: &PgBouncerSetting, target_version: TargetVersion) -> Vec<ValidationIssue> {
+    if setting.pool_mode() != PoolMode::Transaction {
+        return Vec::new();
+    }
+
+    let mut issues = Vec::new();
+    if !setting.ignore_startup_parameters().iter().any(|param| param == "extra_float_digits") {
+        issues.push(ValidationIssue::warning(
+            "pgbouncer",
+            Some("ignore_startup_parameters"),
+            "JDBC and other drivers send extra_float_digits on connect, which PgBouncer rejects in transaction pooling; \
+             add it to ignore_startup_parameters",
+        ));
+    }
+
+    // Protocol-level prepared statements are only tracked from PgBouncer 1.21
+    let max_prepared_statements = setting.extra().get("max_prepared_statements").map(|value| value.trim());
+    if target_version.supports((1, 21)) && matches!(max_prepared_statements, None | Some("0")) {
+        issues.push(ValidationIssue::warning(
+            "pgbouncer",
+            Some("max_prepared_statements"),
+            "prepared statements fail across server connections in transaction pooling; \
+             set max_prepared_statements (e.g. 200) to let PgBouncer track them",
+        ));
+    }
+
+    issues
+}
+
+fn pool_sizing(setting: &PgBouncerSetting) -> Vec<ValidationIssue> {
+    let default_pool_size = setting.default_pool_size();
+    let mut issues = Vec::new();
+
+    if let Some(min_pool_size) = setting.min_pool_size().filter(|size| *size > default_pool_size) {
+        issues.push(ValidationIssue::warning(
+            "pgbouncer",
+            Some("min_pool_size"),
+            &format!(
+                "min_pool_size ({}) is larger than default_pool_size ({}), so every pool keeps more idle connections than it may use",
+                min_pool_size, default_pool_size
+            ),
+        ));
+    }
+
+    match setting.reserve_pool_size().filter(|size| *size > 0) {
+        Some(_) if setting.reserve_pool_timeout() == Some(0) => issues.push(ValidationIssue::warning(
+            "pgbouncer",
+            Some("reserve_pool_timeout"),
+            "reserve_pool_timeout = 0 disables the reserve pool, so reserve_pool_size has no effect",
+        )),
+        Some(reserve_pool_size) if reserve_pool_size > default_pool_size => issues.push(ValidationIssue::warning(
+            "pgbouncer",
+            Some("reserve_pool_size"),
+            &format!(
+                "reserve_pool_size ({}) is larger than default_pool_size ({}); raise default_pool_size instead of relying on the reserve",
+                reserve_pool_size, default_pool_size
+            ),
+        )),
+        _ => {},
+    }
+
+    issues
+}
+
+fn peering(setting: &PgBouncerSetting, peers: Option<&PeersSetting>) -> Vec<ValidationIssue> {
+    let peers = peers.map(PeersSetting::peers).unwrap_or_default();
+
+    let issue = match setting.peer_id().filter(|id| *id != 0) {
+        None if setting.so_reuseport() == Some(true) => Some(ValidationIssue::warning(
+            "pgbouncer",
+            Some("so_reuseport"),
+            "so_reuseport spreads clients over several processes, but without peer_id and [peers] \
+             cancel requests reaching another process are lost",
+        )),
+        None if !peers.is_empty() => Some(ValidationIssue::warning(
+            "pgbouncer",
+            Some("peer_id"),
+            &format!("[peers] lists {} peer(s) but peer_id is not set, so this process does not join them", peers.len()),
+        )),
+        Some(peer_id) if !peers.iter().any(|peer| peer.peer_id() == peer_id) => Some(ValidationIssue::warning(
+            "pgbouncer",
+            Some("peer_id"),
+            &format!("peer_id {} is not listed in [peers], so other peers cannot forward cancel requests to it", peer_id),
+        )),
+        _ => None,
+    };
+
+    issue.into_iter().collect()
+}
+
+/// Addresses of `listen_addr`; empty when PgBouncer only listens on its Unix socket.
+fn listen_addrs(setting: &PgBouncerSetting) -> impl Iterator<Item = &str> {
+    setting.listen_addr().split(',').map(str::trim).filter(|addr| !addr.is_empty())
+}
+
+fn is_loopback(addr: &str) -> bool {
+    addr == "localhost" || addr == "::1" || addr.starts_with("127.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::PgBouncerConfigBuilder;
+    use crate::pgbouncer_config::databases_setting::Database;
+    #[cfg(feature = "io")]
+    use crate::utils::parser::ParserIniFromStr;
+
+    fn config(setting: PgBouncerSetting, databases: DatabasesSetting) -> PgBouncerConfig {
+        PgBouncerConfigBuilder::new(setting, databases).unwrap().build()
+    }
+
+    fn rules(findings: &[LintFinding]) -> Vec<LintRule> {
+        findings.iter().map(LintFinding::rule).collect()
+    }
+
+    #[test]
+    fn risky_settings_are_reported_by_their_rule() {
+        let mut setting = PgBouncerSetting::default();
+        setting
+            .set_listen_addr("*")
+            .set_auth_type(AuthType::Trust)
+            .set_pool_mode(PoolMode::Transaction)
+            .set_min_pool_size(Some(200))
+            .set_so_reuseport(Some(true));
+        let mut databases = DatabasesSetting::new();
+        let mut database = Database::default();
+        database.set_is_output_credentials_to_config(true).set_password("secret");
+        databases.add_database(database);
+
+        let findings = config(setting, databases).lint();
+        let mut found = rules(&findings);
+        found.dedup();
+        assert_eq!(found, LintRule::all());
+        assert_eq!(findings[0].issue().key(), Some("postgres"));
+    }
+
+    #[test]
+    fn safe_settings_and_secret_references_pass() {
+        let mut setting = PgBouncerSetting::default();
+        setting.set_listen_addr("127.0.0.1").set_auth_type(AuthType::Trust).add_admin_user("admin");
+        let mut databases = DatabasesSetting::new();
+        let mut database = Database::default();
+        database.set_is_output_credentials_to_config(true).set_password("vault://secret/db#password");
+        databases.add_database(database);
+
+        assert!(config(setting, databases).lint().is_empty());
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn passwords_written_in_pgbouncer_ini_are_reported() {
+        let ini = "[pgbouncer]\nlisten_addr = 127.0.0.1\nlisten_port = 6432\nauth_type = md5\nauth_file = /etc/userlist.txt\n\
+            max_client_conn = 100\ndefault_pool_size = 20\npool_mode = session\nadmin_users = admin\n\n\
+            [databases]\napp = host=10.0.0.5 dbname=app user=app password=secret\nreports = host=10.0.0.5 dbname=reports\n";
+        let findings = PgBouncerConfig::parse_from_str(ini).unwrap().lint();
+
+        assert_eq!(rules(&findings), vec![LintRule::PlaintextCredentials]);
+        assert_eq!(findings[0].issue().key(), Some("app"));
+    }
+
+    #[test]
+    fn transaction_pooling_needs_startup_parameters_and_prepared_statements() {
+        let mut setting = PgBouncerSetting::default();
+        setting.set_pool_mode(PoolMode::Transaction);
+        let cfg = config(setting.clone(), DatabasesSetting::new());
+        let keys = |linter: &Linter| linter
+            .lint(&cfg)
+            .into_iter()
+            .filter(|finding| finding.rule() == LintRule::TransactionPooling)
+            .map(|finding| finding.issue().key().unwrap_or_default().to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(keys(&Linter::new()), ["ignore_startup_parameters", "max_prepared_statements"]);
+        assert_eq!(keys(Linter::new().set_target_version(Some(TargetVersion::V1_20))), ["ignore_startup_parameters"]);
+
+        setting.add_ignore_startup_parameter("extra_float_digits").set_extra("max_prepared_statements", "200");
+        let cfg = config(setting, DatabasesSetting::new());
+        assert!(cfg.lint().iter().all(|finding| finding.rule() != LintRule::TransactionPooling));
+    }
+
+    #[test]
+    fn pool_sizes_beyond_default_pool_size_are_reported() {
+        let mut setting = PgBouncerSetting::default();
+        setting.add_admin_user("admin").set_min_pool_size(Some(5)).set_reserve_pool_size(Some(5));
+        assert!(config(setting.clone(), DatabasesSetting::new()).lint().is_empty());
+
+        setting.set_default_pool_size(4);
+        let findings = config(setting.clone(), DatabasesSetting::new()).lint();
+        let keys = findings.iter().map(|finding| finding.issue().key().unwrap_or_default()).collect::<Vec<_>>();
+        assert_eq!(rules(&findings), [LintRule::PoolSizing, LintRule::PoolSizing]);
+        assert_eq!(keys, ["min_pool_size", "reserve_pool_size"]);
+
+        setting.set_default_pool_size(100).set_reserve_pool_timeout(Some(0));
+        let findings = config(setting, DatabasesSetting::new()).lint();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].issue().key(), Some("reserve_pool_timeout"));
+    }
+
+    #[test]
+    fn peering_is_checked_against_the_peers_section() {
+        use crate::pgbouncer_config::peers_setting::Peer;
+
+        let lint = |setting: &PgBouncerSetting, peer_ids: &[u16]| {
+            let mut cfg = config(setting.clone(), DatabasesSetting::new());
+            if !peer_ids.is_empty() {
+                let mut peers = PeersSetting::new();
+                for peer_id in peer_ids {
+                    peers.add_peer(Peer::new(*peer_id, &format!("/tmp/pgbouncer{}", peer_id)).unwrap());
+                }
+                cfg.add_config(peers).unwrap();
+            }
+            Linter::new()
+                .lint(&cfg)
+                .into_iter()
+                .filter(|finding| finding.rule() == LintRule::Peering)
+                .map(|finding| finding.issue().key().unwrap_or_default().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let mut setting = PgBouncerSetting::default();
+        assert!(lint(&setting, &[]).is_empty());
+        assert_eq!(lint(&setting, &[1, 2]), ["peer_id"]);
+
+        setting.set_so_reuseport(Some(true));
+        assert_eq!(lint(&setting, &[]), ["so_reuseport"]);
+
+        setting.set_peer_id(Some(3));
+        assert_eq!(lint(&setting, &[1, 2]), ["peer_id"]);
+        assert!(lint(&setting, &[1, 2, 3]).is_empty());
+    }
+
+    #[test]
+    fn rules_parse_from_their_names() {
+        for rule in LintRule::all() {
+            assert_eq!(LintRule::try_from(rule.name()).unwrap(), *rule);
+        }
+        assert!(LintRule::try_from("no-such-rule").is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! Merging two configurations.
+//!
+//! [`PgBouncerConfig::merge`] combines two complete configurations (e.g. the
+//! definitions of two teams sharing one PgBouncer). Sections and keys present
+//! on one side only are kept, database entries of the [databases] section are
+//! matched by name, and values set differently on both sides are resolved by
+//! a [`MergeStrategy`].
+
+use crate::error::PgBouncerError;
+use crate::pgbouncer_config::PgBouncerConfig;
+use crate::pgbouncer_config::overlay::{exposed_names, is_database_entries};
+
+/// How [`PgBouncerConfig::merge`] resolves a key set to different values on both sides.
+///
+/// # Variants
+/// - PreferLeft: Keep the value of the configuration `merge` is called on.
+/// - PreferRight: Take the value of the configuration passed to `merge`.
+/// - ErrorOnConflict: Fail and report the path of the first conflicting key.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    PreferLeft,
+    PreferRight,
+    #[default]
+    ErrorOnConflict,
+}
+
+impl TryFrom<&str> for MergeStrategy {
+    type Error = PgBouncerError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "prefer-left" => Ok(MergeStrategy::PreferLeft),
+            "prefer-right" => Ok(MergeStrategy::PreferRight),
+            "error-on-conflict" => Ok(MergeStrategy::ErrorOnConflict),
+            _ => Err(PgBouncerError::PgBouncer(format!(
+                "Unknown merge strategy {}, expected prefer-left, prefer-right or error-on-conflict",
+                value
+            ))),
+        }
+    }
+}
+
+impl PgBouncerConfig {
+    /// Merges this configuration (left) with `other` (right) into a new configuration.
+    ///
+    /// Sections and keys set on one side only are taken as they are; unset
+    /// (`None`) values never conflict. Database entries of the [databases]
+    /// section are matched by database name or alias (entries without names by
+    /// host, port and user) and merged key by key; unmatched entries of `other`
+    /// are appended. Any other value set differently on both sides, lists
+    /// included, is resolved by `strategy`.
+    ///
+    /// # Parameters
+    /// - other: Configuration merged into this one.
+    /// - strategy: How conflicting values are resolved.
+    ///
+    /// # Returns
+    /// The merged configuration; both inputs are left untouched.
+    ///
+    /// # Errors
+    /// Returns an error on the first conflict with [`MergeStrategy::ErrorOnConflict`],
+    /// or if the merged configuration cannot be built.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::{Database, DatabasesSetting};
+    /// use pgbouncer_config::pgbouncer_config::merge::MergeStrategy;
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut left_databases = DatabasesSetting::new();
+    /// left_databases.add_database(Database::new("10.0.0.10", 5432, "app", "secret", Some(&["app"])));
+    /// let left = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), left_databases).unwrap().build();
+    ///
+    /// let mut right_databases = DatabasesSetting::new();
+    /// right_databases.add_database(Database::new("10.0.0.11", 5432, "report", "secret", Some(&["report"])));
+    /// let mut right_setting = PgBouncerSetting::default();
+    /// right_setting.set_listen_port(7432);
+    /// let right = PgBouncerConfigBuilder::new(right_setting, right_databases).unwrap().build();
+    ///
+    /// assert!(left.merge(&right, MergeStrategy::ErrorOnConflict).is_err());
+    ///
+    /// let merged = left.merge(&right, MergeStrategy::PreferRight).unwrap();
+    /// let text = merged.expr().unwrap();
+    /// assert!(text.contains("listen_port = 7432"));
+    /// assert!(text.contains("app = dbname=app host=10.0.0.10"));
+    /// assert!(text.contains("report = dbname=report host=10.0.0.11"));
+    /// ```
+    pub fn merge(&self, other: &PgBouncerConfig, strategy: MergeStrategy) -> crate::error::Result<PgBouncerConfig> {
+        let mut merged = serde_json::to_value(self)?;
+        merge_value(&mut merged, serde_json::to_value(other)?, strategy, &mut Vec::new())?;
+
+        Ok(serde_json::from_value(merged)?)
+    }
+}
+
+/// Merges `right` into `left`; `path` holds the keys leading to `left`.
+fn merge_value(
+    left: &mut serde_json::Value,
+    right: serde_json::Value,
+    strategy: MergeStrategy,
+    path: &mut Vec<String>,
+) -> crate::error::Result<()> {
+    match (left, right) {
+        (serde_json::Value::Object(left), serde_json::Value::Object(right)) => {
+            for (key, value) in right {
+                match left.get_mut(&key) {
+                    Some(current) => {
+                        path.push(key);
+                        merge_value(current, value, strategy, path)?;
+                        path.pop();
+                    },
+                    None => {
+                        left.insert(key, value);
+                    },
+                }
+            }
+        },
+        (serde_json::Value::Array(left), serde_json::Value::Array(right))
+            if is_database_entries(&path.iter().map(String::as_str).collect::<Vec<_>>()) =>
+        {
+            for entry in right {
+                match left.iter().position(|current| same_database_entry(current, &entry)) {
+                    Some(index) => {
+                        path.push(index.to_string());
+                        merge_value(&mut left[index], entry, strategy, path)?;
+                        path.pop();
+                    },
+                    None => left.push(entry),
+                }
+            }
+        },
+        (_, serde_json::Value::Null) => (),
+        (left, right) if left.is_null() => *left = right,
+        (left, right) if *left == right => (),
+        (left, right) => match strategy {
+            MergeStrategy::PreferLeft => (),
+            MergeStrategy::PreferRight => *left = right,
+            MergeStrategy::ErrorOnConflict => {
+                return Err(PgBouncerError::PgBouncer(format!(
+                    "Conflicting values for {}: {} and {}",
+                    path.join("."),
+                    left,
+                    right
+                )));
+            },
+        },
+    }
+
+    Ok(())
+}
+
+/// Whether two serialized database entries describe the same route.
+fn same_database_entry(left: &serde_json::Value, right: &serde_json::Value) -> bool {
+    let left_names = exposed_names(left);
+    let right_names = exposed_names(right);
+    if left_names.is_empty() && right_names.is_empty() {
+        return ["host", "port", "user"].iter().all(|key| left.get(key) == right.get(key));
+    }
+
+    left_names.iter().any(|name| right_names.contains(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::PgBouncerConfigBuilder;
+    use crate::pgbouncer_config::databases_setting::{Database, DatabasesSetting};
+    use crate::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+
+    fn config(port: u16, databases: &[Database]) -> PgBouncerConfig {
+        let mut setting = PgBouncerSetting::default();
+        setting.set_listen_port(port);
+        let mut databases_setting = DatabasesSetting::new();
+        for database in databases {
+            databases_setting.add_database(database.clone());
+        }
+
+        PgBouncerConfigBuilder::new(setting, databases_setting).unwrap().build()
+    }
+
+    #[test]
+    fn merge_matches_databases_by_name_and_applies_strategy() {
+        let left = config(6432, &[
+            Database::new("10.0.0.10", 5432, "app", "secret", Some(&["app"])),
+            Database::new("10.0.0.11", 5432, "report", "secret", Some(&["report"])),
+        ]);
+        let right = config(7432, &[
+            Database::new("10.0.0.20", 5432, "report", "secret", Some(&["report"])),
+            Database::new("10.0.0.12", 5432, "audit", "secret", Some(&["audit"])),
+        ]);
+
+        let error = left.merge(&right, MergeStrategy::ErrorOnConflict).unwrap_err();
+        assert!(error.to_string().contains("databases.DatabasesSetting.databases.1.host"), "{}", error);
+
+        let text = left.merge(&right, MergeStrategy::PreferLeft).unwrap().expr().unwrap();
+        assert!(text.contains("listen_port = 6432"));
+        assert!(text.contains("report = dbname=report host=10.0.0.11"));
+        assert!(text.contains("audit = dbname=audit host=10.0.0.12"));
+        assert_eq!(text.matches("report = ").count(), 1);
+
+        let text = left.merge(&right, MergeStrategy::PreferRight).unwrap().expr().unwrap();
+        assert!(text.contains("listen_port = 7432"));
+        assert!(text.contains("app = dbname=app host=10.0.0.10"));
+        assert!(text.contains("report = dbname=report host=10.0.0.20"));
+    }
+
+    #[test]
+    fn merge_of_identical_configs_has_no_conflict() {
+        let left = config(6432, &[Database::new("10.0.0.10", 5432, "postgres", "secret", None)]);
+        let merged = left.merge(&left, MergeStrategy::ErrorOnConflict).unwrap();
+
+        assert_eq!(merged.expr().unwrap(), left.expr().unwrap());
+        assert_eq!(MergeStrategy::try_from("prefer-right").unwrap(), MergeStrategy::PreferRight);
+        assert!(MergeStrategy::try_from("newest").is_err());
+    }
+}
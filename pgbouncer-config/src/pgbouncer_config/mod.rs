@@ -9,10 +9,36 @@
 //! Sections currently provided by this crate:
 //! - [`pgbouncer_setting`]: Typed representation of the [pgbouncer] section.
 //! - [`databases_setting`]: Typed representation of the [databases] section.
+//! - [`peers_setting`]: Typed representation of the optional [peers] section.
+//! - [`raw_section`]: Any other section, kept as unparsed key/value pairs.
 //!
-//! Rendering is driven by the [`Expression`] trait; parsing from INI text is
-//! available via the [`ParserIniFromStr`] trait implementation for
-//! [`PgBouncerConfig`].
+//! [`document::Document`] keeps a parsed pgbouncer.ini line by line so it can
+//! be updated without losing comments or key order.
+//!
+//! [`auth_file::AuthFile`] models the userlist.txt referenced by `auth_file`,
+//! and [`hba_file::HbaFile`] the pg_hba.conf referenced by `auth_hba_file`.
+//!
+//! [`PgBouncerConfig::validate`] checks rules spanning several keys or
+//! entries and reports them as [`validation::ValidationIssue`]s, and
+//! [`connectivity`] checks that the backends of the [databases] lines accept connections.
+//!
+//! With the `schema` feature, [`PgBouncerConfig::schema`] describes the
+//! TOML/JSON definition format as a JSON Schema.
+//!
+//! Metadata of the modeled keys (type, default, version) is available in
+//! [`settings_catalog`], and [`shared::SharedPgBouncerConfig`] wraps a
+//! configuration for concurrent access from async tasks.
+//!
+//! [`PgBouncerConfig::overlay`] merges a partial definition (e.g. an
+//! environment-specific layer) on top of a base configuration.
+//! [`PgBouncerConfig::merge`] combines two complete configurations with a
+//! [`merge::MergeStrategy`] for conflicting values.
+//!
+//! Rendering is driven by the [`Expression`] trait, and
+//! [`render::RenderOptions`] controls the layout of the output (key order,
+//! alignment, defaults, spacing, a provenance header and a [`checksum`]
+//! comment); parsing from INI text is available via the [`ParserIniFromStr`] trait implementation for
+//! [`PgBouncerConfig`], which also expands `%include` directives.
 
 use std::any::{Any, TypeId};
 use std::collections::{BTreeMap, HashMap};
@@ -24,17 +50,46 @@ use serde::{Deserialize, Serialize};
 use crate::error::PgBouncerError;
 #[cfg(feature = "io")]
 use regex::Regex;
-#[cfg(feature = "io")]
 use crate::pgbouncer_config::databases_setting::DatabasesSetting;
-#[cfg(feature = "io")]
+use crate::pgbouncer_config::peers_setting::PeersSetting;
 use crate::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
 #[cfg(feature = "io")]
-use crate::utils::parser::{is_comment, ParserIniFromStr};
+use crate::pgbouncer_config::raw_section::RawSection;
+use crate::pgbouncer_config::checksum::embed_checksum;
+use crate::pgbouncer_config::render::RenderOptions;
+#[cfg(feature = "io")]
+use crate::pgbouncer_config::settings_catalog::find_deprecated_setting;
+use crate::pgbouncer_config::validation::{version_issues, TargetVersion, ValidationIssue};
+use crate::utils::secret::SecretResolvers;
+#[cfg(feature = "io")]
+use std::path::Path;
+#[cfg(feature = "io")]
+use crate::utils::parser::{is_comment, resolve_includes, ParserIniFromStr};
 #[cfg(feature = "diff")]
 use crate::utils::diff::Diffable;
 
 pub mod pgbouncer_setting;
 pub mod databases_setting;
+pub mod peers_setting;
+pub mod raw_section;
+pub mod auth_file;
+pub mod connectivity;
+pub mod hba_file;
+pub mod settings_catalog;
+pub mod shared;
+pub mod validation;
+pub mod lint;
+pub mod profile;
+pub mod render;
+pub mod checksum;
+#[cfg(feature = "io")]
+pub mod document;
+#[cfg(feature = "io")]
+mod overlay;
+#[cfg(feature = "schema")]
+mod schema;
+#[cfg(feature = "io")]
+pub mod merge;
 mod derive_expression;
 
 static EXPRESSION_DEFAULT_SECTION_NAME: LazyLock<Mutex<HashMap<TypeId, &'static str>>> =
@@ -48,12 +103,15 @@ macro_rules! define_expression {
         /// used by PgBouncer configuration files or sections. The return value is the
         /// exact text that would appear in pgbouncer.ini for the given node.
         #[typetag::serde]
-        pub trait Expression: ExpressionClone + Any + Debug + $($bound)* {
+        pub trait Expression: ExpressionClone + Any + Debug + Send + Sync + $($bound)* {
             /// Renders this configuration node to its INI text representation.
             ///
             /// # Returns
             /// A `String` containing the text as it should appear in pgbouncer.ini.
             ///
+            /// # Errors
+            /// Returns an error if the node cannot be rendered.
+            ///
             /// # Examples
             /// ```rust
             /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
@@ -63,6 +121,51 @@ macro_rules! define_expression {
             /// assert!(text.contains("[pgbouncer]"));
             /// ```
             fn expr(&self) -> crate::error::Result<String>;
+            /// Renders this configuration node directly into a writer.
+            ///
+            /// Produces the same text as [`Expression::expr`]. The default
+            /// implementation writes the result of `expr`; sections with many
+            /// entries override it to avoid building the whole text in memory.
+            ///
+            /// # Parameters
+            /// - w: Destination of the rendered text.
+            ///
+            /// # Returns
+            /// Unit on success.
+            ///
+            /// # Errors
+            /// Returns an error if rendering or writing fails.
+            ///
+            /// # Examples
+            /// ```rust
+            /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+            /// use pgbouncer_config::pgbouncer_config::Expression;
+            /// let node = PgBouncerSetting::default();
+            /// let mut buf: Vec<u8> = Vec::new();
+            /// node.expr_to(&mut buf).unwrap();
+            /// assert_eq!(String::from_utf8(buf).unwrap(), node.expr().unwrap());
+            /// ```
+            fn expr_to(&self, w: &mut dyn std::io::Write) -> crate::error::Result<()> {
+                w.write_all(self.expr()?.as_bytes())?;
+                Ok(())
+            }
+            /// Brings this node into its canonical form.
+            ///
+            /// Reorders entries whose order has no meaning to PgBouncer (for
+            /// example user lists or `[databases]` entries) so that equal
+            /// configurations render to identical text. The default
+            /// implementation leaves the node unchanged.
+            ///
+            /// # Examples
+            /// ```rust
+            /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+            /// use pgbouncer_config::pgbouncer_config::Expression;
+            /// let mut node = PgBouncerSetting::default();
+            /// node.add_admin_user("ops").add_admin_user("admin").add_admin_user("ops");
+            /// node.canonicalize();
+            /// assert!(node.expr().unwrap().contains("admin_users = admin,ops\n"));
+            /// ```
+            fn canonicalize(&mut self) {}
             /// Returns the name of the section corresponding to the struct's type.
             ///
             /// This method provides a default implementation that uses the structure's type name
@@ -293,6 +396,24 @@ impl PgBouncerConfig {
         Err(PgBouncerError::PgBouncer("failed to get config".to_string()))
     }
 
+    /// Replaces secret references in the [databases] section with the values they point to.
+    ///
+    /// Call this on a copy of the definition right before rendering; the
+    /// definition itself should keep the references.
+    ///
+    /// # Parameters
+    /// - resolvers: Registry used to materialize the references.
+    ///
+    /// # Errors
+    /// Returns an error if a reference cannot be resolved.
+    pub fn resolve_secrets(&mut self, resolvers: &SecretResolvers) -> crate::error::Result<()> {
+        if let Ok(setting) = self.get_config_mut::<DatabasesSetting>() {
+            setting.resolve_secrets(resolvers)?;
+        }
+
+        Ok(())
+    }
+
     /// Generates a concatenated string representation of expressions from the settings.
     ///
     /// This function iterates over the `settings` collection, retrieves and consolidates
@@ -319,6 +440,287 @@ impl PgBouncerConfig {
         Ok(expr_res)
     }
 
+    /// Brings every section into its canonical form.
+    ///
+    /// Two configurations with the same meaning render to byte-identical text
+    /// after canonicalization, on every run and platform: sections are always
+    /// rendered in name order with `\n` line endings, and each section orders
+    /// the entries PgBouncer does not care about (see [`Expression::canonicalize`]).
+    /// The canonical form is what diffs and golden snapshots compare.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::{Database, DatabasesSetting};
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let config = |hosts: &[&str]| {
+    ///     let mut databases = DatabasesSetting::new();
+    ///     for host in hosts {
+    ///         databases.add_database(Database::new(host, 5432, "app", "secret", Some(&["app"])));
+    ///     }
+    ///     PgBouncerConfigBuilder::new(PgBouncerSetting::default(), databases).unwrap().build()
+    /// };
+    /// let (mut a, mut b) = (config(&["10.0.0.1", "10.0.0.2"]), config(&["10.0.0.2", "10.0.0.1"]));
+    /// assert_ne!(a.to_string(), b.to_string());
+    ///
+    /// a.canonicalize();
+    /// b.canonicalize();
+    /// assert_eq!(a.to_string(), b.to_string());
+    /// ```
+    pub fn canonicalize(&mut self) {
+        for setting in self.settings.values_mut() {
+            setting.canonicalize();
+        }
+    }
+
+    /// Renders all sections with the given layout options.
+    ///
+    /// With [`RenderOptions::default`] the result is the same as [`PgBouncerConfig::expr`].
+    ///
+    /// # Parameters
+    /// - options: Key order, `=` alignment, defaults, spacing, provenance header and
+    ///   checksum comment of the output.
+    ///
+    /// # Returns
+    /// A `String` containing the rendered configuration.
+    ///
+    /// # Errors
+    /// Returns an error if a section cannot be rendered.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::DatabasesSetting;
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    /// use pgbouncer_config::pgbouncer_config::render::RenderOptions;
+    ///
+    /// let cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new())
+    ///     .unwrap()
+    ///     .build();
+    /// let mut options = RenderOptions::new();
+    /// options.set_include_defaults(true).set_blank_lines(Some(1));
+    ///
+    /// let text = cfg.expr_with(&options).unwrap();
+    /// assert!(text.contains("server_lifetime = 3600\n"));
+    /// assert!(text.contains("[databases]\n\n[pgbouncer]\n"));
+    /// ```
+    pub fn expr_with(&self, options: &RenderOptions) -> crate::error::Result<String> {
+        let mut expr_res = options.provenance().map(|provenance| provenance.expr()).unwrap_or_default();
+
+        for (section_name, setting) in &self.settings {
+            let expr = setting.expr()?;
+            expr_res.push_str(&options.apply(section_name, &expr));
+            expr_res.push_str(options.section_separator());
+        }
+        if options.checksum() {
+            expr_res = embed_checksum(&expr_res);
+        }
+
+        Ok(expr_res)
+    }
+
+    /// Renders all sections directly into a writer.
+    ///
+    /// Produces the same text as [`PgBouncerConfig::expr`] without building
+    /// it in memory first.
+    ///
+    /// # Parameters
+    /// - w: Destination of the rendered text.
+    ///
+    /// # Returns
+    /// Unit on success.
+    ///
+    /// # Errors
+    /// Returns an error if a section cannot be rendered or writing fails.
+    pub fn expr_to(&self, w: &mut dyn std::io::Write) -> crate::error::Result<()> {
+        for setting in self.settings.values() {
+            setting.expr_to(w)?;
+            w.write_all(b"\n")?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks semantic constraints that rendering alone does not catch.
+    ///
+    /// Rules cover combinations of keys in the [pgbouncer] section (for example
+    /// `auth_type = hba` without `auth_hba_file`) and the entries of the
+    /// [databases] section (for example a database name exposed twice).
+    ///
+    /// # Returns
+    /// Every issue found, in section order; empty when the configuration is valid.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::DatabasesSetting;
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::{AuthType, PgBouncerSetting};
+    ///
+    /// let mut pgbouncer_setting = PgBouncerSetting::default();
+    /// pgbouncer_setting.set_auth_type(AuthType::Hba);
+    /// let cfg = PgBouncerConfigBuilder::new(pgbouncer_setting, DatabasesSetting::new())
+    ///     .unwrap()
+    ///     .build();
+    ///
+    /// let issues = cfg.validate();
+    /// assert!(issues.iter().any(|issue| issue.key() == Some("auth_hba_file")));
+    /// ```
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        if let Ok(setting) = self.get_config::<PgBouncerSetting>() {
+            setting.validate_into(&mut issues);
+        }
+        if let Ok(setting) = self.get_config::<DatabasesSetting>() {
+            setting.validate_into(&mut issues);
+        }
+
+        issues
+    }
+
+    /// Checks semantic constraints and the keys supported by a PgBouncer release.
+    ///
+    /// Runs [`PgBouncerConfig::validate`] and additionally reports every key
+    /// introduced after `target`, according to the `since` column of
+    /// [`settings_catalog`].
+    ///
+    /// # Parameters
+    /// - target: PgBouncer release the configuration is deployed to.
+    ///
+    /// # Returns
+    /// Every issue found; empty when the configuration is valid for `target`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::DatabasesSetting;
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    /// use pgbouncer_config::pgbouncer_config::validation::TargetVersion;
+    ///
+    /// let mut pgbouncer_setting = PgBouncerSetting::default();
+    /// pgbouncer_setting.set_auth_ident_file(Some("/etc/pgbouncer/ident.map"));
+    /// let cfg = PgBouncerConfigBuilder::new(pgbouncer_setting, DatabasesSetting::new())
+    ///     .unwrap()
+    ///     .build();
+    ///
+    /// assert!(cfg.validate_for(TargetVersion::V1_23).is_empty());
+    /// let issues = cfg.validate_for(TargetVersion::V1_22);
+    /// assert_eq!(issues[0].key(), Some("auth_ident_file"));
+    /// ```
+    pub fn validate_for(&self, target: TargetVersion) -> Vec<ValidationIssue> {
+        let mut issues = self.validate();
+        if let Ok(setting) = self.get_config::<PgBouncerSetting>() {
+            issues.extend(version_issues("pgbouncer", setting.configured_keys(), target));
+        }
+        if let Ok(setting) = self.get_config::<DatabasesSetting>() {
+            issues.extend(version_issues("databases", setting.configured_keys(), target));
+        }
+        if let Ok(setting) = self.get_config::<PeersSetting>() {
+            issues.extend(version_issues("peers", setting.configured_keys(), target));
+        }
+
+        issues
+    }
+
+    /// Removes a contained configuration section and returns it.
+    ///
+    /// # Parameters
+    /// - T: Concrete type of the section to remove.
+    ///
+    /// # Returns
+    /// The removed section.
+    ///
+    /// # Errors
+    /// Returns an error if no section of type `T` is found.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::DatabasesSetting;
+    /// use pgbouncer_config::pgbouncer_config::peers_setting::PeersSetting;
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new())
+    ///     .unwrap()
+    ///     .build();
+    /// cfg.replace_config(PeersSetting::new());
+    ///
+    /// assert!(cfg.contains_section("peers"));
+    /// let _peers: PeersSetting = cfg.remove_config::<PeersSetting>().unwrap();
+    /// assert!(!cfg.contains_section("peers"));
+    /// assert!(cfg.remove_config::<PeersSetting>().is_err());
+    /// ```
+    ///
+    /// # Notes
+    /// - If multiple nodes of the same type are present, the first match is removed.
+    pub fn remove_config<T: Expression + 'static>(&mut self) -> crate::error::Result<T> {
+        let name = self
+            .settings
+            .iter()
+            .find(|(_, config)| (config.as_ref() as &dyn Any).is::<T>())
+            .map(|(name, _)| name.clone())
+            .ok_or(PgBouncerError::PgBouncer("failed to remove config".to_string()))?;
+        let config: Box<dyn Any> = self.settings.remove(&name).expect("section found above");
+
+        Ok(*config.downcast::<T>().expect("section type checked above"))
+    }
+
+    /// Adds a configuration section, replacing the section with the same name if any.
+    ///
+    /// Unlike the builder, which rejects duplicate sections, this overwrites
+    /// the existing section in place.
+    ///
+    /// # Parameters
+    /// - config: Section to store under its [`Expression::section_name`].
+    ///
+    /// # Returns
+    /// The replaced section, or `None` if the section was not present.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::DatabasesSetting;
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new())
+    ///     .unwrap()
+    ///     .build();
+    /// let mut pgbouncer_setting = PgBouncerSetting::default();
+    /// pgbouncer_setting.set_listen_port(7432);
+    ///
+    /// assert!(cfg.replace_config(pgbouncer_setting).is_some());
+    /// assert!(cfg.to_string().contains("listen_port = 7432"));
+    /// ```
+    pub fn replace_config<C: Expression + 'static>(&mut self, config: C) -> Option<Box<dyn Expression>> {
+        self.settings.insert(config.section_name().to_string(), Box::new(config))
+    }
+
+    /// Iterates over the contained sections in render order.
+    ///
+    /// # Returns
+    /// An iterator of section names and the sections stored under them.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::DatabasesSetting;
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new())
+    ///     .unwrap()
+    ///     .build();
+    /// let names = cfg.sections().map(|(name, _)| name).collect::<Vec<_>>();
+    /// assert_eq!(names, vec!["databases", "pgbouncer"]);
+    /// ```
+    pub fn sections(&self) -> impl Iterator<Item = (&str, &dyn Expression)> {
+        self.settings.iter().map(|(name, config)| (name.as_str(), config.as_ref()))
+    }
+
+    /// Returns true if a section with the given name (e.g. `peers`) is present.
+    pub fn contains_section(&self, name: &str) -> bool {
+        self.settings.contains_key(name)
+    }
+
     pub(crate) fn add_config<C: Expression + 'static>(&mut self, config: C) -> crate::error::Result<()> {
         if self.settings.contains_key(config.section_name()) {
             return Err(PgBouncerError::PgBouncer(format!("section {} already exists", config.section_name())));
@@ -385,10 +787,84 @@ where
 }
 
 #[cfg(feature = "io")]
-impl ParserIniFromStr for PgBouncerConfig {
-    type Error = PgBouncerError;
+impl PgBouncerConfig {
+    /// Parses pgbouncer.ini text, resolving `%include` directives against a base directory.
+    ///
+    /// [`ParserIniFromStr::parse_from_str`] behaves like this method with the
+    /// current working directory as base directory.
+    ///
+    /// # Parameters
+    /// - value: pgbouncer.ini text.
+    /// - base_dir: Directory used to resolve relative `%include` paths, usually
+    ///   the directory containing the parsed file.
+    ///
+    /// # Returns
+    /// The parsed configuration.
+    ///
+    /// # Errors
+    /// Returns an error if an include cannot be resolved (see
+    /// [`resolve_includes`]) or a section cannot be parsed.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use std::path::Path;
+    /// use pgbouncer_config::pgbouncer_config::PgBouncerConfig;
+    ///
+    /// let ini = "\
+    /// [pgbouncer]\n\
+    /// listen_addr = 127.0.0.1\n\
+    /// listen_port = 6432\n\
+    /// auth_type = md5\n\
+    /// max_client_conn = 100\n\
+    /// default_pool_size = 20\n\
+    /// pool_mode = session\n\
+    /// ";
+    /// let cfg = PgBouncerConfig::parse_from_str_with_base_dir(ini, Path::new("/etc/pgbouncer")).unwrap();
+    /// assert!(cfg.expr().unwrap().contains("listen_port = 6432"));
+    /// ```
+    pub fn parse_from_str_with_base_dir(value: &str, base_dir: &Path) -> crate::error::Result<Self> {
+        Self::parse_from_str_with_warnings(value, base_dir).map(|(config, _)| config)
+    }
 
-    fn parse_from_str(value: &str) -> Result<Self, Self::Error> {
+    /// Parses pgbouncer.ini text like [`parse_from_str_with_base_dir`](Self::parse_from_str_with_base_dir),
+    /// also reporting the deprecated keys and values it contains.
+    ///
+    /// Deprecated settings are still accepted and kept as they are written;
+    /// see [`deprecated_settings`](settings_catalog::deprecated_settings) for
+    /// the keys and values reported.
+    ///
+    /// # Parameters
+    /// - value: pgbouncer.ini text.
+    /// - base_dir: Directory used to resolve relative `%include` paths.
+    ///
+    /// # Returns
+    /// The parsed configuration and one warning per deprecated key or value,
+    /// in order of appearance.
+    ///
+    /// # Errors
+    /// Returns an error if an include cannot be resolved or a section cannot be parsed.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use std::path::Path;
+    /// use pgbouncer_config::pgbouncer_config::PgBouncerConfig;
+    ///
+    /// let ini = "\
+    /// [pgbouncer]\n\
+    /// listen_addr = 127.0.0.1\n\
+    /// listen_port = 6432\n\
+    /// auth_type = plain\n\
+    /// auth_file = /etc/pgbouncer/userlist.txt\n\
+    /// max_client_conn = 100\n\
+    /// default_pool_size = 20\n\
+    /// pool_mode = session\n\
+    /// ";
+    /// let (_, warnings) = PgBouncerConfig::parse_from_str_with_warnings(ini, Path::new(".")).unwrap();
+    /// assert_eq!(warnings[0].key(), Some("auth_type"));
+    /// ```
+    pub fn parse_from_str_with_warnings(value: &str, base_dir: &Path) -> crate::error::Result<(Self, Vec<ValidationIssue>)> {
+        let value = resolve_includes(value, base_dir)?;
+        let value = value.as_str();
         let section_re = Regex::new(r"(?m)^\[([^]\r\n]+)]\s*$")?;
 
         let mut headers = Vec::new();
@@ -411,35 +887,58 @@ impl ParserIniFromStr for PgBouncerConfig {
                 value.len()
             };
 
+            // Keep the line number of each line to locate parse errors
+            let first_line = value[..body_start].matches('\n').count() + 1;
             let mut body_lines = Vec::new();
-            for line in value[body_start..body_end].lines() {
+            for (offset, line) in value[body_start..body_end].lines().enumerate() {
                 if is_comment(line) || line.trim().is_empty() {
                     continue;
                 }
+                if !line.contains('=') {
+                    return Err(PgBouncerError::ParseError {
+                        section: Some(name.to_string()),
+                        key: None,
+                        line: Some(first_line + offset),
+                        column: Some(1),
+                        reason: format!("Invalid format key=value: {}", line.trim()),
+                    });
+                }
 
-                body_lines.push(line.to_string());
-            }
-
-            while body_lines.first().map_or(false, |l| l.trim().is_empty()) {
-                body_lines.remove(0);
-            }
-            while body_lines.last().map_or(false, |l| l.trim().is_empty()) {
-                body_lines.pop();
+                body_lines.push((first_line + offset, line));
             }
 
-            let body = body_lines.join("\n");
+            sections.insert(name.to_string(), body_lines);
+        }
 
-            sections.insert(name.to_string(), body);
+        let mut deprecations = Vec::new();
+        for (name, lines) in &sections {
+            for (number, line) in lines {
+                let Some((key, value)) = line.split_once('=') else { continue };
+                if let Some(deprecated) = find_deprecated_setting(name, key, value) {
+                    deprecations.push((*number, ValidationIssue::warning(name, Some(key.trim()), &deprecated.message())));
+                }
+            }
         }
+        deprecations.sort_by_key(|(number, _)| *number);
+        let warnings = deprecations.into_iter().map(|(_, warning)| warning).collect();
+
+        let parse_section = |name: &str| {
+            sections.get(name).map(|lines| {
+                let body = lines.iter().map(|(_, line)| *line).collect::<Vec<_>>().join("\n");
+                (body, lines.as_slice())
+            })
+        };
 
-        let database_setting = if let Some(section_value) = sections.get("databases") {
-            DatabasesSetting::parse_from_str(section_value)?
+        let database_setting = if let Some((section_value, lines)) = parse_section("databases") {
+            DatabasesSetting::parse_from_str(&section_value)
+                .map_err(|e| locate_parse_error(e, "databases", lines))?
         } else {
             DatabasesSetting::new()
         };
 
-        let pgbouncer_setting = if let Some(section_value) = sections.get("pgbouncer") {
-            PgBouncerSetting::parse_from_str(section_value)?
+        let pgbouncer_setting = if let Some((section_value, lines)) = parse_section("pgbouncer") {
+            PgBouncerSetting::parse_from_str(&section_value)
+                .map_err(|e| locate_parse_error(e, "pgbouncer", lines))?
         } else {
             PgBouncerSetting::default()
         };
@@ -448,7 +947,61 @@ impl ParserIniFromStr for PgBouncerConfig {
         pgbouncer_config.add_config(pgbouncer_setting)?;
         pgbouncer_config.add_config(database_setting)?;
 
-        Ok(pgbouncer_config)
+        // [peers] is optional; only keep it when the source defines it.
+        if let Some((section_value, lines)) = parse_section("peers") {
+            let peers_setting = PeersSetting::parse_from_str(&section_value)
+                .map_err(|e| locate_parse_error(e, "peers", lines))?;
+            pgbouncer_config.add_config(peers_setting)?;
+        }
+
+        // Sections this crate does not model are kept as they are
+        for name in sections.keys().filter(|name| !matches!(name.as_str(), "pgbouncer" | "databases" | "peers")) {
+            if let Some((section_value, lines)) = parse_section(name) {
+                let raw_section = RawSection::parse(name, &section_value)
+                    .map_err(|e| locate_parse_error(e, name, lines))?;
+                pgbouncer_config.add_config(raw_section)?;
+            }
+        }
+
+        Ok((pgbouncer_config, warnings))
+    }
+}
+
+/// Points a parse error of a section at the line it comes from.
+///
+/// The line is the one defining the key of the error, or else the one quoted
+/// in its reason. For a key, the column is the one where its value starts.
+#[cfg(feature = "io")]
+fn locate_parse_error(error: PgBouncerError, section: &str, lines: &[(usize, &str)]) -> PgBouncerError {
+    let error = error.in_section(section);
+    let PgBouncerError::ParseError { key, line: None, reason, .. } = &error else {
+        return error;
+    };
+
+    let location = match key {
+        Some(key) => lines.iter().find_map(|(number, line)| {
+            let (line_key, _) = line.split_once('=')?;
+            let value_start = line.len() - line[line_key.len() + 1..].trim_start().len();
+            (line_key.trim() == key).then(|| (*number, line[..value_start].chars().count() + 1))
+        }),
+        None => lines
+            .iter()
+            .find(|(_, line)| reason.contains(line.trim()))
+            .map(|(number, line)| (*number, line.len() - line.trim_start().len() + 1)),
+    };
+
+    match location {
+        Some((line, column)) => error.at_location(line, column),
+        None => error,
+    }
+}
+
+#[cfg(feature = "io")]
+impl ParserIniFromStr for PgBouncerConfig {
+    type Error = PgBouncerError;
+
+    fn parse_from_str(value: &str) -> Result<Self, Self::Error> {
+        Self::parse_from_str_with_base_dir(value, Path::new("."))
     }
 }
 
@@ -540,6 +1093,23 @@ pool_mode = session\n\
         assert_eq!(cfg[&Dummy.section_name()].expr().unwrap(), "[dummy]\n");
     }
 
+    #[test]
+    fn remove_replace_and_iterate_sections() {
+        let mut cfg = PgBouncerConfig::new();
+        assert!(cfg.replace_config(Dummy).is_none());
+        assert!(cfg.replace_config(Dummy).is_some());
+        cfg.add_config(Dummy2).unwrap();
+
+        let names = cfg.sections().map(|(name, section)| (name, section.section_name())).collect::<Vec<_>>();
+        assert_eq!(names, vec![("dummy", "dummy"), ("dummy2", "dummy2")]);
+
+        cfg.remove_config::<Dummy>().unwrap();
+        assert!(!cfg.contains_section("dummy"));
+        assert!(cfg.contains_section("dummy2"));
+        assert!(cfg.remove_config::<Dummy>().is_err());
+        assert_eq!(cfg.len(), 1);
+    }
+
     #[test]
     fn from_same_slice_builds_config() {
         let arr = [Dummy, Dummy];
@@ -610,4 +1180,146 @@ app = dbname=app host=127.0.0.1 port=5432\n\
         assert!(!text.contains("# a comment"));
         assert!(!text.contains("; inline"));
     }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn parse_from_str_keeps_peers_section() {
+        let ini = format!("{}\n[peers]\n1 = host=/tmp/pgbouncer1\n2 = host=/tmp/pgbouncer2\n", minimal_pgbouncer_section());
+        let cfg = PgBouncerConfig::parse_from_str(&ini).expect("parse ok");
+        assert_eq!(cfg.len(), 3);
+
+        let reparsed = PgBouncerConfig::parse_from_str(&cfg.expr().unwrap()).expect("reparse ok");
+        assert!(reparsed.expr().unwrap().contains("[peers]\n1 = host=/tmp/pgbouncer1\n2 = host=/tmp/pgbouncer2\n"));
+        assert_eq!(PgBouncerConfig::parse_from_str(&minimal_pgbouncer_section()).unwrap().len(), 2);
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn parse_errors_point_at_the_offending_line() {
+        let location = |ini: &str| match PgBouncerConfig::parse_from_str(ini).unwrap_err() {
+            PgBouncerError::ParseError { section, key, line, column, .. } => (section, key, line, column),
+            other => panic!("unexpected error: {}", other),
+        };
+
+        let ini = minimal_pgbouncer_section().replace("listen_port = 6432", "listen_port = many");
+        assert_eq!(location(&ini), (Some("pgbouncer".to_string()), Some("listen_port".to_string()), Some(3), Some(15)));
+
+        let ini = format!("{}\n[databases]\napp = dbname=app host=db port=high\n", minimal_pgbouncer_section());
+        assert_eq!(location(&ini), (Some("databases".to_string()), Some("app".to_string()), Some(10), Some(7)));
+
+        let ini = format!("{}not a setting\n", minimal_pgbouncer_section());
+        assert_eq!(location(&ini), (Some("pgbouncer".to_string()), None, Some(8), Some(1)));
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn deprecated_settings_are_reported_and_kept() {
+        let ini = format!("{}job_name = pgbouncer\n", minimal_pgbouncer_section().replace("auth_type = md5", "auth_type = plain"));
+        let (cfg, warnings) = PgBouncerConfig::parse_from_str_with_warnings(&ini, Path::new(".")).unwrap();
+
+        let keys = warnings.iter().map(|warning| warning.key()).collect::<Vec<_>>();
+        assert_eq!(keys, [Some("auth_type"), Some("job_name")]);
+        assert!(warnings.iter().all(|warning| !warning.is_error()));
+        assert!(cfg.expr().unwrap().contains("job_name = pgbouncer"));
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn canonicalize_renders_reordered_configs_identically() {
+        let ini = |admin_users: &str, databases: &str, peers: &str, users: &str| {
+            format!(
+                "{}admin_users = {}\n[databases]\n{}[peers]\n{}[users]\n{}",
+                minimal_pgbouncer_section(), admin_users, databases, peers, users,
+            )
+        };
+        let mut a = PgBouncerConfig::parse_from_str(&ini(
+            "ops,admin",
+            "app = dbname=app host=10.0.0.2\nweb = dbname=web host=10.0.0.1\n",
+            "2 = host=/tmp/pgbouncer2\n1 = host=/tmp/pgbouncer1\n",
+            "bob = pool_mode=session\nalice = pool_mode=transaction\n",
+        )).expect("parse ok");
+        let mut b = PgBouncerConfig::parse_from_str(&ini(
+            "admin,ops",
+            "web = dbname=web host=10.0.0.1\napp = dbname=app host=10.0.0.2\n",
+            "1 = host=/tmp/pgbouncer1\n2 = host=/tmp/pgbouncer2\n",
+            "alice = pool_mode=transaction\nbob = pool_mode=session\n",
+        )).expect("parse ok");
+        assert_ne!(a.expr().unwrap(), b.expr().unwrap());
+
+        a.canonicalize();
+        b.canonicalize();
+        assert_eq!(a.expr().unwrap(), b.expr().unwrap());
+        assert!(!a.expr().unwrap().contains('\r'));
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn expr_to_streams_the_same_text_as_expr() {
+        use crate::io::write::Writer;
+
+        let ini = format!(
+            "{}\n[databases]\napp = dbname=app host=10.0.0.1 user=app password='s3 cret'\nweb = dbname=web host=10.0.0.2,10.0.0.3\n* = host=10.0.0.9\n[peers]\n1 = host=/tmp/pgbouncer1\n[users]\nalice = pool_mode=transaction\n",
+            minimal_pgbouncer_section(),
+        );
+        let cfg = PgBouncerConfig::parse_from_str(&ini).expect("parse ok");
+        assert_eq!(cfg.len(), 4);
+
+        for (name, section) in cfg.sections() {
+            let mut streamed = Vec::new();
+            section.expr_to(&mut streamed).unwrap();
+            assert_eq!(String::from_utf8(streamed).unwrap(), section.expr().unwrap(), "[{}]", name);
+        }
+
+        let mut streamed = Vec::new();
+        cfg.expr_to(&mut streamed).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), cfg.expr().unwrap());
+
+        let mut written = Vec::new();
+        Writer::new(&mut written).write(&cfg).unwrap();
+        assert_eq!(String::from_utf8(written).unwrap(), format!("{}\n", cfg.expr().unwrap()));
+    }
+
+    #[test]
+    fn validate_reports_cross_field_issues() {
+        use crate::builder::PgBouncerConfigBuilder;
+        use crate::pgbouncer_config::databases_setting::Database;
+        use crate::pgbouncer_config::pgbouncer_setting::AuthType;
+        use crate::pgbouncer_config::validation::Severity;
+
+        let mut pgbouncer_setting = PgBouncerSetting::default();
+        pgbouncer_setting.set_auth_type(AuthType::Hba);
+        pgbouncer_setting.set_default_pool_size(50);
+        pgbouncer_setting.set_max_client_conn(10);
+        let mut databases_setting = DatabasesSetting::new();
+        databases_setting.add_database(Database::new("10.0.0.1", 5432, "u", "p", Some(&["app"][..])));
+        databases_setting.add_database(Database::new("10.0.0.2", 5432, "u", "p", Some(&["app"][..])));
+        let cfg = PgBouncerConfigBuilder::new(pgbouncer_setting, databases_setting).unwrap().build();
+
+        let issues = cfg.validate();
+        let keys = issues.iter().map(|issue| (issue.severity(), issue.key().unwrap())).collect::<Vec<_>>();
+        assert_eq!(keys, vec![
+            (Severity::Error, "auth_hba_file"),
+            (Severity::Warning, "default_pool_size"),
+            (Severity::Error, "app"),
+        ]);
+
+        let valid = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new()).unwrap().build();
+        assert!(valid.validate().is_empty());
+    }
+
+    #[test]
+    fn validate_for_checks_the_version_of_extra_keys() {
+        use crate::builder::PgBouncerConfigBuilder;
+        use crate::pgbouncer_config::validation::TargetVersion;
+
+        let mut pgbouncer_setting = PgBouncerSetting::default();
+        pgbouncer_setting.set_extra("max_prepared_statements", "200");
+        let cfg = PgBouncerConfigBuilder::new(pgbouncer_setting, DatabasesSetting::new()).unwrap().build();
+
+        let issues = cfg.validate_for(TargetVersion::V1_20);
+        assert_eq!(issues.len(), 1, "{:?}", issues);
+        assert!(issues[0].is_error());
+        assert_eq!(issues[0].key(), Some("max_prepared_statements"));
+        assert!(cfg.validate_for(TargetVersion::V1_21).is_empty());
+    }
 }
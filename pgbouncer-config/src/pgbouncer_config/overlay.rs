@@ -0,0 +1,179 @@
+//! Layered definitions.
+//!
+//! A base definition can be refined by environment-specific layers (e.g.
+//! `base.toml` + `prod.toml`). A layer is a partial definition in the same
+//! format: it only lists what differs, and [`PgBouncerConfig::overlay`]
+//! merges it section by section, key by key and database by database.
+
+use crate::error::PgBouncerError;
+use crate::io::ConfigFileFormat;
+use crate::pgbouncer_config::PgBouncerConfig;
+
+/// Type tag of the [databases] section in a definition.
+const DATABASES_SETTING_TAG: &str = "DatabasesSetting";
+
+impl PgBouncerConfig {
+    /// Merges a partial definition on top of this configuration.
+    ///
+    /// Sections and keys present in the layer replace the ones of the
+    /// configuration, objects are merged recursively and arrays are replaced,
+    /// with one exception: database entries of the [databases] section are
+    /// matched by name. A layer entry sharing a database name or alias with an
+    /// existing entry is merged into that entry key by key; any other entry is
+    /// appended and must therefore be a complete database entry. Sections
+    /// missing from the configuration are added as a whole.
+    ///
+    /// # Parameters
+    /// - layer: Text of the layer, a partial TOML or JSON definition.
+    /// - format: Format of the layer; [`ConfigFileFormat::Auto`] detects it.
+    ///
+    /// # Returns
+    /// Unit on success; the configuration is left untouched on error.
+    ///
+    /// # Errors
+    /// Returns an error if the layer is INI, cannot be parsed, or the merged
+    /// definition is not a valid configuration.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+    /// use pgbouncer_config::io::ConfigFileFormat;
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::{Database, DatabasesSetting};
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut databases = DatabasesSetting::new();
+    /// databases.add_database(Database::new("10.0.0.10", 5432, "app", "secret", Some(&["app"])));
+    /// let mut cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), databases).unwrap().build();
+    ///
+    /// let prod = r#"
+    /// [pgbouncer.PgBouncerSetting]
+    /// listen_port = 7432
+    ///
+    /// [[databases.DatabasesSetting.databases]]
+    /// databases = ["app"]
+    /// host = "prod-db.internal"
+    /// "#;
+    /// cfg.overlay(prod, ConfigFileFormat::TOML).unwrap();
+    ///
+    /// let text = cfg.expr().unwrap();
+    /// assert!(text.contains("listen_port = 7432"));
+    /// assert!(text.contains("app = dbname=app host=prod-db.internal port=5432"));
+    /// ```
+    pub fn overlay(&mut self, layer: &str, format: ConfigFileFormat) -> crate::error::Result<()> {
+        let format = match format {
+            ConfigFileFormat::Auto => ConfigFileFormat::detect(layer),
+            format => format,
+        };
+        let layer: serde_json::Value = match format {
+            ConfigFileFormat::JSON => serde_json::from_str(layer)?,
+            ConfigFileFormat::TOML | ConfigFileFormat::Auto => toml::from_str(layer)?,
+            ConfigFileFormat::INI => {
+                return Err(PgBouncerError::PgBouncer("INI files cannot be used as overlay layers".to_string()));
+            },
+        };
+
+        let mut merged = serde_json::to_value(&*self)?;
+        merge_value(&mut merged, layer, &[]);
+        *self = serde_json::from_value(merged)?;
+
+        Ok(())
+    }
+}
+
+/// Merges `layer` into `base`; `path` holds the keys leading to `base`.
+fn merge_value(base: &mut serde_json::Value, layer: serde_json::Value, path: &[&str]) {
+    match (base, layer) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(current) => {
+                        let mut path = path.to_vec();
+                        path.push(&key);
+                        merge_value(current, value, &path);
+                    },
+                    None => {
+                        base.insert(key, value);
+                    },
+                }
+            }
+        },
+        (serde_json::Value::Array(base), serde_json::Value::Array(layer)) if is_database_entries(path) => {
+            for entry in layer {
+                let names = exposed_names(&entry);
+                let matched = base
+                    .iter_mut()
+                    .find(|current| exposed_names(current).iter().any(|name| names.contains(name)));
+                match matched {
+                    Some(current) => merge_value(current, entry, &[]),
+                    None => base.push(entry),
+                }
+            }
+        },
+        (base, layer) => *base = layer,
+    }
+}
+
+/// Returns whether `path` leads to the entries of a [databases] section (`<section>.DatabasesSetting.databases`).
+pub(super) fn is_database_entries(path: &[&str]) -> bool {
+    matches!(path, [_, DATABASES_SETTING_TAG, "databases"])
+}
+
+/// Database names and aliases a serialized database entry exposes.
+pub(super) fn exposed_names(entry: &serde_json::Value) -> Vec<&str> {
+    let databases = entry
+        .get("databases")
+        .and_then(serde_json::Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(serde_json::Value::as_str);
+    let aliases = entry
+        .get("aliases")
+        .and_then(serde_json::Value::as_object)
+        .into_iter()
+        .flat_map(|aliases| aliases.keys().map(String::as_str));
+
+    databases.chain(aliases).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::PgBouncerConfigBuilder;
+    use crate::io::ConfigFileFormat;
+    use crate::pgbouncer_config::databases_setting::{Database, DatabasesSetting};
+    use crate::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+
+    #[test]
+    fn overlay_merges_keys_and_databases_by_name() {
+        let mut databases = DatabasesSetting::new();
+        databases.add_database(Database::new("10.0.0.10", 5432, "app", "secret", Some(&["app"])));
+        databases.add_database(Database::new("10.0.0.11", 5432, "report", "secret", Some(&["report"])));
+        let mut cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), databases).unwrap().build();
+
+        let layer = r#"{
+            "pgbouncer": {"PgBouncerSetting": {"pool_mode": "Transaction"}},
+            "databases": {"DatabasesSetting": {"databases": [
+                {"databases": ["report"], "port": 6543},
+                {"host": "10.0.0.12", "port": 5432, "user": "audit", "password": "secret", "databases": ["audit"],
+                 "ignore_databases": [], "is_output_credentials_to_config": false}
+            ]}}
+        }"#;
+        cfg.overlay(layer, ConfigFileFormat::Auto).unwrap();
+
+        let text = cfg.expr().unwrap();
+        assert!(text.contains("pool_mode = transaction"));
+        assert!(text.contains("listen_port = 6432"));
+        assert!(text.contains("app = dbname=app host=10.0.0.10 port=5432"));
+        assert!(text.contains("report = dbname=report host=10.0.0.11 port=6543"));
+        assert!(text.contains("audit = dbname=audit host=10.0.0.12 port=5432"));
+    }
+
+    #[test]
+    fn overlay_leaves_config_untouched_on_invalid_layer() {
+        let mut cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new()).unwrap().build();
+        let before = cfg.expr().unwrap();
+
+        assert!(cfg.overlay("[pgbouncer.PgBouncerSetting]\nlisten_port = \"x\"\n", ConfigFileFormat::TOML).is_err());
+        assert!(cfg.overlay("[pgbouncer]\nlisten_port = 7432\n", ConfigFileFormat::INI).is_err());
+        assert_eq!(cfg.expr().unwrap(), before);
+    }
+}
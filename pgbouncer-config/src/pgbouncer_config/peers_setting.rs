@@ -0,0 +1,424 @@
+use serde::{Deserialize, Serialize};
+use crate::pgbouncer_config::Expression;
+#[cfg(feature = "io")]
+use std::collections::HashMap;
+#[cfg(feature = "io")]
+use regex::Regex;
+use crate::error::PgBouncerError;
+#[cfg(feature = "io")]
+use crate::utils::parser::{parse_key_value, ParserIniFromStr};
+#[cfg(feature = "diff")]
+use crate::utils::diff::Diffable;
+
+/// Smallest peer ID accepted by PgBouncer.
+pub const MIN_PEER_ID: u16 = 1;
+/// Largest peer ID accepted by PgBouncer.
+pub const MAX_PEER_ID: u16 = 16383;
+
+/// Peers section settings.
+///
+/// Represents the [peers] section of pgbouncer.ini, used when several
+/// PgBouncer processes share a port with `so_reuseport` and forward cancel
+/// requests to each other. Entries are kept ordered by peer ID and a peer ID
+/// appears at most once.
+///
+/// # Fields
+/// - peers: List of peer entries.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PeersSetting {
+    peers: Vec<Peer>,
+}
+
+impl PeersSetting {
+    /// Create an empty PeersSetting.
+    ///
+    /// # Returns
+    /// The initialized PeersSetting with no peers.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::peers_setting::PeersSetting;
+    /// let settings = PeersSetting::new();
+    /// assert!(settings.peers().is_empty());
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            peers: vec![],
+        }
+    }
+
+    /// Add a Peer entry, replacing an existing entry with the same peer ID.
+    ///
+    /// # Parameters
+    /// - peer: The Peer to add.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::peers_setting::{PeersSetting, Peer};
+    /// let mut settings = PeersSetting::new();
+    /// settings.add_peer(Peer::new(2, "/tmp/pgbouncer2").unwrap());
+    /// settings.add_peer(Peer::new(1, "/tmp/pgbouncer1").unwrap());
+    /// assert_eq!(settings.peers()[0].peer_id(), 1);
+    /// ```
+    pub fn add_peer(&mut self, peer: Peer) {
+        match self.peers.binary_search_by_key(&peer.peer_id, |p| p.peer_id) {
+            Ok(index) => self.peers[index] = peer,
+            Err(index) => self.peers.insert(index, peer),
+        }
+    }
+
+    /// Returns the peers ordered by peer ID.
+    ///
+    /// # Returns
+    /// A slice of the configured peers.
+    pub fn peers(&self) -> &[Peer] {
+        &self.peers
+    }
+
+    /// Returns the keys used by any peer entry.
+    pub(crate) fn configured_keys(&self) -> Vec<&'static str> {
+        let mut keys = Vec::new();
+        if !self.peers.is_empty() {
+            keys.push("host");
+        }
+        if self.peers.iter().any(|peer| peer.port.is_some()) {
+            keys.push("port");
+        }
+        if self.peers.iter().any(|peer| peer.pool_size.is_some()) {
+            keys.push("pool_size");
+        }
+
+        keys
+    }
+}
+
+impl Default for PeersSetting {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[typetag::serde]
+impl Expression for PeersSetting {
+    /// Render the [peers] section as configuration text.
+    ///
+    /// # Returns
+    /// The configuration text for the [peers] section.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::peers_setting::{PeersSetting, Peer};
+    /// use pgbouncer_config::pgbouncer_config::Expression;
+    ///
+    /// let mut settings = PeersSetting::new();
+    /// settings.add_peer(Peer::new(1, "/tmp/pgbouncer1").unwrap());
+    /// let text = settings.expr().unwrap();
+    /// assert_eq!(text, "[peers]\n1 = host=/tmp/pgbouncer1\n\n");
+    /// ```
+    fn expr(&self) -> crate::error::Result<String> {
+        let mut text = String::new();
+        text.push_str("[peers]\n");
+        for peer in &self.peers {
+            text.push_str(&peer.expr());
+        }
+        text.push('\n');
+
+        Ok(text)
+    }
+
+    fn expr_to(&self, w: &mut dyn std::io::Write) -> crate::error::Result<()> {
+        w.write_all(b"[peers]\n")?;
+        for peer in &self.peers {
+            w.write_all(peer.expr().as_bytes())?;
+        }
+        w.write_all(b"\n")?;
+
+        Ok(())
+    }
+
+    /// Sorts the peers by id.
+    fn canonicalize(&mut self) {
+        self.peers.sort_by_key(|peer| peer.peer_id);
+    }
+
+    fn section_name(&self) -> &'static str {
+        "peers"
+    }
+
+    /// Renders the [peers] section; the text is the same as [`Expression::expr`].
+    fn to_template_string(&self) -> Result<String, PgBouncerError> {
+        self.expr()
+    }
+
+    /// Parses the text of [`Expression::to_template_string`]; the `[peers]` header is optional.
+    ///
+    /// # Errors
+    /// Returns an error if a peer line is invalid, or if the `io` feature is disabled.
+    fn from_template_string(s: &str) -> Result<Self, PgBouncerError> where Self: Sized {
+        #[cfg(feature = "io")]
+        {
+            let s = s.trim_start();
+            Self::parse_from_str(s.strip_prefix("[peers]").unwrap_or(s))
+        }
+        #[cfg(not(feature = "io"))]
+        {
+            let _ = s;
+            Err(PgBouncerError::PgBouncer("parsing [peers] requires the io feature".to_string()))
+        }
+    }
+}
+
+#[cfg(feature = "io")]
+impl ParserIniFromStr for PeersSetting {
+    type Error = PgBouncerError;
+
+    fn parse_from_str(value: &str) -> Result<Self, Self::Error> {
+        let mut peers_setting = PeersSetting::new();
+        for value_line in value.trim().split("\n") {
+            if value_line.trim().is_empty() {
+                continue;
+            }
+
+            let (peer_id, _) = parse_key_value(value_line)?;
+            let peer = Peer::parse_from_str(value_line)
+                .map_err(|e| e.at_key("peers", &peer_id))?;
+            peers_setting.add_peer(peer);
+        }
+
+        Ok(peers_setting)
+    }
+}
+
+#[cfg(feature = "diff")]
+#[typetag::serde]
+impl Diffable for PeersSetting {}
+
+/// A single peer entry.
+///
+/// Describes how to reach another PgBouncer process of the same peering group.
+///
+/// # Fields
+/// - peer_id: ID of the peer, matching its `peer_id` setting.
+/// - host: Host name, IP address or Unix socket directory of the peer.
+/// - port: Optional port of the peer (PgBouncer defaults to 6432).
+/// - pool_size: Optional maximum number of connections to the peer.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Peer {
+    peer_id: u16,
+    host: String,
+    port: Option<u16>,
+    pool_size: Option<u32>,
+}
+
+impl Peer {
+    /// Creates a new Peer.
+    ///
+    /// # Parameters
+    /// - peer_id: ID of the peer, between 1 and 16383.
+    /// - host: Host name, IP address or Unix socket directory of the peer.
+    ///
+    /// # Returns
+    /// The initialized Peer without port and pool size.
+    ///
+    /// # Errors
+    /// Returns an error if `peer_id` is out of range or `host` is empty.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::peers_setting::Peer;
+    /// assert!(Peer::new(1, "/tmp/pgbouncer1").is_ok());
+    /// assert!(Peer::new(0, "/tmp/pgbouncer1").is_err());
+    /// ```
+    pub fn new(peer_id: u16, host: &str) -> crate::error::Result<Self> {
+        if !(MIN_PEER_ID..=MAX_PEER_ID).contains(&peer_id) {
+            return Err(PgBouncerError::PgBouncer(format!(
+                "peer_id must be between {} and {}: {}", MIN_PEER_ID, MAX_PEER_ID, peer_id
+            )));
+        }
+        if host.trim().is_empty() {
+            return Err(PgBouncerError::PgBouncer(format!("host of peer {} must not be empty", peer_id)));
+        }
+
+        Ok(Self {
+            peer_id,
+            host: host.to_string(),
+            port: None,
+            pool_size: None,
+        })
+    }
+
+    /// Sets the port of the peer.
+    ///
+    /// # Parameters
+    /// - port: Port PgBouncer listens on at the peer.
+    ///
+    /// # Returns
+    /// The updated Peer reflecting the new setting.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::peers_setting::Peer;
+    /// let mut peer = Peer::new(1, "10.0.0.1").unwrap();
+    /// peer.set_port(6433);
+    /// assert_eq!(peer.port(), Some(6433));
+    /// ```
+    pub fn set_port(&mut self, port: u16) -> &mut Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Sets the maximum number of connections to the peer.
+    ///
+    /// # Parameters
+    /// - pool_size: Maximum number of connections to the peer.
+    ///
+    /// # Returns
+    /// The updated Peer reflecting the new setting.
+    pub fn set_pool_size(&mut self, pool_size: u32) -> &mut Self {
+        self.pool_size = Some(pool_size);
+        self
+    }
+
+    /// Returns the peer ID.
+    pub fn peer_id(&self) -> u16 {
+        self.peer_id
+    }
+
+    /// Returns the host of the peer.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Returns the port of the peer, if set.
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// Returns the pool size of the peer, if set.
+    pub fn pool_size(&self) -> Option<u32> {
+        self.pool_size
+    }
+
+    /// Render this Peer as a configuration line.
+    ///
+    /// # Returns
+    /// A line in the form `ID = host=HOST [port=PORT] [pool_size=SIZE]`
+    /// terminated by a newline.
+    pub fn expr(&self) -> String {
+        let mut line = format!("{} = host={}", self.peer_id, self.host);
+        if let Some(port) = self.port {
+            line.push_str(&format!(" port={}", port));
+        }
+        if let Some(pool_size) = self.pool_size {
+            line.push_str(&format!(" pool_size={}", pool_size));
+        }
+
+        format!("{}\n", line)
+    }
+}
+
+#[cfg(feature = "io")]
+impl ParserIniFromStr for Peer {
+    type Error = PgBouncerError;
+
+    fn parse_from_str(value: &str) -> Result<Self, Self::Error> {
+        let (key, body) = parse_key_value(value)?;
+        let peer_id: u16 = key
+            .parse()
+            .map_err(|_| PgBouncerError::PgBouncer(format!("Invalid peer_id: {}", value)))?;
+
+        let pair_re = Regex::new(
+            r#"(?x)(?P<k>\w+)=(?P<v> '(?:[^'\\]|\\.)*'| "(?:[^"\\]|\\.)*"| \S+)"#,
+        )?;
+
+        let mut map: HashMap<String, String> = HashMap::new();
+        for cap in pair_re.captures_iter(&body) {
+            let k = cap.name("k").ok_or(
+                PgBouncerError::PgBouncer(format!("Invalid argument key: {}", value))
+            )?.as_str().to_string();
+            let v = cap.name("v").ok_or(
+                PgBouncerError::PgBouncer(format!("Invalid argument value: {}", value))
+            )?.as_str().to_string();
+            map.insert(k, v);
+        }
+
+        let host = map.remove("host").ok_or(
+            PgBouncerError::PgBouncer(format!("Not found 'host': {}", value))
+        )?;
+        let mut peer = Peer::new(peer_id, &host)?;
+
+        if let Some(port) = map.remove("port") {
+            let port = port
+                .parse()
+                .map_err(|_| PgBouncerError::PgBouncer(format!("Invalid port: {}", value)))?;
+            peer.set_port(port);
+        }
+        if let Some(pool_size) = map.remove("pool_size") {
+            let pool_size = pool_size
+                .parse()
+                .map_err(|_| PgBouncerError::PgBouncer(format!("Invalid pool_size: {}", value)))?;
+            peer.set_pool_size(pool_size);
+        }
+
+        Ok(peer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_peer_orders_and_replaces_by_id() {
+        let mut settings = PeersSetting::new();
+        settings.add_peer(Peer::new(2, "/tmp/pgbouncer2").unwrap());
+        settings.add_peer(Peer::new(1, "/tmp/pgbouncer1").unwrap());
+        let mut peer = Peer::new(2, "10.0.0.2").unwrap();
+        peer.set_port(6433);
+        settings.add_peer(peer);
+
+        assert_eq!(
+            settings.expr().unwrap(),
+            "[peers]\n1 = host=/tmp/pgbouncer1\n2 = host=10.0.0.2 port=6433\n\n"
+        );
+
+        let mut buf: Vec<u8> = Vec::new();
+        settings.expr_to(&mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), settings.expr().unwrap());
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn parse_from_str_round_trips() {
+        let text = "1 = host=/tmp/pgbouncer1\n2 = host=10.0.0.2 port=6433 pool_size=5";
+        let settings = PeersSetting::parse_from_str(text).unwrap();
+
+        assert_eq!(settings.peers().len(), 2);
+        assert_eq!(settings.peers()[1].pool_size(), Some(5));
+        assert_eq!(settings.expr().unwrap(), format!("[peers]\n{}\n\n", text));
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn template_string_round_trips() {
+        let mut settings = PeersSetting::new();
+        let mut peer = Peer::new(2, "10.0.0.2").unwrap();
+        peer.set_port(6433).set_pool_size(5);
+        settings.add_peer(Peer::new(1, "/tmp/pgbouncer1").unwrap());
+        settings.add_peer(peer);
+
+        let text = settings.to_template_string().unwrap();
+        assert_eq!(PeersSetting::from_template_string(&text).unwrap(), settings);
+        assert!(PeersSetting::from_template_string("[peers]
+0 = host=/tmp/pgbouncer").is_err());
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn parse_from_str_rejects_invalid_peer_id() {
+        assert!(PeersSetting::parse_from_str("0 = host=/tmp/pgbouncer").is_err());
+        assert!(PeersSetting::parse_from_str("peer = host=/tmp/pgbouncer").is_err());
+    }
+}
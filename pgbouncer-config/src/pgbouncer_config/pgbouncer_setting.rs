@@ -1,7 +1,11 @@
+use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
 use serde::{Deserialize, Serialize};
 use crate::error::PgBouncerError;
 use crate::pgbouncer_config::Expression;
+use crate::pgbouncer_config::peers_setting::MAX_PEER_ID;
+use crate::pgbouncer_config::settings_catalog::find_section_setting;
+use crate::pgbouncer_config::validation::ValidationIssue;
 #[cfg(feature = "io")]
 use std::collections::HashMap;
 #[cfg(feature = "io")]
@@ -32,6 +36,9 @@ use crate::utils::diff::Diffable;
 /// - unix_socket_dir: Optional directory for PgBouncer Unix domain socket.
 /// - auth_hba_file: Optional path to HBA configuration when using `hba` auth.
 /// - auth_ident_file: Optional path to ident map file.
+/// - auth_user: User that runs `auth_query` to look up passwords.
+/// - auth_query: Query used to load a user's password from the database.
+/// - auth_dbname: Database `auth_query` connects to.
 /// - server_check_delay: How long to keep released connections available before re-checking (seconds).
 /// - server_idle_timeout: If a server connection has been idle longer than this, close it (seconds).
 /// - server_lifetime: Close an unused server connection that has been connected longer than this (seconds).
@@ -48,7 +55,50 @@ use crate::utils::diff::Diffable;
 /// - client_idle_timeout: Client idle timeout (seconds). 0 disables.
 /// - idle_transaction_timeout: Timeout for idle-in-transaction sessions (seconds). 0 disables.
 /// - suspend_timeout: Timeout to wait for suspend to complete (seconds).
+/// - min_pool_size: Server connections kept open in every pool, even when idle.
+/// - reserve_pool_size: Additional server connections a pool may open when clients wait.
+/// - reserve_pool_timeout: How long a client waits before the reserve pool is used (seconds).
+/// - max_db_connections: Maximum server connections per database, across all its pools.
+/// - max_user_connections: Maximum server connections per user, across all its pools.
+/// - server_reset_query: Query run on a server connection before it is given to another client.
+/// - server_reset_query_always: Whether `server_reset_query` also runs in transaction and statement pooling.
+/// - server_fast_close: Whether server connections are closed right after their transaction during a reload or `RECONNECT`.
+/// - server_round_robin: Whether server connections are reused in round-robin order instead of LIFO.
+/// - tcp_keepalive: Whether TCP keepalive is enabled on client and server sockets.
+/// - tcp_keepcnt: Unanswered keepalive probes before a connection is dropped.
+/// - tcp_keepidle: Idle time before the first keepalive probe is sent (seconds).
+/// - tcp_keepintvl: Interval between keepalive probes (seconds).
+/// - tcp_user_timeout: How long sent data may stay unacknowledged before the connection is dropped (milliseconds).
+/// - so_reuseport: Whether several PgBouncer processes may listen on the same port.
+/// - peer_id: ID of this process among the peers of the [peers] section.
+/// - track_extra_parameters: Client parameters tracked per client in addition to the built-in ones.
+/// - application_name_add_host: Whether the client address and port are appended to `application_name`.
+/// - log_connections: Whether successful logins are logged.
+/// - log_disconnections: Whether disconnections are logged, with their reason.
+/// - log_pooler_errors: Whether error messages sent to clients are logged.
+/// - log_stats: Whether aggregated statistics are logged every `stats_period`.
+/// - stats_period: Interval of statistics aggregation and logging (seconds).
+/// - verbose: Log verbosity; 1 and 2 add debug messages.
+/// - syslog: Whether to log to syslog as well.
+/// - syslog_facility: Syslog facility PgBouncer logs under.
+/// - syslog_ident: Program name PgBouncer logs to syslog as.
+/// - client_tls_sslmode: TLS mode for client connections.
+/// - client_tls_key_file: Private key file for client-facing TLS.
+/// - client_tls_cert_file: Certificate file for client-facing TLS.
+/// - client_tls_ca_file: CA file used to validate client certificates.
+/// - client_tls_protocols: Allowed TLS protocol versions for client connections.
+/// - client_tls_ciphers: Allowed TLS ciphers for client connections.
+/// - client_tls_dheparams: DHE key exchange parameters for client connections.
+/// - client_tls_ecdhcurve: Elliptic curve used for ECDH key exchange with clients.
+/// - server_tls_sslmode: TLS mode for server connections.
+/// - server_tls_ca_file: CA file used to validate PostgreSQL server certificates.
+/// - server_tls_key_file: Private key file for authenticating to PostgreSQL servers.
+/// - server_tls_cert_file: Certificate file for authenticating to PostgreSQL servers.
+/// - server_tls_protocols: Allowed TLS protocol versions for server connections.
+/// - server_tls_ciphers: Allowed TLS ciphers for server connections.
+/// - extra: Keys not modeled by this type, rendered as is at the end of the section.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PgBouncerSetting {
     // Required settings
 
@@ -118,6 +168,18 @@ pub struct PgBouncerSetting {
     /// PgBouncer default: not set
     auth_ident_file: Option<String>,
 
+    /// User that runs auth_query to look up passwords of users missing from auth_file.
+    /// PgBouncer default: not set
+    auth_user: Option<String>,
+
+    /// Query used to load a user's password from the database.
+    /// PgBouncer default: SELECT usename, passwd FROM pg_shadow WHERE usename=$1
+    auth_query: Option<String>,
+
+    /// Database auth_query connects to instead of the client's database.
+    /// PgBouncer default: not set
+    auth_dbname: Option<String>,
+
     /// How long to keep released connections available before re-checking (seconds).
     /// PgBouncer default: 0
     server_check_delay: Option<i32>,
@@ -181,6 +243,191 @@ pub struct PgBouncerSetting {
     /// Timeout to wait for suspend to complete (seconds).
     /// PgBouncer default: 10
     suspend_timeout: Option<i32>,
+
+    // Pool settings
+
+    /// Server connections kept open in every pool, even when idle.
+    /// PgBouncer default: 0 (disabled)
+    min_pool_size: Option<u16>,
+
+    /// Additional server connections a pool may open when clients wait.
+    /// PgBouncer default: 0 (disabled)
+    reserve_pool_size: Option<u16>,
+
+    /// How long a client waits before the reserve pool is used (seconds). 0 disables.
+    /// PgBouncer default: 5
+    reserve_pool_timeout: Option<i32>,
+
+    /// Maximum server connections per database, across all its pools.
+    /// PgBouncer default: 0 (unlimited)
+    max_db_connections: Option<u16>,
+
+    /// Maximum server connections per user, across all its pools.
+    /// PgBouncer default: 0 (unlimited)
+    max_user_connections: Option<u16>,
+
+    // Server reset and rotation settings
+
+    /// Query run on a server connection before it is given to another client.
+    /// PgBouncer default: DISCARD ALL
+    server_reset_query: Option<String>,
+
+    /// Whether `server_reset_query` also runs in transaction and statement pooling.
+    /// PgBouncer default: 0
+    server_reset_query_always: Option<bool>,
+
+    /// Whether server connections are closed right after their transaction during a reload or `RECONNECT`.
+    /// PgBouncer default: 0
+    server_fast_close: Option<bool>,
+
+    /// Whether server connections are reused in round-robin order instead of LIFO.
+    /// PgBouncer default: 0
+    server_round_robin: Option<bool>,
+
+    // TCP settings
+
+    /// Whether TCP keepalive is enabled on client and server sockets.
+    /// PgBouncer default: 1
+    tcp_keepalive: Option<bool>,
+
+    /// Unanswered keepalive probes before a connection is dropped.
+    /// PgBouncer default: 0 (OS default)
+    tcp_keepcnt: Option<i32>,
+
+    /// Idle time before the first keepalive probe is sent (seconds).
+    /// PgBouncer default: 0 (OS default)
+    tcp_keepidle: Option<i32>,
+
+    /// Interval between keepalive probes (seconds).
+    /// PgBouncer default: 0 (OS default)
+    tcp_keepintvl: Option<i32>,
+
+    /// How long sent data may stay unacknowledged before the connection is dropped (milliseconds).
+    /// PgBouncer default: 0 (OS default)
+    tcp_user_timeout: Option<i32>,
+
+    // Peering settings
+
+    /// Whether several PgBouncer processes may listen on the same port.
+    /// PgBouncer default: 0
+    so_reuseport: Option<bool>,
+
+    /// ID of this process among the peers of the [peers] section. 0 disables peering.
+    /// PgBouncer default: 0
+    peer_id: Option<u16>,
+
+    // Client parameter settings
+
+    /// Client parameters tracked per client in addition to the built-in ones,
+    /// so they are restored on every server connection the client gets.
+    /// PgBouncer default: IntervalStyle
+    #[serde(default)]
+    track_extra_parameters: Vec<String>,
+
+    /// Whether the client address and port are appended to `application_name`.
+    /// PgBouncer default: 0
+    application_name_add_host: Option<bool>,
+
+    // Logging settings
+
+    /// Whether successful logins are logged.
+    /// PgBouncer default: 1
+    log_connections: Option<bool>,
+
+    /// Whether disconnections are logged, with their reason.
+    /// PgBouncer default: 1
+    log_disconnections: Option<bool>,
+
+    /// Whether error messages sent to clients are logged.
+    /// PgBouncer default: 1
+    log_pooler_errors: Option<bool>,
+
+    /// Whether aggregated statistics are logged every `stats_period`.
+    /// PgBouncer default: 1
+    log_stats: Option<bool>,
+
+    /// Interval of statistics aggregation and logging (seconds).
+    /// PgBouncer default: 60
+    stats_period: Option<i32>,
+
+    /// Log verbosity; 1 and 2 add debug messages.
+    /// PgBouncer default: 0
+    verbose: Option<u8>,
+
+    /// Whether to log to syslog as well.
+    /// PgBouncer default: 0
+    syslog: Option<bool>,
+
+    /// Syslog facility PgBouncer logs under.
+    /// PgBouncer default: daemon
+    syslog_facility: Option<SyslogFacility>,
+
+    /// Program name PgBouncer logs to syslog as.
+    /// PgBouncer default: pgbouncer
+    syslog_ident: Option<String>,
+
+    // TLS settings
+
+    /// TLS mode for client connections.
+    /// PgBouncer default: disable
+    client_tls_sslmode: Option<SslMode>,
+
+    /// Private key file for client-facing TLS.
+    /// PgBouncer default: not set
+    client_tls_key_file: Option<String>,
+
+    /// Certificate file for client-facing TLS.
+    /// PgBouncer default: not set
+    client_tls_cert_file: Option<String>,
+
+    /// CA file used to validate client certificates.
+    /// PgBouncer default: not set
+    client_tls_ca_file: Option<String>,
+
+    /// Allowed TLS protocol versions for client connections.
+    /// PgBouncer default: secure
+    client_tls_protocols: Option<String>,
+
+    /// Allowed TLS ciphers for client connections.
+    /// PgBouncer default: default
+    client_tls_ciphers: Option<String>,
+
+    /// DHE key exchange parameters for client connections.
+    /// PgBouncer default: auto
+    client_tls_dheparams: Option<String>,
+
+    /// Elliptic curve used for ECDH key exchange with clients.
+    /// PgBouncer default: auto
+    client_tls_ecdhcurve: Option<String>,
+
+    /// TLS mode for server connections.
+    /// PgBouncer default: prefer
+    server_tls_sslmode: Option<SslMode>,
+
+    /// CA file used to validate PostgreSQL server certificates.
+    /// PgBouncer default: not set
+    server_tls_ca_file: Option<String>,
+
+    /// Private key file for authenticating to PostgreSQL servers.
+    /// PgBouncer default: not set
+    server_tls_key_file: Option<String>,
+
+    /// Certificate file for authenticating to PostgreSQL servers.
+    /// PgBouncer default: not set
+    server_tls_cert_file: Option<String>,
+
+    /// Allowed TLS protocol versions for server connections.
+    /// PgBouncer default: secure
+    server_tls_protocols: Option<String>,
+
+    /// Allowed TLS ciphers for server connections.
+    /// PgBouncer default: default
+    server_tls_ciphers: Option<String>,
+
+    /// Keys not modeled by this type (e.g. options of a newer PgBouncer),
+    /// rendered as is after the modeled keys.
+    #[serde(default)]
+    extra: BTreeMap<String, String>,
 }
 
 impl PgBouncerSetting {
@@ -233,6 +480,9 @@ impl PgBouncerSetting {
             unix_socket_dir: unix_socket_dir.map(|dir| dir.to_string()),
             auth_hba_file: auth_hba_file.map(|file| file.to_string()),
             auth_ident_file: auth_ident_file.map(|file| file.to_string()),
+            auth_user: None,
+            auth_query: None,
+            auth_dbname: None,
             server_check_delay,
             server_idle_timeout,
             server_lifetime,
@@ -249,6 +499,48 @@ impl PgBouncerSetting {
             client_idle_timeout,
             idle_transaction_timeout,
             suspend_timeout,
+            min_pool_size: None,
+            reserve_pool_size: None,
+            reserve_pool_timeout: None,
+            max_db_connections: None,
+            max_user_connections: None,
+            server_reset_query: None,
+            server_reset_query_always: None,
+            server_fast_close: None,
+            server_round_robin: None,
+            tcp_keepalive: None,
+            tcp_keepcnt: None,
+            tcp_keepidle: None,
+            tcp_keepintvl: None,
+            tcp_user_timeout: None,
+            so_reuseport: None,
+            peer_id: None,
+            track_extra_parameters: vec![],
+            application_name_add_host: None,
+            log_connections: None,
+            log_disconnections: None,
+            log_pooler_errors: None,
+            log_stats: None,
+            stats_period: None,
+            verbose: None,
+            syslog: None,
+            syslog_facility: None,
+            syslog_ident: None,
+            client_tls_sslmode: None,
+            client_tls_key_file: None,
+            client_tls_cert_file: None,
+            client_tls_ca_file: None,
+            client_tls_protocols: None,
+            client_tls_ciphers: None,
+            client_tls_dheparams: None,
+            client_tls_ecdhcurve: None,
+            server_tls_sslmode: None,
+            server_tls_ca_file: None,
+            server_tls_key_file: None,
+            server_tls_cert_file: None,
+            server_tls_protocols: None,
+            server_tls_ciphers: None,
+            extra: BTreeMap::new(),
         }
     }
 
@@ -258,7 +550,7 @@ impl PgBouncerSetting {
     /// - addr: Desired listening address (IP or hostname).
     ///
     /// # Returns
-    /// A cloned instance with the updated address.
+    /// A mutable reference to self with the updated address.
     ///
     /// # Examples
     /// ```rust
@@ -271,9 +563,14 @@ impl PgBouncerSetting {
     /// # Notes
     /// - Updates the `listen_addr` field.
     /// - This method does not parse "host:port"; set the port via [`set_listen_port`].
-    pub fn set_listen_addr(&mut self, addr: &str) -> Self {
+    pub fn set_listen_addr(&mut self, addr: &str) -> &mut Self {
         self.listen_addr = addr.to_string();
-        self.clone()
+        self
+    }
+
+    /// Returns the address PgBouncer listens on.
+    pub fn listen_addr(&self) -> &str {
+        &self.listen_addr
     }
 
     /// Set the listening port.
@@ -282,7 +579,7 @@ impl PgBouncerSetting {
     /// - port: Port number to listen on.
     ///
     /// # Returns
-    /// A cloned instance with the updated port.
+    /// A mutable reference to self with the updated port.
     ///
     /// # Examples
     /// ```rust
@@ -291,9 +588,14 @@ impl PgBouncerSetting {
     /// let mut config = PgBouncerSetting::default();
     /// config.set_listen_port(6432);
     /// ```
-    pub fn set_listen_port(&mut self, port: u16) -> Self {
+    pub fn set_listen_port(&mut self, port: u16) -> &mut Self {
         self.listen_port = port;
-        self.clone()
+        self
+    }
+
+    /// Returns the TCP port PgBouncer listens on.
+    pub fn listen_port(&self) -> u16 {
+        self.listen_port
     }
 
     /// Set the authentication type.
@@ -302,7 +604,7 @@ impl PgBouncerSetting {
     /// - auth_type: Authentication method to use.
     ///
     /// # Returns
-    /// A cloned instance with the updated authentication type.
+    /// A mutable reference to self with the updated authentication type.
     ///
     /// # Examples
     /// ```rust
@@ -311,9 +613,19 @@ impl PgBouncerSetting {
     /// let mut config = PgBouncerSetting::default();
     /// config.set_auth_type(AuthType::ScramSha256);
     /// ```
-    pub fn set_auth_type(&mut self, auth_type: AuthType) -> Self {
+    pub fn set_auth_type(&mut self, auth_type: AuthType) -> &mut Self {
         self.auth_type = auth_type;
-        self.clone()
+        self
+    }
+
+    /// Returns the authentication type.
+    pub fn auth_type(&self) -> AuthType {
+        self.auth_type
+    }
+
+    /// Returns the HBA configuration file path, if set.
+    pub fn auth_hba_file(&self) -> Option<&str> {
+        self.auth_hba_file.as_deref()
     }
 
     /// Set the authentication file path.
@@ -322,7 +634,7 @@ impl PgBouncerSetting {
     /// - auth_file: Path to the authentication file.
     ///
     /// # Returns
-    /// A cloned instance with the updated authentication file path.
+    /// A mutable reference to self with the updated authentication file path.
     ///
     /// # Examples
     /// ```rust
@@ -331,9 +643,14 @@ impl PgBouncerSetting {
     /// let mut config = PgBouncerSetting::default();
     /// config.set_auth_file("/bitnami/pgbouncer-config/conf/userlist.txt");
     /// ```
-    pub fn set_auth_file(&mut self, auth_file: &str) -> Self {
+    pub fn set_auth_file(&mut self, auth_file: &str) -> &mut Self {
         self.auth_file = Some(auth_file.to_string());
-        self.clone()
+        self
+    }
+
+    /// Returns the authentication file path, if set.
+    pub fn auth_file(&self) -> Option<&str> {
+        self.auth_file.as_deref()
     }
 
     /// Set the maximum number of client connections.
@@ -342,7 +659,7 @@ impl PgBouncerSetting {
     /// - max_client_conn: Maximum number of allowed client connections.
     ///
     /// # Returns
-    /// A cloned instance with the updated limit.
+    /// A mutable reference to self with the updated limit.
     ///
     /// # Examples
     /// ```rust
@@ -351,9 +668,14 @@ impl PgBouncerSetting {
     /// let mut config = PgBouncerSetting::default();
     /// config.set_max_client_conn(5000);
     /// ```
-    pub fn set_max_client_conn(&mut self, max_client_conn: u16) -> Self {
+    pub fn set_max_client_conn(&mut self, max_client_conn: u16) -> &mut Self {
         self.max_client_conn = max_client_conn;
-        self.clone()
+        self
+    }
+
+    /// Returns the maximum number of client connections.
+    pub fn max_client_conn(&self) -> u16 {
+        self.max_client_conn
     }
 
     /// Set the default pool size.
@@ -362,7 +684,7 @@ impl PgBouncerSetting {
     /// - default_pool_size: Desired number of server connections per pool.
     ///
     /// # Returns
-    /// A cloned instance with the updated pool size.
+    /// A mutable reference to self with the updated pool size.
     ///
     /// # Examples
     /// ```rust
@@ -371,9 +693,14 @@ impl PgBouncerSetting {
     /// let mut config = PgBouncerSetting::default();
     /// config.set_default_pool_size(50);
     /// ```
-    pub fn set_default_pool_size(&mut self, default_pool_size: u16) -> Self {
+    pub fn set_default_pool_size(&mut self, default_pool_size: u16) -> &mut Self {
         self.default_pool_size = default_pool_size;
-        self.clone()
+        self
+    }
+
+    /// Returns the default number of server connections per pool.
+    pub fn default_pool_size(&self) -> u16 {
+        self.default_pool_size
     }
 
     /// Set the pool mode.
@@ -382,7 +709,7 @@ impl PgBouncerSetting {
     /// - pool_mode: New pooling mode to use.
     ///
     /// # Returns
-    /// A cloned instance with the updated pool mode.
+    /// A mutable reference to self with the updated pool mode.
     ///
     /// # Examples
     /// ```rust
@@ -391,9 +718,14 @@ impl PgBouncerSetting {
     /// let mut config = PgBouncerSetting::default();
     /// config.set_pool_mode(PoolMode::Session);
     /// ```
-    pub fn set_pool_mode(&mut self, pool_mode: PoolMode) -> Self {
+    pub fn set_pool_mode(&mut self, pool_mode: PoolMode) -> &mut Self {
         self.pool_mode = pool_mode;
-        self.clone()
+        self
+    }
+
+    /// Returns the pooling mode.
+    pub fn pool_mode(&self) -> PoolMode {
+        self.pool_mode
     }
 
     /// Add an admin user.
@@ -402,7 +734,7 @@ impl PgBouncerSetting {
     /// - user: Username to grant administrative privileges in PgBouncer.
     ///
     /// # Returns
-    /// A cloned instance with the user added to `admin_users`.
+    /// A mutable reference to self with the user added to `admin_users`.
     ///
     /// # Examples
     /// ```rust
@@ -411,9 +743,14 @@ impl PgBouncerSetting {
     /// let mut config = PgBouncerSetting::default();
     /// config.add_admin_user("admin");
     /// ```
-    pub fn add_admin_user(&mut self, user: &str) -> Self {
+    pub fn add_admin_user(&mut self, user: &str) -> &mut Self {
         self.admin_users.push(user.to_string());
-        self.clone()
+        self
+    }
+
+    /// Returns the users allowed to run admin commands.
+    pub fn admin_users(&self) -> &[String] {
+        &self.admin_users
     }
 
     /// Add a statistics user.
@@ -422,7 +759,7 @@ impl PgBouncerSetting {
     /// - user: Username to grant permissions to view statistics only.
     ///
     /// # Returns
-    /// A cloned instance with the user added to `stats_users`.
+    /// A mutable reference to self with the user added to `stats_users`.
     ///
     /// # Examples
     /// ```rust
@@ -431,9 +768,9 @@ impl PgBouncerSetting {
     /// let mut config = PgBouncerSetting::default();
     /// config.add_stats_user("stats_user");
     /// ```
-    pub fn add_stats_user(&mut self, user: &str) -> Self {
+    pub fn add_stats_user(&mut self, user: &str) -> &mut Self {
         self.stats_users.push(user.to_string());
-        self.clone()
+        self
     }
 
     /// Add an ignored startup parameter.
@@ -444,7 +781,7 @@ impl PgBouncerSetting {
     /// - param: Client startup parameter to ignore.
     ///
     /// # Returns
-    /// A cloned instance with the updated list.
+    /// A mutable reference to self with the updated list.
     ///
     /// # Examples
     /// ```rust
@@ -453,9 +790,14 @@ impl PgBouncerSetting {
     /// let mut config = PgBouncerSetting::default();
     /// config.add_ignore_startup_parameter("extra_float_digits");
     /// ```
-    pub fn add_ignore_startup_parameter(&mut self, param: &str) -> Self {
-    	self.ignore_startup_parameters.push(param.to_string());
-    	self.clone()
+    pub fn add_ignore_startup_parameter(&mut self, param: &str) -> &mut Self {
+        self.ignore_startup_parameters.push(param.to_string());
+        self
+    }
+
+    /// Returns the client startup parameters PgBouncer ignores.
+    pub fn ignore_startup_parameters(&self) -> &[String] {
+        &self.ignore_startup_parameters
     }
 
     /// Set the logfile path.
@@ -464,7 +806,7 @@ impl PgBouncerSetting {
     /// - logfile: Optional path to the logfile. `Some(path)` sets the logfile; `None` clears it.
     ///
     /// # Returns
-    /// A cloned instance with the updated logfile path.
+    /// A mutable reference to self with the updated logfile path.
     ///
     /// # Examples
     /// ```rust
@@ -473,9 +815,9 @@ impl PgBouncerSetting {
     /// let mut config = PgBouncerSetting::default();
     /// config.set_logfile(Some("/path/to/logfile.log"));
     /// ```
-    pub fn set_logfile(&mut self, logfile: Option<&str>) -> Self {
+    pub fn set_logfile(&mut self, logfile: Option<&str>) -> &mut Self {
         self.logfile = logfile.map(|file| file.to_string());
-        self.clone()
+        self
     }
 
     /// Set the PID file path.
@@ -484,7 +826,7 @@ impl PgBouncerSetting {
     /// - pidfile: Optional path to the PID file. `Some(path)` sets the PID file; `None` clears it.
     ///
     /// # Returns
-    /// A cloned instance with the updated PID file path.
+    /// A mutable reference to self with the updated PID file path.
     ///
     /// # Examples
     /// ```rust
@@ -494,9 +836,14 @@ impl PgBouncerSetting {
     /// config.set_pidfile(Some("/var/run/pgbouncer-config.pid"));
     /// config.set_pidfile(None);
     /// ```
-    pub fn set_pidfile(&mut self, pidfile: Option<&str>) -> Self {
+    pub fn set_pidfile(&mut self, pidfile: Option<&str>) -> &mut Self {
         self.pidfile = pidfile.map(|file| file.to_string());
-        self.clone()
+        self
+    }
+
+    /// Returns the PID file path, if set.
+    pub fn pidfile(&self) -> Option<&str> {
+        self.pidfile.as_deref()
     }
 
     /// Set the Unix socket directory.
@@ -505,7 +852,7 @@ impl PgBouncerSetting {
     /// - unix_socket_dir: Optional directory path where the Unix socket is created.
     ///
     /// # Returns
-    /// A cloned instance with the updated Unix socket directory.
+    /// A mutable reference to self with the updated Unix socket directory.
     ///
     /// # Examples
     /// ```rust
@@ -514,9 +861,9 @@ impl PgBouncerSetting {
     /// let mut config = PgBouncerSetting::default();
     /// config.set_unix_socket_dir(Some("/tmp/socket_dir"));
     /// ```
-    pub fn set_unix_socket_dir(&mut self, unix_socket_dir: Option<&str>) -> Self {
+    pub fn set_unix_socket_dir(&mut self, unix_socket_dir: Option<&str>) -> &mut Self {
         self.unix_socket_dir = unix_socket_dir.map(|dir| dir.to_string());
-        self.clone()
+        self
     }
 
     /// Set the HBA configuration file path.
@@ -525,7 +872,7 @@ impl PgBouncerSetting {
     /// - auth_hba_file: Optional path to the HBA configuration file.
     ///
     /// # Returns
-    /// If successful, returns the updated configuration with the new HBA file path.
+    /// If successful, a mutable reference to self with the new HBA file path.
     ///
     /// # Errors
     /// Returns an error if `auth_type` is `AuthType::Hba` and `auth_hba_file` is `None`.
@@ -539,7 +886,7 @@ impl PgBouncerSetting {
     /// config.set_auth_hba_file(Some("/etc/pgbouncer-config/pgb_hba.conf"))
     ///     .expect("hba file required for hba auth");
     /// ```
-    pub fn set_auth_hba_file(&mut self, auth_hba_file: Option<&str>) -> crate::error::Result<Self> {
+    pub fn set_auth_hba_file(&mut self, auth_hba_file: Option<&str>) -> crate::error::Result<&mut Self> {
         if self.auth_type == AuthType::Hba && auth_hba_file.is_none() {
             return Err(PgBouncerError::PgBouncer(
                 "auth_hba_file cannot be None when the auth_type is 'hba'".to_string()
@@ -547,7 +894,7 @@ impl PgBouncerSetting {
         }
 
         self.auth_hba_file = auth_hba_file.map(|file| file.to_string());
-        Ok(self.clone())
+        Ok(self)
     }
 
     /// Set the ident map file path.
@@ -565,9 +912,122 @@ impl PgBouncerSetting {
     /// let mut config = PgBouncerSetting::default();
     /// config.set_auth_ident_file(Some("/etc/pgbouncer-config/pg_ident.map"));
     /// ```
-    pub fn set_auth_ident_file(&mut self, auth_ident_file: Option<&str>) -> Self {
+    pub fn set_auth_ident_file(&mut self, auth_ident_file: Option<&str>) -> &mut Self {
         self.auth_ident_file = auth_ident_file.map(|file| file.to_string());
-        self.clone()
+        self
+    }
+
+    /// Set the user that runs `auth_query`.
+    ///
+    /// Its own password must be resolvable from `auth_file` or from the
+    /// `password` of the [databases] entry.
+    ///
+    /// # Parameters
+    /// - auth_user: Optional user name. `Some(user)` sets the value; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `auth_user`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_auth_user(Some("pgbouncer"));
+    /// ```
+    pub fn set_auth_user(&mut self, auth_user: Option<&str>) -> &mut Self {
+        self.auth_user = auth_user.map(|user| user.to_string());
+        self
+    }
+
+    /// Returns the user that runs `auth_query`, if set.
+    pub fn auth_user(&self) -> Option<&str> {
+        self.auth_user.as_deref()
+    }
+
+    /// Set the query used to look up passwords.
+    ///
+    /// The query receives the user name as `$1` and must return the user name
+    /// and the password (or verifier). [`auth_query_lookup`] builds the query
+    /// matching [`auth_query_function_sql`].
+    ///
+    /// # Parameters
+    /// - auth_query: Optional query. `Some(query)` sets the value; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `auth_query`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::{auth_query_lookup, PgBouncerSetting};
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_auth_query(Some(&auth_query_lookup("pgbouncer")));
+    /// ```
+    pub fn set_auth_query(&mut self, auth_query: Option<&str>) -> &mut Self {
+        self.auth_query = auth_query.map(|query| query.to_string());
+        self
+    }
+
+    /// Returns the query used to look up passwords, if set.
+    pub fn auth_query(&self) -> Option<&str> {
+        self.auth_query.as_deref()
+    }
+
+    /// Set the database `auth_query` connects to.
+    ///
+    /// # Parameters
+    /// - auth_dbname: Optional database name. `Some(name)` sets the value; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `auth_dbname`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_auth_dbname(Some("postgres"));
+    /// ```
+    pub fn set_auth_dbname(&mut self, auth_dbname: Option<&str>) -> &mut Self {
+        self.auth_dbname = auth_dbname.map(|name| name.to_string());
+        self
+    }
+
+    /// Returns the database `auth_query` connects to, if set.
+    pub fn auth_dbname(&self) -> Option<&str> {
+        self.auth_dbname.as_deref()
+    }
+
+    /// Builds the SQL that provisions the `auth_query` lookup function for this setting.
+    ///
+    /// Run the returned SQL as a superuser in every database `auth_query`
+    /// connects to (or in `auth_dbname` only, when it is set).
+    ///
+    /// # Parameters
+    /// - schema: Schema that holds the lookup function.
+    ///
+    /// # Returns
+    /// The SQL from [`auth_query_function_sql`] for the configured `auth_user`.
+    ///
+    /// # Errors
+    /// Returns an error if `auth_user` is not set.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_auth_user(Some("pgbouncer"));
+    /// let sql = config.auth_query_sql("pgbouncer").unwrap();
+    /// assert!(sql.contains("SECURITY DEFINER"));
+    /// ```
+    pub fn auth_query_sql(&self, schema: &str) -> crate::error::Result<String> {
+        let auth_user = self.auth_user.as_deref().ok_or(PgBouncerError::PgBouncer(
+            "auth_user is required to provision the auth_query function".to_string()
+        ))?;
+
+        Ok(auth_query_function_sql(schema, auth_user))
     }
 
     /// Set the server check delay.
@@ -579,7 +1039,7 @@ impl PgBouncerSetting {
     /// - secs: Optional delay in seconds. `Some(seconds)` sets the value; `None` clears it.
     ///
     /// # Returns
-    /// A cloned instance with the updated `server_check_delay`.
+    /// A mutable reference to self with the updated `server_check_delay`.
     ///
     /// # Examples
     /// ```rust
@@ -589,9 +1049,9 @@ impl PgBouncerSetting {
     /// config.set_server_check_delay(Some(5));
     /// config.set_server_check_delay(None);
     /// ```
-    pub fn set_server_check_delay(&mut self, secs: Option<i32>) -> Self {
+    pub fn set_server_check_delay(&mut self, secs: Option<i32>) -> &mut Self {
         self.server_check_delay = secs;
-        self.clone()
+        self
     }
 
     /// Set the server idle timeout.
@@ -603,7 +1063,7 @@ impl PgBouncerSetting {
     /// - secs: Optional timeout in seconds. `Some(seconds)` sets the value; `None` clears it.
     ///
     /// # Returns
-    /// A cloned instance with the updated `server_idle_timeout`.
+    /// A mutable reference to self with the updated `server_idle_timeout`.
     ///
     /// # Examples
     /// ```rust
@@ -613,9 +1073,9 @@ impl PgBouncerSetting {
     /// config.set_server_idle_timeout(Some(3600));
     /// config.set_server_idle_timeout(None);
     /// ```
-    pub fn set_server_idle_timeout(&mut self, secs: Option<i32>) -> Self {
+    pub fn set_server_idle_timeout(&mut self, secs: Option<i32>) -> &mut Self {
         self.server_idle_timeout = secs;
-        self.clone()
+        self
     }
 
     /// Set the server connection lifetime.
@@ -627,7 +1087,7 @@ impl PgBouncerSetting {
     /// - secs: Optional lifetime in seconds. `Some(seconds)` sets the value; `None` clears it.
     ///
     /// # Returns
-    /// A cloned instance with the updated `server_lifetime`.
+    /// A mutable reference to self with the updated `server_lifetime`.
     ///
     /// # Examples
     /// ```rust
@@ -637,9 +1097,9 @@ impl PgBouncerSetting {
     /// config.set_server_lifetime(Some(3600));
     /// config.set_server_lifetime(None);
     /// ```
-    pub fn set_server_lifetime(&mut self, secs: Option<i32>) -> Self {
+    pub fn set_server_lifetime(&mut self, secs: Option<i32>) -> &mut Self {
         self.server_lifetime = secs;
-        self.clone()
+        self
     }
 
     /// Set the server connect timeout.
@@ -651,7 +1111,7 @@ impl PgBouncerSetting {
     /// - secs: Optional timeout in seconds. `Some(seconds)` sets the value; `None` clears it.
     ///
     /// # Returns
-    /// A cloned instance with the updated `server_connect_timeout`.
+    /// A mutable reference to self with the updated `server_connect_timeout`.
     ///
     /// # Examples
     /// ```rust
@@ -661,9 +1121,9 @@ impl PgBouncerSetting {
     /// config.set_server_connect_timeout(Some(15));
     /// config.set_server_connect_timeout(None);
     /// ```
-    pub fn set_server_connect_timeout(&mut self, secs: Option<i32>) -> Self {
+    pub fn set_server_connect_timeout(&mut self, secs: Option<i32>) -> &mut Self {
         self.server_connect_timeout = secs;
-        self.clone()
+        self
     }
 
     /// Set the server login retry delay.
@@ -674,7 +1134,7 @@ impl PgBouncerSetting {
     /// - secs: Optional delay in seconds. `Some(seconds)` sets the value; `None` clears it.
     ///
     /// # Returns
-    /// A cloned instance with the updated `server_login_retry`.
+    /// A mutable reference to self with the updated `server_login_retry`.
     ///
     /// # Examples
     /// ```rust
@@ -684,9 +1144,9 @@ impl PgBouncerSetting {
     /// config.set_server_login_retry(Some(15));
     /// config.set_server_login_retry(None);
     /// ```
-    pub fn set_server_login_retry(&mut self, secs: Option<i32>) -> Self {
+    pub fn set_server_login_retry(&mut self, secs: Option<i32>) -> &mut Self {
         self.server_login_retry = secs;
-        self.clone()
+        self
     }
 
     /// Set the client login timeout.
@@ -698,7 +1158,7 @@ impl PgBouncerSetting {
     /// - secs: Optional timeout in seconds. `Some(seconds)` sets the value; `None` clears it.
     ///
     /// # Returns
-    /// A cloned instance with the updated `client_login_timeout`.
+    /// A mutable reference to self with the updated `client_login_timeout`.
     ///
     /// # Examples
     /// ```rust
@@ -708,9 +1168,9 @@ impl PgBouncerSetting {
     /// config.set_client_login_timeout(Some(15));
     /// config.set_client_login_timeout(None);
     /// ```
-    pub fn set_client_login_timeout(&mut self, secs: Option<i32>) -> Self {
+    pub fn set_client_login_timeout(&mut self, secs: Option<i32>) -> &mut Self {
         self.client_login_timeout = secs;
-        self.clone()
+        self
     }
 
     /// Set the autodb idle timeout.
@@ -721,7 +1181,7 @@ impl PgBouncerSetting {
     /// - secs: Optional timeout in seconds. `Some(seconds)` sets the value; `None` clears it.
     ///
     /// # Returns
-    /// A cloned instance with the updated `autodb_idle_timeout`.
+    /// A mutable reference to self with the updated `autodb_idle_timeout`.
     ///
     /// # Examples
     /// ```rust
@@ -731,9 +1191,9 @@ impl PgBouncerSetting {
     /// config.set_autodb_idle_timeout(Some(60));
     /// config.set_autodb_idle_timeout(None);
     /// ```
-    pub fn set_autodb_idle_timeout(&mut self, secs: Option<i32>) -> Self {
+    pub fn set_autodb_idle_timeout(&mut self, secs: Option<i32>) -> &mut Self {
         self.autodb_idle_timeout = secs;
-        self.clone()
+        self
     }
 
     /// Set the maximum DNS positive cache TTL.
@@ -744,7 +1204,7 @@ impl PgBouncerSetting {
     /// - secs: Optional TTL in seconds. `Some(seconds)` sets the value; `None` clears it.
     ///
     /// # Returns
-    /// A cloned instance with the updated `dns_max_ttl`.
+    /// A mutable reference to self with the updated `dns_max_ttl`.
     ///
     /// # Examples
     /// ```rust
@@ -754,9 +1214,9 @@ impl PgBouncerSetting {
     /// config.set_dns_max_ttl(Some(3600));
     /// config.set_dns_max_ttl(None);
     /// ```
-    pub fn set_dns_max_ttl(&mut self, secs: Option<i32>) -> Self {
+    pub fn set_dns_max_ttl(&mut self, secs: Option<i32>) -> &mut Self {
         self.dns_max_ttl = secs;
-        self.clone()
+        self
     }
 
     /// Set the DNS negative cache TTL (NXDOMAIN).
@@ -767,7 +1227,7 @@ impl PgBouncerSetting {
     /// - secs: Optional TTL in seconds. `Some(seconds)` sets the value; `None` clears it.
     ///
     /// # Returns
-    /// A cloned instance with the updated `dns_nxdomain_ttl`.
+    /// A mutable reference to self with the updated `dns_nxdomain_ttl`.
     ///
     /// # Examples
     /// ```rust
@@ -777,9 +1237,9 @@ impl PgBouncerSetting {
     /// config.set_dns_nxdomain_ttl(Some(15));
     /// config.set_dns_nxdomain_ttl(None);
     /// ```
-    pub fn set_dns_nxdomain_ttl(&mut self, secs: Option<i32>) -> Self {
+    pub fn set_dns_nxdomain_ttl(&mut self, secs: Option<i32>) -> &mut Self {
         self.dns_nxdomain_ttl = secs;
-        self.clone()
+        self
     }
 
     /// Set the resolver configuration file path.
@@ -790,7 +1250,7 @@ impl PgBouncerSetting {
     /// - path: Optional file path to the resolver configuration. `Some(path)` sets the file; `None` clears it.
     ///
     /// # Returns
-    /// A cloned instance with the updated `resolve_conf`.
+    /// A mutable reference to self with the updated `resolve_conf`.
     ///
     /// # Examples
     /// ```rust
@@ -800,9 +1260,9 @@ impl PgBouncerSetting {
     /// config.set_resolve_conf(Some("/etc/resolv.conf"));
     /// config.set_resolve_conf(None);
     /// ```
-    pub fn set_resolve_conf(&mut self, path: Option<&str>) -> Self {
+    pub fn set_resolve_conf(&mut self, path: Option<&str>) -> &mut Self {
         self.resolve_conf = path.map(|p| p.to_string());
-        self.clone()
+        self
     }
 
     /// Set the query execution timeout.
@@ -814,7 +1274,7 @@ impl PgBouncerSetting {
     /// - secs: Optional timeout in seconds. `Some(seconds)` sets the value; `None` clears it.
     ///
     /// # Returns
-    /// A cloned instance with the updated `query_timeout`.
+    /// A mutable reference to self with the updated `query_timeout`.
     ///
     /// # Examples
     /// ```rust
@@ -824,9 +1284,9 @@ impl PgBouncerSetting {
     /// config.set_query_timeout(Some(30));
     /// config.set_query_timeout(None);
     /// ```
-    pub fn set_query_timeout(&mut self, secs: Option<i32>) -> Self {
+    pub fn set_query_timeout(&mut self, secs: Option<i32>) -> &mut Self {
         self.query_timeout = secs;
-        self.clone()
+        self
     }
 
     /// Set the query wait timeout.
@@ -837,7 +1297,7 @@ impl PgBouncerSetting {
     /// - secs: Optional timeout in seconds. `Some(seconds)` sets the value; `None` clears it.
     ///
     /// # Returns
-    /// A cloned instance with the updated `query_wait_timeout`.
+    /// A mutable reference to self with the updated `query_wait_timeout`.
     ///
     /// # Examples
     /// ```rust
@@ -847,9 +1307,9 @@ impl PgBouncerSetting {
     /// config.set_query_wait_timeout(Some(120));
     /// config.set_query_wait_timeout(None);
     /// ```
-    pub fn set_query_wait_timeout(&mut self, secs: Option<i32>) -> Self {
+    pub fn set_query_wait_timeout(&mut self, secs: Option<i32>) -> &mut Self {
         self.query_wait_timeout = secs;
-        self.clone()
+        self
     }
 
     /// Set the cancel request wait timeout.
@@ -860,7 +1320,7 @@ impl PgBouncerSetting {
     /// - secs: Optional timeout in seconds. `Some(seconds)` sets the value; `None` clears it.
     ///
     /// # Returns
-    /// A cloned instance with the updated `cancel_wait_timeout`.
+    /// A mutable reference to self with the updated `cancel_wait_timeout`.
     ///
     /// # Examples
     /// ```rust
@@ -870,9 +1330,9 @@ impl PgBouncerSetting {
     /// config.set_cancel_wait_timeout(Some(10));
     /// config.set_cancel_wait_timeout(None);
     /// ```
-    pub fn set_cancel_wait_timeout(&mut self, secs: Option<i32>) -> Self {
+    pub fn set_cancel_wait_timeout(&mut self, secs: Option<i32>) -> &mut Self {
         self.cancel_wait_timeout = secs;
-        self.clone()
+        self
     }
 
     /// Set the client idle timeout.
@@ -884,7 +1344,7 @@ impl PgBouncerSetting {
     /// - secs: Optional timeout in seconds. `Some(seconds)` sets the value; `None` clears it.
     ///
     /// # Returns
-    /// A cloned instance with the updated `client_idle_timeout`.
+    /// A mutable reference to self with the updated `client_idle_timeout`.
     ///
     /// # Examples
     /// ```rust
@@ -895,9 +1355,9 @@ impl PgBouncerSetting {
     /// config.set_client_idle_timeout(Some(600));
     /// config.set_client_idle_timeout(None);
     /// ```
-    pub fn set_client_idle_timeout(&mut self, secs: Option<i32>) -> Self {
+    pub fn set_client_idle_timeout(&mut self, secs: Option<i32>) -> &mut Self {
         self.client_idle_timeout = secs;
-        self.clone()
+        self
     }
 
     /// Set the idle-in-transaction timeout.
@@ -909,7 +1369,7 @@ impl PgBouncerSetting {
     /// - secs: Optional timeout in seconds. `Some(seconds)` sets the value; `None` clears it.
     ///
     /// # Returns
-    /// A cloned instance with the updated `idle_transaction_timeout`.
+    /// A mutable reference to self with the updated `idle_transaction_timeout`.
     ///
     /// # Examples
     /// ```rust
@@ -920,9 +1380,9 @@ impl PgBouncerSetting {
     /// config.set_idle_transaction_timeout(Some(300));
     /// config.set_idle_transaction_timeout(None);
     /// ```
-    pub fn set_idle_transaction_timeout(&mut self, secs: Option<i32>) -> Self {
+    pub fn set_idle_transaction_timeout(&mut self, secs: Option<i32>) -> &mut Self {
         self.idle_transaction_timeout = secs;
-        self.clone()
+        self
     }
 
     /// Set the suspend timeout.
@@ -933,7 +1393,7 @@ impl PgBouncerSetting {
     /// - secs: Optional timeout in seconds. `Some(seconds)` sets the value; `None` clears it.
     ///
     /// # Returns
-    /// A cloned instance with the updated `suspend_timeout`.
+    /// A mutable reference to self with the updated `suspend_timeout`.
     ///
     /// # Examples
     /// ```rust
@@ -943,101 +1403,1448 @@ impl PgBouncerSetting {
     /// config.set_suspend_timeout(Some(10));
     /// config.set_suspend_timeout(None);
     /// ```
-    pub fn set_suspend_timeout(&mut self, secs: Option<i32>) -> Self {
+    pub fn set_suspend_timeout(&mut self, secs: Option<i32>) -> &mut Self {
         self.suspend_timeout = secs;
-        self.clone()
-    }
-}
-
-impl Default for PgBouncerSetting {
-    fn default() -> Self {
-        Self::new(
-            "127.0.0.1",
-            6432,
-            AuthType::default(),
-            2000,
-            100,
-            PoolMode::default(),
-            vec![],
-            vec![],
-            vec![],
-            None, 
-            None,
-            Some("/etc/pgbouncer-config/userlist.txt"),
-            None, 
-            None, 
-            None, 
-            None, 
-            None, 
-            None, 
-            None, 
-            None, 
-            None, 
-            None, 
-            None,
-            None, 
-            None, 
-            None, 
-            None, 
-            None, 
-            None, 
-            None, 
-            None,
-        )
+        self
     }
-}
-
-#[typetag::serde]
-impl Expression for PgBouncerSetting {
-    fn expr(&self) -> crate::error::Result<String> {
-        let mut expr = "[pgbouncer]\n".to_string();
-        expr.push_str(&format!("listen_addr = {}\n", self.listen_addr));
-        expr.push_str(&format!("listen_port = {}\n", self.listen_port));
-        expr.push_str(&format!("auth_type = {}\n", self.auth_type));
-        expr.push_str(&format!("max_client_conn = {}\n", self.max_client_conn));
-        expr.push_str(&format!("default_pool_size = {}\n", self.default_pool_size));
-        expr.push_str(&format!("pool_mode = {}\n", self.pool_mode));
-
-        if self.admin_users.len() > 0 {
-            expr.push_str(&format!("admin_users = {}\n", self.admin_users.join(",")));
-        }
-        if self.stats_users.len() > 0 {
-            expr.push_str(&format!("stats_users = {}\n", self.stats_users.join(",")));
-        }
-        if self.ignore_startup_parameters.len() > 0 {
-            expr.push_str(&format!("ignore_startup_parameters = {}\n", self.ignore_startup_parameters.join(",")));
-        }
-
-        if let Some(logfile) = &self.logfile {
-            expr.push_str(&format!("logfile = {}\n", logfile));
-        }
-        if let Some(pidfile) = &self.pidfile {
-            expr.push_str(&format!("pidfile = {}\n", pidfile));
-        }
-        if let Some(auth_file) = &self.auth_file {
-            expr.push_str(&format!("auth_file = {}\n", auth_file));
-        }
-        if let Some(unix_socket_dir) = &self.unix_socket_dir {
-            expr.push_str(&format!("unix_socket_dir = {}\n", unix_socket_dir));
-        }
-        if let Some(auth_hba_file) = &self.auth_hba_file {
-            expr.push_str(&format!("auth_hba_file = {}\n", auth_hba_file));
-        }
-        if let Some(auth_ident_file) = &self.auth_ident_file {
-            expr.push_str(&format!("auth_ident_file = {}\n", auth_ident_file));
-        }
 
-        Ok(expr)
+    /// Set the number of server connections kept open in every pool.
+    ///
+    /// # Parameters
+    /// - size: `Some(size)` sets the value; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `min_pool_size`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_min_pool_size(Some(5));
+    /// assert_eq!(config.min_pool_size(), Some(5));
+    /// ```
+    ///
+    /// # Notes
+    /// - A [databases] entry overrides this with its own `min_pool_size`.
+    pub fn set_min_pool_size(&mut self, size: Option<u16>) -> &mut Self {
+        self.min_pool_size = size;
+        self
     }
 
-    fn section_name(&self) -> &'static str {
-        "pgbouncer"
+    /// Returns the number of server connections kept open in every pool, if set.
+    pub fn min_pool_size(&self) -> Option<u16> {
+        self.min_pool_size
     }
-}
-
-#[cfg(feature = "io")]
-impl ParserIniFromStr for PgBouncerSetting {
-    type Error = PgBouncerError;
+
+    /// Set the number of additional server connections a pool may open when clients wait.
+    ///
+    /// # Parameters
+    /// - size: `Some(size)` sets the value; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `reserve_pool_size`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_reserve_pool_size(Some(5)).set_reserve_pool_timeout(Some(3));
+    /// assert_eq!(config.reserve_pool_size(), Some(5));
+    /// ```
+    ///
+    /// # Notes
+    /// - A [databases] entry overrides this with its own `reserve_pool`.
+    pub fn set_reserve_pool_size(&mut self, size: Option<u16>) -> &mut Self {
+        self.reserve_pool_size = size;
+        self
+    }
+
+    /// Returns the number of additional server connections of the reserve pool, if set.
+    pub fn reserve_pool_size(&self) -> Option<u16> {
+        self.reserve_pool_size
+    }
+
+    /// Set how long a client waits before the reserve pool is used.
+    ///
+    /// # Parameters
+    /// - secs: Optional timeout in seconds. `Some(seconds)` sets the value; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `reserve_pool_timeout`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_reserve_pool_timeout(Some(3));
+    /// config.set_reserve_pool_timeout(None);
+    /// ```
+    ///
+    /// # Notes
+    /// - `0` disables the reserve pool regardless of `reserve_pool_size`.
+    pub fn set_reserve_pool_timeout(&mut self, secs: Option<i32>) -> &mut Self {
+        self.reserve_pool_timeout = secs;
+        self
+    }
+
+    /// Returns how long a client waits before the reserve pool is used, if set.
+    pub fn reserve_pool_timeout(&self) -> Option<i32> {
+        self.reserve_pool_timeout
+    }
+
+    /// Set the maximum number of server connections per database.
+    ///
+    /// Caps the connections of every pool of a database together, protecting
+    /// PostgreSQL's `max_connections` when many users share a database.
+    ///
+    /// # Parameters
+    /// - count: `Some(count)` sets the value (`0` means unlimited); `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `max_db_connections`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_max_db_connections(Some(80));
+    /// assert_eq!(config.max_db_connections(), Some(80));
+    /// ```
+    ///
+    /// # Notes
+    /// - A [databases] entry overrides this with its own `max_db_connections`.
+    pub fn set_max_db_connections(&mut self, count: Option<u16>) -> &mut Self {
+        self.max_db_connections = count;
+        self
+    }
+
+    /// Returns the maximum number of server connections per database, if set.
+    pub fn max_db_connections(&self) -> Option<u16> {
+        self.max_db_connections
+    }
+
+    /// Set the maximum number of server connections per user.
+    ///
+    /// # Parameters
+    /// - count: `Some(count)` sets the value (`0` means unlimited); `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `max_user_connections`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_max_user_connections(Some(40));
+    /// assert_eq!(config.max_user_connections(), Some(40));
+    /// ```
+    ///
+    /// # Notes
+    /// - A [users] entry overrides this with its own `max_user_connections`.
+    pub fn set_max_user_connections(&mut self, count: Option<u16>) -> &mut Self {
+        self.max_user_connections = count;
+        self
+    }
+
+    /// Returns the maximum number of server connections per user, if set.
+    pub fn max_user_connections(&self) -> Option<u16> {
+        self.max_user_connections
+    }
+
+    /// Set the query run on a server connection before it is given to another client.
+    ///
+    /// # Parameters
+    /// - query: `Some(query)` sets the query, `Some("")` disables the reset; `None` clears the setting.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `server_reset_query`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_server_reset_query(Some("DISCARD ALL"));
+    /// assert_eq!(config.server_reset_query(), Some("DISCARD ALL"));
+    /// ```
+    ///
+    /// # Notes
+    /// - In transaction and statement pooling the query only runs with `server_reset_query_always`.
+    pub fn set_server_reset_query(&mut self, query: Option<&str>) -> &mut Self {
+        self.server_reset_query = query.map(|q| q.to_string());
+        self
+    }
+
+    /// Returns the query run on a server connection before it is reused, if set.
+    pub fn server_reset_query(&self) -> Option<&str> {
+        self.server_reset_query.as_deref()
+    }
+
+    /// Set whether `server_reset_query` also runs in transaction and statement pooling.
+    ///
+    /// # Parameters
+    /// - enabled: `Some(true)` runs the query in every pooling mode, `Some(false)` only in
+    ///   session pooling; `None` clears the setting.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `server_reset_query_always`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_server_reset_query_always(Some(true));
+    /// assert_eq!(config.server_reset_query_always(), Some(true));
+    /// ```
+    ///
+    /// # Notes
+    /// - Only needed for clients that leak session state in transaction pooling; it
+    ///   adds a round trip to every transaction.
+    pub fn set_server_reset_query_always(&mut self, enabled: Option<bool>) -> &mut Self {
+        self.server_reset_query_always = enabled;
+        self
+    }
+
+    /// Returns whether `server_reset_query` runs in every pooling mode, if set.
+    pub fn server_reset_query_always(&self) -> Option<bool> {
+        self.server_reset_query_always
+    }
+
+    /// Set whether server connections are closed right after their transaction during a reload or `RECONNECT`.
+    ///
+    /// # Parameters
+    /// - enabled: `Some(true)` closes them after the current transaction, `Some(false)` waits
+    ///   for their clients to release them; `None` clears the setting.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `server_fast_close`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_server_fast_close(Some(true));
+    /// config.set_server_fast_close(None);
+    /// ```
+    pub fn set_server_fast_close(&mut self, enabled: Option<bool>) -> &mut Self {
+        self.server_fast_close = enabled;
+        self
+    }
+
+    /// Returns whether server connections are closed early during a reload, if set.
+    pub fn server_fast_close(&self) -> Option<bool> {
+        self.server_fast_close
+    }
+
+    /// Set whether server connections are reused in round-robin order instead of LIFO.
+    ///
+    /// # Parameters
+    /// - enabled: `Some(true)` spreads the load over every server connection, `Some(false)`
+    ///   reuses the most recent one; `None` clears the setting.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `server_round_robin`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_server_round_robin(Some(true));
+    /// config.set_server_round_robin(None);
+    /// ```
+    ///
+    /// # Notes
+    /// - Useful when the server host resolves to several replicas behind one name.
+    pub fn set_server_round_robin(&mut self, enabled: Option<bool>) -> &mut Self {
+        self.server_round_robin = enabled;
+        self
+    }
+
+    /// Returns whether server connections are reused in round-robin order, if set.
+    pub fn server_round_robin(&self) -> Option<bool> {
+        self.server_round_robin
+    }
+
+    /// Set whether TCP keepalive is enabled on client and server sockets.
+    ///
+    /// # Parameters
+    /// - enabled: `Some(true)` enables keepalive, `Some(false)` disables it; `None` clears the setting.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `tcp_keepalive`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_tcp_keepalive(Some(true)).set_tcp_keepidle(Some(60));
+    /// assert_eq!(config.tcp_keepalive(), Some(true));
+    /// ```
+    ///
+    /// # Notes
+    /// - `tcp_keepcnt`, `tcp_keepidle` and `tcp_keepintvl` only apply while keepalive is enabled.
+    pub fn set_tcp_keepalive(&mut self, enabled: Option<bool>) -> &mut Self {
+        self.tcp_keepalive = enabled;
+        self
+    }
+
+    /// Returns whether TCP keepalive is enabled, if set.
+    pub fn tcp_keepalive(&self) -> Option<bool> {
+        self.tcp_keepalive
+    }
+
+    /// Set the number of unanswered keepalive probes before a connection is dropped.
+    ///
+    /// # Parameters
+    /// - count: `Some(count)` sets the value (`0` uses the OS default); `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `tcp_keepcnt`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_tcp_keepcnt(Some(4));
+    /// config.set_tcp_keepcnt(None);
+    /// ```
+    pub fn set_tcp_keepcnt(&mut self, count: Option<i32>) -> &mut Self {
+        self.tcp_keepcnt = count;
+        self
+    }
+
+    /// Returns the number of keepalive probes before a connection is dropped, if set.
+    pub fn tcp_keepcnt(&self) -> Option<i32> {
+        self.tcp_keepcnt
+    }
+
+    /// Set the idle time before the first keepalive probe is sent.
+    ///
+    /// # Parameters
+    /// - secs: Optional time in seconds (`0` uses the OS default). `Some(seconds)` sets the value; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `tcp_keepidle`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_tcp_keepidle(Some(60));
+    /// config.set_tcp_keepidle(None);
+    /// ```
+    ///
+    /// # Notes
+    /// - Keep it below the idle timeout of any NAT gateway or load balancer on the path.
+    pub fn set_tcp_keepidle(&mut self, secs: Option<i32>) -> &mut Self {
+        self.tcp_keepidle = secs;
+        self
+    }
+
+    /// Returns the idle time before the first keepalive probe, if set.
+    pub fn tcp_keepidle(&self) -> Option<i32> {
+        self.tcp_keepidle
+    }
+
+    /// Set the interval between keepalive probes.
+    ///
+    /// # Parameters
+    /// - secs: Optional interval in seconds (`0` uses the OS default). `Some(seconds)` sets the value; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `tcp_keepintvl`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_tcp_keepintvl(Some(15));
+    /// config.set_tcp_keepintvl(None);
+    /// ```
+    pub fn set_tcp_keepintvl(&mut self, secs: Option<i32>) -> &mut Self {
+        self.tcp_keepintvl = secs;
+        self
+    }
+
+    /// Returns the interval between keepalive probes, if set.
+    pub fn tcp_keepintvl(&self) -> Option<i32> {
+        self.tcp_keepintvl
+    }
+
+    /// Set how long sent data may stay unacknowledged before the connection is dropped.
+    ///
+    /// # Parameters
+    /// - millis: Optional timeout in milliseconds (`0` uses the OS default). `Some(millis)` sets the value; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `tcp_user_timeout`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_tcp_user_timeout(Some(30_000));
+    /// config.set_tcp_user_timeout(None);
+    /// ```
+    ///
+    /// # Notes
+    /// - Only supported on Linux; PgBouncer ignores it elsewhere.
+    pub fn set_tcp_user_timeout(&mut self, millis: Option<i32>) -> &mut Self {
+        self.tcp_user_timeout = millis;
+        self
+    }
+
+    /// Returns how long sent data may stay unacknowledged, in milliseconds, if set.
+    pub fn tcp_user_timeout(&self) -> Option<i32> {
+        self.tcp_user_timeout
+    }
+
+    /// Set whether several PgBouncer processes may listen on the same port.
+    ///
+    /// # Parameters
+    /// - enabled: `Some(true)` sets `SO_REUSEPORT` on the listening sockets, `Some(false)` does not;
+    ///   `None` clears the setting.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `so_reuseport`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_so_reuseport(Some(true)).set_peer_id(Some(1));
+    /// assert_eq!(config.so_reuseport(), Some(true));
+    /// ```
+    ///
+    /// # Notes
+    /// - Give every process its own `peer_id` and list them all in [peers], so cancel
+    ///   requests reaching the wrong process are forwarded.
+    pub fn set_so_reuseport(&mut self, enabled: Option<bool>) -> &mut Self {
+        self.so_reuseport = enabled;
+        self
+    }
+
+    /// Returns whether several processes may listen on the same port, if set.
+    pub fn so_reuseport(&self) -> Option<bool> {
+        self.so_reuseport
+    }
+
+    /// Set the ID of this process among the peers of the [peers] section.
+    ///
+    /// # Parameters
+    /// - peer_id: `Some(id)` sets the ID (`0` disables peering); `None` clears the setting.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `peer_id`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_peer_id(Some(2));
+    /// assert_eq!(config.peer_id(), Some(2));
+    /// ```
+    ///
+    /// # Notes
+    /// - IDs above [`MAX_PEER_ID`](crate::pgbouncer_config::peers_setting::MAX_PEER_ID)
+    ///   are reported by `validate`.
+    pub fn set_peer_id(&mut self, peer_id: Option<u16>) -> &mut Self {
+        self.peer_id = peer_id;
+        self
+    }
+
+    /// Returns the ID of this process among its peers, if set.
+    pub fn peer_id(&self) -> Option<u16> {
+        self.peer_id
+    }
+
+    /// Add a tracked client parameter.
+    ///
+    /// Appends the given parameter to the `track_extra_parameters` list.
+    ///
+    /// # Parameters
+    /// - param: Client parameter to track, e.g. `search_path`.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated list.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.add_track_extra_parameter("search_path");
+    /// assert_eq!(config.track_extra_parameters(), ["search_path"]);
+    /// ```
+    ///
+    /// # Notes
+    /// - Setting the list replaces PgBouncer's default, so add `IntervalStyle` as well to keep tracking it.
+    pub fn add_track_extra_parameter(&mut self, param: &str) -> &mut Self {
+        self.track_extra_parameters.push(param.to_string());
+        self
+    }
+
+    /// Returns the client parameters tracked in addition to the built-in ones.
+    pub fn track_extra_parameters(&self) -> &[String] {
+        &self.track_extra_parameters
+    }
+
+    /// Set whether the client address and port are appended to `application_name`.
+    ///
+    /// # Parameters
+    /// - enabled: `Some(true)` appends them, `Some(false)` does not; `None` clears the setting.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `application_name_add_host`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_application_name_add_host(Some(true));
+    /// assert_eq!(config.application_name_add_host(), Some(true));
+    /// ```
+    pub fn set_application_name_add_host(&mut self, enabled: Option<bool>) -> &mut Self {
+        self.application_name_add_host = enabled;
+        self
+    }
+
+    /// Returns whether the client address is appended to `application_name`, if set.
+    pub fn application_name_add_host(&self) -> Option<bool> {
+        self.application_name_add_host
+    }
+
+    /// Set whether successful logins are logged.
+    ///
+    /// # Parameters
+    /// - enabled: `Some(true)` logs logins, `Some(false)` does not; `None` clears the setting.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `log_connections`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_log_connections(Some(false));
+    /// config.set_log_connections(None);
+    /// ```
+    pub fn set_log_connections(&mut self, enabled: Option<bool>) -> &mut Self {
+        self.log_connections = enabled;
+        self
+    }
+
+    /// Set whether disconnections are logged, with their reason.
+    ///
+    /// # Parameters
+    /// - enabled: `Some(true)` logs disconnections, `Some(false)` does not; `None` clears the setting.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `log_disconnections`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_log_disconnections(Some(false));
+    /// config.set_log_disconnections(None);
+    /// ```
+    pub fn set_log_disconnections(&mut self, enabled: Option<bool>) -> &mut Self {
+        self.log_disconnections = enabled;
+        self
+    }
+
+    /// Set whether error messages PgBouncer sends to clients are logged.
+    ///
+    /// # Parameters
+    /// - enabled: `Some(true)` logs pooler errors, `Some(false)` does not; `None` clears the setting.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `log_pooler_errors`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_log_pooler_errors(Some(false));
+    /// config.set_log_pooler_errors(None);
+    /// ```
+    pub fn set_log_pooler_errors(&mut self, enabled: Option<bool>) -> &mut Self {
+        self.log_pooler_errors = enabled;
+        self
+    }
+
+    /// Set whether aggregated statistics are logged every `stats_period`.
+    ///
+    /// # Parameters
+    /// - enabled: `Some(true)` logs statistics, `Some(false)` does not; `None` clears the setting.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `log_stats`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_log_stats(Some(false));
+    /// config.set_log_stats(None);
+    /// ```
+    pub fn set_log_stats(&mut self, enabled: Option<bool>) -> &mut Self {
+        self.log_stats = enabled;
+        self
+    }
+
+    /// Set the statistics period.
+    ///
+    /// Controls how often PgBouncer aggregates statistics and, with `log_stats`, logs them.
+    ///
+    /// # Parameters
+    /// - secs: Optional period in seconds. `Some(seconds)` sets the value; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `stats_period`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_stats_period(Some(30));
+    /// config.set_stats_period(None);
+    /// ```
+    pub fn set_stats_period(&mut self, secs: Option<i32>) -> &mut Self {
+        self.stats_period = secs;
+        self
+    }
+
+    /// Set the log verbosity.
+    ///
+    /// # Parameters
+    /// - level: Optional level. 0 logs the default messages, 1 and 2 add debug
+    ///   messages; `None` clears the setting.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `verbose`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_verbose(Some(1));
+    /// config.set_verbose(None);
+    /// ```
+    pub fn set_verbose(&mut self, level: Option<u8>) -> &mut Self {
+        self.verbose = level;
+        self
+    }
+
+    /// Set whether PgBouncer logs to syslog as well.
+    ///
+    /// # Parameters
+    /// - enabled: `Some(true)` logs to syslog, `Some(false)` does not; `None` clears the setting.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `syslog`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_syslog(Some(true));
+    /// config.set_syslog(None);
+    /// ```
+    pub fn set_syslog(&mut self, enabled: Option<bool>) -> &mut Self {
+        self.syslog = enabled;
+        self
+    }
+
+    /// Set the `syslog_facility`.
+    ///
+    /// # Parameters
+    /// - facility: Optional facility. `Some(facility)` sets it; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `syslog_facility`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::{PgBouncerSetting, SyslogFacility};
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_syslog_facility(Some(SyslogFacility::Local0));
+    /// config.set_syslog_facility(None);
+    /// ```
+    pub fn set_syslog_facility(&mut self, facility: Option<SyslogFacility>) -> &mut Self {
+        self.syslog_facility = facility;
+        self
+    }
+
+    /// Set the `syslog_ident`.
+    ///
+    /// # Parameters
+    /// - value: Optional value. `Some(value)` sets it; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `syslog_ident`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_syslog_ident(Some("pgbouncer-main"));
+    /// config.set_syslog_ident(None);
+    /// ```
+    pub fn set_syslog_ident(&mut self, value: Option<&str>) -> &mut Self {
+        self.syslog_ident = value.map(|v| v.to_string());
+        self
+    }
+
+    /// Set the `client_tls_sslmode`.
+    ///
+    /// # Parameters
+    /// - mode: Optional TLS mode. `Some(mode)` sets the value; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `client_tls_sslmode`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::{PgBouncerSetting, SslMode};
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_client_tls_sslmode(Some(SslMode::Require));
+    /// config.set_client_tls_sslmode(None);
+    /// ```
+    pub fn set_client_tls_sslmode(&mut self, mode: Option<SslMode>) -> &mut Self {
+        self.client_tls_sslmode = mode;
+        self
+    }
+
+    /// Returns the TLS mode of client connections, if set.
+    pub fn client_tls_sslmode(&self) -> Option<SslMode> {
+        self.client_tls_sslmode
+    }
+
+    /// Set the `client_tls_key_file`.
+    ///
+    /// # Parameters
+    /// - value: Optional value. `Some(value)` sets it; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `client_tls_key_file`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_client_tls_key_file(Some("/etc/pgbouncer/server.key"));
+    /// config.set_client_tls_key_file(None);
+    /// ```
+    pub fn set_client_tls_key_file(&mut self, value: Option<&str>) -> &mut Self {
+        self.client_tls_key_file = value.map(|v| v.to_string());
+        self
+    }
+
+    /// Set the `client_tls_cert_file`.
+    ///
+    /// # Parameters
+    /// - value: Optional value. `Some(value)` sets it; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `client_tls_cert_file`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_client_tls_cert_file(Some("/etc/pgbouncer/server.crt"));
+    /// config.set_client_tls_cert_file(None);
+    /// ```
+    pub fn set_client_tls_cert_file(&mut self, value: Option<&str>) -> &mut Self {
+        self.client_tls_cert_file = value.map(|v| v.to_string());
+        self
+    }
+
+    /// Set the `client_tls_ca_file`.
+    ///
+    /// # Parameters
+    /// - value: Optional value. `Some(value)` sets it; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `client_tls_ca_file`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_client_tls_ca_file(Some("/etc/pgbouncer/root.crt"));
+    /// config.set_client_tls_ca_file(None);
+    /// ```
+    pub fn set_client_tls_ca_file(&mut self, value: Option<&str>) -> &mut Self {
+        self.client_tls_ca_file = value.map(|v| v.to_string());
+        self
+    }
+
+    /// Set the `client_tls_protocols`.
+    ///
+    /// # Parameters
+    /// - value: Optional value. `Some(value)` sets it; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `client_tls_protocols`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_client_tls_protocols(Some("tlsv1.2,tlsv1.3"));
+    /// config.set_client_tls_protocols(None);
+    /// ```
+    pub fn set_client_tls_protocols(&mut self, value: Option<&str>) -> &mut Self {
+        self.client_tls_protocols = value.map(|v| v.to_string());
+        self
+    }
+
+    /// Set the `client_tls_ciphers`.
+    ///
+    /// # Parameters
+    /// - value: Optional value. `Some(value)` sets it; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `client_tls_ciphers`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_client_tls_ciphers(Some("HIGH:!aNULL"));
+    /// config.set_client_tls_ciphers(None);
+    /// ```
+    pub fn set_client_tls_ciphers(&mut self, value: Option<&str>) -> &mut Self {
+        self.client_tls_ciphers = value.map(|v| v.to_string());
+        self
+    }
+
+    /// Set the `client_tls_dheparams`.
+    ///
+    /// # Parameters
+    /// - value: Optional value. `Some(value)` sets it; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `client_tls_dheparams`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_client_tls_dheparams(Some("auto"));
+    /// config.set_client_tls_dheparams(None);
+    /// ```
+    pub fn set_client_tls_dheparams(&mut self, value: Option<&str>) -> &mut Self {
+        self.client_tls_dheparams = value.map(|v| v.to_string());
+        self
+    }
+
+    /// Set the `client_tls_ecdhcurve`.
+    ///
+    /// # Parameters
+    /// - value: Optional value. `Some(value)` sets it; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `client_tls_ecdhcurve`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_client_tls_ecdhcurve(Some("prime256v1"));
+    /// config.set_client_tls_ecdhcurve(None);
+    /// ```
+    pub fn set_client_tls_ecdhcurve(&mut self, value: Option<&str>) -> &mut Self {
+        self.client_tls_ecdhcurve = value.map(|v| v.to_string());
+        self
+    }
+
+    /// Set the `server_tls_sslmode`.
+    ///
+    /// # Parameters
+    /// - mode: Optional TLS mode. `Some(mode)` sets the value; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `server_tls_sslmode`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::{PgBouncerSetting, SslMode};
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_server_tls_sslmode(Some(SslMode::VerifyFull));
+    /// config.set_server_tls_sslmode(None);
+    /// ```
+    pub fn set_server_tls_sslmode(&mut self, mode: Option<SslMode>) -> &mut Self {
+        self.server_tls_sslmode = mode;
+        self
+    }
+
+    /// Set the `server_tls_ca_file`.
+    ///
+    /// # Parameters
+    /// - value: Optional value. `Some(value)` sets it; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `server_tls_ca_file`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_server_tls_ca_file(Some("/etc/pgbouncer/root.crt"));
+    /// config.set_server_tls_ca_file(None);
+    /// ```
+    pub fn set_server_tls_ca_file(&mut self, value: Option<&str>) -> &mut Self {
+        self.server_tls_ca_file = value.map(|v| v.to_string());
+        self
+    }
+
+    /// Set the `server_tls_key_file`.
+    ///
+    /// # Parameters
+    /// - value: Optional value. `Some(value)` sets it; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `server_tls_key_file`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_server_tls_key_file(Some("/etc/pgbouncer/client.key"));
+    /// config.set_server_tls_key_file(None);
+    /// ```
+    pub fn set_server_tls_key_file(&mut self, value: Option<&str>) -> &mut Self {
+        self.server_tls_key_file = value.map(|v| v.to_string());
+        self
+    }
+
+    /// Set the `server_tls_cert_file`.
+    ///
+    /// # Parameters
+    /// - value: Optional value. `Some(value)` sets it; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `server_tls_cert_file`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_server_tls_cert_file(Some("/etc/pgbouncer/client.crt"));
+    /// config.set_server_tls_cert_file(None);
+    /// ```
+    pub fn set_server_tls_cert_file(&mut self, value: Option<&str>) -> &mut Self {
+        self.server_tls_cert_file = value.map(|v| v.to_string());
+        self
+    }
+
+    /// Set the `server_tls_protocols`.
+    ///
+    /// # Parameters
+    /// - value: Optional value. `Some(value)` sets it; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `server_tls_protocols`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_server_tls_protocols(Some("tlsv1.2,tlsv1.3"));
+    /// config.set_server_tls_protocols(None);
+    /// ```
+    pub fn set_server_tls_protocols(&mut self, value: Option<&str>) -> &mut Self {
+        self.server_tls_protocols = value.map(|v| v.to_string());
+        self
+    }
+
+    /// Set the `server_tls_ciphers`.
+    ///
+    /// # Parameters
+    /// - value: Optional value. `Some(value)` sets it; `None` clears it.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated `server_tls_ciphers`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_server_tls_ciphers(Some("HIGH:!aNULL"));
+    /// config.set_server_tls_ciphers(None);
+    /// ```
+    pub fn set_server_tls_ciphers(&mut self, value: Option<&str>) -> &mut Self {
+        self.server_tls_ciphers = value.map(|v| v.to_string());
+        self
+    }
+
+    /// Set a key this type does not model.
+    ///
+    /// Extra keys are rendered as is, in key order, after the modeled keys,
+    /// and keys without a typed field found while parsing are stored here. Use the typed
+    /// setters for modeled keys; [`PgBouncerConfig::validate`](crate::pgbouncer_config::PgBouncerConfig::validate)
+    /// reports extra keys that shadow one of them, and
+    /// [`PgBouncerConfig::validate_for`](crate::pgbouncer_config::PgBouncerConfig::validate_for)
+    /// checks the version of the extra keys listed in the settings catalog.
+    ///
+    /// # Parameters
+    /// - key: Key as written in pgbouncer.ini.
+    /// - value: Raw value, rendered as is.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated extra keys.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    /// use pgbouncer_config::pgbouncer_config::Expression;
+    ///
+    /// let mut config = PgBouncerSetting::default();
+    /// config.set_extra("some_new_key", "value");
+    /// assert_eq!(config.extra().get("some_new_key").map(String::as_str), Some("value"));
+    /// assert!(config.expr().unwrap().ends_with("some_new_key = value\n"));
+    /// ```
+    pub fn set_extra(&mut self, key: &str, value: &str) -> &mut Self {
+        self.extra.insert(key.trim().to_string(), value.to_string());
+        self
+    }
+
+    /// Remove a key set with [`set_extra`](Self::set_extra).
+    ///
+    /// # Parameters
+    /// - key: Key to remove.
+    ///
+    /// # Returns
+    /// The removed value, or `None` if the key was not set.
+    pub fn remove_extra(&mut self, key: &str) -> Option<String> {
+        self.extra.remove(key)
+    }
+
+    /// Returns the keys not modeled by this type and their raw values.
+    pub fn extra(&self) -> &BTreeMap<String, String> {
+        &self.extra
+    }
+
+    /// Builds a setting from the rows returned by the admin console's `SHOW CONFIG`.
+    ///
+    /// Rows with an empty value (unset settings) and keys not modeled by this
+    /// type are skipped (they are not kept as extra keys), so the result can be compared with a definition using
+    /// [`compute_diff`](crate::utils::diff::compute_diff).
+    ///
+    /// # Parameters
+    /// - rows: `(key, value)` pairs of `SHOW CONFIG`.
+    ///
+    /// # Returns
+    /// The effective setting of the running instance.
+    ///
+    /// # Errors
+    /// Returns an error if a required key is missing or a value cannot be parsed.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::{AuthType, PgBouncerSetting};
+    ///
+    /// let rows = [
+    ///     ("listen_addr", "0.0.0.0"), ("listen_port", "6432"), ("auth_type", "scram-sha-256"),
+    ///     ("max_client_conn", "100"), ("default_pool_size", "20"), ("pool_mode", "transaction"),
+    ///     ("admin_users", ""), ("server_lifetime", "3600"), ("verbose", "0"),
+    /// ];
+    /// let setting = PgBouncerSetting::from_show_config(rows).unwrap();
+    /// assert_eq!(setting.auth_type(), AuthType::ScramSha256);
+    /// ```
+    #[cfg(feature = "io")]
+    pub fn from_show_config<I, K, V>(rows: I) -> crate::error::Result<Self>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let ini = rows
+            .into_iter()
+            .filter(|(key, value)| {
+                !value.as_ref().trim().is_empty() && find_section_setting("pgbouncer", key.as_ref()).is_some()
+            })
+            .map(|(key, value)| format!("{} = {}\n", key.as_ref(), value.as_ref()))
+            .collect::<String>();
+
+        Self::parse_from_str(&ini)
+    }
+
+    /// Returns the keys written to pgbouncer.ini, in render order.
+    pub(crate) fn configured_keys(&self) -> Vec<String> {
+        self.configured_values()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    /// Keys and rendered values written to the [pgbouncer] section, in output order.
+    pub(crate) fn configured_values(&self) -> Vec<(String, String)> {
+        self.expr()
+            .map(|text| text
+                .lines()
+                .filter_map(|line| line.split_once(" = ").map(|(key, value)| (key.to_string(), value.to_string())))
+                .collect())
+            .unwrap_or_default()
+    }
+
+    /// Appends the issues found in this section to `issues`.
+    pub(crate) fn validate_into(&self, issues: &mut Vec<ValidationIssue>) {
+        if self.auth_type == AuthType::Hba && self.auth_hba_file.is_none() {
+            issues.push(ValidationIssue::error(
+                "pgbouncer",
+                Some("auth_hba_file"),
+                "auth_hba_file is required when auth_type is 'hba'",
+            ));
+        }
+
+        // With auth_query, passwords come from the database and the auth_user's
+        // own password may be given in [databases] instead of auth_file.
+        let needs_auth_file = matches!(self.auth_type, AuthType::Md5 | AuthType::ScramSha256 | AuthType::Plain);
+        if needs_auth_file && self.auth_file.is_none() && self.auth_user.is_none() {
+            issues.push(ValidationIssue::error(
+                "pgbouncer",
+                Some("auth_file"),
+                &format!("auth_file is required when auth_type is '{}'", self.auth_type),
+            ));
+        }
+
+        if self.auth_query.is_some() && self.auth_user.is_none() {
+            issues.push(ValidationIssue::warning(
+                "pgbouncer",
+                Some("auth_user"),
+                "auth_query is set but auth_user is not; it is only used by databases that set their own auth_user",
+            ));
+        }
+
+        if self.default_pool_size > self.max_client_conn {
+            issues.push(ValidationIssue::warning(
+                "pgbouncer",
+                Some("default_pool_size"),
+                &format!(
+                    "default_pool_size ({}) is larger than max_client_conn ({})",
+                    self.default_pool_size, self.max_client_conn
+                ),
+            ));
+        }
+
+        if let Some(peer_id) = self.peer_id.filter(|id| *id > MAX_PEER_ID) {
+            issues.push(ValidationIssue::error(
+                "pgbouncer",
+                Some("peer_id"),
+                &format!("peer_id must be between 0 and {}: {}", MAX_PEER_ID, peer_id),
+            ));
+        }
+
+        for key in self.extra.keys() {
+            if find_section_setting("pgbouncer", key).is_some_and(|setting| setting.modeled) {
+                issues.push(ValidationIssue::error(
+                    "pgbouncer",
+                    Some(key.as_str()),
+                    &format!("{} is modeled by PgBouncerSetting; set it with its setter instead of set_extra", key),
+                ));
+            }
+        }
+
+        let client_tls_enabled = self.client_tls_sslmode.is_some_and(|mode| mode != SslMode::Disable);
+        if client_tls_enabled {
+            for (key, value) in [
+                ("client_tls_key_file", &self.client_tls_key_file),
+                ("client_tls_cert_file", &self.client_tls_cert_file),
+            ] {
+                if value.is_none() {
+                    issues.push(ValidationIssue::error(
+                        "pgbouncer",
+                        Some(key),
+                        &format!("{} is required when client_tls_sslmode is not 'disable'", key),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+impl Default for PgBouncerSetting {
+    fn default() -> Self {
+        Self::new(
+            "127.0.0.1",
+            6432,
+            AuthType::default(),
+            2000,
+            100,
+            PoolMode::default(),
+            vec![],
+            vec![],
+            vec![],
+            None, 
+            None,
+            Some("/etc/pgbouncer-config/userlist.txt"),
+            None, 
+            None, 
+            None, 
+            None, 
+            None, 
+            None, 
+            None, 
+            None, 
+            None, 
+            None, 
+            None,
+            None, 
+            None, 
+            None, 
+            None, 
+            None, 
+            None, 
+            None, 
+            None,
+        )
+    }
+}
+
+#[typetag::serde]
+impl Expression for PgBouncerSetting {
+    fn expr(&self) -> crate::error::Result<String> {
+        let mut expr = "[pgbouncer]\n".to_string();
+        expr.push_str(&format!("listen_addr = {}\n", self.listen_addr));
+        expr.push_str(&format!("listen_port = {}\n", self.listen_port));
+        expr.push_str(&format!("auth_type = {}\n", self.auth_type));
+        expr.push_str(&format!("max_client_conn = {}\n", self.max_client_conn));
+        expr.push_str(&format!("default_pool_size = {}\n", self.default_pool_size));
+        expr.push_str(&format!("pool_mode = {}\n", self.pool_mode));
+
+        if self.admin_users.len() > 0 {
+            expr.push_str(&format!("admin_users = {}\n", self.admin_users.join(",")));
+        }
+        if self.stats_users.len() > 0 {
+            expr.push_str(&format!("stats_users = {}\n", self.stats_users.join(",")));
+        }
+        if self.ignore_startup_parameters.len() > 0 {
+            expr.push_str(&format!("ignore_startup_parameters = {}\n", self.ignore_startup_parameters.join(",")));
+        }
+
+        if let Some(logfile) = &self.logfile {
+            expr.push_str(&format!("logfile = {}\n", logfile));
+        }
+        if let Some(pidfile) = &self.pidfile {
+            expr.push_str(&format!("pidfile = {}\n", pidfile));
+        }
+        if let Some(auth_file) = &self.auth_file {
+            expr.push_str(&format!("auth_file = {}\n", auth_file));
+        }
+        if let Some(unix_socket_dir) = &self.unix_socket_dir {
+            expr.push_str(&format!("unix_socket_dir = {}\n", unix_socket_dir));
+        }
+        if let Some(auth_hba_file) = &self.auth_hba_file {
+            expr.push_str(&format!("auth_hba_file = {}\n", auth_hba_file));
+        }
+        if let Some(auth_ident_file) = &self.auth_ident_file {
+            expr.push_str(&format!("auth_ident_file = {}\n", auth_ident_file));
+        }
+        if let Some(auth_user) = &self.auth_user {
+            expr.push_str(&format!("auth_user = {}\n", auth_user));
+        }
+        if let Some(auth_query) = &self.auth_query {
+            expr.push_str(&format!("auth_query = {}\n", auth_query));
+        }
+        if let Some(auth_dbname) = &self.auth_dbname {
+            expr.push_str(&format!("auth_dbname = {}\n", auth_dbname));
+        }
+
+        let optional_numbers = [
+            ("server_check_delay", self.server_check_delay),
+            ("server_idle_timeout", self.server_idle_timeout),
+            ("server_lifetime", self.server_lifetime),
+            ("server_connect_timeout", self.server_connect_timeout),
+            ("server_login_retry", self.server_login_retry),
+            ("client_login_timeout", self.client_login_timeout),
+            ("autodb_idle_timeout", self.autodb_idle_timeout),
+            ("dns_max_ttl", self.dns_max_ttl),
+            ("dns_nxdomain_ttl", self.dns_nxdomain_ttl),
+        ];
+        for (key, value) in optional_numbers {
+            if let Some(value) = value {
+                expr.push_str(&format!("{} = {}\n", key, value));
+            }
+        }
+        if let Some(resolve_conf) = &self.resolve_conf {
+            expr.push_str(&format!("resolve_conf = {}\n", resolve_conf));
+        }
+        let optional_timeouts = [
+            ("query_timeout", self.query_timeout),
+            ("query_wait_timeout", self.query_wait_timeout),
+            ("cancel_wait_timeout", self.cancel_wait_timeout),
+            ("client_idle_timeout", self.client_idle_timeout),
+            ("idle_transaction_timeout", self.idle_transaction_timeout),
+            ("suspend_timeout", self.suspend_timeout),
+        ];
+        for (key, value) in optional_timeouts {
+            if let Some(value) = value {
+                expr.push_str(&format!("{} = {}\n", key, value));
+            }
+        }
+        let optional_pool = [
+            ("min_pool_size", self.min_pool_size.map(|size| size.to_string())),
+            ("reserve_pool_size", self.reserve_pool_size.map(|size| size.to_string())),
+            ("reserve_pool_timeout", self.reserve_pool_timeout.map(|secs| secs.to_string())),
+            ("max_db_connections", self.max_db_connections.map(|count| count.to_string())),
+            ("max_user_connections", self.max_user_connections.map(|count| count.to_string())),
+        ];
+        for (key, value) in optional_pool {
+            if let Some(value) = value {
+                expr.push_str(&format!("{} = {}\n", key, value));
+            }
+        }
+        let optional_server = [
+            ("server_reset_query", self.server_reset_query.clone()),
+            ("server_reset_query_always", self.server_reset_query_always.map(render_bool)),
+            ("server_fast_close", self.server_fast_close.map(render_bool)),
+            ("server_round_robin", self.server_round_robin.map(render_bool)),
+        ];
+        for (key, value) in optional_server {
+            if let Some(value) = value {
+                expr.push_str(&format!("{} = {}\n", key, value));
+            }
+        }
+        let optional_tcp = [
+            ("tcp_keepalive", self.tcp_keepalive.map(render_bool)),
+            ("tcp_keepcnt", self.tcp_keepcnt.map(|count| count.to_string())),
+            ("tcp_keepidle", self.tcp_keepidle.map(|secs| secs.to_string())),
+            ("tcp_keepintvl", self.tcp_keepintvl.map(|secs| secs.to_string())),
+            ("tcp_user_timeout", self.tcp_user_timeout.map(|millis| millis.to_string())),
+        ];
+        for (key, value) in optional_tcp {
+            if let Some(value) = value {
+                expr.push_str(&format!("{} = {}\n", key, value));
+            }
+        }
+        let optional_peering = [
+            ("so_reuseport", self.so_reuseport.map(render_bool)),
+            ("peer_id", self.peer_id.map(|id| id.to_string())),
+        ];
+        for (key, value) in optional_peering {
+            if let Some(value) = value {
+                expr.push_str(&format!("{} = {}\n", key, value));
+            }
+        }
+        if !self.track_extra_parameters.is_empty() {
+            expr.push_str(&format!("track_extra_parameters = {}\n", self.track_extra_parameters.join(",")));
+        }
+        if let Some(enabled) = self.application_name_add_host {
+            expr.push_str(&format!("application_name_add_host = {}\n", render_bool(enabled)));
+        }
+        let optional_logging = [
+            ("log_connections", self.log_connections.map(render_bool)),
+            ("log_disconnections", self.log_disconnections.map(render_bool)),
+            ("log_pooler_errors", self.log_pooler_errors.map(render_bool)),
+            ("log_stats", self.log_stats.map(render_bool)),
+            ("stats_period", self.stats_period.map(|secs| secs.to_string())),
+            ("verbose", self.verbose.map(|level| level.to_string())),
+            ("syslog", self.syslog.map(render_bool)),
+            ("syslog_facility", self.syslog_facility.map(|facility| facility.to_string())),
+            ("syslog_ident", self.syslog_ident.clone()),
+        ];
+        for (key, value) in optional_logging {
+            if let Some(value) = value {
+                expr.push_str(&format!("{} = {}\n", key, value));
+            }
+        }
+        let optional_tls = [
+            ("client_tls_sslmode", self.client_tls_sslmode.map(|m| m.to_string())),
+            ("client_tls_key_file", self.client_tls_key_file.clone()),
+            ("client_tls_cert_file", self.client_tls_cert_file.clone()),
+            ("client_tls_ca_file", self.client_tls_ca_file.clone()),
+            ("client_tls_protocols", self.client_tls_protocols.clone()),
+            ("client_tls_ciphers", self.client_tls_ciphers.clone()),
+            ("client_tls_dheparams", self.client_tls_dheparams.clone()),
+            ("client_tls_ecdhcurve", self.client_tls_ecdhcurve.clone()),
+            ("server_tls_sslmode", self.server_tls_sslmode.map(|m| m.to_string())),
+            ("server_tls_ca_file", self.server_tls_ca_file.clone()),
+            ("server_tls_key_file", self.server_tls_key_file.clone()),
+            ("server_tls_cert_file", self.server_tls_cert_file.clone()),
+            ("server_tls_protocols", self.server_tls_protocols.clone()),
+            ("server_tls_ciphers", self.server_tls_ciphers.clone()),
+        ];
+        for (key, value) in optional_tls {
+            if let Some(value) = value {
+                expr.push_str(&format!("{} = {}\n", key, value));
+            }
+        }
+        for (key, value) in &self.extra {
+            expr.push_str(&format!("{} = {}\n", key, value));
+        }
+
+        Ok(expr)
+    }
+
+    /// Sorts and deduplicates the user and startup parameter lists, and trims
+    /// the values of unmodeled keys.
+    fn canonicalize(&mut self) {
+        for list in [
+            &mut self.admin_users,
+            &mut self.stats_users,
+            &mut self.ignore_startup_parameters,
+            &mut self.track_extra_parameters,
+        ] {
+            list.sort();
+            list.dedup();
+        }
+        for value in self.extra.values_mut() {
+            *value = value.trim().to_string();
+        }
+    }
+
+    fn section_name(&self) -> &'static str {
+        "pgbouncer"
+    }
+}
+
+#[cfg(feature = "io")]
+impl ParserIniFromStr for PgBouncerSetting {
+    type Error = PgBouncerError;
 
     fn parse_from_str(value: &str) -> Result<Self, Self::Error> {
         let mut pgbouncer_setting = HashMap::new();
@@ -1049,41 +2856,41 @@ impl ParserIniFromStr for PgBouncerSetting {
 
         let listen_addr = pgbouncer_setting.get("listen_addr")
             .ok_or(
-                PgBouncerError::PgBouncer("listen_addr is required in [pgbouncer] section".to_string())
+                PgBouncerError::parse_error("pgbouncer", "listen_addr", "listen_addr is required in [pgbouncer] section")
             )?
             .to_string();
         let listen_port: u16 = pgbouncer_setting.get("listen_port")
             .ok_or(
-                PgBouncerError::PgBouncer("listen_port is required in [pgbouncer] section".to_string())
+                PgBouncerError::parse_error("pgbouncer", "listen_port", "listen_port is required in [pgbouncer] section")
             )?
             .parse()
-            .map_err(|_| PgBouncerError::PgBouncer("listen_port must be a number".to_string()))?;
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "listen_port", "listen_port must be a number"))?;
         let auth_type_str = pgbouncer_setting.get("auth_type")
             .ok_or(
-                PgBouncerError::PgBouncer("auth_type is required in [pgbouncer] section".to_string())
+                PgBouncerError::parse_error("pgbouncer", "auth_type", "auth_type is required in [pgbouncer] section")
             )?.to_string();
-        let auth_type = AuthType::try_from(auth_type_str)?;
+        let auth_type = AuthType::try_from(auth_type_str).map_err(|e| e.at_key("pgbouncer", "auth_type"))?;
 
         let max_client_conn: u16 = pgbouncer_setting.get("max_client_conn")
             .ok_or(
-                PgBouncerError::PgBouncer("max_client_conn is required in [pgbouncer] section".to_string())
+                PgBouncerError::parse_error("pgbouncer", "max_client_conn", "max_client_conn is required in [pgbouncer] section")
             )?
             .parse()
-            .map_err(|_| PgBouncerError::PgBouncer("max_client_conn must be a number".to_string()))?;
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "max_client_conn", "max_client_conn must be a number"))?;
 
         let default_pool_size: u16 = pgbouncer_setting.get("default_pool_size")
             .ok_or(
-                PgBouncerError::PgBouncer("default_pool_size is required in [pgbouncer] section".to_string())
+                PgBouncerError::parse_error("pgbouncer", "default_pool_size", "default_pool_size is required in [pgbouncer] section")
             )?
             .parse()
-            .map_err(|_| PgBouncerError::PgBouncer("default_pool_size must be a number".to_string()))?;
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "default_pool_size", "default_pool_size must be a number"))?;
 
         let pool_mode = match pgbouncer_setting.get("pool_mode")
-            .ok_or(PgBouncerError::PgBouncer("pool_mode is required in [pgbouncer] section".to_string()))? {
+            .ok_or(PgBouncerError::parse_error("pgbouncer", "pool_mode", "pool_mode is required in [pgbouncer] section"))? {
             s if s.eq_ignore_ascii_case("session") => PoolMode::Session,
             s if s.eq_ignore_ascii_case("transaction") => PoolMode::Transaction,
             s if s.eq_ignore_ascii_case("statement") => PoolMode::Statement,
-            other => return Err(PgBouncerError::PgBouncer(format!("Invalid pool_mode: {}", other))),
+            other => return Err(PgBouncerError::parse_error("pgbouncer", "pool_mode", format!("Invalid pool_mode: {}", other))),
         };
 
         let admin_users = pgbouncer_setting.get("admin_users")
@@ -1104,83 +2911,199 @@ impl ParserIniFromStr for PgBouncerSetting {
         let unix_socket_dir = pgbouncer_setting.get("unix_socket_dir").map(|s| s.to_string());
         let auth_hba_file = pgbouncer_setting.get("auth_hba_file").map(|s| s.to_string());
         let auth_ident_file = pgbouncer_setting.get("auth_ident_file").map(|s| s.to_string());
+        let auth_user = pgbouncer_setting.get("auth_user").map(|s| s.to_string());
+        let auth_query = pgbouncer_setting.get("auth_query").map(|s| s.to_string());
+        let auth_dbname = pgbouncer_setting.get("auth_dbname").map(|s| s.to_string());
 
         let server_check_delay = pgbouncer_setting.get("server_check_delay")
             .map(|v| v.parse::<i32>())
             .transpose()
-            .map_err(|_| PgBouncerError::PgBouncer("server_check_delay must be a number".to_string()))?;
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "server_check_delay", "server_check_delay must be a number"))?;
 
         let server_idle_timeout = pgbouncer_setting.get("server_idle_timeout")
             .map(|v| v.parse::<i32>())
             .transpose()
-            .map_err(|_| PgBouncerError::PgBouncer("server_idle_timeout must be a number".to_string()))?;
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "server_idle_timeout", "server_idle_timeout must be a number"))?;
 
         let server_lifetime = pgbouncer_setting.get("server_lifetime")
             .map(|v| v.parse::<i32>())
             .transpose()
-            .map_err(|_| PgBouncerError::PgBouncer("server_lifetime must be a number".to_string()))?;
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "server_lifetime", "server_lifetime must be a number"))?;
 
         let server_connect_timeout = pgbouncer_setting.get("server_connect_timeout")
             .map(|v| v.parse::<i32>())
             .transpose()
-            .map_err(|_| PgBouncerError::PgBouncer("server_connect_timeout must be a number".to_string()))?;
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "server_connect_timeout", "server_connect_timeout must be a number"))?;
 
         let server_login_retry = pgbouncer_setting.get("server_login_retry")
             .map(|v| v.parse::<i32>())
             .transpose()
-            .map_err(|_| PgBouncerError::PgBouncer("server_login_retry must be a number".to_string()))?;
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "server_login_retry", "server_login_retry must be a number"))?;
 
         let client_login_timeout = pgbouncer_setting.get("client_login_timeout")
             .map(|v| v.parse::<i32>())
             .transpose()
-            .map_err(|_| PgBouncerError::PgBouncer("client_login_timeout must be a number".to_string()))?;
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "client_login_timeout", "client_login_timeout must be a number"))?;
 
         let autodb_idle_timeout = pgbouncer_setting.get("autodb_idle_timeout")
             .map(|v| v.parse::<i32>())
             .transpose()
-            .map_err(|_| PgBouncerError::PgBouncer("autodb_idle_timeout must be a number".to_string()))?;
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "autodb_idle_timeout", "autodb_idle_timeout must be a number"))?;
 
         let dns_max_ttl = pgbouncer_setting.get("dns_max_ttl")
             .map(|v| v.parse::<i32>())
             .transpose()
-            .map_err(|_| PgBouncerError::PgBouncer("dns_max_ttl must be a number".to_string()))?;
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "dns_max_ttl", "dns_max_ttl must be a number"))?;
 
         let dns_nxdomain_ttl = pgbouncer_setting.get("dns_nxdomain_ttl")
             .map(|v| v.parse::<i32>())
             .transpose()
-            .map_err(|_| PgBouncerError::PgBouncer("dns_nxdomain_ttl must be a number".to_string()))?;
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "dns_nxdomain_ttl", "dns_nxdomain_ttl must be a number"))?;
 
         let resolve_conf = pgbouncer_setting.get("resolve_conf").map(|s| s.to_string());
 
         let query_timeout = pgbouncer_setting.get("query_timeout")
             .map(|v| v.parse::<i32>())
             .transpose()
-            .map_err(|_| PgBouncerError::PgBouncer("query_timeout must be a number".to_string()))?;
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "query_timeout", "query_timeout must be a number"))?;
 
         let query_wait_timeout = pgbouncer_setting.get("query_wait_timeout")
             .map(|v| v.parse::<i32>())
             .transpose()
-            .map_err(|_| PgBouncerError::PgBouncer("query_wait_timeout must be a number".to_string()))?;
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "query_wait_timeout", "query_wait_timeout must be a number"))?;
 
         let cancel_wait_timeout = pgbouncer_setting.get("cancel_wait_timeout")
             .map(|v| v.parse::<i32>())
             .transpose()
-            .map_err(|_| PgBouncerError::PgBouncer("cancel_wait_timeout must be a number".to_string()))?;
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "cancel_wait_timeout", "cancel_wait_timeout must be a number"))?;
 
         let client_idle_timeout = pgbouncer_setting.get("client_idle_timeout")
             .map(|v| v.parse::<i32>())
             .transpose()
-            .map_err(|_| PgBouncerError::PgBouncer("client_idle_timeout must be a number".to_string()))?;
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "client_idle_timeout", "client_idle_timeout must be a number"))?;
 
         let idle_transaction_timeout = pgbouncer_setting.get("idle_transaction_timeout")
             .map(|v| v.parse::<i32>())
             .transpose()
-            .map_err(|_| PgBouncerError::PgBouncer("idle_transaction_timeout must be a number".to_string()))?;
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "idle_transaction_timeout", "idle_transaction_timeout must be a number"))?;
 
         let suspend_timeout = pgbouncer_setting.get("suspend_timeout")
             .map(|v| v.parse::<i32>())
             .transpose()
-            .map_err(|_| PgBouncerError::PgBouncer("suspend_timeout must be a number".to_string()))?;
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "suspend_timeout", "suspend_timeout must be a number"))?;
+
+        let min_pool_size = pgbouncer_setting.get("min_pool_size")
+            .map(|v| v.parse::<u16>())
+            .transpose()
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "min_pool_size", "min_pool_size must be a number"))?;
+
+        let reserve_pool_size = pgbouncer_setting.get("reserve_pool_size")
+            .map(|v| v.parse::<u16>())
+            .transpose()
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "reserve_pool_size", "reserve_pool_size must be a number"))?;
+
+        let reserve_pool_timeout = pgbouncer_setting.get("reserve_pool_timeout")
+            .map(|v| v.parse::<i32>())
+            .transpose()
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "reserve_pool_timeout", "reserve_pool_timeout must be a number"))?;
+
+        let max_db_connections = pgbouncer_setting.get("max_db_connections")
+            .map(|v| v.parse::<u16>())
+            .transpose()
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "max_db_connections", "max_db_connections must be a number"))?;
+
+        let max_user_connections = pgbouncer_setting.get("max_user_connections")
+            .map(|v| v.parse::<u16>())
+            .transpose()
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "max_user_connections", "max_user_connections must be a number"))?;
+
+        let server_reset_query = pgbouncer_setting.get("server_reset_query").map(|s| s.to_string());
+        let server_reset_query_always = parse_bool(&pgbouncer_setting, "server_reset_query_always")?;
+        let server_fast_close = parse_bool(&pgbouncer_setting, "server_fast_close")?;
+        let server_round_robin = parse_bool(&pgbouncer_setting, "server_round_robin")?;
+
+        let tcp_keepalive = parse_bool(&pgbouncer_setting, "tcp_keepalive")?;
+
+        let tcp_keepcnt = pgbouncer_setting.get("tcp_keepcnt")
+            .map(|v| v.parse::<i32>())
+            .transpose()
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "tcp_keepcnt", "tcp_keepcnt must be a number"))?;
+
+        let tcp_keepidle = pgbouncer_setting.get("tcp_keepidle")
+            .map(|v| v.parse::<i32>())
+            .transpose()
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "tcp_keepidle", "tcp_keepidle must be a number"))?;
+
+        let tcp_keepintvl = pgbouncer_setting.get("tcp_keepintvl")
+            .map(|v| v.parse::<i32>())
+            .transpose()
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "tcp_keepintvl", "tcp_keepintvl must be a number"))?;
+
+        let tcp_user_timeout = pgbouncer_setting.get("tcp_user_timeout")
+            .map(|v| v.parse::<i32>())
+            .transpose()
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "tcp_user_timeout", "tcp_user_timeout must be a number"))?;
+
+        let so_reuseport = parse_bool(&pgbouncer_setting, "so_reuseport")?;
+        let peer_id = pgbouncer_setting.get("peer_id")
+            .map(|v| v.parse::<u16>())
+            .transpose()
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "peer_id", "peer_id must be a number"))?;
+
+        let track_extra_parameters = pgbouncer_setting.get("track_extra_parameters")
+            .map(|s| s.split(',').map(|s| s.trim().to_string()).collect())
+            .unwrap_or_default();
+        let application_name_add_host = parse_bool(&pgbouncer_setting, "application_name_add_host")?;
+
+        let log_connections = parse_bool(&pgbouncer_setting, "log_connections")?;
+        let log_disconnections = parse_bool(&pgbouncer_setting, "log_disconnections")?;
+        let log_pooler_errors = parse_bool(&pgbouncer_setting, "log_pooler_errors")?;
+        let log_stats = parse_bool(&pgbouncer_setting, "log_stats")?;
+
+        let stats_period = pgbouncer_setting.get("stats_period")
+            .map(|v| v.parse::<i32>())
+            .transpose()
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "stats_period", "stats_period must be a number"))?;
+
+        let verbose = pgbouncer_setting.get("verbose")
+            .map(|v| v.parse::<u8>())
+            .transpose()
+            .map_err(|_| PgBouncerError::parse_error("pgbouncer", "verbose", "verbose must be a number from 0 to 255"))?;
+
+        let syslog = parse_bool(&pgbouncer_setting, "syslog")?;
+        let syslog_facility = pgbouncer_setting.get("syslog_facility")
+            .map(|v| SyslogFacility::try_from(v.as_str()))
+            .transpose()
+            .map_err(|e| e.at_key("pgbouncer", "syslog_facility"))?;
+        let syslog_ident = pgbouncer_setting.get("syslog_ident").map(|s| s.to_string());
+
+        let client_tls_sslmode = pgbouncer_setting.get("client_tls_sslmode")
+            .map(|v| SslMode::try_from(v.as_str()))
+            .transpose()
+            .map_err(|e| e.at_key("pgbouncer", "client_tls_sslmode"))?;
+        let client_tls_key_file = pgbouncer_setting.get("client_tls_key_file").map(|s| s.to_string());
+        let client_tls_cert_file = pgbouncer_setting.get("client_tls_cert_file").map(|s| s.to_string());
+        let client_tls_ca_file = pgbouncer_setting.get("client_tls_ca_file").map(|s| s.to_string());
+        let client_tls_protocols = pgbouncer_setting.get("client_tls_protocols").map(|s| s.to_string());
+        let client_tls_ciphers = pgbouncer_setting.get("client_tls_ciphers").map(|s| s.to_string());
+        let client_tls_dheparams = pgbouncer_setting.get("client_tls_dheparams").map(|s| s.to_string());
+        let client_tls_ecdhcurve = pgbouncer_setting.get("client_tls_ecdhcurve").map(|s| s.to_string());
+
+        let server_tls_sslmode = pgbouncer_setting.get("server_tls_sslmode")
+            .map(|v| SslMode::try_from(v.as_str()))
+            .transpose()
+            .map_err(|e| e.at_key("pgbouncer", "server_tls_sslmode"))?;
+        let server_tls_ca_file = pgbouncer_setting.get("server_tls_ca_file").map(|s| s.to_string());
+        let server_tls_key_file = pgbouncer_setting.get("server_tls_key_file").map(|s| s.to_string());
+        let server_tls_cert_file = pgbouncer_setting.get("server_tls_cert_file").map(|s| s.to_string());
+        let server_tls_protocols = pgbouncer_setting.get("server_tls_protocols").map(|s| s.to_string());
+        let server_tls_ciphers = pgbouncer_setting.get("server_tls_ciphers").map(|s| s.to_string());
+
+        let extra = pgbouncer_setting
+            .iter()
+            .filter(|(key, _)| !find_section_setting("pgbouncer", key).is_some_and(|setting| setting.modeled))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
 
         Ok(Self {
             listen_addr,
@@ -1198,6 +3121,9 @@ impl ParserIniFromStr for PgBouncerSetting {
             unix_socket_dir,
             auth_hba_file,
             auth_ident_file,
+            auth_user,
+            auth_query,
+            auth_dbname,
             server_check_delay,
             server_idle_timeout,
             server_lifetime,
@@ -1214,19 +3140,145 @@ impl ParserIniFromStr for PgBouncerSetting {
             client_idle_timeout,
             idle_transaction_timeout,
             suspend_timeout,
+            min_pool_size,
+            reserve_pool_size,
+            reserve_pool_timeout,
+            max_db_connections,
+            max_user_connections,
+            server_reset_query,
+            server_reset_query_always,
+            server_fast_close,
+            server_round_robin,
+            tcp_keepalive,
+            tcp_keepcnt,
+            tcp_keepidle,
+            tcp_keepintvl,
+            tcp_user_timeout,
+            so_reuseport,
+            peer_id,
+            track_extra_parameters,
+            application_name_add_host,
+            log_connections,
+            log_disconnections,
+            log_pooler_errors,
+            log_stats,
+            stats_period,
+            verbose,
+            syslog,
+            syslog_facility,
+            syslog_ident,
+            client_tls_sslmode,
+            client_tls_key_file,
+            client_tls_cert_file,
+            client_tls_ca_file,
+            client_tls_protocols,
+            client_tls_ciphers,
+            client_tls_dheparams,
+            client_tls_ecdhcurve,
+            server_tls_sslmode,
+            server_tls_ca_file,
+            server_tls_key_file,
+            server_tls_cert_file,
+            server_tls_protocols,
+            server_tls_ciphers,
+            extra,
         })
     }
 }
 
+/// Renders a boolean setting the way PgBouncer writes it, as `1` or `0`.
+fn render_bool(value: bool) -> String {
+    u8::from(value).to_string()
+}
+
+/// Parses the boolean setting `key`, written as `1`/`0` (or `true`/`false`, `on`/`off`, `yes`/`no`).
+#[cfg(feature = "io")]
+fn parse_bool(settings: &HashMap<String, String>, key: &str) -> crate::error::Result<Option<bool>> {
+    settings.get(key)
+        .map(|value| match value.to_ascii_lowercase().as_str() {
+            "1" | "true" | "on" | "yes" => Ok(true),
+            "0" | "false" | "off" | "no" => Ok(false),
+            _ => Err(PgBouncerError::parse_error("pgbouncer", key, format!("{} must be 0 or 1", key))),
+        })
+        .transpose()
+}
+
 #[cfg(feature = "diff")]
 #[typetag::serde]
 impl Diffable for PgBouncerSetting {}
 
+/// Builds the recommended `SECURITY DEFINER` function for `auth_query`.
+///
+/// `auth_user` only needs to execute the function instead of reading
+/// `pg_shadow` directly. The function returns no password for roles whose
+/// password has expired.
+///
+/// # Parameters
+/// - schema: Schema that holds the function; it is created if missing.
+/// - auth_user: Role granted `EXECUTE` on the function.
+///
+/// # Returns
+/// SQL creating `<schema>.user_lookup(text)` and restricting access to `auth_user`.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::auth_query_function_sql;
+///
+/// let sql = auth_query_function_sql("pgbouncer", "pgbouncer");
+/// assert!(sql.contains("CREATE OR REPLACE FUNCTION \"pgbouncer\".user_lookup"));
+/// assert!(sql.contains("GRANT EXECUTE ON FUNCTION \"pgbouncer\".user_lookup(text) TO \"pgbouncer\";"));
+/// ```
+pub fn auth_query_function_sql(schema: &str, auth_user: &str) -> String {
+    let schema = quote_identifier(schema);
+    let auth_user = quote_identifier(auth_user);
+
+    format!(
+        "CREATE SCHEMA IF NOT EXISTS {schema} AUTHORIZATION {auth_user};
+
+CREATE OR REPLACE FUNCTION {schema}.user_lookup(in i_username text, out uname text, out phash text)
+RETURNS record AS $$
+BEGIN
+    SELECT rolname, CASE WHEN rolvaliduntil < now() THEN NULL ELSE rolpassword END
+    FROM pg_catalog.pg_authid
+    WHERE rolname = i_username AND rolcanlogin
+    INTO uname, phash;
+    RETURN;
+END;
+$$ LANGUAGE plpgsql SECURITY DEFINER SET search_path = pg_catalog, pg_temp;
+
+REVOKE ALL ON FUNCTION {schema}.user_lookup(text) FROM PUBLIC;
+GRANT EXECUTE ON FUNCTION {schema}.user_lookup(text) TO {auth_user};
+"
+    )
+}
+
+/// Builds the `auth_query` calling the function from [`auth_query_function_sql`].
+///
+/// # Parameters
+/// - schema: Schema that holds the function.
+///
+/// # Returns
+/// The query to set with [`PgBouncerSetting::set_auth_query`].
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::auth_query_lookup;
+/// assert_eq!(auth_query_lookup("pgbouncer"), "SELECT uname, phash FROM \"pgbouncer\".user_lookup($1)");
+/// ```
+pub fn auth_query_lookup(schema: &str) -> String {
+    format!("SELECT uname, phash FROM {}.user_lookup($1)", quote_identifier(schema))
+}
+
+fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
 /// Authentication type used by PgBouncer.
 ///
 /// Controls how clients are authenticated. See the official PgBouncer
 /// documentation for detailed behavior of each method.
 #[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum AuthType {
     #[default]
     #[serde(rename = "md5")]
@@ -1301,10 +3353,15 @@ impl Display for AuthType {
 /// - Transaction: server assigned per transaction.
 /// - Statement: server assigned per statement.
 #[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum PoolMode {
+    // The lowercase aliases accept the spelling used in pgbouncer.ini.
     #[default]
+    #[serde(alias = "session")]
     Session,
+    #[serde(alias = "transaction")]
     Transaction,
+    #[serde(alias = "statement")]
     Statement,
 }
 
@@ -1318,6 +3375,119 @@ impl Display for PoolMode {
     }
 }
 
+/// TLS mode used by `client_tls_sslmode` and `server_tls_sslmode`.
+///
+/// The variants follow the libpq `sslmode` values; PgBouncer defaults to
+/// `disable` for client connections and `prefer` for server connections.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum SslMode {
+    Disable,
+    Allow,
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+impl TryFrom<&str> for SslMode {
+    type Error = PgBouncerError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let ssl_mode = match value.to_lowercase().as_str() {
+            "disable" => SslMode::Disable,
+            "allow" => SslMode::Allow,
+            "prefer" => SslMode::Prefer,
+            "require" => SslMode::Require,
+            "verify-ca" | "verify_ca" => SslMode::VerifyCa,
+            "verify-full" | "verify_full" => SslMode::VerifyFull,
+            _ => return Err(PgBouncerError::PgBouncer(format!("Unsupported sslmode: {}", value))),
+        };
+
+        Ok(ssl_mode)
+    }
+}
+
+impl Display for SslMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SslMode::Disable => write!(f, "disable"),
+            SslMode::Allow => write!(f, "allow"),
+            SslMode::Prefer => write!(f, "prefer"),
+            SslMode::Require => write!(f, "require"),
+            SslMode::VerifyCa => write!(f, "verify-ca"),
+            SslMode::VerifyFull => write!(f, "verify-full"),
+        }
+    }
+}
+
+/// Syslog facility used by `syslog_facility`.
+///
+/// # Variants
+/// - Auth, Authpriv, Daemon, User: The facilities of the same name.
+/// - Local0 ... Local7: The facilities reserved for local use.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum SyslogFacility {
+    Auth,
+    Authpriv,
+    Daemon,
+    User,
+    Local0,
+    Local1,
+    Local2,
+    Local3,
+    Local4,
+    Local5,
+    Local6,
+    Local7,
+}
+
+impl SyslogFacility {
+    /// Returns every facility, in the order of the syslog facility codes.
+    pub fn all() -> &'static [SyslogFacility] {
+        &[
+            SyslogFacility::Auth, SyslogFacility::Authpriv, SyslogFacility::Daemon, SyslogFacility::User,
+            SyslogFacility::Local0, SyslogFacility::Local1, SyslogFacility::Local2, SyslogFacility::Local3,
+            SyslogFacility::Local4, SyslogFacility::Local5, SyslogFacility::Local6, SyslogFacility::Local7,
+        ]
+    }
+}
+
+impl TryFrom<&str> for SyslogFacility {
+    type Error = PgBouncerError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let name = value.trim().to_lowercase();
+        SyslogFacility::all()
+            .iter()
+            .copied()
+            .find(|facility| facility.to_string() == name)
+            .ok_or(PgBouncerError::PgBouncer(format!("Unsupported syslog_facility: {}", value)))
+    }
+}
+
+impl Display for SyslogFacility {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyslogFacility::Auth => write!(f, "auth"),
+            SyslogFacility::Authpriv => write!(f, "authpriv"),
+            SyslogFacility::Daemon => write!(f, "daemon"),
+            SyslogFacility::User => write!(f, "user"),
+            SyslogFacility::Local0 => write!(f, "local0"),
+            SyslogFacility::Local1 => write!(f, "local1"),
+            SyslogFacility::Local2 => write!(f, "local2"),
+            SyslogFacility::Local3 => write!(f, "local3"),
+            SyslogFacility::Local4 => write!(f, "local4"),
+            SyslogFacility::Local5 => write!(f, "local5"),
+            SyslogFacility::Local6 => write!(f, "local6"),
+            SyslogFacility::Local7 => write!(f, "local7"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1325,8 +3495,7 @@ mod tests {
     #[test]
     fn expr_includes_header_and_basic_fields_after_setters() {
         let mut s = PgBouncerSetting::default();
-        s = s
-            .set_listen_addr("0.0.0.0")
+        s.set_listen_addr("0.0.0.0")
             .set_listen_port(6432)
             .set_auth_type(AuthType::Md5)
             .set_max_client_conn(200)
@@ -1359,6 +3528,46 @@ mod tests {
         assert!(text.contains("auth_ident_file = /etc/pgbouncer/ident.map"));
     }
 
+    #[test]
+    fn expr_includes_optional_timeouts_when_set() {
+        let mut s = PgBouncerSetting::default();
+        s.set_server_idle_timeout(Some(600));
+        s.set_dns_max_ttl(Some(30));
+        s.set_resolve_conf(Some("/etc/resolv.conf"));
+        s.set_query_wait_timeout(Some(60));
+
+        let text = s.expr().unwrap();
+        assert!(text.contains("server_idle_timeout = 600"));
+        assert!(text.contains("dns_max_ttl = 30"));
+        assert!(text.contains("resolve_conf = /etc/resolv.conf"));
+        assert!(text.contains("query_wait_timeout = 60"));
+        assert!(!text.contains("suspend_timeout"));
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn extra_keys_render_last_and_parse_back() {
+        let mut s = PgBouncerSetting::default();
+        s.set_extra("some_new_key", "value");
+        s.set_extra("another_key", "1");
+        // Cataloged keys without a typed field are kept as extra keys
+        s.set_extra("max_prepared_statements", "100");
+
+        let text = s.expr().unwrap();
+        assert!(text.ends_with("another_key = 1\nmax_prepared_statements = 100\nsome_new_key = value\n"));
+
+        let body = text.strip_prefix("[pgbouncer]\n").unwrap();
+        let parsed = PgBouncerSetting::parse_from_str(body).unwrap();
+        assert_eq!(parsed, s);
+        assert!(parsed.extra().get("listen_port").is_none());
+
+        let mut issues = Vec::new();
+        s.set_extra("pool_mode", "transaction");
+        s.validate_into(&mut issues);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].key(), Some("pool_mode"));
+    }
+
     #[test]
     fn auth_type_try_from_and_display() {
         // Lower-case and dashes should be accepted per TryFrom
@@ -1388,4 +3597,278 @@ mod tests {
         assert_eq!(format!("{}", PoolMode::Transaction), "transaction");
         assert_eq!(format!("{}", PoolMode::Statement), "statement");
     }
+
+    #[test]
+    fn tls_settings_render_when_set() {
+        let mut s = PgBouncerSetting::default();
+        s.set_client_tls_sslmode(Some(SslMode::Require));
+        s.set_client_tls_cert_file(Some("/etc/pgbouncer/server.crt"));
+        s.set_server_tls_sslmode(Some(SslMode::VerifyFull));
+
+        let text = s.expr().unwrap();
+        assert!(text.contains("client_tls_sslmode = require\n"));
+        assert!(text.contains("client_tls_cert_file = /etc/pgbouncer/server.crt\n"));
+        assert!(text.contains("server_tls_sslmode = verify-full\n"));
+        assert!(!text.contains("client_tls_key_file"));
+    }
+
+    #[test]
+    fn auth_query_settings_render_and_relax_auth_file() {
+        let mut s = PgBouncerSetting { auth_file: None, ..Default::default() };
+        s.set_auth_user(Some("pgbouncer"));
+        s.set_auth_query(Some(&auth_query_lookup("pgbouncer")));
+        s.set_auth_dbname(Some("postgres"));
+
+        let text = s.expr().unwrap();
+        assert!(text.contains("auth_user = pgbouncer\n"));
+        assert!(text.contains("auth_query = SELECT uname, phash FROM \"pgbouncer\".user_lookup($1)\n"));
+        assert!(text.contains("auth_dbname = postgres\n"));
+
+        let mut issues = Vec::new();
+        s.validate_into(&mut issues);
+        assert!(issues.is_empty());
+
+        assert!(auth_query_function_sql("pg\"bouncer", "app").contains("\"pg\"\"bouncer\".user_lookup"));
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn from_show_config_skips_unset_and_unknown_keys() {
+        let rows = vec![
+            ("listen_addr", "*"), ("listen_port", "6432"), ("auth_type", "md5"),
+            ("max_client_conn", "100"), ("default_pool_size", "20"), ("pool_mode", "session"),
+            ("auth_file", "/etc/pgbouncer/userlist.txt"), ("logfile", ""), ("listen_backlog", "128"),
+        ];
+        let setting = PgBouncerSetting::from_show_config(rows.clone()).unwrap();
+        let text = setting.expr().unwrap();
+        assert!(text.contains("auth_file = /etc/pgbouncer/userlist.txt\n"));
+        assert!(!text.contains("logfile"));
+        assert!(!text.contains("listen_backlog"));
+
+        assert!(PgBouncerSetting::from_show_config(rows.into_iter().skip(1)).is_err());
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn tls_settings_parse_from_str() {
+        let ini = "\
+listen_addr = 127.0.0.1\n\
+listen_port = 6432\n\
+auth_type = md5\n\
+max_client_conn = 100\n\
+default_pool_size = 20\n\
+pool_mode = session\n\
+client_tls_sslmode = verify-ca\n\
+server_tls_protocols = tlsv1.3\
+";
+        let s = PgBouncerSetting::parse_from_str(ini).unwrap();
+        let text = s.expr().unwrap();
+        assert!(text.contains("client_tls_sslmode = verify-ca\n"));
+        assert!(text.contains("server_tls_protocols = tlsv1.3\n"));
+
+        let invalid = ini.replace("verify-ca", "sometimes");
+        assert!(PgBouncerSetting::parse_from_str(&invalid).is_err());
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn logging_settings_parse_from_str() {
+        let ini = "\
+listen_addr = 127.0.0.1\n\
+listen_port = 6432\n\
+auth_type = md5\n\
+max_client_conn = 100\n\
+default_pool_size = 20\n\
+pool_mode = session\n\
+log_connections = 0\n\
+log_pooler_errors = true\n\
+stats_period = 30\n\
+verbose = 2\
+";
+        let s = PgBouncerSetting::parse_from_str(ini).unwrap();
+        let text = s.expr().unwrap();
+        assert!(text.contains("log_connections = 0\nlog_pooler_errors = 1\nstats_period = 30\nverbose = 2\n"));
+        assert!(!text.contains("log_disconnections"));
+
+        let invalid = ini.replace("log_connections = 0", "log_connections = sometimes");
+        assert!(PgBouncerSetting::parse_from_str(&invalid).is_err());
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn syslog_settings_parse_from_str() {
+        let ini = "\
+listen_addr = 127.0.0.1\n\
+listen_port = 6432\n\
+auth_type = md5\n\
+max_client_conn = 100\n\
+default_pool_size = 20\n\
+pool_mode = session\n\
+syslog = 1\n\
+syslog_facility = LOCAL3\n\
+syslog_ident = pgbouncer-main\
+";
+        let s = PgBouncerSetting::parse_from_str(ini).unwrap();
+        let text = s.expr().unwrap();
+        assert!(text.contains("syslog = 1\nsyslog_facility = local3\nsyslog_ident = pgbouncer-main\n"));
+
+        let invalid = ini.replace("LOCAL3", "local8");
+        assert!(PgBouncerSetting::parse_from_str(&invalid).is_err());
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn pool_settings_parse_from_str() {
+        let ini = "\
+listen_addr = 127.0.0.1\n\
+listen_port = 6432\n\
+auth_type = md5\n\
+max_client_conn = 100\n\
+default_pool_size = 20\n\
+pool_mode = session\n\
+min_pool_size = 5\n\
+reserve_pool_size = 3\n\
+reserve_pool_timeout = 2\
+";
+        let s = PgBouncerSetting::parse_from_str(ini).unwrap();
+        assert_eq!(s.min_pool_size(), Some(5));
+        assert_eq!(s.reserve_pool_size(), Some(3));
+        assert_eq!(s.reserve_pool_timeout(), Some(2));
+        assert!(s.expr().unwrap().contains("min_pool_size = 5\nreserve_pool_size = 3\nreserve_pool_timeout = 2\n"));
+
+        let invalid = ini.replace("reserve_pool_size = 3", "reserve_pool_size = -3");
+        assert!(PgBouncerSetting::parse_from_str(&invalid).is_err());
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn connection_caps_parse_from_str() {
+        let ini = "\
+listen_addr = 127.0.0.1\n\
+listen_port = 6432\n\
+auth_type = md5\n\
+max_client_conn = 100\n\
+default_pool_size = 20\n\
+pool_mode = session\n\
+max_db_connections = 80\n\
+max_user_connections = 40\
+";
+        let s = PgBouncerSetting::parse_from_str(ini).unwrap();
+        assert_eq!(s.max_db_connections(), Some(80));
+        assert_eq!(s.max_user_connections(), Some(40));
+        assert!(s.expr().unwrap().contains("max_db_connections = 80\nmax_user_connections = 40\n"));
+
+        let invalid = ini.replace("max_user_connections = 40", "max_user_connections = many");
+        assert!(PgBouncerSetting::parse_from_str(&invalid).is_err());
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn server_reset_and_rotation_settings_parse_from_str() {
+        let ini = "\
+listen_addr = 127.0.0.1\n\
+listen_port = 6432\n\
+auth_type = md5\n\
+max_client_conn = 100\n\
+default_pool_size = 20\n\
+pool_mode = transaction\n\
+server_reset_query = DISCARD ALL\n\
+server_reset_query_always = on\n\
+server_fast_close = 1\n\
+server_round_robin = 0\
+";
+        let s = PgBouncerSetting::parse_from_str(ini).unwrap();
+        assert_eq!(s.server_reset_query(), Some("DISCARD ALL"));
+        assert_eq!(s.server_reset_query_always(), Some(true));
+        assert_eq!(s.server_round_robin(), Some(false));
+        assert!(s.expr().unwrap().contains(
+            "server_reset_query = DISCARD ALL\nserver_reset_query_always = 1\nserver_fast_close = 1\nserver_round_robin = 0\n"
+        ));
+
+        let invalid = ini.replace("server_fast_close = 1", "server_fast_close = 2");
+        assert!(PgBouncerSetting::parse_from_str(&invalid).is_err());
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn tcp_settings_parse_from_str() {
+        let ini = "\
+listen_addr = 127.0.0.1\n\
+listen_port = 6432\n\
+auth_type = md5\n\
+max_client_conn = 100\n\
+default_pool_size = 20\n\
+pool_mode = session\n\
+tcp_keepalive = 1\n\
+tcp_keepcnt = 4\n\
+tcp_keepidle = 60\n\
+tcp_keepintvl = 15\n\
+tcp_user_timeout = 30000\
+";
+        let s = PgBouncerSetting::parse_from_str(ini).unwrap();
+        assert_eq!(s.tcp_keepalive(), Some(true));
+        assert_eq!(s.tcp_keepidle(), Some(60));
+        assert_eq!(s.tcp_user_timeout(), Some(30000));
+        assert!(s.expr().unwrap().contains(
+            "tcp_keepalive = 1\ntcp_keepcnt = 4\ntcp_keepidle = 60\ntcp_keepintvl = 15\ntcp_user_timeout = 30000\n"
+        ));
+
+        let invalid = ini.replace("tcp_keepidle = 60", "tcp_keepidle = 1m");
+        assert!(PgBouncerSetting::parse_from_str(&invalid).is_err());
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn peering_settings_parse_from_str() {
+        let ini = "\
+listen_addr = 127.0.0.1\n\
+listen_port = 6432\n\
+auth_type = md5\n\
+max_client_conn = 100\n\
+default_pool_size = 20\n\
+pool_mode = session\n\
+so_reuseport = 1\n\
+peer_id = 2\
+";
+        let s = PgBouncerSetting::parse_from_str(ini).unwrap();
+        assert_eq!(s.so_reuseport(), Some(true));
+        assert_eq!(s.peer_id(), Some(2));
+        assert!(s.expr().unwrap().contains("so_reuseport = 1\npeer_id = 2\n"));
+
+        let invalid = ini.replace("peer_id = 2", "peer_id = -1");
+        assert!(PgBouncerSetting::parse_from_str(&invalid).is_err());
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn client_parameter_settings_parse_from_str() {
+        let ini = "\
+listen_addr = 127.0.0.1\n\
+listen_port = 6432\n\
+auth_type = md5\n\
+max_client_conn = 100\n\
+default_pool_size = 20\n\
+pool_mode = transaction\n\
+track_extra_parameters = IntervalStyle, search_path\n\
+application_name_add_host = 1\
+";
+        let s = PgBouncerSetting::parse_from_str(ini).unwrap();
+        assert_eq!(s.track_extra_parameters(), ["IntervalStyle", "search_path"]);
+        assert_eq!(s.application_name_add_host(), Some(true));
+        assert!(s.expr().unwrap().contains(
+            "track_extra_parameters = IntervalStyle,search_path\napplication_name_add_host = 1\n"
+        ));
+
+        let invalid = ini.replace("application_name_add_host = 1", "application_name_add_host = maybe");
+        assert!(PgBouncerSetting::parse_from_str(&invalid).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_peer_id_out_of_range() {
+        let mut s = PgBouncerSetting::default();
+        s.set_peer_id(Some(MAX_PEER_ID + 1));
+        let mut issues = Vec::new();
+        s.validate_into(&mut issues);
+        assert!(issues.iter().any(|issue| issue.is_error() && issue.key() == Some("peer_id")));
+    }
 }
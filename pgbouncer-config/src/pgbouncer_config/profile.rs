@@ -0,0 +1,202 @@
+//! Pre-tuned `[pgbouncer]` settings for common deployments.
+//!
+//! A [`Profile`] is a starting point, not a finished configuration: the
+//! returned [`PgBouncerSetting`] still needs the `admin_users`, `auth_file`
+//! and TLS files of the deployment.
+
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use crate::error::PgBouncerError;
+use crate::pgbouncer_config::pgbouncer_setting::{AuthType, PgBouncerSetting, PoolMode, SslMode};
+
+/// A built-in preset of `[pgbouncer]` settings.
+///
+/// # Variants
+/// - TransactionHeavy: Many short transactions from stateless clients (web
+///   apps, serverless functions): transaction pooling with a large client
+///   limit, prepared statement tracking and short waits.
+/// - SessionCompat: Clients relying on session state (`SET`, advisory locks,
+///   `LISTEN`, temporary tables): session pooling with one server connection
+///   per client.
+/// - RdsBehindNlb: PgBouncer behind an AWS Network Load Balancer in front of
+///   RDS/Aurora: listens on every interface, requires TLS to the server and
+///   keeps idle connections alive below the 350 second idle timeout of the NLB.
+///
+/// TransactionHeavy and RdsBehindNlb set `max_prepared_statements`, which needs
+/// PgBouncer 1.21 or later;
+/// [`PgBouncerConfig::validate_for`](crate::pgbouncer_config::PgBouncerConfig::validate_for)
+/// reports it for older targets.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum Profile {
+    TransactionHeavy,
+    SessionCompat,
+    RdsBehindNlb,
+}
+
+impl Profile {
+    /// Returns every profile.
+    pub fn all() -> &'static [Profile] {
+        &[Profile::TransactionHeavy, Profile::SessionCompat, Profile::RdsBehindNlb]
+    }
+
+    /// Returns the name of the profile used on the command line, e.g. `transaction-heavy`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Profile::TransactionHeavy => "transaction-heavy",
+            Profile::SessionCompat => "session-compat",
+            Profile::RdsBehindNlb => "rds-behind-nlb",
+        }
+    }
+
+    /// Returns a one-line description of the deployment the profile is tuned for.
+    pub fn description(self) -> &'static str {
+        match self {
+            Profile::TransactionHeavy => "Transaction pooling for many short transactions from stateless clients",
+            Profile::SessionCompat => "Session pooling for clients relying on session state",
+            Profile::RdsBehindNlb => "Transaction pooling behind an AWS NLB in front of RDS/Aurora",
+        }
+    }
+
+    /// Builds the `[pgbouncer]` settings of the profile.
+    ///
+    /// # Returns
+    /// The settings, with the defaults of [`PgBouncerSetting::default`] for
+    /// everything the profile does not tune.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PoolMode;
+    /// use pgbouncer_config::pgbouncer_config::profile::Profile;
+    ///
+    /// let setting = Profile::TransactionHeavy.setting();
+    /// assert_eq!(setting.pool_mode(), PoolMode::Transaction);
+    /// ```
+    pub fn setting(self) -> PgBouncerSetting {
+        let mut setting = PgBouncerSetting::default();
+        setting.set_auth_type(AuthType::ScramSha256);
+
+        match self {
+            Profile::TransactionHeavy => {
+                setting
+                    .set_pool_mode(PoolMode::Transaction)
+                    .set_max_client_conn(5000)
+                    .set_default_pool_size(50)
+                    .add_ignore_startup_parameter("extra_float_digits")
+                    .set_extra("max_prepared_statements", "200")
+                    .set_server_idle_timeout(Some(60))
+                    .set_server_lifetime(Some(1800))
+                    .set_query_wait_timeout(Some(30))
+                    .set_idle_transaction_timeout(Some(60));
+            },
+            Profile::SessionCompat => {
+                setting
+                    .set_pool_mode(PoolMode::Session)
+                    .set_max_client_conn(200)
+                    .set_default_pool_size(100)
+                    .set_server_idle_timeout(Some(600))
+                    .set_server_lifetime(Some(3600));
+            },
+            Profile::RdsBehindNlb => {
+                setting
+                    .set_listen_addr("0.0.0.0")
+                    .set_pool_mode(PoolMode::Transaction)
+                    .set_max_client_conn(2000)
+                    .set_default_pool_size(40)
+                    .add_ignore_startup_parameter("extra_float_digits")
+                    .set_extra("max_prepared_statements", "200")
+                    .set_server_tls_sslmode(Some(SslMode::Require))
+                    .set_server_connect_timeout(Some(5))
+                    .set_server_login_retry(Some(5))
+                    .set_server_idle_timeout(Some(300))
+                    .set_server_lifetime(Some(1800))
+                    .set_query_wait_timeout(Some(30))
+                    .set_dns_max_ttl(Some(5))
+                    .set_tcp_keepalive(Some(true))
+                    .set_tcp_keepidle(Some(60))
+                    .set_tcp_keepintvl(Some(15))
+                    .set_tcp_keepcnt(Some(4));
+            },
+        }
+
+        setting
+    }
+}
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl TryFrom<&str> for Profile {
+    type Error = PgBouncerError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Profile::all()
+            .iter()
+            .copied()
+            .find(|profile| profile.name() == value)
+            .ok_or_else(|| {
+                let names = Profile::all().iter().map(|profile| profile.name()).collect::<Vec<_>>();
+                PgBouncerError::PgBouncer(format!("Unknown profile {}, expected one of {}", value, names.join(", ")))
+            })
+    }
+}
+
+impl From<Profile> for PgBouncerSetting {
+    fn from(profile: Profile) -> Self {
+        profile.setting()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::PgBouncerConfigBuilder;
+    use crate::pgbouncer_config::databases_setting::DatabasesSetting;
+    use crate::pgbouncer_config::lint::LintRule;
+    use crate::pgbouncer_config::validation::TargetVersion;
+
+    #[test]
+    fn profiles_validate_and_pass_the_pooling_lint() {
+        for profile in Profile::all() {
+            let cfg = PgBouncerConfigBuilder::new(profile.setting(), DatabasesSetting::new())
+                .unwrap()
+                .build();
+
+            assert!(cfg.validate().iter().all(|issue| !issue.is_error()), "{}: {:?}", profile, cfg.validate());
+            assert!(cfg.lint().iter().all(|finding| finding.rule() != LintRule::TransactionPooling), "{}", profile);
+        }
+    }
+
+    #[test]
+    fn prepared_statement_profiles_require_pgbouncer_1_21() {
+        for profile in [Profile::TransactionHeavy, Profile::RdsBehindNlb] {
+            let cfg = PgBouncerConfigBuilder::new(profile.setting(), DatabasesSetting::new())
+                .unwrap()
+                .build();
+
+            let keys = cfg.validate_for(TargetVersion::V1_20)
+                .into_iter()
+                .filter(|issue| issue.is_error())
+                .filter_map(|issue| issue.key().map(str::to_string))
+                .collect::<Vec<_>>();
+            assert_eq!(keys, vec!["max_prepared_statements"], "{}", profile);
+            assert!(cfg.validate_for(TargetVersion::V1_21).iter().all(|issue| !issue.is_error()), "{}", profile);
+        }
+
+        let cfg = PgBouncerConfigBuilder::new(Profile::SessionCompat.setting(), DatabasesSetting::new())
+            .unwrap()
+            .build();
+        assert!(cfg.validate_for(TargetVersion::V1_20).iter().all(|issue| !issue.is_error()));
+    }
+
+    #[test]
+    fn profiles_parse_from_their_names() {
+        for profile in Profile::all() {
+            assert_eq!(Profile::try_from(profile.name()).unwrap(), *profile);
+        }
+        assert!(Profile::try_from("oltp").is_err());
+    }
+}
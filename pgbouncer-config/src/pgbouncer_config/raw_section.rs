@@ -0,0 +1,265 @@
+use std::collections::HashSet;
+use std::sync::{LazyLock, Mutex};
+use serde::{Deserialize, Serialize};
+use crate::pgbouncer_config::Expression;
+use crate::error::PgBouncerError;
+#[cfg(feature = "io")]
+use crate::utils::parser::ParserIniFromStr;
+#[cfg(feature = "diff")]
+use crate::utils::diff::Diffable;
+
+/// Names of the raw sections seen so far; each name is leaked once to satisfy
+/// [`Expression::section_name`].
+static RAW_SECTION_NAMES: LazyLock<Mutex<HashSet<&'static str>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Section this crate does not model, kept as unparsed key/value pairs.
+///
+/// Parsing pgbouncer.ini stores every unknown section (e.g. [users] or a
+/// vendor extension) as a `RawSection`, so it survives a parse → render round
+/// trip unchanged. Keys keep their order of appearance.
+///
+/// # Fields
+/// - name: Name of the section, without brackets.
+/// - entries: Key/value pairs in order of appearance.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RawSection {
+    name: String,
+    entries: Vec<(String, String)>,
+}
+
+impl RawSection {
+    /// Creates an empty RawSection.
+    ///
+    /// # Parameters
+    /// - name: Name of the section, without brackets.
+    ///
+    /// # Returns
+    /// The initialized RawSection with no entries.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::raw_section::RawSection;
+    /// use pgbouncer_config::pgbouncer_config::Expression;
+    ///
+    /// let mut section = RawSection::new("users");
+    /// section.set("app", "pool_mode=transaction");
+    /// assert_eq!(section.expr().unwrap(), "[users]\napp = pool_mode=transaction\n\n");
+    /// ```
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            entries: vec![],
+        }
+    }
+
+    /// Returns the name of the section.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the value of a key, if present.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Sets a key, replacing its value in place or appending it at the end.
+    ///
+    /// # Parameters
+    /// - key: Key to set.
+    /// - value: Raw value, rendered as is.
+    pub fn set(&mut self, key: &str, value: &str) {
+        match self.entries.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.entries.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    /// Removes a key and returns its value, if present.
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self.entries.iter().position(|(k, _)| k == key)?;
+
+        Some(self.entries.remove(index).1)
+    }
+
+    /// Returns the key/value pairs in order of appearance.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Parses the body of a section (the lines after its header).
+    ///
+    /// Blank lines and comments are skipped; an empty value (`key =`) is kept.
+    ///
+    /// # Parameters
+    /// - name: Name of the section, without brackets.
+    /// - body: Lines of the section.
+    ///
+    /// # Returns
+    /// The parsed RawSection.
+    ///
+    /// # Errors
+    /// Returns an error if a line is not in `key = value` form.
+    #[cfg(feature = "io")]
+    pub fn parse(name: &str, body: &str) -> crate::error::Result<Self> {
+        let mut section = RawSection::new(name);
+        for line in body.lines() {
+            let line = line.trim();
+            if line.is_empty() || crate::utils::parser::is_comment(line) {
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or(
+                PgBouncerError::PgBouncer(format!("Invalid format key=value in [{}]: {}", name, line))
+            )?;
+            section.set(key.trim(), value.trim());
+        }
+
+        Ok(section)
+    }
+}
+
+#[typetag::serde]
+impl Expression for RawSection {
+    /// Render the section as configuration text.
+    ///
+    /// # Returns
+    /// The configuration text for the section, keys in order of appearance.
+    fn expr(&self) -> crate::error::Result<String> {
+        let mut text = format!("[{}]\n", self.name);
+        for (key, value) in &self.entries {
+            text.push_str(&format!("{} = {}\n", key, value));
+        }
+        text.push('\n');
+
+        Ok(text)
+    }
+
+    /// Sorts the entries by key and trims their values; entries with the same
+    /// key keep their relative order.
+    fn canonicalize(&mut self) {
+        for (_, value) in self.entries.iter_mut() {
+            *value = value.trim().to_string();
+        }
+        self.entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+
+    fn section_name(&self) -> &'static str {
+        let mut names = RAW_SECTION_NAMES.lock().unwrap();
+        match names.get(self.name.as_str()) {
+            Some(name) => name,
+            None => {
+                let name: &'static str = Box::leak(self.name.clone().into_boxed_str());
+                names.insert(name);
+                name
+            },
+        }
+    }
+
+    /// Renders the section including its header; the text is the same as [`Expression::expr`].
+    fn to_template_string(&self) -> Result<String, PgBouncerError> {
+        self.expr()
+    }
+
+    /// Parses the text of [`Expression::to_template_string`], taking the name from its header.
+    ///
+    /// # Errors
+    /// Returns an error if the header or a line is invalid, or if the `io` feature is disabled.
+    fn from_template_string(s: &str) -> Result<Self, PgBouncerError> where Self: Sized {
+        #[cfg(feature = "io")]
+        {
+            Self::parse_from_str(s)
+        }
+        #[cfg(not(feature = "io"))]
+        {
+            let _ = s;
+            Err(PgBouncerError::PgBouncer("parsing a raw section requires the io feature".to_string()))
+        }
+    }
+}
+
+#[cfg(feature = "io")]
+impl ParserIniFromStr for RawSection {
+    type Error = PgBouncerError;
+
+    /// Parses a whole section including its `[name]` header.
+    fn parse_from_str(value: &str) -> Result<Self, Self::Error> {
+        let value = value.trim_start();
+        let (header, body) = value.split_once('\n').unwrap_or((value, ""));
+        let name = header
+            .trim()
+            .strip_prefix('[')
+            .and_then(|header| header.strip_suffix(']'))
+            .ok_or(PgBouncerError::PgBouncer(format!("Invalid section header: {}", header)))?;
+
+        Self::parse(name.trim(), body)
+    }
+}
+
+#[cfg(feature = "diff")]
+#[typetag::serde]
+impl Diffable for RawSection {}
+
+#[cfg(all(test, feature = "io"))]
+mod tests {
+    use super::*;
+    use crate::pgbouncer_config::PgBouncerConfig;
+
+    #[test]
+    fn unknown_sections_survive_parse_and_render() {
+        let ini = "\
+[pgbouncer]\n\
+listen_addr = 127.0.0.1\n\
+listen_port = 6432\n\
+auth_type = md5\n\
+max_client_conn = 100\n\
+default_pool_size = 20\n\
+pool_mode = session\n\
+\n\
+[users]\n\
+app = pool_mode=transaction\n\
+\n\
+[vendor_extension]\n\
+; vendor comment\n\
+feature_flag = on\n\
+empty =\n\
+";
+        let cfg = PgBouncerConfig::parse_from_str(ini).unwrap();
+        assert!(cfg.contains_section("users"));
+        assert_eq!(cfg.get_config::<RawSection>().unwrap().name(), "users");
+
+        let text = cfg.expr().unwrap();
+        assert!(text.contains("[users]\napp = pool_mode=transaction\n"));
+        assert!(text.contains("[vendor_extension]\nfeature_flag = on\nempty = \n"));
+
+        let reparsed = PgBouncerConfig::parse_from_str(&text).unwrap();
+        assert_eq!(reparsed.expr().unwrap(), text);
+
+        // Definitions (TOML/JSON) keep the raw sections as well
+        let definition: PgBouncerConfig = toml::from_str(&toml::to_string(&cfg).unwrap()).unwrap();
+        assert_eq!(definition.expr().unwrap(), text);
+    }
+
+    #[test]
+    fn parse_from_str_reads_header_and_rejects_invalid_lines() {
+        let section = RawSection::parse_from_str("[vendor]\na = 1\nb=2\n").unwrap();
+        assert_eq!(section.entries().collect::<Vec<_>>(), vec![("a", "1"), ("b", "2")]);
+        assert!(RawSection::parse("vendor", "not a pair").is_err());
+    }
+
+    #[test]
+    fn template_string_round_trips() {
+        let mut section = RawSection::new("vendor");
+        section.set("a", "1");
+        section.set("empty", "");
+
+        let text = section.to_template_string().unwrap();
+        assert_eq!(RawSection::from_template_string(&text).unwrap(), section);
+        assert!(RawSection::from_template_string("vendor
+a = 1").is_err());
+    }
+}
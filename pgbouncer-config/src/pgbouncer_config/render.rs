@@ -0,0 +1,452 @@
+//! Layout options for rendering a configuration as pgbouncer.ini.
+//!
+//! [`RenderOptions`] is applied on top of the text produced by each section's
+//! [`crate::pgbouncer_config::Expression::expr`], so every section supports it
+//! without a dedicated implementation. The default options reproduce
+//! [`crate::pgbouncer_config::PgBouncerConfig::expr`] exactly.
+//!
+//! [`Provenance`] adds a comment block at the top of the output recording
+//! which tool generated the file, when, and from which definition, and a
+//! checksum comment (see [`crate::pgbouncer_config::checksum`]) can be
+//! embedded to detect later manual edits.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+use sha2::{Digest, Sha256};
+use crate::pgbouncer_config::settings_catalog::settings;
+
+/// Order of the keys inside a section.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyOrder {
+    /// Keys in the order the section renders them (declaration order).
+    #[default]
+    Declaration,
+    /// Keys sorted by name.
+    Alphabetical,
+}
+
+/// Options controlling the layout of the rendered pgbouncer.ini.
+///
+/// # Fields
+/// - key_order: Order of the keys inside each section.
+/// - align_equals: Whether the `=` of a section's keys are aligned in one column.
+/// - include_defaults: Whether [pgbouncer] keys left unset are written with
+///   their PgBouncer default from [`crate::pgbouncer_config::settings_catalog`].
+/// - blank_lines: Number of blank lines between sections; `None` keeps the
+///   spacing of the sections' own rendering.
+/// - provenance: Comment block written before the first section.
+/// - checksum: Whether a checksum comment is written as the first line.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+/// use pgbouncer_config::pgbouncer_config::databases_setting::DatabasesSetting;
+/// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+/// use pgbouncer_config::pgbouncer_config::render::{KeyOrder, RenderOptions};
+///
+/// let cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new())
+///     .unwrap()
+///     .build();
+/// let mut options = RenderOptions::new();
+/// options.set_key_order(KeyOrder::Alphabetical).set_align_equals(true);
+///
+/// let text = cfg.expr_with(&options).unwrap();
+/// assert!(text.contains("auth_file         = /etc/pgbouncer-config/userlist.txt\n"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderOptions {
+    key_order: KeyOrder,
+    align_equals: bool,
+    include_defaults: bool,
+    blank_lines: Option<usize>,
+    provenance: Option<Provenance>,
+    checksum: bool,
+}
+
+impl RenderOptions {
+    /// Creates options producing the same text as [`crate::pgbouncer_config::PgBouncerConfig::expr`].
+    ///
+    /// # Returns
+    /// Options in declaration order, unaligned, without defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the order of the keys inside each section.
+    ///
+    /// # Parameters
+    /// - key_order: Declaration or alphabetical order.
+    ///
+    /// # Returns
+    /// `&mut Self` for chaining.
+    pub fn set_key_order(&mut self, key_order: KeyOrder) -> &mut Self {
+        self.key_order = key_order;
+        self
+    }
+
+    /// Sets whether the `=` of a section's keys are aligned in one column.
+    ///
+    /// # Parameters
+    /// - align_equals: `true` to pad keys to the longest key of their section.
+    ///
+    /// # Returns
+    /// `&mut Self` for chaining.
+    pub fn set_align_equals(&mut self, align_equals: bool) -> &mut Self {
+        self.align_equals = align_equals;
+        self
+    }
+
+    /// Sets whether unset [pgbouncer] keys are written with their default value.
+    ///
+    /// # Parameters
+    /// - include_defaults: `true` to write every key of the settings catalog
+    ///   having a non-empty default.
+    ///
+    /// # Returns
+    /// `&mut Self` for chaining.
+    pub fn set_include_defaults(&mut self, include_defaults: bool) -> &mut Self {
+        self.include_defaults = include_defaults;
+        self
+    }
+
+    /// Sets the number of blank lines between sections.
+    ///
+    /// # Parameters
+    /// - blank_lines: Blank lines written after each section; `None` keeps the
+    ///   sections' own spacing.
+    ///
+    /// # Returns
+    /// `&mut Self` for chaining.
+    pub fn set_blank_lines(&mut self, blank_lines: Option<usize>) -> &mut Self {
+        self.blank_lines = blank_lines;
+        self
+    }
+
+    /// Sets the provenance comment written at the top of the output.
+    ///
+    /// # Parameters
+    /// - provenance: Origin of the generated file; `None` writes no header.
+    ///
+    /// # Returns
+    /// `&mut Self` for chaining.
+    pub fn set_provenance(&mut self, provenance: Option<Provenance>) -> &mut Self {
+        self.provenance = provenance;
+        self
+    }
+
+    /// Sets whether a checksum comment is written as the first line.
+    ///
+    /// # Parameters
+    /// - checksum: `true` to embed a SHA-256 of the output, checked by
+    ///   [`crate::pgbouncer_config::checksum::verify_checksum`].
+    ///
+    /// # Returns
+    /// `&mut Self` for chaining.
+    pub fn set_checksum(&mut self, checksum: bool) -> &mut Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// Returns the order of the keys inside each section.
+    pub fn key_order(&self) -> KeyOrder {
+        self.key_order
+    }
+
+    /// Returns whether the `=` of a section's keys are aligned.
+    pub fn align_equals(&self) -> bool {
+        self.align_equals
+    }
+
+    /// Returns whether unset [pgbouncer] keys are written with their default.
+    pub fn include_defaults(&self) -> bool {
+        self.include_defaults
+    }
+
+    /// Returns the number of blank lines between sections, if fixed.
+    pub fn blank_lines(&self) -> Option<usize> {
+        self.blank_lines
+    }
+
+    /// Returns the provenance comment written at the top of the output.
+    pub fn provenance(&self) -> Option<&Provenance> {
+        self.provenance.as_ref()
+    }
+
+    /// Returns whether a checksum comment is written as the first line.
+    pub fn checksum(&self) -> bool {
+        self.checksum
+    }
+
+    /// Whether the options leave the layout of the sections unchanged.
+    fn keeps_layout(&self) -> bool {
+        self.key_order == KeyOrder::Declaration
+            && !self.align_equals
+            && !self.include_defaults
+            && self.blank_lines.is_none()
+    }
+
+    /// Applies the options to the rendered text of one section.
+    ///
+    /// The text is expected as produced by `Expression::expr`: a `[name]`
+    /// header followed by `key = value` lines. Comment lines stay attached to
+    /// the key that follows them.
+    pub(crate) fn apply(&self, section: &str, text: &str) -> String {
+        if self.keeps_layout() {
+            return text.to_string();
+        }
+
+        let mut lines = text.lines();
+        let header = lines.next().unwrap_or_default();
+        let mut body = lines.collect::<Vec<_>>();
+        let trailing_blank_lines = body.iter().rev().take_while(|line| line.trim().is_empty()).count();
+        body.truncate(body.len() - trailing_blank_lines);
+
+        // Each entry is a key line with the comments written above it.
+        let mut entries: Vec<(Vec<String>, String, String)> = Vec::new();
+        let mut comments = Vec::new();
+        for line in body {
+            match line.split_once(" = ") {
+                Some((key, value)) if !line.trim_start().starts_with([';', '#']) => {
+                    entries.push((std::mem::take(&mut comments), key.trim().to_string(), value.to_string()));
+                },
+                _ => comments.push(line.to_string()),
+            }
+        }
+
+        if self.include_defaults && section == "pgbouncer" {
+            for setting in settings().iter().filter(|setting| setting.section == section) {
+                if !setting.default.is_empty() && !entries.iter().any(|(_, key, _)| key == setting.name) {
+                    entries.push((Vec::new(), setting.name.to_string(), setting.default.to_string()));
+                }
+            }
+        }
+        if self.key_order == KeyOrder::Alphabetical {
+            entries.sort_by(|(_, a, _), (_, b, _)| a.cmp(b));
+        }
+        let width = match self.align_equals {
+            true => entries.iter().map(|(_, key, _)| key.len()).max().unwrap_or(0),
+            false => 0,
+        };
+
+        let mut rendered = format!("{}\n", header);
+        for (comments, key, value) in entries {
+            for comment in comments {
+                rendered.push_str(&format!("{}\n", comment));
+            }
+            rendered.push_str(&format!("{:<width$} = {}\n", key, value, width = width));
+        }
+        for comment in comments {
+            rendered.push_str(&format!("{}\n", comment));
+        }
+        let blank_lines = self.blank_lines.unwrap_or(trailing_blank_lines);
+        rendered.push_str(&"\n".repeat(blank_lines));
+
+        rendered
+    }
+
+    /// Separator written after each section by `PgBouncerConfig::expr`.
+    pub(crate) fn section_separator(&self) -> &'static str {
+        match self.blank_lines {
+            Some(_) => "",
+            None => "\n",
+        }
+    }
+}
+
+/// Origin of a generated pgbouncer.ini, written as a comment block at its top.
+///
+/// Lets operators trace a running configuration back to the tool and the
+/// definition it was generated from.
+///
+/// # Fields
+/// - tool: Name and version of the generating tool.
+/// - generated_at: Generation time, written in UTC as RFC 3339.
+/// - source: Location of the source definition, if any.
+/// - source_sha256: Hex-encoded SHA-256 of the source definition, if known.
+///
+/// # Examples
+/// ```rust
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use pgbouncer_config::pgbouncer_config::render::Provenance;
+///
+/// let mut provenance = Provenance::new();
+/// provenance
+///     .set_generated_at(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+///     .set_source("pgbouncer_definition.toml", Some(b"[pgbouncer]\n"));
+///
+/// let header = provenance.expr();
+/// assert!(header.starts_with("; Generated by pgbouncer-config "));
+/// assert!(header.contains("; generated_at: 2023-11-14T22:13:20Z\n"));
+/// assert!(header.contains("; source: pgbouncer_definition.toml\n"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    tool: String,
+    generated_at: SystemTime,
+    source: Option<String>,
+    source_sha256: Option<String>,
+}
+
+impl Default for Provenance {
+    fn default() -> Self {
+        Self {
+            tool: format!("pgbouncer-config {}", env!("CARGO_PKG_VERSION")),
+            generated_at: SystemTime::now(),
+            source: None,
+            source_sha256: None,
+        }
+    }
+}
+
+impl Provenance {
+    /// Creates a provenance generated now by this crate, without a source.
+    ///
+    /// # Returns
+    /// A `Provenance` naming `pgbouncer-config` and its version as the tool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the name and version of the generating tool.
+    ///
+    /// # Parameters
+    /// - tool: Tool written in the header, e.g. `pgbouncer-generator 0.1.0`.
+    ///
+    /// # Returns
+    /// `&mut Self` for chaining.
+    pub fn set_tool(&mut self, tool: &str) -> &mut Self {
+        self.tool = tool.to_string();
+        self
+    }
+
+    /// Sets the generation time.
+    ///
+    /// # Parameters
+    /// - generated_at: Time written in the header.
+    ///
+    /// # Returns
+    /// `&mut Self` for chaining.
+    pub fn set_generated_at(&mut self, generated_at: SystemTime) -> &mut Self {
+        self.generated_at = generated_at;
+        self
+    }
+
+    /// Sets the source definition of the generated file.
+    ///
+    /// # Parameters
+    /// - location: Path or URL of the definition.
+    /// - content: Content of the definition, hashed with SHA-256; `None` omits the hash.
+    ///
+    /// # Returns
+    /// `&mut Self` for chaining.
+    pub fn set_source(&mut self, location: &str, content: Option<&[u8]>) -> &mut Self {
+        self.source = Some(location.to_string());
+        self.source_sha256 = content.map(|content| {
+            Sha256::digest(content).iter().map(|b| format!("{:02x}", b)).collect()
+        });
+        self
+    }
+
+    /// Returns the name and version of the generating tool.
+    pub fn tool(&self) -> &str {
+        &self.tool
+    }
+
+    /// Returns the generation time.
+    pub fn generated_at(&self) -> SystemTime {
+        self.generated_at
+    }
+
+    /// Returns the location of the source definition.
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Returns the hex-encoded SHA-256 of the source definition.
+    pub fn source_sha256(&self) -> Option<&str> {
+        self.source_sha256.as_deref()
+    }
+
+    /// Renders the comment block, followed by a blank line.
+    ///
+    /// # Returns
+    /// The header as it appears at the top of pgbouncer.ini.
+    pub fn expr(&self) -> String {
+        let mut text = format!("; Generated by {}\n", self.tool);
+        text.push_str(&format!("; generated_at: {}\n", rfc3339_utc(self.generated_at)));
+        if let Some(source) = &self.source {
+            text.push_str(&format!("; source: {}\n", source));
+        }
+        if let Some(sha256) = &self.source_sha256 {
+            text.push_str(&format!("; source_sha256: {}\n", sha256));
+        }
+        text.push('\n');
+
+        text
+    }
+}
+
+/// Formats a time as `YYYY-MM-DDTHH:MM:SSZ` in UTC.
+fn rfc3339_utc(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, secs_of_day / 3_600, secs_of_day % 3_600 / 60, secs_of_day % 60,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECTION: &str = "[pgbouncer]\nlisten_port = 6432\n; admins\nadmin_users = ops\n\n";
+
+    #[test]
+    fn default_options_keep_the_text() {
+        assert_eq!(RenderOptions::new().apply("pgbouncer", SECTION), SECTION);
+    }
+
+    #[test]
+    fn keys_are_sorted_and_aligned_with_their_comments() {
+        let mut options = RenderOptions::new();
+        options.set_key_order(KeyOrder::Alphabetical).set_align_equals(true).set_blank_lines(Some(0));
+
+        assert_eq!(
+            options.apply("pgbouncer", SECTION),
+            "[pgbouncer]\n; admins\nadmin_users = ops\nlisten_port = 6432\n",
+        );
+    }
+
+    #[test]
+    fn defaults_are_added_only_for_unset_keys() {
+        let mut options = RenderOptions::new();
+        options.set_include_defaults(true);
+
+        let text = options.apply("pgbouncer", SECTION);
+        assert!(text.contains("listen_port = 6432\n"));
+        assert!(text.contains("server_lifetime = 3600\n"));
+        assert!(text.contains("listen_addr = 127.0.0.1\n"));
+        assert!(!text.contains("logfile"));
+        assert!(!options.apply("databases", "[databases]\n\n").contains("port"));
+    }
+
+    #[test]
+    fn timestamps_are_formatted_in_utc() {
+        use std::time::Duration;
+
+        assert_eq!(rfc3339_utc(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(rfc3339_utc(UNIX_EPOCH + Duration::from_secs(951_827_696)), "2000-02-29T12:34:56Z");
+    }
+}
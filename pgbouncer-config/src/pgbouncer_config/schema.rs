@@ -0,0 +1,97 @@
+//! JSON Schema of the intermediate definition format.
+//!
+//! A definition (TOML or JSON) maps each section name to its type tag and
+//! content, e.g. `[pgbouncer.PgBouncerSetting]`. [`PgBouncerConfig::schema`]
+//! describes that shape so editors can validate and complete definition files.
+
+use std::collections::BTreeMap;
+use schemars::JsonSchema;
+use crate::pgbouncer_config::PgBouncerConfig;
+use crate::pgbouncer_config::databases_setting::DatabasesSetting;
+use crate::pgbouncer_config::peers_setting::PeersSetting;
+use crate::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+use crate::pgbouncer_config::raw_section::RawSection;
+
+/// Shape of a whole definition file.
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+#[schemars(title = "PgBouncer definition")]
+struct Definition {
+    /// The [pgbouncer] section.
+    pgbouncer: PgBouncerSection,
+    /// The [databases] section.
+    databases: Option<DatabasesSection>,
+    /// The optional [peers] section.
+    peers: Option<PeersSection>,
+    /// Any other section, kept as unparsed key/value pairs.
+    #[serde(flatten)]
+    other: BTreeMap<String, RawSectionTag>,
+}
+
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct PgBouncerSection {
+    #[serde(rename = "PgBouncerSetting")]
+    setting: PgBouncerSetting,
+}
+
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct DatabasesSection {
+    #[serde(rename = "DatabasesSetting")]
+    setting: DatabasesSetting,
+}
+
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct PeersSection {
+    #[serde(rename = "PeersSetting")]
+    setting: PeersSetting,
+}
+
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct RawSectionTag {
+    #[serde(rename = "RawSection")]
+    section: RawSection,
+}
+
+impl PgBouncerConfig {
+    /// Returns the JSON Schema of the definition format.
+    ///
+    /// The schema follows the serialized form of [`PgBouncerConfig`] written
+    /// by `Writer::write_config`; it applies to TOML definitions as well, as
+    /// editors validate TOML against JSON Schemas.
+    ///
+    /// # Returns
+    /// A JSON Schema (draft 2020-12) as a JSON value.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::PgBouncerConfig;
+    ///
+    /// let schema = PgBouncerConfig::schema();
+    /// assert_eq!(schema["title"], "PgBouncer definition");
+    /// assert!(schema["properties"]["pgbouncer"].is_object());
+    /// ```
+    pub fn schema() -> serde_json::Value {
+        schemars::schema_for!(Definition).to_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_describes_the_section_tags_and_keys() {
+        let text = PgBouncerConfig::schema().to_string();
+        for name in ["PgBouncerSetting", "DatabasesSetting", "listen_port", "ssh_tunnel_host", "proxy_type"] {
+            assert!(text.contains(name), "{} missing from the schema", name);
+        }
+    }
+}
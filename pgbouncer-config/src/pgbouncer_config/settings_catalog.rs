@@ -0,0 +1,445 @@
+//! Catalog of the PgBouncer settings known to this crate.
+//!
+//! Each entry describes a configuration key: the section it belongs to, its
+//! value type, the PgBouncer default, a short description and the PgBouncer
+//! version that introduced it. The catalog is used by tooling such as the
+//! generator's `explain` subcommand and by version-aware validation.
+//!
+//! Most keys are modeled by typed fields; the others are set with
+//! [`PgBouncerSetting::set_extra`](crate::pgbouncer_config::pgbouncer_setting::PgBouncerSetting::set_extra)
+//! and are listed so that their version is checked as well.
+
+/// Metadata of a single PgBouncer configuration key.
+///
+/// # Fields
+/// - name: Key name as written in pgbouncer.ini.
+/// - section: Section the key belongs to (e.g. `pgbouncer`, `databases` or `peers`).
+/// - value_type: Human-readable value type.
+/// - default: PgBouncer default value (empty if not set by default).
+/// - description: Short description of the setting.
+/// - since: PgBouncer version that introduced the key.
+/// - modeled: Whether the key has a typed field; other keys are set as extra keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SettingInfo {
+    pub name: &'static str,
+    pub section: &'static str,
+    pub value_type: &'static str,
+    pub default: &'static str,
+    pub description: &'static str,
+    pub since: &'static str,
+    pub modeled: bool,
+}
+
+impl SettingInfo {
+    /// Returns the version that introduced the key as `(major, minor)`.
+    ///
+    /// # Returns
+    /// The parsed `since` version; unparsable parts are treated as 0.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::settings_catalog::find_setting;
+    /// assert_eq!(find_setting("cancel_wait_timeout").unwrap().since_version(), (1, 16));
+    /// ```
+    pub fn since_version(&self) -> (u8, u8) {
+        let mut parts = self.since.split('.').map(|part| part.parse::<u8>().unwrap_or(0));
+        (parts.next().unwrap_or(0), parts.next().unwrap_or(0))
+    }
+}
+
+macro_rules! setting {
+    (extra $section:literal, $name:literal, $value_type:literal, $default:literal, $since:literal, $description:literal) => {
+        SettingInfo {
+            name: $name,
+            section: $section,
+            value_type: $value_type,
+            default: $default,
+            description: $description,
+            since: $since,
+            modeled: false,
+        }
+    };
+    ($section:literal, $name:literal, $value_type:literal, $default:literal, $since:literal, $description:literal) => {
+        SettingInfo {
+            name: $name,
+            section: $section,
+            value_type: $value_type,
+            default: $default,
+            description: $description,
+            since: $since,
+            modeled: true,
+        }
+    };
+}
+
+static SETTINGS: &[SettingInfo] = &[
+    // [pgbouncer]
+    setting!("pgbouncer", "listen_addr", "string", "127.0.0.1", "1.0",
+        "IP address or hostname PgBouncer listens on."),
+    setting!("pgbouncer", "listen_port", "integer", "6432", "1.0",
+        "TCP port for incoming client connections."),
+    setting!("pgbouncer", "auth_type", "enum (md5, scram-sha-256, cert, plain, trust, any, hba, pam)", "md5", "1.0",
+        "Authentication method used for client connections."),
+    setting!("pgbouncer", "max_client_conn", "integer", "100", "1.0",
+        "Maximum number of allowed client connections."),
+    setting!("pgbouncer", "default_pool_size", "integer", "20", "1.0",
+        "Default number of server connections per database/user pool."),
+    setting!("pgbouncer", "pool_mode", "enum (session, transaction, statement)", "session", "1.0",
+        "When a server connection can be reused by other clients."),
+    setting!("pgbouncer", "admin_users", "comma-separated list", "", "1.0",
+        "PostgreSQL users allowed to run admin commands in PgBouncer."),
+    setting!("pgbouncer", "stats_users", "comma-separated list", "", "1.0",
+        "PostgreSQL users allowed to read statistics only."),
+    setting!("pgbouncer", "ignore_startup_parameters", "comma-separated list", "", "1.3",
+        "Client startup parameters to ignore instead of rejecting the connection."),
+    setting!("pgbouncer", "logfile", "path", "", "1.0",
+        "Path to the PgBouncer log file."),
+    setting!("pgbouncer", "pidfile", "path", "", "1.0",
+        "Path to the PgBouncer PID file."),
+    setting!("pgbouncer", "auth_file", "path", "", "1.0",
+        "Path to the authentication file (commonly userlist.txt)."),
+    setting!("pgbouncer", "unix_socket_dir", "path", "/tmp", "1.0",
+        "Directory where the Unix domain socket is created."),
+    setting!("pgbouncer", "auth_hba_file", "path", "", "1.7",
+        "Path to the HBA configuration file used when auth_type = hba."),
+    setting!("pgbouncer", "auth_ident_file", "path", "", "1.23",
+        "Path to the ident map file."),
+    setting!("pgbouncer", "auth_user", "string", "", "1.7",
+        "User that runs auth_query to look up passwords of users missing from auth_file."),
+    setting!("pgbouncer", "auth_query", "string", "SELECT usename, passwd FROM pg_shadow WHERE usename=$1", "1.7",
+        "Query used to load a user's password from the database."),
+    setting!("pgbouncer", "auth_dbname", "string", "", "1.20",
+        "Database auth_query connects to instead of the client's database."),
+    setting!("pgbouncer", "server_check_delay", "seconds", "30", "1.0",
+        "How long to keep released connections available before re-checking them."),
+    setting!("pgbouncer", "server_idle_timeout", "seconds", "600", "1.0",
+        "Close a server connection that has been idle longer than this."),
+    setting!("pgbouncer", "server_lifetime", "seconds", "3600", "1.0",
+        "Close an unused server connection that has been connected longer than this."),
+    setting!("pgbouncer", "server_connect_timeout", "seconds", "15", "1.0",
+        "Timeout for establishing a server connection and logging in."),
+    setting!("pgbouncer", "server_login_retry", "seconds", "15", "1.0",
+        "Wait time before retrying a server login after a failure."),
+    setting!("pgbouncer", "client_login_timeout", "seconds", "60", "1.0",
+        "Disconnect a client that does not finish logging in within this time."),
+    setting!("pgbouncer", "autodb_idle_timeout", "seconds", "3600", "1.4",
+        "Idle lifetime of pools created for the \"*\" fallback database."),
+    setting!("pgbouncer", "dns_max_ttl", "seconds", "15", "1.4",
+        "Maximum time successful DNS lookups are cached."),
+    setting!("pgbouncer", "dns_nxdomain_ttl", "seconds", "15", "1.6",
+        "How long negative DNS results (NXDOMAIN) are cached."),
+    setting!("pgbouncer", "resolve_conf", "path", "", "1.8",
+        "Resolver configuration file; the OS defaults are used when not set."),
+    setting!("pgbouncer", "query_timeout", "seconds", "0", "1.0",
+        "Cancel queries running longer than this. 0 disables the timeout."),
+    setting!("pgbouncer", "query_wait_timeout", "seconds", "120", "1.3",
+        "Maximum time a query may wait for a server connection."),
+    setting!("pgbouncer", "cancel_wait_timeout", "seconds", "10", "1.16",
+        "Maximum time a cancel request may wait to be forwarded to the server."),
+    setting!("pgbouncer", "client_idle_timeout", "seconds", "0", "1.0",
+        "Close client connections idle longer than this. 0 disables the timeout."),
+    setting!("pgbouncer", "idle_transaction_timeout", "seconds", "0", "1.6",
+        "Close clients idle in a transaction longer than this. 0 disables the timeout."),
+    setting!("pgbouncer", "suspend_timeout", "seconds", "10", "1.4",
+        "How long to wait for buffer flushing during SUSPEND or reboot."),
+    setting!("pgbouncer", "min_pool_size", "integer", "0", "1.0",
+        "Server connections kept open in every pool, even when idle."),
+    setting!("pgbouncer", "reserve_pool_size", "integer", "0", "1.0",
+        "Additional server connections a pool may open when clients wait."),
+    setting!("pgbouncer", "reserve_pool_timeout", "seconds", "5", "1.0",
+        "How long a client waits before the reserve pool is used. 0 disables the reserve pool."),
+    setting!("pgbouncer", "max_db_connections", "integer", "0", "1.5",
+        "Maximum server connections per database across all its pools. 0 means unlimited."),
+    setting!("pgbouncer", "max_user_connections", "integer", "0", "1.5",
+        "Maximum server connections per user across all its pools. 0 means unlimited."),
+    setting!(extra "pgbouncer", "max_db_client_connections", "integer", "0", "1.24",
+        "Maximum client connections per database. 0 means unlimited."),
+    setting!(extra "pgbouncer", "max_user_client_connections", "integer", "0", "1.24",
+        "Maximum client connections per user. 0 means unlimited."),
+    setting!(extra "pgbouncer", "max_prepared_statements", "integer", "200", "1.21",
+        "Protocol-level prepared statements tracked per server connection in transaction and statement pooling. 0 disables the support."),
+    setting!("pgbouncer", "server_reset_query", "string", "DISCARD ALL", "1.0",
+        "Query run on a server connection before it is given to another client."),
+    setting!("pgbouncer", "server_reset_query_always", "boolean (0/1)", "0", "1.5",
+        "Run server_reset_query in transaction and statement pooling as well."),
+    setting!("pgbouncer", "server_fast_close", "boolean (0/1)", "0", "1.17",
+        "Close server connections right after their transaction during a reload or RECONNECT."),
+    setting!("pgbouncer", "server_round_robin", "boolean (0/1)", "0", "1.0",
+        "Reuse server connections in round-robin order instead of LIFO."),
+    setting!("pgbouncer", "tcp_keepalive", "boolean (0/1)", "1", "1.0",
+        "Enable TCP keepalive on client and server sockets."),
+    setting!("pgbouncer", "tcp_keepcnt", "integer", "0", "1.0",
+        "Unanswered keepalive probes before a connection is dropped. 0 uses the OS default."),
+    setting!("pgbouncer", "tcp_keepidle", "seconds", "0", "1.0",
+        "Idle time before the first keepalive probe is sent. 0 uses the OS default."),
+    setting!("pgbouncer", "tcp_keepintvl", "seconds", "0", "1.0",
+        "Interval between keepalive probes. 0 uses the OS default."),
+    setting!("pgbouncer", "tcp_user_timeout", "milliseconds", "0", "1.12",
+        "How long sent data may stay unacknowledged before the connection is dropped. 0 uses the OS default."),
+    setting!("pgbouncer", "so_reuseport", "boolean (0/1)", "0", "1.12",
+        "Let several PgBouncer processes listen on the same port."),
+    setting!("pgbouncer", "peer_id", "integer", "0", "1.19",
+        "ID of this process among the peers of the [peers] section. 0 disables peering."),
+    setting!("pgbouncer", "track_extra_parameters", "comma-separated list", "IntervalStyle", "1.20",
+        "Client parameters tracked per client in addition to the built-in ones."),
+    setting!("pgbouncer", "application_name_add_host", "boolean (0/1)", "0", "1.6",
+        "Append the client address and port to application_name."),
+    setting!("pgbouncer", "log_connections", "boolean (0/1)", "1", "1.0",
+        "Log successful logins."),
+    setting!("pgbouncer", "log_disconnections", "boolean (0/1)", "1", "1.0",
+        "Log disconnections with their reason."),
+    setting!("pgbouncer", "log_pooler_errors", "boolean (0/1)", "1", "1.0",
+        "Log error messages the pooler sends to clients."),
+    setting!("pgbouncer", "log_stats", "boolean (0/1)", "1", "1.7",
+        "Log aggregated statistics every stats_period."),
+    setting!("pgbouncer", "stats_period", "seconds", "60", "1.0",
+        "Interval of statistics aggregation and logging."),
+    setting!("pgbouncer", "verbose", "integer", "0", "1.0",
+        "Log verbosity; 1 and 2 add debug messages."),
+    setting!("pgbouncer", "syslog", "boolean (0/1)", "0", "1.0",
+        "Log to syslog as well."),
+    setting!("pgbouncer", "syslog_facility", "enum (auth, authpriv, daemon, user, local0-local7)", "daemon", "1.0",
+        "Syslog facility PgBouncer logs under."),
+    setting!("pgbouncer", "syslog_ident", "string", "pgbouncer", "1.0",
+        "Program name PgBouncer logs to syslog as."),
+    setting!("pgbouncer", "client_tls_sslmode", "enum (disable, allow, prefer, require, verify-ca, verify-full)", "disable", "1.7",
+        "TLS mode for client connections."),
+    setting!("pgbouncer", "client_tls_key_file", "path", "", "1.7",
+        "Private key file for client-facing TLS."),
+    setting!("pgbouncer", "client_tls_cert_file", "path", "", "1.7",
+        "Certificate file for client-facing TLS."),
+    setting!("pgbouncer", "client_tls_ca_file", "path", "", "1.7",
+        "CA file used to validate client certificates."),
+    setting!("pgbouncer", "client_tls_protocols", "comma-separated list", "secure", "1.7",
+        "Allowed TLS protocol versions for client connections."),
+    setting!("pgbouncer", "client_tls_ciphers", "string", "default", "1.7",
+        "Allowed TLS ciphers for client connections."),
+    setting!("pgbouncer", "client_tls_dheparams", "string", "auto", "1.7",
+        "DHE key exchange parameters for client connections."),
+    setting!("pgbouncer", "client_tls_ecdhcurve", "string", "auto", "1.7",
+        "Elliptic curve used for ECDH key exchange with clients."),
+    setting!("pgbouncer", "server_tls_sslmode", "enum (disable, allow, prefer, require, verify-ca, verify-full)", "prefer", "1.7",
+        "TLS mode for server connections."),
+    setting!("pgbouncer", "server_tls_ca_file", "path", "", "1.7",
+        "CA file used to validate PostgreSQL server certificates."),
+    setting!("pgbouncer", "server_tls_key_file", "path", "", "1.7",
+        "Private key file for authenticating to PostgreSQL servers."),
+    setting!("pgbouncer", "server_tls_cert_file", "path", "", "1.7",
+        "Certificate file for authenticating to PostgreSQL servers."),
+    setting!("pgbouncer", "server_tls_protocols", "comma-separated list", "secure", "1.7",
+        "Allowed TLS protocol versions for server connections."),
+    setting!("pgbouncer", "server_tls_ciphers", "string", "default", "1.7",
+        "Allowed TLS ciphers for server connections."),
+    // [databases]
+    setting!("databases", "dbname", "string", "", "1.0",
+        "Name of the backend database the entry connects to."),
+    setting!("databases", "host", "string", "", "1.0",
+        "Backend PostgreSQL host name or IP address."),
+    setting!("databases", "port", "integer", "5432", "1.0",
+        "Backend PostgreSQL port."),
+    setting!("databases", "user", "string", "", "1.0",
+        "User used for all server connections of the entry."),
+    setting!("databases", "password", "string", "", "1.0",
+        "Password used together with user for server connections."),
+    setting!("databases", "pool_size", "integer", "", "1.0",
+        "Maximum pool size of the entry; defaults to default_pool_size."),
+    setting!("databases", "min_pool_size", "integer", "", "1.18",
+        "Minimum number of server connections kept in the pool of the entry."),
+    setting!("databases", "reserve_pool", "integer", "", "1.7",
+        "Additional connections allowed for the entry when its pool is exhausted."),
+    setting!("databases", "pool_mode", "enum (session, transaction, statement)", "", "1.7",
+        "Pool mode of the entry, overriding the global pool_mode."),
+    setting!("databases", "max_db_connections", "integer", "", "1.8",
+        "Maximum number of server connections for the database."),
+    setting!("databases", "connect_query", "string", "", "1.4",
+        "Query executed after establishing a new server connection."),
+    setting!("databases", "client_encoding", "string", "", "1.0",
+        "client_encoding set on server connections of the entry."),
+    setting!("databases", "datestyle", "string", "", "1.0",
+        "DateStyle set on server connections of the entry."),
+    setting!("databases", "timezone", "string", "", "1.0",
+        "TimeZone set on server connections of the entry."),
+    setting!("databases", "auth_user", "string", "", "1.8",
+        "User used to look up passwords of the entry with auth_query."),
+    // [peers]
+    setting!("peers", "host", "string", "", "1.19",
+        "Host name, IP address or Unix socket directory of the peer."),
+    setting!("peers", "port", "integer", "6432", "1.19",
+        "Port the peer listens on."),
+    setting!("peers", "pool_size", "integer", "", "1.19",
+        "Maximum number of connections to the peer; defaults to default_pool_size."),
+];
+
+/// Returns all settings known to the catalog.
+///
+/// # Returns
+/// A static slice of every [`SettingInfo`], ordered by section and declaration order.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::pgbouncer_config::settings_catalog::settings;
+/// assert!(settings().iter().any(|s| s.name == "pool_mode"));
+/// ```
+pub fn settings() -> &'static [SettingInfo] {
+    SETTINGS
+}
+
+/// Looks up a setting by its key name.
+///
+/// # Parameters
+/// - name: Key name as written in pgbouncer.ini. The lookup is case-insensitive.
+///
+/// # Returns
+/// The matching [`SettingInfo`], or `None` if the key is not in the catalog.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::pgbouncer_config::settings_catalog::find_setting;
+/// let info = find_setting("pool_mode").unwrap();
+/// assert_eq!(info.default, "session");
+/// ```
+pub fn find_setting(name: &str) -> Option<&'static SettingInfo> {
+    let name = name.trim();
+    SETTINGS.iter().find(|setting| setting.name.eq_ignore_ascii_case(name))
+}
+
+/// Looks up a setting by section and key name.
+///
+/// Unlike [`find_setting`], keys shared by several sections (such as `host`)
+/// resolve to the entry of the given section.
+///
+/// # Parameters
+/// - section: Section name (e.g. `pgbouncer`, `databases` or `peers`).
+/// - name: Key name as written in pgbouncer.ini. The lookup is case-insensitive.
+///
+/// # Returns
+/// The matching [`SettingInfo`], or `None` if the key is not in the catalog.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::pgbouncer_config::settings_catalog::find_section_setting;
+/// let info = find_section_setting("peers", "pool_size").unwrap();
+/// assert_eq!(info.since, "1.19");
+/// ```
+pub fn find_section_setting(section: &str, name: &str) -> Option<&'static SettingInfo> {
+    let name = name.trim();
+    SETTINGS
+        .iter()
+        .find(|setting| setting.section == section && setting.name.eq_ignore_ascii_case(name))
+}
+
+/// A key, or a value of a key, that PgBouncer deprecates or keeps only for compatibility.
+///
+/// # Fields
+/// - name: Key name as written in pgbouncer.ini.
+/// - section: Section the key belongs to.
+/// - value: The deprecated value, or `None` when the key itself is deprecated.
+/// - replacement: Key or value to use instead, if any.
+/// - reason: Short explanation shown with the warning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeprecatedSetting {
+    pub name: &'static str,
+    pub section: &'static str,
+    pub value: Option<&'static str>,
+    pub replacement: Option<&'static str>,
+    pub reason: &'static str,
+}
+
+impl DeprecatedSetting {
+    /// Returns the message of the warning reported for the setting.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::settings_catalog::find_deprecated_setting;
+    ///
+    /// let deprecated = find_deprecated_setting("pgbouncer", "job_name", "pgbouncer").unwrap();
+    /// assert_eq!(deprecated.message(), "job_name is deprecated (old name of service_name); use service_name instead");
+    /// ```
+    pub fn message(&self) -> String {
+        let subject = match self.value {
+            Some(value) => format!("{} = {}", self.name, value),
+            None => self.name.to_string(),
+        };
+        match self.replacement {
+            Some(replacement) => format!("{} is deprecated ({}); use {} instead", subject, self.reason, replacement),
+            None => format!("{} is deprecated ({})", subject, self.reason),
+        }
+    }
+}
+
+static DEPRECATED_SETTINGS: &[DeprecatedSetting] = &[
+    DeprecatedSetting {
+        name: "job_name",
+        section: "pgbouncer",
+        value: None,
+        replacement: Some("service_name"),
+        reason: "old name of service_name",
+    },
+    DeprecatedSetting {
+        name: "auth_type",
+        section: "pgbouncer",
+        value: Some("plain"),
+        replacement: Some("auth_type = scram-sha-256"),
+        reason: "the password is sent over the wire in clear text",
+    },
+];
+
+/// Returns every deprecated key and value known to the catalog.
+pub fn deprecated_settings() -> &'static [DeprecatedSetting] {
+    DEPRECATED_SETTINGS
+}
+
+/// Looks up the deprecation of a key, or of its value, in a section.
+///
+/// # Parameters
+/// - section: Section of the key, without brackets.
+/// - name: Key name as written in pgbouncer.ini. The lookup is case-insensitive.
+/// - value: Value of the key.
+///
+/// # Returns
+/// The deprecation, or `None` if the key and its value are current.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::pgbouncer_config::settings_catalog::find_deprecated_setting;
+///
+/// assert!(find_deprecated_setting("pgbouncer", "auth_type", "plain").is_some());
+/// assert!(find_deprecated_setting("pgbouncer", "auth_type", "scram-sha-256").is_none());
+/// ```
+pub fn find_deprecated_setting(section: &str, name: &str, value: &str) -> Option<&'static DeprecatedSetting> {
+    let name = name.trim();
+    let value = value.trim();
+    DEPRECATED_SETTINGS.iter().find(|deprecated| {
+        deprecated.section == section
+            && deprecated.name.eq_ignore_ascii_case(name)
+            && deprecated.value.is_none_or(|deprecated_value| deprecated_value.eq_ignore_ascii_case(value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn setting_names_are_unique_per_section() {
+        let mut seen = HashSet::new();
+        for setting in settings() {
+            assert!(seen.insert((setting.section, setting.name)), "duplicated: {}", setting.name);
+        }
+    }
+
+    #[test]
+    fn extra_keys_are_cataloged_with_their_version() {
+        let info = find_section_setting("pgbouncer", "max_prepared_statements").unwrap();
+        assert!(!info.modeled);
+        assert_eq!(info.since_version(), (1, 21));
+        assert!(find_section_setting("pgbouncer", "listen_port").unwrap().modeled);
+    }
+
+    #[test]
+    fn find_setting_is_case_insensitive() {
+        assert_eq!(find_setting("POOL_MODE").map(|s| s.name), Some("pool_mode"));
+        assert!(find_setting("no_such_setting").is_none());
+    }
+}
@@ -0,0 +1,200 @@
+//! Thread-safe handle to a [`PgBouncerConfig`] shared between tasks.
+
+use std::sync::Arc;
+use tokio::sync::{RwLock, RwLockReadGuard};
+use crate::pgbouncer_config::PgBouncerConfig;
+
+/// Cloneable, thread-safe handle to a single [`PgBouncerConfig`].
+///
+/// Every clone points at the same configuration. Readers take a shared lock
+/// while updates are applied to a copy and only committed when they succeed,
+/// so concurrent readers never observe a half-applied change.
+///
+/// # Fields
+/// - inner: Configuration guarded by an async read/write lock.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+/// use pgbouncer_config::pgbouncer_config::shared::SharedPgBouncerConfig;
+/// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+/// use pgbouncer_config::pgbouncer_config::databases_setting::DatabasesSetting;
+///
+/// let cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new())
+///     .unwrap()
+///     .build();
+/// let shared = SharedPgBouncerConfig::new(cfg);
+///
+/// let rt = tokio::runtime::Runtime::new().unwrap();
+/// rt.block_on(async {
+///     shared.update(|cfg| {
+///         cfg.get_config_mut::<PgBouncerSetting>()?.set_listen_port(6433);
+///         Ok(())
+///     }).await.unwrap();
+///     assert!(shared.snapshot().await.to_string().contains("listen_port = 6433"));
+/// });
+/// ```
+#[derive(Clone, Debug)]
+pub struct SharedPgBouncerConfig {
+    inner: Arc<RwLock<PgBouncerConfig>>,
+}
+
+impl SharedPgBouncerConfig {
+    /// Wraps a configuration in a shared handle.
+    ///
+    /// # Parameters
+    /// - config: Initial configuration.
+    ///
+    /// # Returns
+    /// A new `SharedPgBouncerConfig`.
+    pub fn new(config: PgBouncerConfig) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(config)),
+        }
+    }
+
+    /// Acquires a shared read lock on the configuration.
+    ///
+    /// # Returns
+    /// A guard dereferencing to the current configuration.
+    pub async fn read(&self) -> RwLockReadGuard<'_, PgBouncerConfig> {
+        self.inner.read().await
+    }
+
+    /// Returns a copy of the current configuration.
+    ///
+    /// # Returns
+    /// A clone of the configuration at the time of the call.
+    pub async fn snapshot(&self) -> PgBouncerConfig {
+        self.inner.read().await.clone()
+    }
+
+    /// Replaces the configuration.
+    ///
+    /// # Parameters
+    /// - config: New configuration.
+    ///
+    /// # Returns
+    /// The configuration that was replaced.
+    pub async fn replace(&self, config: PgBouncerConfig) -> PgBouncerConfig {
+        std::mem::replace(&mut *self.inner.write().await, config)
+    }
+
+    /// Applies a change to the configuration atomically.
+    ///
+    /// The closure runs on a copy of the current configuration while the write
+    /// lock is held; the copy is committed only if the closure succeeds.
+    ///
+    /// # Parameters
+    /// - f: Closure modifying the configuration.
+    ///
+    /// # Returns
+    /// The value returned by `f`.
+    ///
+    /// # Errors
+    /// Returns the error produced by `f`; the configuration is left unchanged.
+    pub async fn update<F, T>(&self, f: F) -> crate::error::Result<T>
+    where
+        F: FnOnce(&mut PgBouncerConfig) -> crate::error::Result<T>,
+    {
+        let mut guard = self.inner.write().await;
+        let mut candidate = guard.clone();
+        let res = f(&mut candidate)?;
+        *guard = candidate;
+
+        Ok(res)
+    }
+
+    /// Variant of [`SharedPgBouncerConfig::update`] whose change is asynchronous.
+    ///
+    /// The write lock is held until the closure completes, so the closure may
+    /// await work that must happen before the change is committed (e.g.
+    /// persisting it with `spawn_blocking`) without racing other updates.
+    ///
+    /// # Parameters
+    /// - f: Async closure modifying the configuration.
+    ///
+    /// # Returns
+    /// The value returned by `f`.
+    ///
+    /// # Errors
+    /// Returns the error produced by `f`; the configuration is left unchanged.
+    pub async fn update_async<F, T>(&self, f: F) -> crate::error::Result<T>
+    where
+        F: AsyncFnOnce(&mut PgBouncerConfig) -> crate::error::Result<T>,
+    {
+        let mut guard = self.inner.write().await;
+        let mut candidate = guard.clone();
+        let res = f(&mut candidate).await?;
+        *guard = candidate;
+
+        Ok(res)
+    }
+}
+
+impl From<PgBouncerConfig> for SharedPgBouncerConfig {
+    fn from(config: PgBouncerConfig) -> Self {
+        Self::new(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::PgBouncerConfigBuilder;
+    use crate::error::PgBouncerError;
+    use crate::pgbouncer_config::databases_setting::DatabasesSetting;
+    use crate::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+
+    fn config() -> PgBouncerConfig {
+        PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new())
+            .unwrap()
+            .build()
+    }
+
+    #[tokio::test]
+    async fn clones_share_the_same_configuration() {
+        let shared = SharedPgBouncerConfig::new(config());
+        let other = shared.clone();
+
+        other.update(|cfg| {
+            cfg.get_config_mut::<PgBouncerSetting>()?.set_listen_port(7000);
+            Ok(())
+        }).await.unwrap();
+
+        assert!(shared.read().await.expr().unwrap().contains("listen_port = 7000"));
+    }
+
+    #[tokio::test]
+    async fn failed_update_leaves_configuration_unchanged() {
+        let shared = SharedPgBouncerConfig::new(config());
+
+        let res: crate::error::Result<()> = shared.update(|cfg| {
+            cfg.get_config_mut::<PgBouncerSetting>()?.set_listen_port(7000);
+            Err(PgBouncerError::PgBouncer("rejected".to_string()))
+        }).await;
+
+        assert!(res.is_err());
+        assert!(shared.read().await.expr().unwrap().contains("listen_port = 6432"));
+    }
+
+    #[tokio::test]
+    async fn update_async_commits_after_the_closure_completes() {
+        let shared = SharedPgBouncerConfig::new(config());
+
+        let port = shared.update_async(async |cfg| {
+            tokio::task::yield_now().await;
+            cfg.get_config_mut::<PgBouncerSetting>()?.set_listen_port(7000);
+            Ok(7000)
+        }).await.unwrap();
+        assert_eq!(port, 7000);
+        assert!(shared.read().await.expr().unwrap().contains("listen_port = 7000"));
+
+        let res: crate::error::Result<()> = shared.update_async(async |cfg| {
+            cfg.get_config_mut::<PgBouncerSetting>()?.set_listen_port(7001);
+            Err(PgBouncerError::PgBouncer("rejected".to_string()))
+        }).await;
+        assert!(res.is_err());
+        assert!(shared.read().await.expr().unwrap().contains("listen_port = 7000"));
+    }
+}
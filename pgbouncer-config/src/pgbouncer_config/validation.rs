@@ -0,0 +1,271 @@
+//! Semantic validation of a configuration.
+//!
+//! Rendering only proves that a configuration can be written out;
+//! [`PgBouncerConfig::validate`](crate::pgbouncer_config::PgBouncerConfig::validate)
+//! additionally checks rules spanning several keys or entries, such as the
+//! files required by the selected `auth_type`. Each finding is reported as a
+//! [`ValidationIssue`] so callers decide how strict to be.
+//!
+//! [`PgBouncerConfig::validate_for`](crate::pgbouncer_config::PgBouncerConfig::validate_for)
+//! also rejects keys that the chosen [`TargetVersion`] does not support, based
+//! on the `since` column of [`settings_catalog`](crate::pgbouncer_config::settings_catalog).
+
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use crate::error::PgBouncerError;
+use crate::pgbouncer_config::settings_catalog::find_section_setting;
+
+/// How serious a [`ValidationIssue`] is.
+///
+/// `Error` means PgBouncer refuses to start or cannot serve clients with the
+/// configuration; `Warning` means the configuration works but is likely a mistake.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A single finding of [`PgBouncerConfig::validate`](crate::pgbouncer_config::PgBouncerConfig::validate).
+///
+/// # Fields
+/// - severity: How serious the finding is.
+/// - section: Section the finding belongs to (e.g. `pgbouncer`).
+/// - key: Key or database name the finding is about, if any.
+/// - message: Human-readable description.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ValidationIssue {
+    severity: Severity,
+    section: String,
+    key: Option<String>,
+    message: String,
+}
+
+impl ValidationIssue {
+    /// Creates an issue with [`Severity::Error`].
+    ///
+    /// # Parameters
+    /// - section: Section the issue belongs to.
+    /// - key: Key the issue is about, if any.
+    /// - message: Human-readable description.
+    ///
+    /// # Returns
+    /// The initialized ValidationIssue.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::pgbouncer_config::validation::{Severity, ValidationIssue};
+    /// let issue = ValidationIssue::error("pgbouncer", Some("auth_file"), "auth_file is required");
+    /// assert_eq!(issue.severity(), Severity::Error);
+    /// assert_eq!(issue.to_string(), "error: [pgbouncer] auth_file: auth_file is required");
+    /// ```
+    pub fn error(section: &str, key: Option<&str>, message: &str) -> Self {
+        Self::new(Severity::Error, section, key, message)
+    }
+
+    /// Creates an issue with [`Severity::Warning`].
+    ///
+    /// # Parameters
+    /// - section: Section the issue belongs to.
+    /// - key: Key the issue is about, if any.
+    /// - message: Human-readable description.
+    ///
+    /// # Returns
+    /// The initialized ValidationIssue.
+    pub fn warning(section: &str, key: Option<&str>, message: &str) -> Self {
+        Self::new(Severity::Warning, section, key, message)
+    }
+
+    fn new(severity: Severity, section: &str, key: Option<&str>, message: &str) -> Self {
+        Self {
+            severity,
+            section: section.to_string(),
+            key: key.map(|k| k.to_string()),
+            message: message.to_string(),
+        }
+    }
+
+    /// Returns the severity of the issue.
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Returns the section the issue belongs to.
+    pub fn section(&self) -> &str {
+        &self.section
+    }
+
+    /// Returns the key the issue is about, if any.
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    /// Returns the description of the issue.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns true when the issue is an error.
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl Display for ValidationIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.key {
+            Some(key) => write!(f, "{}: [{}] {}: {}", self.severity, self.section, key, self.message),
+            None => write!(f, "{}: [{}] {}", self.severity, self.section, self.message),
+        }
+    }
+}
+
+/// PgBouncer release a configuration is validated against.
+///
+/// Variants are ordered, so `TargetVersion::V1_18 < TargetVersion::V1_21`.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::pgbouncer_config::validation::TargetVersion;
+/// let target = TargetVersion::try_from("1.18").unwrap();
+/// assert_eq!(target, TargetVersion::V1_18);
+/// assert_eq!(target.to_string(), "1.18");
+/// assert!(!target.supports((1, 19)));
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum TargetVersion {
+    #[serde(rename = "1.7")]
+    V1_7 = 7,
+    #[serde(rename = "1.8")]
+    V1_8 = 8,
+    #[serde(rename = "1.9")]
+    V1_9 = 9,
+    #[serde(rename = "1.10")]
+    V1_10 = 10,
+    #[serde(rename = "1.11")]
+    V1_11 = 11,
+    #[serde(rename = "1.12")]
+    V1_12 = 12,
+    #[serde(rename = "1.13")]
+    V1_13 = 13,
+    #[serde(rename = "1.14")]
+    V1_14 = 14,
+    #[serde(rename = "1.15")]
+    V1_15 = 15,
+    #[serde(rename = "1.16")]
+    V1_16 = 16,
+    #[serde(rename = "1.17")]
+    V1_17 = 17,
+    #[serde(rename = "1.18")]
+    V1_18 = 18,
+    #[serde(rename = "1.19")]
+    V1_19 = 19,
+    #[serde(rename = "1.20")]
+    V1_20 = 20,
+    #[serde(rename = "1.21")]
+    V1_21 = 21,
+    #[serde(rename = "1.22")]
+    V1_22 = 22,
+    #[serde(rename = "1.23")]
+    V1_23 = 23,
+    #[serde(rename = "1.24")]
+    V1_24 = 24,
+}
+
+impl TargetVersion {
+    /// Every supported target, oldest first.
+    pub const ALL: [TargetVersion; 18] = [
+        TargetVersion::V1_7, TargetVersion::V1_8, TargetVersion::V1_9, TargetVersion::V1_10,
+        TargetVersion::V1_11, TargetVersion::V1_12, TargetVersion::V1_13, TargetVersion::V1_14,
+        TargetVersion::V1_15, TargetVersion::V1_16, TargetVersion::V1_17, TargetVersion::V1_18,
+        TargetVersion::V1_19, TargetVersion::V1_20, TargetVersion::V1_21, TargetVersion::V1_22,
+        TargetVersion::V1_23, TargetVersion::V1_24,
+    ];
+
+    /// Returns the newest target known to this crate.
+    pub fn latest() -> Self {
+        TargetVersion::V1_24
+    }
+
+    /// Returns the target as `(major, minor)`.
+    pub fn version(self) -> (u8, u8) {
+        (1, self as u8)
+    }
+
+    /// Returns true when a key introduced in `since` is available in this target.
+    ///
+    /// # Parameters
+    /// - since: Version that introduced the key as `(major, minor)`.
+    pub fn supports(self, since: (u8, u8)) -> bool {
+        since <= self.version()
+    }
+}
+
+impl Display for TargetVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (major, minor) = self.version();
+        write!(f, "{}.{}", major, minor)
+    }
+}
+
+impl TryFrom<&str> for TargetVersion {
+    type Error = PgBouncerError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let version = value.trim().trim_start_matches(['v', 'V']);
+        TargetVersion::ALL
+            .into_iter()
+            .find(|target| target.to_string() == version)
+            .ok_or(PgBouncerError::PgBouncer(format!("Unsupported target version: {}", value)))
+    }
+}
+
+/// Reports the keys of `section` that `target` does not support.
+///
+/// Keys missing from the catalog are not reported.
+pub(crate) fn version_issues<I, S>(section: &str, keys: I, target: TargetVersion) -> Vec<ValidationIssue>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    keys.into_iter()
+        .filter_map(|key| find_section_setting(section, key.as_ref()))
+        .filter(|setting| !target.supports(setting.since_version()))
+        .map(|setting| ValidationIssue::error(
+            section,
+            Some(setting.name),
+            &format!("{} requires PgBouncer {} or later (target: {})", setting.name, setting.since, target),
+        ))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_version_parses_and_orders() {
+        assert_eq!(TargetVersion::try_from("v1.21").unwrap(), TargetVersion::V1_21);
+        assert!(TargetVersion::try_from("2.0").is_err());
+        assert!(TargetVersion::V1_18 < TargetVersion::V1_21);
+        assert_eq!(TargetVersion::ALL.last(), Some(&TargetVersion::latest()));
+    }
+
+    #[test]
+    fn version_issues_report_newer_keys_only() {
+        let issues = version_issues("databases", ["host", "min_pool_size", "no_such_key"], TargetVersion::V1_17);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].key(), Some("min_pool_size"));
+        assert!(version_issues("databases", ["min_pool_size"], TargetVersion::V1_18).is_empty());
+    }
+}
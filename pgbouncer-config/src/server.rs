@@ -0,0 +1,389 @@
+//! Embedded HTTP API for managing a configuration as a small service.
+//!
+//! Enabled with the `server` feature. [`router`] builds an axum [`Router`]
+//! serving a [`SharedPgBouncerConfig`]:
+//!
+//! | Method | Path               | Description                                               |
+//! |--------|--------------------|-----------------------------------------------------------|
+//! | GET    | `/config`          | Current definition as JSON                                |
+//! | PUT    | `/config`          | Replace the definition (validated first); returns a diff  |
+//! | PATCH  | `/config`          | Apply a JSON merge patch (RFC 7386); returns a diff       |
+//! | GET    | `/config/ini`      | Current configuration rendered as pgbouncer.ini           |
+//! | POST   | `/config/validate` | Validate a definition without applying it                 |
+//! | POST   | `/config/diff`     | Diff a definition against the current one                 |
+//! | POST   | `/generate`        | Write pgbouncer.ini to [`ServiceOptions::output`]         |
+//! | POST   | `/reload`          | Run [`ServiceOptions::reload_command`]                    |
+//!
+//! Request bodies are definitions in the JSON representation of
+//! [`PgBouncerConfig`]. Errors are returned as `{"error": "..."}`. Passwords,
+//! SSH keys and their passphrases read `***` in every response.
+
+use std::net::SocketAddr;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Serialize;
+use crate::control::{self, ServiceOptions};
+use crate::error::PgBouncerError;
+use crate::pgbouncer_config::PgBouncerConfig;
+use crate::pgbouncer_config::shared::SharedPgBouncerConfig;
+use crate::utils::diff::{compute_diff_pg_config, Diff, DiffOptions};
+
+#[derive(Clone)]
+struct AppState {
+    config: SharedPgBouncerConfig,
+    options: ServiceOptions,
+}
+
+/// Error returned by the HTTP handlers.
+///
+/// Parse and validation failures map to `422 Unprocessable Entity`, a missing
+/// server option to `409 Conflict`, and everything else to
+/// `500 Internal Server Error`.
+#[derive(Debug)]
+struct ApiError(StatusCode, String);
+
+impl From<PgBouncerError> for ApiError {
+    fn from(e: PgBouncerError) -> Self {
+        let status = match e {
+            PgBouncerError::PgBouncer(_)
+            | PgBouncerError::ParseError { .. }
+            | PgBouncerError::SerdeJson(_)
+            | PgBouncerError::Deserialize(_)
+            | PgBouncerError::Regex(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self(status, e.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+type ApiResult<T> = std::result::Result<T, ApiError>;
+
+#[derive(Serialize)]
+struct Validation {
+    valid: bool,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Generated {
+    output: String,
+}
+
+/// Builds the HTTP API router.
+///
+/// # Parameters
+/// - config: Shared configuration served and updated by the API.
+/// - options: Paths and commands used by the persisting endpoints.
+///
+/// # Returns
+/// An axum `Router` ready to be served or nested into a larger application.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+/// use pgbouncer_config::pgbouncer_config::shared::SharedPgBouncerConfig;
+/// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+/// use pgbouncer_config::pgbouncer_config::databases_setting::DatabasesSetting;
+/// use pgbouncer_config::control::ServiceOptions;
+/// use pgbouncer_config::server::router;
+///
+/// let cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new())
+///     .unwrap()
+///     .build();
+/// let _app = router(SharedPgBouncerConfig::new(cfg), ServiceOptions::default());
+/// ```
+pub fn router(config: SharedPgBouncerConfig, options: ServiceOptions) -> Router {
+    Router::new()
+        .route("/config", get(get_config).put(put_config).patch(patch_config))
+        .route("/config/ini", get(get_ini))
+        .route("/config/validate", post(validate_config))
+        .route("/config/diff", post(diff_config))
+        .route("/generate", post(generate))
+        .route("/reload", post(reload))
+        .with_state(AppState { config, options })
+}
+
+/// Serves the HTTP API until the process is terminated.
+///
+/// # Parameters
+/// - addr: Socket address to listen on.
+/// - config: Shared configuration served and updated by the API.
+/// - options: Paths and commands used by the persisting endpoints.
+///
+/// # Returns
+/// Unit when the server stops.
+///
+/// # Errors
+/// Returns an error if the address cannot be bound or serving fails.
+///
+/// # Examples
+/// ```rust,no_run
+/// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+/// use pgbouncer_config::pgbouncer_config::shared::SharedPgBouncerConfig;
+/// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+/// use pgbouncer_config::pgbouncer_config::databases_setting::DatabasesSetting;
+/// use pgbouncer_config::control::ServiceOptions;
+/// use pgbouncer_config::server::serve;
+///
+/// let cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new())
+///     .unwrap()
+///     .build();
+/// let rt = tokio::runtime::Runtime::new().unwrap();
+/// rt.block_on(async {
+///     serve("127.0.0.1:8080".parse().unwrap(), SharedPgBouncerConfig::new(cfg), ServiceOptions::default())
+///         .await
+///         .unwrap();
+/// });
+/// ```
+pub async fn serve(
+    addr: SocketAddr,
+    config: SharedPgBouncerConfig,
+    options: ServiceOptions,
+) -> crate::error::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, router(config, options)).await?;
+
+    Ok(())
+}
+
+/// Options masking the secrets of every response.
+///
+/// The `auth` word of the default secret fields is left out so that
+/// `auth_type` and `auth_file` stay readable.
+fn response_masking() -> DiffOptions {
+    let mut options = DiffOptions::new();
+    options
+        .set_mask_secrets(true)
+        .set_secret_fields(&["password", "passphrase", "key"]);
+    options
+}
+
+async fn get_config(State(state): State<AppState>) -> ApiResult<Json<serde_json::Value>> {
+    let mut value = serde_json::to_value(&*state.config.read().await).map_err(PgBouncerError::from)?;
+    response_masking().mask_json(&mut value);
+
+    Ok(Json(value))
+}
+
+async fn put_config(State(state): State<AppState>, body: String) -> ApiResult<Json<Diff>> {
+    let new = control::parse_definition(&body)?;
+    apply(&state, |_| Ok(new)).await
+}
+
+async fn patch_config(State(state): State<AppState>, body: String) -> ApiResult<Json<Diff>> {
+    apply(&state, |current| control::apply_merge_patch(current, &body)).await
+}
+
+/// Builds the new definition from the current one, then persists and commits it
+/// under the write lock so concurrent updates are applied one after another.
+///
+/// The definition file is written on the blocking thread pool.
+async fn apply<F>(state: &AppState, build: F) -> ApiResult<Json<Diff>>
+where
+    F: FnOnce(&PgBouncerConfig) -> crate::error::Result<PgBouncerConfig>,
+{
+    let diff = state.config.update_async(async |config| {
+        let new = build(config)?;
+        let diff = compute_diff_pg_config(config, &new)?;
+        if let Some(definition) = state.options.definition.clone() {
+            let saved = new.clone();
+            tokio::task::spawn_blocking(move || control::save_definition(&definition, &saved)).await??;
+        }
+        *config = new;
+
+        Ok(diff)
+    }).await?;
+
+    Ok(Json(diff.with_options(&response_masking())))
+}
+
+async fn get_ini(State(state): State<AppState>) -> ApiResult<String> {
+    Ok(state.config.read().await.expr()?)
+}
+
+async fn validate_config(body: String) -> Json<Validation> {
+    match control::parse_definition(&body) {
+        Ok(_) => Json(Validation { valid: true, error: None }),
+        Err(e) => Json(Validation { valid: false, error: Some(e.to_string()) }),
+    }
+}
+
+async fn diff_config(State(state): State<AppState>, body: String) -> ApiResult<Json<Diff>> {
+    let new = control::parse_definition(&body)?;
+    let current = state.config.snapshot().await;
+
+    Ok(Json(compute_diff_pg_config(&current, &new)?.with_options(&response_masking())))
+}
+
+async fn generate(State(state): State<AppState>) -> ApiResult<Json<Generated>> {
+    let output = state.options.output
+        .as_ref()
+        .ok_or_else(|| ApiError(StatusCode::CONFLICT, "no output path is configured".to_string()))?;
+
+    control::generate(output, &state.config.snapshot().await)?;
+
+    Ok(Json(Generated { output: output.display().to_string() }))
+}
+
+async fn reload(State(state): State<AppState>) -> ApiResult<StatusCode> {
+    let command = state.options.reload_command
+        .as_deref()
+        .ok_or_else(|| ApiError(StatusCode::CONFLICT, "no reload command is configured".to_string()))?;
+    control::run_reload_command(command).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+    use crate::builder::PgBouncerConfigBuilder;
+    use crate::pgbouncer_config::databases_setting::DatabasesSetting;
+    use crate::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+
+    fn config(port: u16) -> PgBouncerConfig {
+        let mut pgbouncer = PgBouncerSetting::default();
+        pgbouncer.set_listen_port(port);
+        PgBouncerConfigBuilder::new(pgbouncer, DatabasesSetting::new())
+            .unwrap()
+            .build()
+    }
+
+    async fn send(app: Router, method: &str, uri: &str, body: String) -> (StatusCode, String) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::from(body))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        (status, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn put_config_replaces_definition_and_returns_diff() {
+        let shared = SharedPgBouncerConfig::new(config(6432));
+        let app = router(shared.clone(), ServiceOptions::default());
+
+        let body = serde_json::to_string(&config(7432)).unwrap();
+        let (status, diff) = send(app, "PUT", "/config", body).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(diff.contains("Changed"), "{}", diff);
+        assert!(shared.read().await.expr().unwrap().contains("listen_port = 7432"));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_patches_are_all_applied() {
+        let shared = SharedPgBouncerConfig::new(config(6432));
+        let app = router(shared.clone(), ServiceOptions::default());
+        let keys = [
+            "server_check_delay",
+            "server_idle_timeout",
+            "server_lifetime",
+            "server_connect_timeout",
+            "server_login_retry",
+            "client_login_timeout",
+            "autodb_idle_timeout",
+            "dns_max_ttl",
+        ];
+
+        let tasks: Vec<_> = keys.iter().enumerate().map(|(i, key)| {
+            let app = app.clone();
+            let body = format!(r#"{{"pgbouncer": {{"PgBouncerSetting": {{"{}": {}}}}}}}"#, key, i + 10);
+            tokio::spawn(async move { send(app, "PATCH", "/config", body).await })
+        }).collect();
+        for task in tasks {
+            let (status, body) = task.await.unwrap();
+            assert_eq!(status, StatusCode::OK, "{}", body);
+        }
+
+        let ini = shared.read().await.expr().unwrap();
+        for (i, key) in keys.iter().enumerate() {
+            assert!(ini.contains(&format!("{} = {}", key, i + 10)), "{} is missing:\n{}", key, ini);
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_definition_is_rejected_without_changes() {
+        let shared = SharedPgBouncerConfig::new(config(6432));
+        let app = router(shared.clone(), ServiceOptions::default());
+
+        let (status, body) = send(app.clone(), "PUT", "/config", "{not json".to_string()).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(body.contains("error"));
+
+        let (status, body) = send(app, "POST", "/config/validate", "{not json".to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("\"valid\":false"));
+        assert!(shared.read().await.expr().unwrap().contains("listen_port = 6432"));
+    }
+
+    fn config_with_password(password: &str) -> PgBouncerConfig {
+        use crate::pgbouncer_config::databases_setting::Database;
+
+        let mut databases = DatabasesSetting::new();
+        databases.add_database(Database::new("10.0.0.1", 5432, "app", password, Some(&["app"])));
+        PgBouncerConfigBuilder::new(PgBouncerSetting::default(), databases)
+            .unwrap()
+            .build()
+    }
+
+    #[tokio::test]
+    async fn responses_mask_secrets() {
+        let shared = SharedPgBouncerConfig::new(config_with_password("hunter2"));
+        let app = router(shared.clone(), ServiceOptions::default());
+
+        let (status, body) = send(app.clone(), "GET", "/config", String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(!body.contains("hunter2"), "{}", body);
+        assert!(body.contains(r#""password":"***""#), "{}", body);
+        assert!(body.contains(r#""auth_type":"md5""#), "{}", body);
+
+        let new = serde_json::to_string(&config_with_password("swordfish")).unwrap();
+        let (status, diff) = send(app.clone(), "POST", "/config/diff", new.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(diff.contains("Changed") && diff.contains("***"), "{}", diff);
+        assert!(!diff.contains("hunter2") && !diff.contains("swordfish"), "{}", diff);
+
+        let (status, diff) = send(app, "PUT", "/config", new).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(!diff.contains("hunter2") && !diff.contains("swordfish"), "{}", diff);
+        assert_eq!(shared.read().await.get_config::<DatabasesSetting>().unwrap()[0].password(), "swordfish");
+    }
+
+    #[tokio::test]
+    async fn put_config_saves_the_definition() {
+        let path = std::env::temp_dir().join(format!("pgbouncer-config-server-save-{}.toml", std::process::id()));
+        let options = ServiceOptions { definition: Some(path.clone()), ..ServiceOptions::default() };
+        let app = router(SharedPgBouncerConfig::new(config(6432)), options);
+
+        let (status, body) = send(app, "PUT", "/config", serde_json::to_string(&config(7432)).unwrap()).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let saved = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(saved.contains("listen_port = 7432"), "{}", saved);
+    }
+
+    #[tokio::test]
+    async fn generate_requires_an_output_path() {
+        let app = router(SharedPgBouncerConfig::new(config(6432)), ServiceOptions::default());
+
+        let (status, _) = send(app, "POST", "/generate", String::new()).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+}
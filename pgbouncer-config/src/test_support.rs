@@ -0,0 +1,192 @@
+//! Disposable PostgreSQL servers for integration tests.
+//!
+//! Enabled with the `test-support` feature. [`PostgresFixture`] starts a
+//! PostgreSQL container through testcontainers, seeds databases and roles,
+//! and hands out [`Database`] entries pointing at it so the import path
+//! (`Database::get_databases_from_host`, `DatabasesSetting::add_database_from_hosts`)
+//! can be exercised against a real server. A running Docker daemon is required.
+
+use sqlx::{Connection, PgConnection};
+use testcontainers_modules::postgres::Postgres;
+use testcontainers_modules::testcontainers::ContainerAsync;
+use testcontainers_modules::testcontainers::runners::AsyncRunner;
+use crate::pgbouncer_config::databases_setting::Database;
+
+/// Superuser name of the fixture server.
+pub const FIXTURE_USER: &str = "postgres";
+
+/// Superuser password of the fixture server.
+pub const FIXTURE_PASSWORD: &str = "postgres";
+
+/// A PostgreSQL container that lives as long as the fixture.
+///
+/// # Fields
+/// - container: Running container; it is removed when the fixture is dropped.
+/// - host: Host name the container is reachable on.
+/// - port: Host port mapped to the container's 5432.
+pub struct PostgresFixture {
+    #[allow(dead_code)]
+    container: ContainerAsync<Postgres>,
+    host: String,
+    port: u16,
+}
+
+impl PostgresFixture {
+    /// Starts a PostgreSQL container.
+    ///
+    /// # Returns
+    /// A fixture connected to the started container.
+    ///
+    /// # Errors
+    /// Returns an error if the container cannot be started (e.g. Docker is not running)
+    /// or its mapped port cannot be resolved.
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// use pgbouncer_config::test_support::PostgresFixture;
+    ///
+    /// let rt = tokio::runtime::Runtime::new().unwrap();
+    /// rt.block_on(async {
+    ///     let fixture = PostgresFixture::start().await.unwrap();
+    ///     fixture.seed(&["app"], &[("app_user", "secret")]).await.unwrap();
+    /// });
+    /// ```
+    pub async fn start() -> crate::error::Result<Self> {
+        let container = Postgres::default()
+            .with_user(FIXTURE_USER)
+            .with_password(FIXTURE_PASSWORD)
+            .start()
+            .await?;
+        let host = container.get_host().await?.to_string();
+        let port = container.get_host_port_ipv4(5432).await?;
+
+        Ok(Self { container, host, port })
+    }
+
+    /// Returns the host the server is reachable on.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Returns the host port mapped to the server.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Creates databases and login roles on the server.
+    ///
+    /// # Parameters
+    /// - databases: Names of the databases to create.
+    /// - roles: `(name, password)` pairs of login roles to create.
+    ///
+    /// # Returns
+    /// Unit on success.
+    ///
+    /// # Errors
+    /// Returns an error if connecting to the server or running a statement fails.
+    pub async fn seed(&self, databases: &[&str], roles: &[(&str, &str)]) -> crate::error::Result<()> {
+        let mut conn = PgConnection::connect(&self.url("postgres")).await?;
+
+        for (name, password) in roles {
+            let statement = format!(
+                "CREATE ROLE {} LOGIN PASSWORD '{}'",
+                quote_identifier(name),
+                password.replace('\'', "''"),
+            );
+            sqlx::query(&statement).execute(&mut conn).await?;
+        }
+
+        for database in databases {
+            let statement = format!("CREATE DATABASE {}", quote_identifier(database));
+            sqlx::query(&statement).execute(&mut conn).await?;
+        }
+
+        conn.close().await?;
+
+        Ok(())
+    }
+
+    /// Builds a [`Database`] entry pointing at the fixture server as the superuser.
+    ///
+    /// # Parameters
+    /// - databases: Optional initial database names, as for [`Database::new`].
+    ///
+    /// # Returns
+    /// A `Database` whose host, port and credentials target the container.
+    pub fn database(&self, databases: Option<&[&str]>) -> Database {
+        Database::new(&self.host, self.port, FIXTURE_USER, FIXTURE_PASSWORD, databases)
+    }
+
+    /// Returns a connection URL for the given database on the fixture server.
+    ///
+    /// # Parameters
+    /// - database: Database name to connect to.
+    ///
+    /// # Returns
+    /// A `postgres://` URL using the superuser credentials.
+    pub fn url(&self, database: &str) -> String {
+        format!(
+            "postgres://{}:{}@{}:{}/{}",
+            FIXTURE_USER, FIXTURE_PASSWORD, self.host, self.port, database
+        )
+    }
+}
+
+fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pg_client::PgClient;
+    use crate::pgbouncer_config::databases_setting::ConnectionOptions;
+
+    #[test]
+    fn quote_identifier_escapes_double_quotes() {
+        assert_eq!(quote_identifier("app"), "\"app\"");
+        assert_eq!(quote_identifier("we\"ird"), "\"we\"\"ird\"");
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Docker daemon"]
+    async fn pg_client_lists_seeded_databases() {
+        let fixture = PostgresFixture::start().await.unwrap();
+        fixture.seed(&["app", "reporting"], &[]).await.unwrap();
+
+        let client = PgClient::new(fixture.host(), fixture.port(), FIXTURE_USER, FIXTURE_PASSWORD, "postgres", None, &ConnectionOptions::default())
+            .await
+            .unwrap();
+        let databases = client
+            .get_databases(false)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|database| database.name)
+            .collect::<Vec<_>>();
+
+        assert!(databases.contains(&"app".to_string()));
+        assert!(databases.contains(&"reporting".to_string()));
+        // template0 does not allow connections and must not be listed
+        assert!(!databases.contains(&"template0".to_string()));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Docker daemon"]
+    async fn import_roles_reads_seeded_role_verifiers() {
+        use crate::pgbouncer_config::auth_file::{AuthFile, RoleFilter};
+        use crate::pgbouncer_config::databases_setting::DatabasesSetting;
+
+        let fixture = PostgresFixture::start().await.unwrap();
+        fixture.seed(&[], &[("app", "secret"), ("app_admin", "secret")]).await.unwrap();
+        let mut settings = DatabasesSetting::new();
+        settings.add_database(fixture.database(None));
+
+        let mut filter = RoleFilter::new();
+        filter.set_include(Some("^app")).unwrap().set_exclude(Some("_admin$")).unwrap();
+        let auth_file = AuthFile::import_roles(&settings, &filter).await.unwrap();
+
+        assert_eq!(auth_file.users().keys().collect::<Vec<_>>(), ["app"]);
+        assert!(auth_file.password("app").unwrap().starts_with("SCRAM-SHA-256$"));
+    }
+}
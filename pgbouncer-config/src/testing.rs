@@ -0,0 +1,382 @@
+//! Golden-snapshot helpers for testing generated pgbouncer.ini files.
+//!
+//! Render a [`PgBouncerConfig`] with [`render_snapshot`] and compare it with a
+//! checked-in fixture file via [`compare_snapshot`] or [`assert_snapshot`].
+//! Volatile or secret values can be redacted and the text normalized through
+//! [`SnapshotOptions`] so the fixtures stay stable across machines.
+//!
+//! Set the `PGBOUNCER_CONFIG_UPDATE_SNAPSHOTS=1` environment variable to
+//! (re)write fixture files instead of comparing against them. Missing fixtures
+//! are always written on first use.
+
+use std::path::Path;
+use regex::Regex;
+use crate::pgbouncer_config::PgBouncerConfig;
+
+/// Environment variable that switches snapshot helpers to update mode.
+pub const UPDATE_SNAPSHOTS_ENV: &str = "PGBOUNCER_CONFIG_UPDATE_SNAPSHOTS";
+
+/// Placeholder written in place of redacted values.
+pub const REDACTED: &str = "<redacted>";
+
+/// Options controlling how a configuration is turned into snapshot text.
+///
+/// # Fields
+/// - redactions: Patterns and replacements applied to the rendered text, in order.
+/// - normalize_line_endings: Convert CRLF/CR line endings to LF.
+/// - trim_trailing_whitespace: Strip trailing whitespace of each line and collapse
+///   trailing blank lines into a single final newline.
+#[derive(Debug, Clone)]
+pub struct SnapshotOptions {
+    redactions: Vec<(Regex, String)>,
+    normalize_line_endings: bool,
+    trim_trailing_whitespace: bool,
+}
+
+impl SnapshotOptions {
+    /// Creates options that normalize line endings and trailing whitespace without redactions.
+    ///
+    /// # Returns
+    /// A new `SnapshotOptions`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::testing::SnapshotOptions;
+    /// let _opts = SnapshotOptions::new();
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            redactions: vec![],
+            normalize_line_endings: true,
+            trim_trailing_whitespace: true,
+        }
+    }
+
+    /// Redacts the value of a setting wherever it appears.
+    ///
+    /// Both `key = value` lines and `key=value` pairs inside `[databases]`
+    /// connection strings are redacted.
+    ///
+    /// # Parameters
+    /// - key: Setting name whose value should be replaced with [`REDACTED`].
+    ///
+    /// # Returns
+    /// A mutable reference to `self` for chaining.
+    ///
+    /// # Errors
+    /// Returns an error if the key produces an invalid pattern.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::testing::SnapshotOptions;
+    /// let mut opts = SnapshotOptions::new();
+    /// opts.redact_key("password").unwrap().redact_key("host").unwrap();
+    /// ```
+    pub fn redact_key(&mut self, key: &str) -> crate::error::Result<&mut Self> {
+        let pattern = format!(r"(?m)(^|\s)({})(\s*=\s*)\S+", regex::escape(key));
+        self.redactions.push((Regex::new(&pattern)?, format!("${{1}}${{2}}${{3}}{}", REDACTED)));
+        Ok(self)
+    }
+
+    /// Replaces every match of a regular expression.
+    ///
+    /// # Parameters
+    /// - pattern: Regular expression to search for.
+    /// - replacement: Replacement text; capture groups can be referenced as `$1`.
+    ///
+    /// # Returns
+    /// A mutable reference to `self` for chaining.
+    ///
+    /// # Errors
+    /// Returns an error if `pattern` is not a valid regular expression.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::testing::SnapshotOptions;
+    /// let mut opts = SnapshotOptions::new();
+    /// opts.redact_pattern(r"port=\d+", "port=<port>").unwrap();
+    /// ```
+    pub fn redact_pattern(&mut self, pattern: &str, replacement: &str) -> crate::error::Result<&mut Self> {
+        self.redactions.push((Regex::new(pattern)?, replacement.to_string()));
+        Ok(self)
+    }
+
+    /// Controls line ending normalization.
+    ///
+    /// # Parameters
+    /// - normalize: Whether CRLF/CR line endings are converted to LF.
+    ///
+    /// # Returns
+    /// A mutable reference to `self` for chaining.
+    pub fn set_normalize_line_endings(&mut self, normalize: bool) -> &mut Self {
+        self.normalize_line_endings = normalize;
+        self
+    }
+
+    /// Controls trailing whitespace trimming.
+    ///
+    /// # Parameters
+    /// - trim: Whether trailing whitespace and trailing blank lines are removed.
+    ///
+    /// # Returns
+    /// A mutable reference to `self` for chaining.
+    pub fn set_trim_trailing_whitespace(&mut self, trim: bool) -> &mut Self {
+        self.trim_trailing_whitespace = trim;
+        self
+    }
+
+    /// Applies redactions and normalization to arbitrary text.
+    ///
+    /// # Parameters
+    /// - text: Text to normalize (e.g. a rendered pgbouncer.ini).
+    ///
+    /// # Returns
+    /// The normalized text.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::testing::SnapshotOptions;
+    /// let mut opts = SnapshotOptions::new();
+    /// opts.redact_key("password").unwrap();
+    /// assert_eq!(opts.normalize("password = secret  \r\n"), "password = <redacted>\n");
+    /// ```
+    pub fn normalize(&self, text: &str) -> String {
+        let mut text = if self.normalize_line_endings {
+            text.replace("\r\n", "\n").replace('\r', "\n")
+        } else {
+            text.to_string()
+        };
+
+        for (pattern, replacement) in &self.redactions {
+            text = pattern.replace_all(&text, replacement.as_str()).to_string();
+        }
+
+        if self.trim_trailing_whitespace {
+            let mut trimmed = text
+                .lines()
+                .map(|line| line.trim_end())
+                .collect::<Vec<_>>()
+                .join("\n");
+            let trimmed_len = trimmed.trim_end().len();
+            trimmed.truncate(trimmed_len);
+            trimmed.push('\n');
+            text = trimmed;
+        }
+
+        text
+    }
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Result of comparing a rendered configuration with a fixture file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotResult {
+    /// The rendered text matches the fixture.
+    Matched,
+    /// The fixture did not exist or update mode was enabled; it has been written.
+    Written,
+    /// The rendered text differs from the fixture.
+    ///
+    /// # Fields
+    /// - expected: Normalized fixture content.
+    /// - actual: Normalized rendered content.
+    Mismatched { expected: String, actual: String },
+}
+
+/// Renders the canonical form of a configuration to normalized snapshot text.
+///
+/// See [`PgBouncerConfig::canonicalize`].
+///
+/// # Parameters
+/// - config: Configuration to render.
+/// - options: Redaction and normalization options.
+///
+/// # Returns
+/// The rendered pgbouncer.ini text after applying `options`.
+///
+/// # Errors
+/// Returns an error if the configuration cannot be rendered.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+/// use pgbouncer_config::pgbouncer_config::{pgbouncer_setting::PgBouncerSetting, databases_setting::DatabasesSetting};
+/// use pgbouncer_config::testing::{render_snapshot, SnapshotOptions};
+///
+/// let cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new()).unwrap().build();
+/// let text = render_snapshot(&cfg, &SnapshotOptions::new()).unwrap();
+/// assert!(text.contains("[databases]\n"));
+/// assert!(text.ends_with('\n') && !text.ends_with("\n\n"));
+/// ```
+pub fn render_snapshot(config: &PgBouncerConfig, options: &SnapshotOptions) -> crate::error::Result<String> {
+    let mut config = config.clone();
+    config.canonicalize();
+
+    Ok(options.normalize(&config.expr()?))
+}
+
+/// Compares a rendered configuration against a fixture file.
+///
+/// The fixture is written instead of compared when it does not exist yet or when
+/// the [`UPDATE_SNAPSHOTS_ENV`] environment variable is set to `1`/`true`.
+///
+/// # Parameters
+/// - config: Configuration to render.
+/// - fixture: Path of the checked-in fixture file.
+/// - options: Redaction and normalization options, applied to both sides.
+///
+/// # Returns
+/// The [`SnapshotResult`] of the comparison.
+///
+/// # Errors
+/// Returns an error if rendering fails or the fixture cannot be read or written.
+pub fn compare_snapshot(
+    config: &PgBouncerConfig,
+    fixture: &Path,
+    options: &SnapshotOptions,
+) -> crate::error::Result<SnapshotResult> {
+    let actual = render_snapshot(config, options)?;
+
+    if !fixture.exists() || update_mode() {
+        if let Some(parent) = fixture.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(fixture, &actual)?;
+        return Ok(SnapshotResult::Written);
+    }
+
+    let expected = options.normalize(&std::fs::read_to_string(fixture)?);
+    if expected == actual {
+        Ok(SnapshotResult::Matched)
+    } else {
+        Ok(SnapshotResult::Mismatched { expected, actual })
+    }
+}
+
+/// Asserts that a rendered configuration matches a fixture file.
+///
+/// # Parameters
+/// - config: Configuration to render.
+/// - fixture: Path of the checked-in fixture file.
+/// - options: Redaction and normalization options.
+///
+/// # Panics
+/// Panics with a line-by-line report if the rendered text differs from the
+/// fixture, or if rendering or fixture I/O fails.
+///
+/// # Examples
+/// ```rust,no_run
+/// use std::path::Path;
+/// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+/// use pgbouncer_config::pgbouncer_config::{pgbouncer_setting::PgBouncerSetting, databases_setting::DatabasesSetting};
+/// use pgbouncer_config::testing::{assert_snapshot, SnapshotOptions};
+///
+/// let cfg = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), DatabasesSetting::new()).unwrap().build();
+/// assert_snapshot(&cfg, Path::new("tests/fixtures/default.ini"), &SnapshotOptions::new());
+/// ```
+pub fn assert_snapshot(config: &PgBouncerConfig, fixture: &Path, options: &SnapshotOptions) {
+    match compare_snapshot(config, fixture, options) {
+        Ok(SnapshotResult::Matched) | Ok(SnapshotResult::Written) => {},
+        Ok(SnapshotResult::Mismatched { expected, actual }) => {
+            panic!(
+                "snapshot {} does not match (set {}=1 to update):\n{}",
+                fixture.display(),
+                UPDATE_SNAPSHOTS_ENV,
+                line_diff(&expected, &actual),
+            );
+        },
+        Err(e) => panic!("snapshot {} failed: {}", fixture.display(), e),
+    }
+}
+
+fn update_mode() -> bool {
+    std::env::var(UPDATE_SNAPSHOTS_ENV)
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+fn line_diff(expected: &str, actual: &str) -> String {
+    let expected_lines = expected.lines().collect::<Vec<_>>();
+    let actual_lines = actual.lines().collect::<Vec<_>>();
+    let len = expected_lines.len().max(actual_lines.len());
+
+    let mut out = String::new();
+    for i in 0..len {
+        match (expected_lines.get(i), actual_lines.get(i)) {
+            (Some(e), Some(a)) if e == a => {},
+            (e, a) => {
+                if let Some(e) = e {
+                    out.push_str(&format!("{:>4} -{}\n", i + 1, e));
+                }
+                if let Some(a) = a {
+                    out.push_str(&format!("{:>4} +{}\n", i + 1, a));
+                }
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::PgBouncerConfigBuilder;
+    use crate::pgbouncer_config::databases_setting::{Database, DatabasesSetting};
+    use crate::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+
+    fn config() -> PgBouncerConfig {
+        let mut databases = DatabasesSetting::new();
+        let mut db = Database::new("10.0.0.1", 5432, "app", "secret", Some(&["app"]));
+        db.set_is_output_credentials_to_config(true);
+        databases.add_database(db);
+        PgBouncerConfigBuilder::new(PgBouncerSetting::default(), databases)
+            .unwrap()
+            .build()
+    }
+
+    fn fixture_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir()
+            .join(format!("pgbouncer-config-snapshots-{}", std::process::id()))
+            .join(name)
+    }
+
+    #[test]
+    fn redact_key_replaces_values_in_lines_and_connstrings() {
+        let mut opts = SnapshotOptions::new();
+        opts.redact_key("password").unwrap().redact_key("host").unwrap();
+        let text = render_snapshot(&config(), &opts).unwrap();
+        assert!(text.contains("host=<redacted>"));
+        assert!(text.contains("password=<redacted>"));
+        assert!(!text.contains("secret"));
+        assert!(!text.contains("10.0.0.1"));
+    }
+
+    #[test]
+    fn compare_snapshot_writes_then_matches_then_detects_changes() {
+        let path = fixture_path("roundtrip.ini");
+        let _ = std::fs::remove_file(&path);
+        let opts = SnapshotOptions::new();
+
+        assert_eq!(compare_snapshot(&config(), &path, &opts).unwrap(), SnapshotResult::Written);
+        assert_eq!(compare_snapshot(&config(), &path, &opts).unwrap(), SnapshotResult::Matched);
+
+        let mut changed = config();
+        changed.get_config_mut::<PgBouncerSetting>().unwrap().set_listen_port(7432);
+        assert!(matches!(
+            compare_snapshot(&changed, &path, &opts).unwrap(),
+            SnapshotResult::Mismatched { .. }
+        ));
+    }
+
+    #[test]
+    fn line_diff_reports_only_differing_lines() {
+        let diff = line_diff("a\nb\nc\n", "a\nx\n");
+        assert_eq!(diff, "   2 -b\n   2 +x\n   3 -c\n");
+    }
+}
@@ -0,0 +1,204 @@
+//! `proptest` strategies for the configuration types.
+//!
+//! Enabled with the `arbitrary` feature. The generated values are always valid
+//! configurations that render to pgbouncer.ini text the parser understands, so
+//! downstream crates can fuzz their own tooling with `any::<PgBouncerSetting>()`,
+//! `any::<Database>()`, `any::<DatabasesSetting>()` and `any::<PgBouncerConfig>()`.
+
+use proptest::prelude::*;
+use crate::builder::PgBouncerConfigBuilder;
+use crate::pgbouncer_config::PgBouncerConfig;
+use crate::pgbouncer_config::databases_setting::{Database, DatabasesSetting};
+use crate::pgbouncer_config::pgbouncer_setting::{AuthType, PgBouncerSetting, PoolMode};
+
+fn identifier() -> impl Strategy<Value = String> {
+    "[a-z_][a-z0-9_]{0,15}"
+}
+
+fn host_name() -> impl Strategy<Value = String> {
+    "[a-z][a-z0-9-]{0,15}(\\.[a-z][a-z0-9-]{0,10}){0,2}"
+}
+
+fn file_path() -> impl Strategy<Value = String> {
+    "(/[a-z0-9_.-]{1,12}){1,4}"
+}
+
+fn auth_type() -> impl Strategy<Value = AuthType> {
+    prop_oneof![
+        Just(AuthType::Md5),
+        Just(AuthType::ScramSha256),
+        Just(AuthType::Cert),
+        Just(AuthType::Plain),
+        Just(AuthType::Trust),
+        Just(AuthType::Any),
+        Just(AuthType::Hba),
+        Just(AuthType::Pam),
+    ]
+}
+
+fn pool_mode() -> impl Strategy<Value = PoolMode> {
+    prop_oneof![
+        Just(PoolMode::Session),
+        Just(PoolMode::Transaction),
+        Just(PoolMode::Statement),
+    ]
+}
+
+impl Arbitrary for AuthType {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        auth_type().boxed()
+    }
+}
+
+impl Arbitrary for PoolMode {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        pool_mode().boxed()
+    }
+}
+
+impl Arbitrary for PgBouncerSetting {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        let required = (
+            host_name(),
+            any::<u16>(),
+            auth_type(),
+            any::<u16>(),
+            any::<u16>(),
+            pool_mode(),
+            proptest::collection::vec(identifier(), 0..3),
+            proptest::collection::vec(identifier(), 0..3),
+            proptest::collection::vec(identifier(), 0..3),
+        );
+        let paths = (
+            proptest::option::of(file_path()),
+            proptest::option::of(file_path()),
+            proptest::option::of(file_path()),
+            proptest::option::of(file_path()),
+            proptest::option::of(file_path()),
+            proptest::option::of(file_path()),
+            proptest::option::of(file_path()),
+        );
+        let timeouts = proptest::collection::vec(proptest::option::of(any::<i32>()), 15);
+
+        (required, paths, timeouts)
+            .prop_map(|(required, paths, timeouts)| {
+                let (
+                    listen_addr,
+                    listen_port,
+                    auth_type,
+                    max_client_conn,
+                    default_pool_size,
+                    pool_mode,
+                    admin_users,
+                    stats_users,
+                    ignore_startup_parameters,
+                ) = required;
+                let (logfile, pidfile, auth_file, unix_socket_dir, auth_hba_file, auth_ident_file, resolve_conf) = paths;
+
+                PgBouncerSetting::new(
+                    &listen_addr,
+                    listen_port,
+                    auth_type,
+                    max_client_conn,
+                    default_pool_size,
+                    pool_mode,
+                    admin_users.iter().map(String::as_str).collect(),
+                    stats_users.iter().map(String::as_str).collect(),
+                    ignore_startup_parameters.iter().map(String::as_str).collect(),
+                    logfile.as_deref(),
+                    pidfile.as_deref(),
+                    auth_file.as_deref(),
+                    unix_socket_dir.as_deref(),
+                    auth_hba_file.as_deref(),
+                    auth_ident_file.as_deref(),
+                    timeouts[0],
+                    timeouts[1],
+                    timeouts[2],
+                    timeouts[3],
+                    timeouts[4],
+                    timeouts[5],
+                    timeouts[6],
+                    timeouts[7],
+                    timeouts[8],
+                    resolve_conf.as_deref(),
+                    timeouts[9],
+                    timeouts[10],
+                    timeouts[11],
+                    timeouts[12],
+                    timeouts[13],
+                    timeouts[14],
+                )
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Database {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            host_name(),
+            1..=u16::MAX,
+            "[a-zA-Z0-9_]{1,12}",
+            // Spaces and quotes make the rendered password quoted
+            "[a-zA-Z0-9_ '!@#%]{1,12}",
+            proptest::collection::vec(identifier(), 1..4),
+            proptest::collection::vec(identifier(), 0..2),
+            any::<bool>(),
+        )
+            .prop_map(|(host, port, user, password, databases, ignore_databases, output_credentials)| {
+                let databases = databases.iter().map(String::as_str).collect::<Vec<_>>();
+                let mut database = Database::new(&host, port, &user, &password, Some(&databases));
+                for ignore_database in &ignore_databases {
+                    database.add_ignore_database(ignore_database);
+                }
+                database.set_is_output_credentials_to_config(output_credentials);
+                database
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for DatabasesSetting {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        proptest::collection::vec(any::<Database>(), 0..4)
+            .prop_map(|databases| {
+                let mut setting = DatabasesSetting::new();
+                for database in databases {
+                    setting.add_database(database);
+                }
+                setting
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for PgBouncerConfig {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (any::<PgBouncerSetting>(), any::<DatabasesSetting>())
+            .prop_map(|(pgbouncer_setting, databases_setting)| {
+                // SAFETY: the two sections have distinct names, so seeding them cannot fail
+                PgBouncerConfigBuilder::new(pgbouncer_setting, databases_setting)
+                    .unwrap()
+                    .build()
+            })
+            .boxed()
+    }
+}
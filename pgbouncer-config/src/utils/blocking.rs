@@ -0,0 +1,31 @@
+//! Runtime helper behind the `blocking` feature's synchronous wrappers.
+
+use std::future::Future;
+
+/// Runs a future to completion on a fresh single-threaded Tokio runtime.
+///
+/// # Errors
+/// Returns an error if the runtime cannot be created.
+///
+/// # Panics
+/// Panics when called from within an asynchronous runtime; use the async API there.
+pub(crate) fn block_on<F: Future>(future: F) -> crate::error::Result<F::Output> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+
+    Ok(runtime.block_on(future))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_on_drives_spawned_tasks_to_completion() {
+        let res = block_on(async {
+            tokio::spawn(async { 40 + 2 }).await.unwrap()
+        }).unwrap();
+        assert_eq!(res, 42);
+    }
+}
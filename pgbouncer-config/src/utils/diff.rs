@@ -4,9 +4,13 @@
 //! `serde_json::Value`. It detects additions, removals, and changes across
 //! objects (maps), arrays (by index), and scalar values.
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use crate::error::PgBouncerError;
 use crate::pgbouncer_config::PgBouncerConfig;
+use crate::pgbouncer_config::auth_file::AuthFile;
+use crate::pgbouncer_config::document::Document;
 
 /// The `Diffable` trait is designed to facilitate the implementation of
 /// objects that can be compared for differences. The `#[typetag::serde]`
@@ -65,7 +69,7 @@ impl Clone for Box<dyn Diffable> {
 /// # Notes
 /// - String values are JSON-serialized; expect surrounding quotes in `old`/`new`
 ///   (e.g., `"foo"`).
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "kind")]
 pub enum Diff {
     /// Both sides are equal (no difference).
@@ -109,6 +113,484 @@ pub enum Diff {
     },
 }
 
+impl Diff {
+    /// Returns whether the diff contains any addition, removal or change.
+    ///
+    /// Objects and arrays are inspected recursively, so an object whose
+    /// fields are all [`Diff::Same`] has no changes.
+    ///
+    /// # Notes
+    /// - [`Diff::Unevaluated`] is not counted as a change: hidden credentials
+    ///   cannot be compared, and counting them would report a difference for
+    ///   every configuration containing a password.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::utils::diff::compute_diff;
+    /// assert!(!compute_diff(&1, &1).unwrap().has_changes());
+    /// assert!(compute_diff(&1, &2).unwrap().has_changes());
+    /// ```
+    pub fn has_changes(&self) -> bool {
+        match self {
+            Diff::Same { .. } | Diff::Unevaluated => false,
+            Diff::Changed { .. } | Diff::Added { .. } | Diff::Removed { .. } => true,
+            Diff::Object { fields } => fields.values().any(Diff::has_changes),
+            Diff::Array { items } => items.iter().any(|(_, diff)| diff.has_changes()),
+        }
+    }
+
+    /// Applies the diff to a configuration, turning its old values into the new ones.
+    ///
+    /// The diff is expected to come from [`compute_diff_pg_config`] (possibly
+    /// stored as a [`Patch`] in between). Every changed or removed value must
+    /// still hold its old value in `cfg`, and every added value must not exist
+    /// yet; otherwise the configuration has drifted since the diff was computed
+    /// and nothing is applied. [`Diff::Unevaluated`] entries are skipped.
+    ///
+    /// # Parameters
+    /// - cfg: Configuration to update in place.
+    ///
+    /// # Returns
+    /// Unit on success; `cfg` is left untouched on error.
+    ///
+    /// # Errors
+    /// Returns an error if a value in `cfg` conflicts with the diff, if the diff
+    /// contains masked values (see [`Diff::masked`]) or if the result is not a
+    /// valid configuration.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+    /// use pgbouncer_config::pgbouncer_config::databases_setting::DatabasesSetting;
+    /// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    /// use pgbouncer_config::utils::diff::compute_diff_pg_config;
+    ///
+    /// let config = |listen_port: u16| {
+    ///     let mut pgbouncer = PgBouncerSetting::default();
+    ///     pgbouncer.set_listen_port(listen_port);
+    ///     PgBouncerConfigBuilder::new(pgbouncer, DatabasesSetting::new()).unwrap().build()
+    /// };
+    ///
+    /// let diff = compute_diff_pg_config(&config(6432), &config(6433)).unwrap();
+    /// let mut deployed = config(6432);
+    /// diff.apply(&mut deployed).unwrap();
+    /// assert!(deployed.expr().unwrap().contains("listen_port = 6433"));
+    ///
+    /// // Applying it again conflicts because listen_port is no longer 6432
+    /// assert!(diff.apply(&mut deployed).is_err());
+    /// ```
+    pub fn apply(&self, cfg: &mut PgBouncerConfig) -> crate::error::Result<()> {
+        let mut value = serde_json::to_value(DiffablePgBouncerConfig::from(cfg.clone()))?;
+        apply_value(&mut value, self, "")?;
+        *cfg = config_from_diffable_value(value)?;
+
+        Ok(())
+    }
+
+    /// Applies every option of `options`: [`Diff::filtered`] and then [`Diff::masked`].
+    ///
+    /// # Parameters
+    /// - options: Options of the diff.
+    ///
+    /// # Returns
+    /// The filtered and masked diff.
+    pub fn with_options(self, options: &DiffOptions) -> Diff {
+        self.filtered(options).masked(options)
+    }
+
+    /// Keeps only the entries selected by the include and exclude patterns of `options`.
+    ///
+    /// Every entry is addressed by its path: the field names from the root
+    /// joined with `.`, array elements by their index (e.g.
+    /// `pgbouncer.listen_port` or `databases.databases.0.host`). An entry is
+    /// kept when no include pattern is set or one matches the path or one of
+    /// its ancestors, and no exclude pattern matches the path or one of its
+    /// ancestors. Objects and arrays left without entries are dropped.
+    ///
+    /// # Parameters
+    /// - options: Options holding the include and exclude patterns.
+    ///
+    /// # Returns
+    /// The filtered diff; [`Diff::Same`] when every entry was filtered out.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::utils::diff::{compute_diff, DiffOptions};
+    ///
+    /// let old = serde_json::json!({"listen_port": 6432, "generated_at": "2024-01-01"});
+    /// let new = serde_json::json!({"listen_port": 6432, "generated_at": "2024-02-01"});
+    /// let mut options = DiffOptions::new();
+    /// options.set_exclude(&["generated_*"]);
+    ///
+    /// assert!(!compute_diff(&old, &new).unwrap().filtered(&options).has_changes());
+    /// ```
+    pub fn filtered(self, options: &DiffOptions) -> Diff {
+        if options.include.is_empty() && options.exclude.is_empty() {
+            return self;
+        }
+
+        self.filter_path("", options).unwrap_or(Diff::Same { value: "".to_string() })
+    }
+
+    fn filter_path(self, path: &str, options: &DiffOptions) -> Option<Diff> {
+        let child_path = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+        match self {
+            Diff::Object { fields } => {
+                let fields = fields
+                    .into_iter()
+                    .filter_map(|(key, diff)| diff.filter_path(&child_path(&key), options).map(|diff| (key, diff)))
+                    .collect::<BTreeMap<_, _>>();
+                (!fields.is_empty()).then_some(Diff::Object { fields })
+            },
+            Diff::Array { items } => {
+                let items = items
+                    .into_iter()
+                    .filter_map(|(index, diff)| diff.filter_path(&child_path(&index.to_string()), options).map(|diff| (index, diff)))
+                    .collect::<Vec<_>>();
+                (!items.is_empty()).then_some(Diff::Array { items })
+            },
+            other => options.is_path_included(path).then_some(other),
+        }
+    }
+
+    /// Replaces the values of secret fields with `***` according to `options`.
+    ///
+    /// A field is secret when one of the `_`/`-` separated words of its name
+    /// matches [`DiffOptions::secret_fields`] (case-insensitive). The kind of
+    /// each masked entry is kept, so a changed password is still reported as
+    /// [`Diff::Changed`] without revealing either value. Nested values of a
+    /// secret field are masked as a whole.
+    ///
+    /// # Parameters
+    /// - options: Options deciding whether and which fields are masked.
+    ///
+    /// # Returns
+    /// The masked diff, or the diff unchanged when masking is disabled.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::utils::diff::{compute_diff, Diff, DiffOptions};
+    ///
+    /// let old = serde_json::json!({"user": "app", "password": "one"});
+    /// let new = serde_json::json!({"user": "app", "password": "two"});
+    /// let mut options = DiffOptions::new();
+    /// options.set_mask_secrets(true);
+    ///
+    /// let Diff::Object { fields } = compute_diff(&old, &new).unwrap().masked(&options) else { panic!("expected object diff") };
+    /// assert_eq!(fields.get("password"), Some(&Diff::Changed { old: "\"***\"".to_string(), new: "\"***\"".to_string() }));
+    /// ```
+    pub fn masked(self, options: &DiffOptions) -> Diff {
+        if !options.mask_secrets {
+            return self;
+        }
+
+        match self {
+            Diff::Object { fields } => Diff::Object {
+                fields: fields
+                    .into_iter()
+                    .map(|(key, diff)| {
+                        let diff = if options.is_secret(&key) { diff.mask_all() } else { diff.masked(options) };
+                        (key, diff)
+                    })
+                    .collect(),
+            },
+            Diff::Array { items } => Diff::Array {
+                items: items.into_iter().map(|(index, diff)| (index, diff.masked(options))).collect(),
+            },
+            other => other,
+        }
+    }
+
+    fn mask_all(self) -> Diff {
+        let mask = || serde_json::Value::String(MASK.to_string()).to_string();
+        match self {
+            Diff::Same { .. } => Diff::Same { value: mask() },
+            Diff::Unevaluated => Diff::Unevaluated,
+            Diff::Changed { .. } => Diff::Changed { old: mask(), new: mask() },
+            Diff::Added { .. } => Diff::Added { new: mask() },
+            Diff::Removed { .. } => Diff::Removed { old: mask() },
+            Diff::Object { fields } => Diff::Object {
+                fields: fields.into_iter().map(|(key, diff)| (key, diff.mask_all())).collect(),
+            },
+            Diff::Array { items } => Diff::Array {
+                items: items.into_iter().map(|(index, diff)| (index, diff.mask_all())).collect(),
+            },
+        }
+    }
+}
+
+/// Text replacing the values of secret fields in a masked diff.
+const MASK: &str = "***";
+
+/// Replaces every non-null value nested in `value` with [`MASK`].
+fn mask_json_all(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Null => {},
+        serde_json::Value::Object(fields) => fields.values_mut().for_each(mask_json_all),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(mask_json_all),
+        other => *other = serde_json::Value::String(MASK.to_string()),
+    }
+}
+
+/// Key holding the section type in serialized [`Diffable`] values.
+const DIFFABLE_TAG: &str = "rust_struct_pg_bouncer_config_internal";
+
+/// Version of the [`Patch`] format written by this crate.
+pub const PATCH_FORMAT_VERSION: u32 = 1;
+
+/// A diff stored for review and later application.
+///
+/// A patch is the JSON form of a [`Diff`] computed by [`compute_diff_pg_config`]
+/// together with the format version, so a change can be proposed, reviewed and
+/// approved before [`Patch::apply`] brings it onto the deployed configuration.
+///
+/// # Fields
+/// - format_version: Version of the patch format, [`PATCH_FORMAT_VERSION`].
+/// - diff: The changes to apply.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Patch {
+    format_version: u32,
+    diff: Diff,
+}
+
+impl Patch {
+    /// Creates a patch from a diff.
+    ///
+    /// # Parameters
+    /// - diff: Diff computed by [`compute_diff_pg_config`]; it must not be masked.
+    ///
+    /// # Returns
+    /// The patch in the current format version.
+    pub fn new(diff: Diff) -> Self {
+        Self { format_version: PATCH_FORMAT_VERSION, diff }
+    }
+
+    /// Returns the changes of the patch.
+    pub fn diff(&self) -> &Diff {
+        &self.diff
+    }
+
+    /// Serializes the patch as pretty-printed JSON.
+    ///
+    /// # Returns
+    /// The patch as JSON text.
+    ///
+    /// # Errors
+    /// Returns an error if serialization fails.
+    pub fn to_json(&self) -> crate::error::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parses a patch written by [`Patch::to_json`].
+    ///
+    /// # Parameters
+    /// - value: Patch as JSON text.
+    ///
+    /// # Returns
+    /// The parsed patch.
+    ///
+    /// # Errors
+    /// Returns an error if the text is not a patch or was written in a newer
+    /// format version.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::utils::diff::{compute_diff, Patch};
+    ///
+    /// let patch = Patch::new(compute_diff(&1, &2).unwrap());
+    /// assert_eq!(Patch::from_json(&patch.to_json().unwrap()).unwrap(), patch);
+    /// ```
+    pub fn from_json(value: &str) -> crate::error::Result<Self> {
+        let patch: Self = serde_json::from_str(value)?;
+        if patch.format_version > PATCH_FORMAT_VERSION {
+            return Err(PgBouncerError::PgBouncer(format!(
+                "the patch format version {} is newer than the supported version {}",
+                patch.format_version, PATCH_FORMAT_VERSION,
+            )));
+        }
+
+        Ok(patch)
+    }
+
+    /// Applies the patch to a configuration; see [`Diff::apply`].
+    ///
+    /// # Parameters
+    /// - cfg: Configuration to update in place.
+    ///
+    /// # Returns
+    /// Unit on success; `cfg` is left untouched on error.
+    ///
+    /// # Errors
+    /// Returns an error under the same conditions as [`Diff::apply`].
+    pub fn apply(&self, cfg: &mut PgBouncerConfig) -> crate::error::Result<()> {
+        self.diff.apply(cfg)
+    }
+}
+
+/// Words of a field name that mark it as secret by default.
+pub const DEFAULT_SECRET_FIELDS: [&str; 3] = ["password", "auth", "key"];
+
+/// Options of the diff computation.
+///
+/// # Fields
+/// - mask_secrets: Whether values of secret fields are replaced with `***`.
+/// - secret_fields: Words of a field name that mark it as secret,
+///   [`DEFAULT_SECRET_FIELDS`] by default.
+/// - include: Path patterns of the entries to keep; empty keeps every entry.
+/// - exclude: Path patterns of the entries to drop, e.g. volatile fields.
+///
+/// # Notes
+/// - Patterns are matched against the whole path; `*` matches any sequence of
+///   characters, including `.`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffOptions {
+    mask_secrets: bool,
+    secret_fields: Vec<String>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            mask_secrets: false,
+            secret_fields: DEFAULT_SECRET_FIELDS.iter().map(ToString::to_string).collect(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+}
+
+impl DiffOptions {
+    /// Creates options that leave every value visible.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether values of secret fields are masked.
+    ///
+    /// # Parameters
+    /// - mask_secrets: `true` to replace secret values with `***`.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated flag.
+    pub fn set_mask_secrets(&mut self, mask_secrets: bool) -> &mut Self {
+        self.mask_secrets = mask_secrets;
+        self
+    }
+
+    /// Returns whether values of secret fields are masked.
+    pub fn mask_secrets(&self) -> bool {
+        self.mask_secrets
+    }
+
+    /// Sets the words of a field name that mark it as secret.
+    ///
+    /// # Parameters
+    /// - secret_fields: Words matched case-insensitively against the `_`/`-`
+    ///   separated words of each field name.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated words.
+    pub fn set_secret_fields(&mut self, secret_fields: &[&str]) -> &mut Self {
+        self.secret_fields = secret_fields.iter().map(|field| field.to_lowercase()).collect();
+        self
+    }
+
+    /// Returns the words of a field name that mark it as secret.
+    pub fn secret_fields(&self) -> &[String] {
+        &self.secret_fields
+    }
+
+    /// Sets the path patterns of the entries to keep.
+    ///
+    /// # Parameters
+    /// - include: Patterns such as `pgbouncer.*` or `databases.app.*`; an empty
+    ///   slice keeps every entry.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated patterns.
+    pub fn set_include(&mut self, include: &[&str]) -> &mut Self {
+        self.include = include.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    /// Returns the path patterns of the entries to keep.
+    pub fn include(&self) -> &[String] {
+        &self.include
+    }
+
+    /// Sets the path patterns of the entries to drop.
+    ///
+    /// # Parameters
+    /// - exclude: Patterns such as `*.password` or `pgbouncer.auth_*`.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated patterns.
+    pub fn set_exclude(&mut self, exclude: &[&str]) -> &mut Self {
+        self.exclude = exclude.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    /// Returns the path patterns of the entries to drop.
+    pub fn exclude(&self) -> &[String] {
+        &self.exclude
+    }
+
+    /// Replaces the values of secret fields in a JSON value with `***`.
+    ///
+    /// Fields are secret under the same rule as in [`Diff::masked`]; `null`
+    /// values are kept so an unset secret stays visible as unset. Nothing is
+    /// replaced when masking is disabled.
+    ///
+    /// # Parameters
+    /// - value: Value to mask in place, e.g. a serialized definition.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::utils::diff::DiffOptions;
+    ///
+    /// let mut value = serde_json::json!({"user": "app", "password": "one", "ssh": {"ssh_key_passphrase": null}});
+    /// let mut options = DiffOptions::new();
+    /// options.set_mask_secrets(true).mask_json(&mut value);
+    /// assert_eq!(value, serde_json::json!({"user": "app", "password": "***", "ssh": {"ssh_key_passphrase": null}}));
+    /// ```
+    pub fn mask_json(&self, value: &mut serde_json::Value) {
+        if !self.mask_secrets {
+            return;
+        }
+
+        match value {
+            serde_json::Value::Object(fields) => {
+                for (key, value) in fields.iter_mut() {
+                    if self.is_secret(key) {
+                        mask_json_all(value);
+                    } else {
+                        self.mask_json(value);
+                    }
+                }
+            },
+            serde_json::Value::Array(items) => items.iter_mut().for_each(|item| self.mask_json(item)),
+            _ => {},
+        }
+    }
+
+    /// Returns whether the entry at `path` passes the include and exclude patterns.
+    fn is_path_included(&self, path: &str) -> bool {
+        let matches_any = |patterns: &[String]| {
+            path_and_ancestors(path).any(|candidate| patterns.iter().any(|pattern| glob_match(pattern, candidate)))
+        };
+
+        (self.include.is_empty() || matches_any(&self.include)) && !matches_any(&self.exclude)
+    }
+
+    fn is_secret(&self, key: &str) -> bool {
+        key.to_lowercase()
+            .split(['_', '-'])
+            .any(|word| self.secret_fields.iter().any(|field| field == word))
+    }
+}
+
 /// Computes the difference between two PgBouncer configuration objects.
 ///
 /// This function takes two references to `PgBouncerConfig`, converts them into
@@ -133,6 +615,453 @@ pub fn compute_diff_pg_config(old: &PgBouncerConfig, new: &PgBouncerConfig) -> c
     compute_diff(&diffable_old, &diffable_new)
 }
 
+/// Computes the difference between two PgBouncer configurations with options.
+///
+/// # Parameters
+/// - old: Configuration currently deployed.
+/// - new: Configuration about to be written.
+/// - options: Options of the diff, e.g. secret masking.
+///
+/// # Returns
+/// The diff of [`compute_diff_pg_config`] with `options` applied by [`Diff::with_options`].
+///
+/// # Errors
+/// Returns an error if either configuration cannot be serialized.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+/// use pgbouncer_config::pgbouncer_config::databases_setting::{Database, DatabasesSetting};
+/// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+/// use pgbouncer_config::utils::diff::{compute_diff_pg_config_with_options, DiffOptions};
+///
+/// let config = |password: &str| {
+///     let mut databases = DatabasesSetting::new();
+///     databases.add_database(Database::new("10.0.0.10", 5432, "app", password, Some(&["app"])));
+///     PgBouncerConfigBuilder::new(PgBouncerSetting::default(), databases).unwrap().build()
+/// };
+///
+/// let mut options = DiffOptions::new();
+/// options.set_mask_secrets(true);
+/// let diff = compute_diff_pg_config_with_options(&config("old-secret"), &config("new-secret"), &options).unwrap();
+/// let json = serde_json::to_string(&diff).unwrap();
+/// assert!(diff.has_changes());
+/// assert!(!json.contains("old-secret") && !json.contains("new-secret"));
+/// ```
+pub fn compute_diff_pg_config_with_options(
+    old: &PgBouncerConfig,
+    new: &PgBouncerConfig,
+    options: &DiffOptions,
+) -> crate::error::Result<Diff> {
+    Ok(compute_diff_pg_config(old, new)?.with_options(options))
+}
+
+/// Computes the difference between two auth files (userlist.txt).
+///
+/// The result is an object keyed by user name, so added, removed and changed
+/// users can be told apart. Passwords are compared as stored but never shown:
+/// every value in the diff reads `"<hidden>"`.
+///
+/// # Parameters
+/// - old: Auth file currently deployed.
+/// - new: Auth file about to be written.
+///
+/// # Returns
+/// [`Diff::Same`] when both contain the same users and passwords, otherwise
+/// [`Diff::Object`] with one entry per user.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::pgbouncer_config::auth_file::AuthFile;
+/// use pgbouncer_config::utils::diff::{compute_diff_auth_file, Diff};
+///
+/// let mut old = AuthFile::new();
+/// old.add_user("app", "one");
+/// let mut new = old.clone();
+/// new.add_user("app", "two");
+/// new.add_user("report", "three");
+///
+/// let Diff::Object { fields } = compute_diff_auth_file(&old, &new) else { panic!("expected object diff") };
+/// assert!(matches!(fields.get("app"), Some(Diff::Changed { .. })));
+/// assert!(matches!(fields.get("report"), Some(Diff::Added { .. })));
+/// ```
+pub fn compute_diff_auth_file(old: &AuthFile, new: &AuthFile) -> Diff {
+    let hidden = serde_json::Value::String("<hidden>".to_string()).to_string();
+    let users = old.users().keys().chain(new.users().keys()).collect::<BTreeSet<_>>();
+
+    let mut changed = false;
+    let mut fields = BTreeMap::new();
+    for user in users {
+        let diff = match (old.password(user), new.password(user)) {
+            (Some(old), Some(new)) if old == new => Diff::Same { value: hidden.clone() },
+            (Some(_), Some(_)) => Diff::Changed { old: hidden.clone(), new: hidden.clone() },
+            (Some(_), None) => Diff::Removed { old: hidden.clone() },
+            (None, Some(_)) => Diff::Added { new: hidden.clone() },
+            (None, None) => continue,
+        };
+        changed |= !matches!(diff, Diff::Same { .. });
+        fields.insert(user.clone(), diff);
+    }
+
+    if changed {
+        Diff::Object { fields }
+    } else {
+        Diff::Same { value: "".to_string() }
+    }
+}
+
+/// Applies `diff` to the serialized value at `path`.
+fn apply_value(target: &mut serde_json::Value, diff: &Diff, path: &str) -> crate::error::Result<()> {
+    let child_path = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+    match diff {
+        Diff::Same { .. } | Diff::Unevaluated => (),
+        Diff::Changed { old, new } => {
+            expect_value(target, old, path)?;
+            *target = patch_value(new, path)?;
+        },
+        Diff::Added { .. } | Diff::Removed { .. } => {
+            return Err(conflict(path, "additions and removals must belong to an object or an array"));
+        },
+        Diff::Object { fields } => {
+            let object = target.as_object_mut().ok_or_else(|| conflict(path, "expected an object"))?;
+            for (key, diff) in fields {
+                let path = child_path(key);
+                match diff {
+                    Diff::Same { .. } | Diff::Unevaluated => (),
+                    Diff::Added { new } => {
+                        if object.contains_key(key) {
+                            return Err(conflict(&path, "already exists"));
+                        }
+                        object.insert(key.clone(), patch_value(new, &path)?);
+                    },
+                    Diff::Removed { old } => {
+                        expect_value(object.get(key).ok_or_else(|| conflict(&path, "does not exist"))?, old, &path)?;
+                        object.remove(key);
+                    },
+                    other => {
+                        let current = object.get_mut(key).ok_or_else(|| conflict(&path, "does not exist"))?;
+                        apply_value(current, other, &path)?;
+                    },
+                }
+            }
+        },
+        Diff::Array { items } => {
+            let array = target.as_array_mut().ok_or_else(|| conflict(path, "expected an array"))?;
+            let mut removed = Vec::new();
+            let mut added = Vec::new();
+            for (index, diff) in items {
+                let path = child_path(&index.to_string());
+                match diff {
+                    Diff::Removed { old } => removed.push((*index, old, path)),
+                    Diff::Added { new } => added.push((*index, new, path)),
+                    other => {
+                        let current = array.get_mut(*index).ok_or_else(|| conflict(&path, "does not exist"))?;
+                        apply_value(current, other, &path)?;
+                    },
+                }
+            }
+
+            // Removals are trailing elements and additions are appended, as produced by `compute_diff`
+            removed.sort_by_key(|(index, _, _)| std::cmp::Reverse(*index));
+            for (index, old, path) in removed {
+                expect_value(array.get(index).ok_or_else(|| conflict(&path, "does not exist"))?, old, &path)?;
+                array.remove(index);
+            }
+            added.sort_by_key(|(index, _, _)| *index);
+            for (index, new, path) in added {
+                if index != array.len() {
+                    return Err(conflict(&path, "is not the next element of the array"));
+                }
+                array.push(patch_value(new, &path)?);
+            }
+        },
+    }
+
+    Ok(())
+}
+
+/// Checks that `current` still holds the JSON-serialized `expected` value.
+fn expect_value(current: &serde_json::Value, expected: &str, path: &str) -> crate::error::Result<()> {
+    if *current != patch_value(expected, path)? {
+        return Err(conflict(path, &format!("expected {} but found {}", expected, current)));
+    }
+
+    Ok(())
+}
+
+/// Parses a JSON-serialized value of a diff, rejecting masked values.
+fn patch_value(value: &str, path: &str) -> crate::error::Result<serde_json::Value> {
+    let value: serde_json::Value = serde_json::from_str(value)?;
+    if value.as_str() == Some(MASK) {
+        return Err(conflict(path, "the value is masked and cannot be applied"));
+    }
+
+    Ok(value)
+}
+
+fn conflict(path: &str, message: &str) -> PgBouncerError {
+    let path = if path.is_empty() { "<root>" } else { path };
+    PgBouncerError::PgBouncer(format!("cannot apply the diff at {}: {}", path, message))
+}
+
+/// Rebuilds a configuration from the serialized form of [`DiffablePgBouncerConfig`].
+///
+/// Sections are tagged inline with [`DIFFABLE_TAG`], while [`PgBouncerConfig`]
+/// wraps each section in an object keyed by its type, so the tag is moved out.
+fn config_from_diffable_value(value: serde_json::Value) -> crate::error::Result<PgBouncerConfig> {
+    let serde_json::Value::Object(sections) = value else {
+        return Err(PgBouncerError::PgBouncer("a configuration must be an object of sections".to_string()));
+    };
+
+    let mut config = serde_json::Map::new();
+    for (section, body) in sections {
+        let serde_json::Value::Object(mut body) = body else {
+            return Err(PgBouncerError::PgBouncer(format!("the section {} is not an object", section)));
+        };
+        let Some(serde_json::Value::String(type_name)) = body.remove(DIFFABLE_TAG) else {
+            return Err(PgBouncerError::PgBouncer(format!("the section {} has no type", section)));
+        };
+
+        let mut tagged = serde_json::Map::new();
+        tagged.insert(type_name, serde_json::Value::Object(body));
+        config.insert(section, serde_json::Value::Object(tagged));
+    }
+
+    Ok(serde_json::from_value(serde_json::Value::Object(config))?)
+}
+
+/// Returns `path` followed by each of its `.` separated ancestors, longest first.
+fn path_and_ancestors(path: &str) -> impl Iterator<Item = &str> {
+    std::iter::once(path).chain(path.rmatch_indices('.').map(move |(index, _)| &path[..index]))
+}
+
+/// Matches `text` against a pattern in which `*` stands for any sequence of characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (pattern.as_bytes(), text.as_bytes());
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == text[t] {
+            p += 1;
+            t += 1;
+        } else if let Some((star, matched)) = backtrack {
+            // Let the last `*` swallow one more character and retry
+            backtrack = Some((star, matched + 1));
+            p = star + 1;
+            t = matched + 1;
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// Sections whose values are `key=value` connection strings.
+const CONNECTION_STRING_SECTIONS: [&str; 3] = ["databases", "peers", "users"];
+
+/// One setting that differs between two rendered pgbouncer.ini files.
+///
+/// Renders as `[databases] app.host: 10.0.0.1 → 10.0.0.2`; an unset side reads `(unset)`.
+///
+/// # Fields
+/// - section: Section name without brackets.
+/// - key: Setting name. Entries of [databases], [peers] and [users] are addressed
+///   per connection parameter as `<name>.<parameter>`.
+/// - old: Rendered value before the change, or `None` when it was added.
+/// - new: Rendered value after the change, or `None` when it was removed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SettingChange {
+    section: String,
+    key: String,
+    old: Option<String>,
+    new: Option<String>,
+}
+
+impl SettingChange {
+    /// Returns the section name without brackets.
+    pub fn section(&self) -> &str {
+        &self.section
+    }
+
+    /// Returns the setting name, e.g. `listen_port` or `app.host`.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Returns the value before the change, or `None` when it was added.
+    pub fn old_value(&self) -> Option<&str> {
+        self.old.as_deref()
+    }
+
+    /// Returns the value after the change, or `None` when it was removed.
+    pub fn new_value(&self) -> Option<&str> {
+        self.new.as_deref()
+    }
+}
+
+impl std::fmt::Display for SettingChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}] {}: {} → {}",
+            self.section,
+            self.key,
+            self.old.as_deref().unwrap_or("(unset)"),
+            self.new.as_deref().unwrap_or("(unset)"),
+        )
+    }
+}
+
+/// Computes the differences between two configurations setting by setting.
+///
+/// Unlike [`compute_diff_pg_config`], which compares the serialized models
+/// and addresses database entries by index, both configurations are rendered
+/// to pgbouncer.ini and compared by section and key. Entries of [databases],
+/// [peers] and [users] are matched by the name clients connect to and
+/// compared per connection parameter, so a moved database reads
+/// `[databases] app.host: 10.0.0.1 → 10.0.0.2` and an added database lists
+/// each of its parameters as changed from `(unset)`. Both sides are compared
+/// in their canonical form ([`PgBouncerConfig::canonicalize`]), so reordering
+/// `admin_users` is not reported as a change.
+///
+/// # Parameters
+/// - old: Configuration currently deployed.
+/// - new: Configuration about to be written.
+///
+/// # Returns
+/// The changed settings ordered by section and key; empty when both render
+/// the same settings.
+///
+/// # Errors
+/// Returns an error if either configuration cannot be rendered.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::builder::PgBouncerConfigBuilder;
+/// use pgbouncer_config::pgbouncer_config::databases_setting::{Database, DatabasesSetting};
+/// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+/// use pgbouncer_config::utils::diff::compute_ini_diff;
+///
+/// let config = |host: &str, listen_port: u16| {
+///     let mut pgbouncer = PgBouncerSetting::default();
+///     pgbouncer.set_listen_port(listen_port);
+///     let mut databases = DatabasesSetting::new();
+///     databases.add_database(Database::new(host, 5432, "app", "secret", Some(&["app"])));
+///     PgBouncerConfigBuilder::new(pgbouncer, databases).unwrap().build()
+/// };
+///
+/// let changes = compute_ini_diff(&config("10.0.0.1", 6432), &config("10.0.0.2", 6433)).unwrap();
+/// let lines = changes.iter().map(ToString::to_string).collect::<Vec<_>>();
+/// assert_eq!(lines, vec![
+///     "[databases] app.host: 10.0.0.1 → 10.0.0.2",
+///     "[pgbouncer] listen_port: 6432 → 6433",
+/// ]);
+/// ```
+pub fn compute_ini_diff(old: &PgBouncerConfig, new: &PgBouncerConfig) -> crate::error::Result<Vec<SettingChange>> {
+    let old = rendered_settings(old)?;
+    let new = rendered_settings(new)?;
+
+    let mut changes = Vec::new();
+    for (section, key) in old.keys().chain(new.keys()).collect::<BTreeSet<_>>() {
+        let old_value = old.get(&(section.clone(), key.clone()));
+        let new_value = new.get(&(section.clone(), key.clone()));
+        if old_value == new_value {
+            continue;
+        }
+
+        if CONNECTION_STRING_SECTIONS.contains(&section.as_str()) {
+            let old_pairs = old_value.map(|value| connection_parameters(value)).transpose()?.unwrap_or_default();
+            let new_pairs = new_value.map(|value| connection_parameters(value)).transpose()?.unwrap_or_default();
+            for parameter in old_pairs.keys().chain(new_pairs.keys()).collect::<BTreeSet<_>>() {
+                let (old_pair, new_pair) = (old_pairs.get(parameter), new_pairs.get(parameter));
+                if old_pair != new_pair {
+                    changes.push(SettingChange {
+                        section: section.clone(),
+                        key: format!("{}.{}", key, parameter),
+                        old: old_pair.cloned(),
+                        new: new_pair.cloned(),
+                    });
+                }
+            }
+            continue;
+        }
+
+        changes.push(SettingChange {
+            section: section.clone(),
+            key: key.clone(),
+            old: old_value.cloned(),
+            new: new_value.cloned(),
+        });
+    }
+
+    Ok(changes)
+}
+
+/// Computes the differences between two configurations setting by setting with options.
+///
+/// # Parameters
+/// - old: Configuration currently deployed.
+/// - new: Configuration about to be written.
+/// - options: Options of the diff. Include and exclude patterns are matched
+///   against `<section>.<key>` (e.g. `databases.app.host`); with secret
+///   masking the values of secret settings and connection parameters (e.g.
+///   `app.password`) read `***`.
+///
+/// # Returns
+/// The changes of [`compute_ini_diff`] with `options` applied.
+///
+/// # Errors
+/// Returns an error under the same conditions as [`compute_ini_diff`].
+pub fn compute_ini_diff_with_options(
+    old: &PgBouncerConfig,
+    new: &PgBouncerConfig,
+    options: &DiffOptions,
+) -> crate::error::Result<Vec<SettingChange>> {
+    let mut changes = compute_ini_diff(old, new)?;
+    changes.retain(|change| options.is_path_included(&format!("{}.{}", change.section, change.key)));
+    if options.mask_secrets {
+        for change in changes.iter_mut() {
+            let name = change.key.rsplit('.').next().unwrap_or_default();
+            if options.is_secret(name) {
+                change.old = change.old.as_ref().map(|_| MASK.to_string());
+                change.new = change.new.as_ref().map(|_| MASK.to_string());
+            }
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Splits a rendered connection string into its parameters; quoted values keep their quotes.
+///
+/// Credentials are rendered as `user = name`, so spaces around `=` are accepted.
+fn connection_parameters(value: &str) -> crate::error::Result<BTreeMap<String, String>> {
+    let parameter_re = Regex::new(
+        r#"(?x)(?P<k>\w+)\s*=\s*(?P<v> '(?:[^'\\]|\\.|'')*'| "(?:[^"\\]|\\.)*"| [^\s=]+)"#,
+    )?;
+
+    Ok(parameter_re
+        .captures_iter(value)
+        .map(|cap| (cap["k"].to_string(), cap["v"].to_string()))
+        .collect())
+}
+
+/// Renders the canonical form of `config` and returns its entries keyed by section and key.
+fn rendered_settings(config: &PgBouncerConfig) -> crate::error::Result<BTreeMap<(String, String), String>> {
+    let mut config = config.clone();
+    config.canonicalize();
+    let document = Document::parse(&config.expr()?)?;
+
+    Ok(document
+        .entries()
+        .into_iter()
+        .map(|(section, key, value)| ((section.to_string(), key.to_string()), value.to_string()))
+        .collect())
+}
 
 /// Computes a structured diff between two serializable values.
 ///
@@ -185,14 +1114,14 @@ fn diff_value(old: &serde_json::Value, new: &serde_json::Value) -> Diff {
         (serde_json::Value::Object(old), serde_json::Value::Object(new)) => {
             let mut keys: BTreeMap<String, ()> = BTreeMap::new();
             for k in old.keys() {
-                if k == "rust_struct_pg_bouncer_config_internal" {
+                if k == DIFFABLE_TAG {
                     continue;
                 }
 
                 keys.insert(k.clone(), ());
             }
             for k in new.keys() {
-                if k == "rust_struct_pg_bouncer_config_internal" {
+                if k == DIFFABLE_TAG {
                     continue;
                 }
 
@@ -387,4 +1316,150 @@ mod tests {
         let d = compute_diff(&v1, &v2).expect("ok");
         assert_eq!(d, Diff::Same { value: "".to_string() });
     }
+
+    #[test]
+    fn has_changes_ignores_same_and_unevaluated_fields() {
+        let same = serde_json::json!({"a": {"x": 1}, "password": "<hidden>"});
+        let d = compute_diff(&same, &same).expect("ok");
+        assert!(matches!(d, Diff::Object { .. }));
+        assert!(!d.has_changes());
+
+        let changed = serde_json::json!({"a": {"x": 2}, "password": "<hidden>"});
+        assert!(compute_diff(&same, &changed).expect("ok").has_changes());
+    }
+
+    #[test]
+    fn masked_hides_secret_fields_but_keeps_their_kind() {
+        let old = serde_json::json!({"auth_type": "md5", "ssh_key_string": "old", "monkey": 1, "list": [{"password": "a"}]});
+        let new = serde_json::json!({"auth_type": "md5", "ssh_key_string": "new", "monkey": 2, "list": [{"password": "b"}]});
+        let diff = compute_diff(&old, &new).expect("ok");
+
+        assert_eq!(diff.clone().masked(&DiffOptions::new()), diff);
+
+        let mut options = DiffOptions::new();
+        options.set_mask_secrets(true);
+        let masked = diff.masked(&options);
+        let Diff::Object { fields } = &masked else { panic!("expected object diff, got {:?}", masked) };
+        let mask = "\"***\"".to_string();
+        assert_eq!(fields.get("auth_type"), Some(&Diff::Same { value: mask.clone() }));
+        assert_eq!(fields.get("ssh_key_string"), Some(&Diff::Changed { old: mask.clone(), new: mask.clone() }));
+        assert_eq!(fields.get("monkey"), Some(&Diff::Changed { old: "1".to_string(), new: "2".to_string() }));
+        assert!(!serde_json::to_string(&masked).unwrap().contains("\\\"b\\\""));
+    }
+
+    #[test]
+    fn filtered_keeps_included_paths_and_drops_excluded_ones() {
+        let old = serde_json::json!({"pgbouncer": {"listen_port": 6432, "auth_type": "md5"}, "databases": [{"host": "a"}]});
+        let new = serde_json::json!({"pgbouncer": {"listen_port": 6433, "auth_type": "scram-sha-256"}, "databases": [{"host": "b"}]});
+        let diff = compute_diff(&old, &new).expect("ok");
+
+        let mut options = DiffOptions::new();
+        options.set_include(&["pgbouncer"]).set_exclude(&["*.auth_*"]);
+        let Diff::Object { fields } = diff.clone().filtered(&options) else { panic!("expected object diff") };
+        assert_eq!(fields.keys().collect::<Vec<_>>(), vec!["pgbouncer"]);
+        let Some(Diff::Object { fields }) = fields.get("pgbouncer") else { panic!("expected nested object diff") };
+        assert_eq!(fields.keys().collect::<Vec<_>>(), vec!["listen_port"]);
+
+        let mut options = DiffOptions::new();
+        options.set_include(&["databases.*.host"]);
+        let only_hosts = diff.clone().filtered(&options);
+        assert!(matches!(only_hosts, Diff::Object { ref fields } if fields.len() == 1 && fields.contains_key("databases")));
+        let mut options = DiffOptions::new();
+        options.set_exclude(&["*"]);
+        assert_eq!(diff.filtered(&options), Diff::Same { value: "".to_string() });
+
+        assert!(glob_match("a*c*", "abbcd") && !glob_match("a*c", "abcd") && glob_match("*", ""));
+    }
+
+    #[test]
+    fn apply_adds_and_removes_databases_and_rejects_drift() {
+        use crate::builder::PgBouncerConfigBuilder;
+        use crate::pgbouncer_config::databases_setting::{Database, DatabasesSetting};
+        use crate::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+
+        let config = |hosts: &[&str]| {
+            let mut setting = DatabasesSetting::new();
+            for host in hosts {
+                setting.add_database(Database::new(host, 5432, "app", "secret", Some(&["app"])));
+            }
+            PgBouncerConfigBuilder::new(PgBouncerSetting::default(), setting).unwrap().build()
+        };
+        let old = config(&["10.0.0.1", "10.0.0.2"]);
+        let new = config(&["10.0.0.3"]);
+
+        let patch = Patch::from_json(&Patch::new(compute_diff_pg_config(&old, &new).unwrap()).to_json().unwrap()).unwrap();
+        let mut deployed = old.clone();
+        patch.apply(&mut deployed).unwrap();
+        assert_eq!(deployed.expr().unwrap(), new.expr().unwrap());
+
+        let mut drifted = config(&["10.0.0.9", "10.0.0.2"]);
+        let before = drifted.expr().unwrap();
+        assert!(patch.apply(&mut drifted).is_err());
+        assert_eq!(drifted.expr().unwrap(), before);
+
+        let mut options = DiffOptions::new();
+        options.set_mask_secrets(true).set_secret_fields(&["host"]);
+        let masked = compute_diff_pg_config(&old, &new).unwrap().masked(&options);
+        assert!(masked.apply(&mut old.clone()).is_err());
+    }
+
+    #[test]
+    fn ini_diff_lists_parameters_of_added_databases_and_masks_secrets() {
+        use crate::builder::PgBouncerConfigBuilder;
+        use crate::pgbouncer_config::databases_setting::{Database, DatabasesSetting};
+        use crate::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+
+        let config = |databases: &[(&str, &str)]| {
+            let mut setting = DatabasesSetting::new();
+            for (name, password) in databases {
+                let mut database = Database::new("10.0.0.10", 5432, name, password, Some(&[name]));
+                database.set_is_output_credentials_to_config(true);
+                setting.add_database(database);
+            }
+            PgBouncerConfigBuilder::new(PgBouncerSetting::default(), setting).unwrap().build()
+        };
+        let old = config(&[("app", "one")]);
+        let new = config(&[("app", "two"), ("report", "three")]);
+
+        let lines = compute_ini_diff(&old, &new).unwrap().iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert!(lines.contains(&"[databases] app.password: one → two".to_string()));
+        assert!(lines.contains(&"[databases] report.host: (unset) → 10.0.0.10".to_string()));
+        assert!(compute_ini_diff(&old, &old).unwrap().is_empty());
+
+        let mut options = DiffOptions::new();
+        options.set_mask_secrets(true);
+        let lines = compute_ini_diff_with_options(&old, &new, &options).unwrap().iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert!(lines.contains(&"[databases] app.password: *** → ***".to_string()));
+        assert!(lines.contains(&"[databases] report.password: (unset) → ***".to_string()));
+
+        let mut options = DiffOptions::new();
+        options.set_include(&["databases.report.*"]).set_exclude(&["*.port"]);
+        let keys = compute_ini_diff_with_options(&old, &new, &options).unwrap().into_iter().map(|change| change.key).collect::<Vec<_>>();
+        assert_eq!(keys, vec!["report.dbname", "report.host", "report.password", "report.user"]);
+    }
+
+    #[test]
+    fn connection_caps_are_diffed_and_patched() {
+        use crate::builder::PgBouncerConfigBuilder;
+        use crate::pgbouncer_config::databases_setting::DatabasesSetting;
+        use crate::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+
+        let config = |max_db_connections: Option<u16>, max_user_connections: Option<u16>| {
+            let mut setting = PgBouncerSetting::default();
+            setting.set_max_db_connections(max_db_connections).set_max_user_connections(max_user_connections);
+            PgBouncerConfigBuilder::new(setting, DatabasesSetting::new()).unwrap().build()
+        };
+        let old = config(Some(50), None);
+        let new = config(Some(80), Some(40));
+
+        let lines = compute_ini_diff(&old, &new).unwrap().iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(lines, vec![
+            "[pgbouncer] max_db_connections: 50 → 80",
+            "[pgbouncer] max_user_connections: (unset) → 40",
+        ]);
+
+        let mut deployed = old.clone();
+        Patch::new(compute_diff_pg_config(&old, &new).unwrap()).apply(&mut deployed).unwrap();
+        assert_eq!(deployed.expr().unwrap(), new.expr().unwrap());
+    }
 }
@@ -0,0 +1,167 @@
+//! Reading definition files from git history.
+//!
+//! Enabled with the `git` feature. These helpers look up the repository that
+//! contains a file, read the file's content at a revision (anything
+//! `git rev-parse` understands, e.g. `HEAD~1` or a branch name) and diff it
+//! against the working copy.
+
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use git2::Repository;
+use crate::error::PgBouncerError;
+use crate::io::ConfigFileFormat;
+use crate::io::read::Reader;
+use crate::pgbouncer_config::PgBouncerConfig;
+use crate::utils::diff::{compute_diff_pg_config, Diff};
+
+/// Reads a file's content at a git revision.
+///
+/// # Parameters
+/// - path: Path of the file in the working tree; the repository is discovered from it.
+/// - revision: Revision to read, e.g. `HEAD`, `HEAD~1` or `main`.
+///
+/// # Returns
+/// The file content at `revision`.
+///
+/// # Errors
+/// Returns an error if no repository contains `path`, the revision cannot be
+/// resolved, the file does not exist at that revision or it is not valid UTF-8.
+///
+/// # Examples
+/// ```rust,no_run
+/// use std::path::Path;
+/// use pgbouncer_config::utils::git::read_file_at_revision;
+///
+/// let previous = read_file_at_revision(Path::new("generated/pgbouncer_definition.toml"), "HEAD~1").unwrap();
+/// ```
+pub fn read_file_at_revision(path: &Path, revision: &str) -> crate::error::Result<String> {
+    let absolute = std::path::absolute(path)?;
+    let start = absolute.parent().unwrap_or(Path::new("."));
+    let repo = Repository::discover(start)?;
+    let relative = relative_to_workdir(&repo, &absolute)?;
+
+    let tree = repo.revparse_single(revision)?.peel_to_tree()?;
+    let entry = tree.get_path(&relative).map_err(|_| {
+        PgBouncerError::PgBouncer(format!("{} does not exist at revision {}", relative.display(), revision))
+    })?;
+    let blob = entry.to_object(&repo)?.peel_to_blob()?;
+
+    String::from_utf8(blob.content().to_vec())
+        .map_err(|_| PgBouncerError::PgBouncer(format!("{} is not valid UTF-8 at revision {}", relative.display(), revision)))
+}
+
+/// Reads a definition file at a git revision.
+///
+/// # Parameters
+/// - path: Path of the definition file in the working tree.
+/// - revision: Revision to read.
+/// - format: Serialization format of the definition file.
+///
+/// # Returns
+/// The configuration defined at `revision`.
+///
+/// # Errors
+/// Returns an error if the file cannot be read at `revision` or cannot be deserialized.
+pub fn read_config_at_revision(
+    path: &Path,
+    revision: &str,
+    format: ConfigFileFormat,
+) -> crate::error::Result<PgBouncerConfig> {
+    let text = read_file_at_revision(path, revision)?;
+    Reader::new(Cursor::new(text.as_bytes())).read_config(format)
+}
+
+/// Diffs the definition at a git revision against the working copy.
+///
+/// # Parameters
+/// - path: Path of the definition file in the working tree.
+/// - revision: Revision used as the old side of the diff.
+/// - format: Serialization format of the definition file.
+///
+/// # Returns
+/// The diff from the definition at `revision` to the working copy.
+///
+/// # Errors
+/// Returns an error if either side cannot be read or deserialized.
+pub fn compute_diff_against_revision(
+    path: &Path,
+    revision: &str,
+    format: ConfigFileFormat,
+) -> crate::error::Result<Diff> {
+    let text = std::fs::read_to_string(path)?;
+    let current = Reader::new(Cursor::new(text.as_bytes())).read_config(format)?;
+    let committed = read_config_at_revision(path, revision, format)?;
+
+    compute_diff_pg_config(&committed, &current)
+}
+
+fn relative_to_workdir(repo: &Repository, absolute: &Path) -> crate::error::Result<PathBuf> {
+    let workdir = repo
+        .workdir()
+        .ok_or(PgBouncerError::PgBouncer("bare repositories are not supported".to_string()))?;
+    // Canonicalize both sides so symlinked temp dirs or `..` segments still match.
+    let workdir = workdir.canonicalize()?;
+    let parent = absolute.parent().unwrap_or(Path::new(".")).canonicalize()?;
+    let file_name = absolute
+        .file_name()
+        .ok_or(PgBouncerError::PgBouncer(format!("{} is not a file path", absolute.display())))?;
+
+    parent
+        .join(file_name)
+        .strip_prefix(&workdir)
+        .map(Path::to_path_buf)
+        .map_err(|_| PgBouncerError::PgBouncer(format!("{} is outside of the repository", absolute.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+
+    fn commit_file(repo: &Repository, name: &str, content: &str, message: &str) {
+        let workdir = repo.workdir().unwrap();
+        std::fs::write(workdir.join(name), content).unwrap();
+
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(name)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("test", "test@example.com").unwrap();
+        let parents = repo
+            .head()
+            .ok()
+            .and_then(|head| head.peel_to_commit().ok())
+            .into_iter()
+            .collect::<Vec<_>>();
+        let parents = parents.iter().collect::<Vec<_>>();
+        repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents).unwrap();
+    }
+
+    fn temp_repo(name: &str) -> Repository {
+        let dir = std::env::temp_dir().join(format!("pgbouncer-config-git-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Repository::init(&dir).unwrap()
+    }
+
+    #[test]
+    fn read_file_at_revision_returns_older_content() {
+        let repo = temp_repo("read");
+        commit_file(&repo, "def.toml", "first\n", "first");
+        commit_file(&repo, "def.toml", "second\n", "second");
+        let path = repo.workdir().unwrap().join("def.toml");
+
+        assert_eq!(read_file_at_revision(&path, "HEAD").unwrap(), "second\n");
+        assert_eq!(read_file_at_revision(&path, "HEAD~1").unwrap(), "first\n");
+    }
+
+    #[test]
+    fn read_file_at_revision_fails_for_untracked_file() {
+        let repo = temp_repo("missing");
+        commit_file(&repo, "def.toml", "first\n", "first");
+        let path = repo.workdir().unwrap().join("other.toml");
+        std::fs::write(&path, "x").unwrap();
+
+        assert!(read_file_at_revision(&path, "HEAD").is_err());
+    }
+}
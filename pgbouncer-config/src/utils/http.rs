@@ -0,0 +1,20 @@
+//! HTTP client shared by the Vault backend and URL readers.
+
+use ureq::Agent;
+use ureq::tls::{Certificate, RootCerts, TlsConfig};
+
+/// Builds an agent trusting the platform's root certificates plus `extra_certs`.
+pub(crate) fn build_agent(extra_certs: Vec<Certificate<'static>>) -> Agent {
+    let mut certs: Vec<Certificate<'static>> = rustls_native_certs::load_native_certs()
+        .certs
+        .iter()
+        .map(|cert| Certificate::from_der(cert.as_ref()).to_owned())
+        .collect();
+    certs.extend(extra_certs);
+
+    let tls = TlsConfig::builder()
+        .root_certs(RootCerts::new_with_certs(&certs))
+        .build();
+
+    Agent::config_builder().tls_config(tls).build().into()
+}
@@ -2,4 +2,19 @@
 pub mod parser;
 #[cfg(feature = "diff")]
 pub mod diff;
-pub mod ssh_tunnel;
\ No newline at end of file
+pub mod ssh_tunnel;
+pub(crate) mod ssh_config;
+pub mod proxy_tunnel;
+pub mod password;
+pub mod retry;
+pub mod secret;
+#[cfg(feature = "vault")]
+pub mod vault;
+#[cfg(any(feature = "vault", feature = "url"))]
+pub(crate) mod http;
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+#[cfg(feature = "blocking")]
+pub(crate) mod blocking;
+#[cfg(feature = "git")]
+pub mod git;
@@ -1,4 +1,12 @@
+use std::path::{Path, PathBuf};
 use regex::Regex;
+use crate::error::PgBouncerError;
+
+/// Directive that inlines another file into pgbouncer.ini.
+pub const INCLUDE_DIRECTIVE: &str = "%include";
+
+/// Maximum nesting depth of `%include` directives, matching PgBouncer's limit.
+pub const MAX_INCLUDE_DEPTH: usize = 10;
 
 pub trait ParserIniFromStr {
     type Error;
@@ -26,6 +34,89 @@ pub(crate) fn is_comment(value: &str) -> bool {
     value.starts_with("#") || value.starts_with(";")
 }
 
+/// Expands `%include` directives in pgbouncer.ini text.
+///
+/// Each `%include <path>` line is replaced with the content of the referenced
+/// file, recursively. Relative paths are resolved against `base_dir` for the
+/// top-level text and against the directory of the including file for nested
+/// includes.
+///
+/// # Parameters
+/// - value: pgbouncer.ini text that may contain `%include` directives.
+/// - base_dir: Directory used to resolve relative paths in `value`.
+///
+/// # Returns
+/// The text with every include expanded.
+///
+/// # Errors
+/// Returns an error if an included file cannot be read, a file includes
+/// itself directly or indirectly, or the nesting exceeds [`MAX_INCLUDE_DEPTH`].
+///
+/// # Examples
+/// ```rust
+/// use std::path::Path;
+/// use pgbouncer_config::utils::parser::resolve_includes;
+///
+/// let text = resolve_includes("[pgbouncer]\nlisten_port = 6432\n", Path::new(".")).unwrap();
+/// assert_eq!(text, "[pgbouncer]\nlisten_port = 6432\n");
+/// ```
+pub fn resolve_includes(value: &str, base_dir: &Path) -> crate::error::Result<String> {
+    resolve_includes_inner(value, base_dir, &mut Vec::new())
+}
+
+fn resolve_includes_inner(value: &str, base_dir: &Path, stack: &mut Vec<PathBuf>) -> crate::error::Result<String> {
+    let mut text = String::with_capacity(value.len());
+    for line in value.split_inclusive('\n') {
+        let Some(include) = include_target(line) else {
+            text.push_str(line);
+            continue;
+        };
+
+        if stack.len() >= MAX_INCLUDE_DEPTH {
+            return Err(PgBouncerError::PgBouncer(format!(
+                "%include nesting exceeds {} levels at {}", MAX_INCLUDE_DEPTH, include
+            )));
+        }
+
+        let path = base_dir.join(include);
+        let canonical = path.canonicalize().map_err(|e| {
+            PgBouncerError::PgBouncer(format!("failed to include {}: {}", path.display(), e))
+        })?;
+        if stack.contains(&canonical) {
+            let chain = stack
+                .iter()
+                .chain(std::iter::once(&canonical))
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(PgBouncerError::PgBouncer(format!("%include cycle detected: {}", chain)));
+        }
+
+        let content = std::fs::read_to_string(&canonical)?;
+        let include_dir = canonical.parent().unwrap_or(Path::new(".")).to_path_buf();
+        stack.push(canonical);
+        let expanded = resolve_includes_inner(&content, &include_dir, stack)?;
+        stack.pop();
+
+        text.push_str(&expanded);
+        if !expanded.ends_with('\n') {
+            text.push('\n');
+        }
+    }
+
+    Ok(text)
+}
+
+fn include_target(line: &str) -> Option<&str> {
+    let rest = line.trim().strip_prefix(INCLUDE_DIRECTIVE)?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let target = rest.trim();
+
+    (!target.is_empty()).then_some(target)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -49,6 +140,21 @@ mod tests {
         assert_eq!(v2, "'quoted value'");
     }
 
+    #[test]
+    fn test_resolve_includes_expands_nested_files_and_detects_cycles() {
+        let dir = std::env::temp_dir().join(format!("pgbouncer-config-include-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("conf.d")).unwrap();
+        std::fs::write(dir.join("conf.d/databases.ini"), "[databases]\n%include app.ini\n").unwrap();
+        std::fs::write(dir.join("conf.d/app.ini"), "app = dbname=app host=127.0.0.1 port=5432").unwrap();
+
+        let text = resolve_includes("[pgbouncer]\n%include conf.d/databases.ini\n", &dir).unwrap();
+        assert_eq!(text, "[pgbouncer]\n[databases]\napp = dbname=app host=127.0.0.1 port=5432\n");
+
+        std::fs::write(dir.join("conf.d/app.ini"), "%include databases.ini\n").unwrap();
+        let err = resolve_includes("%include conf.d/databases.ini\n", &dir).unwrap_err();
+        assert!(err.to_string().contains("cycle"));
+    }
+
     #[test]
     fn test_parse_key_value_format() {
         let (key, value) = parse_key_value("no-braces = value").unwrap();
@@ -0,0 +1,211 @@
+//! Password verifiers for the entries of an auth file (userlist.txt).
+//!
+//! PgBouncer accepts plain-text passwords, `md5` hashes and SCRAM-SHA-256
+//! verifiers in its auth file. The helpers here produce the same verifiers as
+//! PostgreSQL so plain-text passwords do not have to be stored on disk.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use hmac::{Hmac, Mac};
+use md5::{Digest, Md5};
+use rand::RngCore;
+use sha2::Sha256;
+use crate::pgbouncer_config::pgbouncer_setting::AuthType;
+
+/// Iteration count used by PostgreSQL for new SCRAM-SHA-256 verifiers.
+pub const SCRAM_DEFAULT_ITERATIONS: u32 = 4096;
+/// Salt length in bytes used by PostgreSQL for new SCRAM-SHA-256 verifiers.
+pub const SCRAM_DEFAULT_SALT_LENGTH: usize = 16;
+
+const MD5_PREFIX: &str = "md5";
+const SCRAM_PREFIX: &str = "SCRAM-SHA-256$";
+
+/// Builds the `md5` verifier of a user's password.
+///
+/// # Parameters
+/// - user: User name, used as the salt.
+/// - password: Plain-text password.
+///
+/// # Returns
+/// `md5` followed by the hex MD5 digest of `password || user`.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::utils::password::md5_verifier;
+/// assert_eq!(md5_verifier("postgres", "postgres"), "md53175bce1d3201d16594cebf9d7eb3f9d");
+/// ```
+pub fn md5_verifier(user: &str, password: &str) -> String {
+    let digest = Md5::new()
+        .chain_update(password.as_bytes())
+        .chain_update(user.as_bytes())
+        .finalize();
+
+    format!("{}{}", MD5_PREFIX, digest.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+}
+
+/// Builds a SCRAM-SHA-256 verifier with a random salt and the default iteration count.
+///
+/// # Parameters
+/// - password: Plain-text password.
+///
+/// # Returns
+/// A verifier in the form `SCRAM-SHA-256$<iterations>:<salt>$<StoredKey>:<ServerKey>`.
+///
+/// # Notes
+/// - The password is used as is; SASLprep normalization is not applied, which
+///   matches PostgreSQL for ASCII passwords.
+pub fn scram_sha256_verifier(password: &str) -> String {
+    let mut salt = [0u8; SCRAM_DEFAULT_SALT_LENGTH];
+    rand::thread_rng().fill_bytes(&mut salt);
+
+    scram_sha256_verifier_with(password, &salt, SCRAM_DEFAULT_ITERATIONS)
+}
+
+/// Builds a SCRAM-SHA-256 verifier from an explicit salt and iteration count.
+///
+/// # Parameters
+/// - password: Plain-text password.
+/// - salt: Salt bytes.
+/// - iterations: PBKDF2 iteration count.
+///
+/// # Returns
+/// A verifier in the form `SCRAM-SHA-256$<iterations>:<salt>$<StoredKey>:<ServerKey>`.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::utils::password::scram_sha256_verifier_with;
+/// let verifier = scram_sha256_verifier_with("secret", b"0123456789abcdef", 4096);
+/// assert!(verifier.starts_with("SCRAM-SHA-256$4096:MDEyMzQ1Njc4OWFiY2RlZg==$"));
+/// ```
+pub fn scram_sha256_verifier_with(password: &str, salt: &[u8], iterations: u32) -> String {
+    let mut salted_password = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, iterations, &mut salted_password);
+
+    let client_key = hmac_sha256(&salted_password, b"Client Key");
+    let stored_key = Sha256::digest(client_key);
+    let server_key = hmac_sha256(&salted_password, b"Server Key");
+
+    format!(
+        "{}{}:{}${}:{}",
+        SCRAM_PREFIX,
+        iterations,
+        STANDARD.encode(salt),
+        STANDARD.encode(stored_key),
+        STANDARD.encode(server_key),
+    )
+}
+
+/// Returns true when `password` already is an `md5` or SCRAM-SHA-256 verifier.
+///
+/// # Parameters
+/// - password: Value of an auth file entry.
+pub fn is_verifier(password: &str) -> bool {
+    let is_md5 = password.len() == MD5_PREFIX.len() + 32
+        && password.starts_with(MD5_PREFIX)
+        && password[MD5_PREFIX.len()..].chars().all(|c| c.is_ascii_hexdigit());
+
+    is_md5 || password.starts_with(SCRAM_PREFIX)
+}
+
+/// Checks whether a stored auth file value corresponds to a plain-text password.
+///
+/// `md5` and SCRAM-SHA-256 verifiers are recomputed from `password` (reusing
+/// the salt and iteration count of the stored SCRAM verifier); any other value
+/// is compared as plain text.
+///
+/// # Parameters
+/// - stored: Value stored in the auth file.
+/// - user: User name.
+/// - password: Plain-text password to check.
+///
+/// # Returns
+/// True when `stored` was derived from `password`.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::utils::password::{scram_sha256_verifier, verifier_matches};
+/// let verifier = scram_sha256_verifier("secret");
+/// assert!(verifier_matches(&verifier, "app", "secret"));
+/// assert!(!verifier_matches(&verifier, "app", "other"));
+/// ```
+pub fn verifier_matches(stored: &str, user: &str, password: &str) -> bool {
+    if let Some(scram) = stored.strip_prefix(SCRAM_PREFIX) {
+        let salt_and_iterations = scram.split('$').next().and_then(|params| params.split_once(':'));
+        let Some((iterations, salt)) = salt_and_iterations else {
+            return false;
+        };
+        let (Ok(iterations), Ok(salt)) = (iterations.parse::<u32>(), STANDARD.decode(salt)) else {
+            return false;
+        };
+
+        return scram_sha256_verifier_with(password, &salt, iterations) == stored;
+    }
+    if is_verifier(stored) {
+        return md5_verifier(user, password) == stored;
+    }
+
+    stored == password
+}
+
+/// Builds the verifier suited to an authentication method.
+///
+/// `md5` produces an `md5` verifier and `scram-sha-256` a SCRAM-SHA-256
+/// verifier. Other methods, and passwords that already are verifiers, are
+/// returned unchanged because PgBouncer needs them as stored.
+///
+/// # Parameters
+/// - auth_type: Authentication method the auth file is used with.
+/// - user: User name.
+/// - password: Plain-text password or an existing verifier.
+///
+/// # Returns
+/// The value to store in the auth file.
+///
+/// # Examples
+/// ```rust
+/// use pgbouncer_config::pgbouncer_config::pgbouncer_setting::AuthType;
+/// use pgbouncer_config::utils::password::password_verifier;
+/// assert!(password_verifier(AuthType::ScramSha256, "app", "secret").starts_with("SCRAM-SHA-256$"));
+/// assert_eq!(password_verifier(AuthType::Plain, "app", "secret"), "secret");
+/// ```
+pub fn password_verifier(auth_type: AuthType, user: &str, password: &str) -> String {
+    if is_verifier(password) {
+        return password.to_string();
+    }
+
+    match auth_type {
+        AuthType::Md5 => md5_verifier(user, password),
+        AuthType::ScramSha256 => scram_sha256_verifier(password),
+        _ => password.to_string(),
+    }
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    // SAFETY: HMAC accepts keys of any length
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scram_verifier_matches_reference() {
+        // Reference keys computed independently with Python's hashlib and hmac.
+        let salt = STANDARD.decode("c2FsdHNhbHRzYWx0c2FsdA==").unwrap();
+        assert_eq!(
+            scram_sha256_verifier_with("postgres", &salt, 4096),
+            "SCRAM-SHA-256$4096:c2FsdHNhbHRzYWx0c2FsdA==$IVvP/XI/7CgH+AJuZtX2Qde/91FRiYJj4670fS22WIE=:s35xvmJ89N/i+qC5gnE2/a3VvP/DFyOXfZFoUAG51Ek="
+        );
+    }
+
+    #[test]
+    fn existing_verifiers_are_kept() {
+        let md5 = md5_verifier("app", "secret");
+        assert!(is_verifier(&md5));
+        assert_eq!(password_verifier(AuthType::ScramSha256, "app", &md5), md5);
+        assert!(!is_verifier("md5-but-not-a-hash"));
+    }
+}
@@ -0,0 +1,315 @@
+use std::net::SocketAddr;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use crate::error::PgBouncerError;
+use crate::pgbouncer_config::databases_setting::{ProxyKind, ProxyTunnelBuilder};
+
+const SOCKS_VERSION: u8 = 0x05;
+const SOCKS_NO_AUTH: u8 = 0x00;
+const SOCKS_USER_PASSWORD: u8 = 0x02;
+const SOCKS_CONNECT: u8 = 0x01;
+const SOCKS_ATYP_IPV4: u8 = 0x01;
+const SOCKS_ATYP_DOMAIN: u8 = 0x03;
+const SOCKS_ATYP_IPV6: u8 = 0x04;
+
+/// Forwards local connections to a backend through a SOCKS5 or HTTP CONNECT proxy.
+#[derive(Clone)]
+pub struct ProxyTunnel {
+    kind: ProxyKind,
+    proxy_host: String,
+    proxy_port: u16,
+    credentials: Option<(String, String)>,
+    local_port: u16,
+    target_host: Option<String>,
+    target_port: u16,
+}
+
+pub struct ProxyTunnelHandler {
+    shutdown_tx: watch::Sender<()>,
+    local_addr: SocketAddr,
+}
+
+impl ProxyTunnelHandler {
+    pub async fn shutdown(self) {
+        drop(self.shutdown_tx);
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl ProxyTunnel {
+    pub fn set_target(&mut self, host: &str, port: u16) -> &mut Self {
+        self.target_host = Some(host.to_string());
+        self.target_port = port;
+        self
+    }
+
+    pub async fn run(&self) -> crate::error::Result<ProxyTunnelHandler> {
+        let (shutdown_tx, mut shutdown_rx) = watch::channel(());
+
+        let Some(target_host) = self.target_host.clone() else {
+            return Err(PgBouncerError::Connection("Postgres hostname is required but isn't given".to_string()));
+        };
+
+        let listener = TcpListener::bind(("127.0.0.1", self.local_port)).await?;
+        let local_addr = listener.local_addr()?;
+
+        let tunnel = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    accepted = listener.accept() => {
+                        match accepted {
+                            Ok((socket, addr)) => {
+                                let tunnel = tunnel.clone();
+                                let target_host = target_host.clone();
+                                tokio::spawn(async move {
+                                    if let Err(e) = tunnel.handle_connection(socket, addr, &target_host).await {
+                                        log::error!("Error handling connection: {}", e);
+                                    }
+                                });
+                            },
+                            Err(e) => {
+                                log::error!("Error accepting connection: {}", e);
+                            }
+                        }
+                    },
+                    _ = shutdown_rx.changed() => {
+                        log::info!("Shutting down");
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(ProxyTunnelHandler { shutdown_tx, local_addr })
+    }
+
+    async fn handle_connection(
+        &self,
+        mut local_socket: TcpStream,
+        client_addr: SocketAddr,
+        target_host: &str,
+    ) -> crate::error::Result<()> {
+        let mut proxy_stream = TcpStream::connect((self.proxy_host.as_str(), self.proxy_port)).await?;
+        match self.kind {
+            ProxyKind::Socks5 => self.socks5_connect(&mut proxy_stream, target_host).await?,
+            ProxyKind::Http => self.http_connect(&mut proxy_stream, target_host).await?,
+        }
+
+        match tokio::io::copy_bidirectional(&mut local_socket, &mut proxy_stream).await {
+            Ok((up, down)) => {
+                log::debug!(
+                    "Connection from {} closed. Bytes uploaded: {}, downloaded: {}",
+                    client_addr,
+                    up,
+                    down,
+                );
+            },
+            Err(e) => {
+                return Err(PgBouncerError::Connection(format!("Error reading bidirectional: {}", e)));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Negotiates a SOCKS5 `CONNECT` to the target (RFC 1928), authenticating
+    /// with username/password (RFC 1929) when credentials are set.
+    async fn socks5_connect(&self, stream: &mut TcpStream, target_host: &str) -> crate::error::Result<()> {
+        let method = if self.credentials.is_some() { SOCKS_USER_PASSWORD } else { SOCKS_NO_AUTH };
+        stream.write_all(&[SOCKS_VERSION, 1, method]).await?;
+
+        let mut reply = [0u8; 2];
+        stream.read_exact(&mut reply).await?;
+        if reply[0] != SOCKS_VERSION {
+            return Err(PgBouncerError::Connection(format!("Unexpected SOCKS version {} from the proxy", reply[0])));
+        }
+        if reply[1] != method {
+            return Err(PgBouncerError::Connection("The proxy accepts none of the offered SOCKS5 authentication methods".to_string()));
+        }
+
+        if let Some((user, password)) = &self.credentials {
+            if user.len() > u8::MAX as usize || password.len() > u8::MAX as usize {
+                return Err(PgBouncerError::Connection("SOCKS5 username and password must be at most 255 bytes".to_string()));
+            }
+            let mut request = vec![0x01, user.len() as u8];
+            request.extend_from_slice(user.as_bytes());
+            request.push(password.len() as u8);
+            request.extend_from_slice(password.as_bytes());
+            stream.write_all(&request).await?;
+
+            let mut reply = [0u8; 2];
+            stream.read_exact(&mut reply).await?;
+            if reply[1] != 0x00 {
+                return Err(PgBouncerError::Connection(format!("SOCKS5 authentication failed for user {}", user)));
+            }
+        }
+
+        let mut request = vec![SOCKS_VERSION, SOCKS_CONNECT, 0x00];
+        match target_host.parse::<std::net::IpAddr>() {
+            Ok(std::net::IpAddr::V4(ip)) => {
+                request.push(SOCKS_ATYP_IPV4);
+                request.extend_from_slice(&ip.octets());
+            },
+            Ok(std::net::IpAddr::V6(ip)) => {
+                request.push(SOCKS_ATYP_IPV6);
+                request.extend_from_slice(&ip.octets());
+            },
+            Err(_) => {
+                if target_host.len() > u8::MAX as usize {
+                    return Err(PgBouncerError::Connection(format!("Hostname {} is too long for SOCKS5", target_host)));
+                }
+                request.push(SOCKS_ATYP_DOMAIN);
+                request.push(target_host.len() as u8);
+                request.extend_from_slice(target_host.as_bytes());
+            },
+        }
+        request.extend_from_slice(&self.target_port.to_be_bytes());
+        stream.write_all(&request).await?;
+
+        let mut header = [0u8; 4];
+        stream.read_exact(&mut header).await?;
+        if header[1] != 0x00 {
+            return Err(PgBouncerError::Connection(format!(
+                "The SOCKS5 proxy refused to connect to {}:{} (reply {})", target_host, self.target_port, header[1]
+            )));
+        }
+        // Skip the bound address and port the proxy reports.
+        let address_len = match header[3] {
+            SOCKS_ATYP_IPV4 => 4,
+            SOCKS_ATYP_IPV6 => 16,
+            SOCKS_ATYP_DOMAIN => stream.read_u8().await? as usize,
+            atyp => return Err(PgBouncerError::Connection(format!("Unknown SOCKS5 address type {}", atyp))),
+        };
+        let mut bound = vec![0u8; address_len + 2];
+        stream.read_exact(&mut bound).await?;
+
+        Ok(())
+    }
+
+    /// Opens an HTTP `CONNECT` tunnel to the target.
+    async fn http_connect(&self, stream: &mut TcpStream, target_host: &str) -> crate::error::Result<()> {
+        let authority = if target_host.contains(':') {
+            format!("[{}]:{}", target_host, self.target_port)
+        } else {
+            format!("{}:{}", target_host, self.target_port)
+        };
+        let mut request = format!("CONNECT {} HTTP/1.1\r\nHost: {}\r\n", authority, authority);
+        if let Some((user, password)) = &self.credentials {
+            let token = STANDARD.encode(format!("{}:{}", user, password));
+            request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", token));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes()).await?;
+
+        // Read the response head byte by byte so nothing the backend sends
+        // after it is consumed here.
+        let mut reader = BufReader::with_capacity(1, stream);
+        let mut status_line = String::new();
+        reader.read_line(&mut status_line).await?;
+        let status = status_line.split_whitespace().nth(1).unwrap_or_default();
+        if status != "200" {
+            return Err(PgBouncerError::Connection(format!(
+                "The HTTP proxy refused to connect to {}: {}", authority, status_line.trim_end()
+            )));
+        }
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await? == 0 || line == "\r\n" || line == "\n" {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl From<ProxyTunnelBuilder> for ProxyTunnel {
+    fn from(value: ProxyTunnelBuilder) -> Self {
+        let credentials = value.user.map(|user| (user, value.password.unwrap_or_default()));
+
+        Self {
+            kind: value.kind,
+            proxy_host: value.host,
+            proxy_port: value.port,
+            credentials,
+            // If the port is 0 in TcpListener means auto get port.
+            local_port: value.local_port.unwrap_or(0),
+            target_host: None,
+            target_port: 5432,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn socks5_tunnel_forwards_to_the_requested_target() {
+        let proxy = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let proxy_port = proxy.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = proxy.accept().await.unwrap();
+            let mut greeting = [0u8; 3];
+            socket.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [SOCKS_VERSION, 1, SOCKS_USER_PASSWORD]);
+            socket.write_all(&[SOCKS_VERSION, SOCKS_USER_PASSWORD]).await.unwrap();
+
+            let mut auth = [0u8; 1 + 1 + 5 + 1 + 6];
+            socket.read_exact(&mut auth).await.unwrap();
+            assert_eq!(&auth[2..7], b"alice");
+            assert_eq!(&auth[8..], b"secret");
+            socket.write_all(&[0x01, 0x00]).await.unwrap();
+
+            let mut request = [0u8; 4 + 1 + 7 + 2];
+            socket.read_exact(&mut request).await.unwrap();
+            assert_eq!(request[3], SOCKS_ATYP_DOMAIN);
+            assert_eq!(&request[5..12], b"db.host");
+            assert_eq!(u16::from_be_bytes([request[12], request[13]]), 5433);
+            socket.write_all(&[SOCKS_VERSION, 0x00, 0x00, SOCKS_ATYP_IPV4, 0, 0, 0, 0, 0, 0]).await.unwrap();
+
+            socket.write_all(b"pong").await.unwrap();
+        });
+
+        let mut builder = ProxyTunnelBuilder::new(ProxyKind::Socks5, "127.0.0.1", proxy_port);
+        builder.set_credentials("alice", "secret");
+        let mut tunnel = ProxyTunnel::from(builder);
+        tunnel.set_target("db.host", 5433);
+        let handler = tunnel.run().await.unwrap();
+
+        let mut client = TcpStream::connect(handler.local_addr()).await.unwrap();
+        let mut received = [0u8; 4];
+        client.read_exact(&mut received).await.unwrap();
+        assert_eq!(&received, b"pong");
+
+        server.await.unwrap();
+        handler.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn http_tunnel_reports_a_refused_connect() {
+        let proxy = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let proxy_port = proxy.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (socket, _) = proxy.accept().await.unwrap();
+            let mut reader = BufReader::new(socket);
+            let mut line = String::new();
+            reader.read_line(&mut line).await.unwrap();
+            assert_eq!(line, "CONNECT db.host:5432 HTTP/1.1\r\n");
+            reader.get_mut().write_all(b"HTTP/1.1 403 Forbidden\r\n\r\n").await.unwrap();
+        });
+
+        let mut tunnel = ProxyTunnel::from(ProxyTunnelBuilder::new(ProxyKind::Http, "127.0.0.1", proxy_port));
+        tunnel.set_target("db.host", 5432);
+        let mut stream = TcpStream::connect(("127.0.0.1", proxy_port)).await.unwrap();
+        let error = tunnel.http_connect(&mut stream, "db.host").await.unwrap_err();
+        assert!(error.to_string().contains("403"), "{}", error);
+    }
+}
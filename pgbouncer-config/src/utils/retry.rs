@@ -0,0 +1,174 @@
+//! Retry with exponential backoff for the connections made to backend hosts.
+//!
+//! Imports connect to every host of a [databases] section; a host that is
+//! briefly unreachable should not make the whole import fail. A
+//! [`RetryPolicy`] decides whether and when a failed attempt is retried.
+
+use std::time::Duration;
+use rand::Rng;
+use crate::error::PgBouncerError;
+
+/// Number of attempts of [`RetryPolicy::default`], including the first one.
+pub const DEFAULT_RETRY_ATTEMPTS: u32 = 3;
+/// Delay before the first retry of [`RetryPolicy::default`].
+pub const DEFAULT_RETRY_INITIAL_DELAY: Duration = Duration::from_millis(500);
+/// Upper bound of the random delay [`RetryPolicy::default`] adds to every retry.
+pub const DEFAULT_RETRY_JITTER: Duration = Duration::from_millis(250);
+
+/// How failed connections to a host are retried.
+///
+/// Only transient failures (I/O errors, timeouts and SSH connection errors)
+/// are retried; authentication and SQL errors fail right away. The delay
+/// before the `n`-th retry is `initial_delay * 2^(n-1)` plus a random jitter
+/// between zero and `jitter`.
+///
+/// # Fields
+/// - attempts: Maximum number of attempts, including the first one.
+/// - initial_delay: Delay before the first retry.
+/// - jitter: Upper bound of the random delay added to every retry.
+///
+/// # Examples
+/// ```rust
+/// use std::time::Duration;
+/// use pgbouncer_config::utils::retry::RetryPolicy;
+///
+/// let mut retry = RetryPolicy::new();
+/// retry
+///     .set_attempts(5)
+///     .set_initial_delay(Duration::from_secs(1))
+///     .set_jitter(Duration::ZERO);
+/// assert_eq!(retry.attempts(), 5);
+/// assert_eq!(RetryPolicy::none().attempts(), 1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    attempts: u32,
+    initial_delay: Duration,
+    jitter: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: DEFAULT_RETRY_ATTEMPTS,
+            initial_delay: DEFAULT_RETRY_INITIAL_DELAY,
+            jitter: DEFAULT_RETRY_JITTER,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Creates the default policy: 3 attempts, 500ms initial delay and 250ms jitter.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a policy making a single attempt.
+    pub fn none() -> Self {
+        let mut policy = Self::default();
+        policy.set_attempts(1);
+        policy
+    }
+
+    /// Sets the maximum number of attempts, including the first one; `0` is treated as `1`.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated attempts.
+    pub fn set_attempts(&mut self, attempts: u32) -> &mut Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    /// Sets the delay before the first retry; it doubles with every further retry.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated initial delay.
+    pub fn set_initial_delay(&mut self, initial_delay: Duration) -> &mut Self {
+        self.initial_delay = initial_delay;
+        self
+    }
+
+    /// Sets the upper bound of the random delay added to every retry.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated jitter.
+    pub fn set_jitter(&mut self, jitter: Duration) -> &mut Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Returns the maximum number of attempts.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Returns the delay before the first retry.
+    pub fn initial_delay(&self) -> Duration {
+        self.initial_delay
+    }
+
+    /// Returns the upper bound of the random delay added to every retry.
+    pub fn jitter(&self) -> Duration {
+        self.jitter
+    }
+
+    /// Decides whether a failed attempt is retried.
+    ///
+    /// # Parameters
+    /// - attempt: Number of the attempt that failed, starting at 1.
+    /// - error: Error the attempt failed with.
+    ///
+    /// # Returns
+    /// The delay to wait before the next attempt, or `None` when the error is
+    /// not transient or no attempt is left.
+    pub fn retry_delay(&self, attempt: u32, error: &PgBouncerError) -> Option<Duration> {
+        if attempt >= self.attempts || !is_transient(error) {
+            return None;
+        }
+
+        let backoff = self.initial_delay.saturating_mul(2u32.saturating_pow(attempt - 1));
+        let jitter = if self.jitter.is_zero() {
+            Duration::ZERO
+        } else {
+            rand::thread_rng().gen_range(Duration::ZERO..=self.jitter)
+        };
+
+        Some(backoff.saturating_add(jitter))
+    }
+}
+
+/// Whether a connection error may go away by itself (network, timeout or SSH transport).
+fn is_transient(error: &PgBouncerError) -> bool {
+    match error {
+        PgBouncerError::Sqlx(e) => matches!(
+            e,
+            sqlx::Error::Io(_) | sqlx::Error::Tls(_) | sqlx::Error::PoolTimedOut | sqlx::Error::WorkerCrashed
+        ),
+        PgBouncerError::Io(_) | PgBouncerError::SshConnection(_) | PgBouncerError::Connection(_) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_backs_off_on_transient_errors_only() {
+        let mut retry = RetryPolicy::new();
+        retry
+            .set_attempts(3)
+            .set_initial_delay(Duration::from_millis(100))
+            .set_jitter(Duration::ZERO);
+        let refused: PgBouncerError = sqlx::Error::Io(std::io::Error::from(std::io::ErrorKind::ConnectionRefused)).into();
+
+        assert_eq!(retry.retry_delay(1, &refused), Some(Duration::from_millis(100)));
+        assert_eq!(retry.retry_delay(2, &refused), Some(Duration::from_millis(200)));
+        assert_eq!(retry.retry_delay(3, &refused), None);
+        assert_eq!(retry.retry_delay(1, &PgBouncerError::SshAuth("denied".to_string())), None);
+        assert_eq!(RetryPolicy::none().retry_delay(1, &refused), None);
+
+        let jittered = retry.set_jitter(Duration::from_millis(50)).retry_delay(1, &refused).unwrap();
+        assert!(jittered >= Duration::from_millis(100) && jittered <= Duration::from_millis(150));
+    }
+}
@@ -0,0 +1,283 @@
+//! Resolving secret references in definitions.
+//!
+//! A definition may hold a reference such as `vault://secret/pgbouncer#password`
+//! or `env://PGBOUNCER_DB_PASSWORD` instead of a literal secret. A
+//! [`SecretResolvers`] registry maps each reference scheme to a
+//! [`SecretResolver`] and replaces references with the values they point to.
+//! Only the registered schemes are references: any other value, including a
+//! literal password such as `abc://xyz`, is kept as it is.
+//!
+//! The `env` scheme is always available through [`EnvSecretResolver`]; the
+//! `vault` scheme is provided by `utils::vault::VaultSecretResolver` with the
+//! `vault` feature.
+
+use std::fmt::{Display, Formatter};
+use crate::error::PgBouncerError;
+
+/// A parsed `scheme://path#key` secret reference.
+///
+/// # Fields
+/// - scheme: Lowercase scheme selecting the resolver, e.g. `vault` or `env`.
+/// - path: Location of the secret in the backend.
+/// - key: Optional field of the secret to read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretReference {
+    scheme: String,
+    path: String,
+    key: Option<String>,
+}
+
+impl SecretReference {
+    /// Parses a secret reference.
+    ///
+    /// The scheme must start with an ASCII letter and contain only ASCII
+    /// letters, digits, `+`, `-` and `.`; the path must not be empty. Whether
+    /// the value is resolved also depends on the scheme being registered, see
+    /// [`SecretResolvers::resolve`].
+    ///
+    /// # Parameters
+    /// - value: Value from the definition, e.g. `vault://secret/pgbouncer#password`.
+    ///
+    /// # Returns
+    /// The reference, or `None` when `value` is not a secret reference.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::utils::secret::SecretReference;
+    ///
+    /// let reference = SecretReference::parse("vault://secret/pgbouncer#password").unwrap();
+    /// assert_eq!(reference.scheme(), "vault");
+    /// assert_eq!(reference.path(), "secret/pgbouncer");
+    /// assert_eq!(reference.key(), Some("password"));
+    /// assert!(SecretReference::parse("plain-password").is_none());
+    /// ```
+    pub fn parse(value: &str) -> Option<Self> {
+        let (scheme, rest) = value.split_once("://")?;
+        let mut chars = scheme.chars();
+        if !chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+            || !chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')) {
+            return None;
+        }
+
+        let (path, key) = match rest.split_once('#') {
+            Some((path, key)) => (path, Some(key.to_string())),
+            None => (rest, None),
+        };
+        if path.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            scheme: scheme.to_lowercase(),
+            path: path.to_string(),
+            key,
+        })
+    }
+
+    /// Returns the scheme of the reference.
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    /// Returns the location of the secret in the backend.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the field of the secret to read, if any.
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+}
+
+impl Display for SecretReference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}://{}", self.scheme, self.path)?;
+        if let Some(key) = &self.key {
+            write!(f, "#{}", key)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A backend that materializes secret references of one scheme.
+pub trait SecretResolver: Send + Sync {
+    /// Returns the scheme this resolver handles, e.g. `vault`.
+    fn scheme(&self) -> &str;
+
+    /// Reads the secret a reference points to.
+    ///
+    /// # Parameters
+    /// - reference: Reference whose scheme equals [`SecretResolver::scheme`].
+    ///
+    /// # Returns
+    /// The secret value.
+    ///
+    /// # Errors
+    /// Returns an error if the secret does not exist or the backend cannot be reached.
+    fn resolve(&self, reference: &SecretReference) -> crate::error::Result<String>;
+}
+
+/// Resolves `env://NAME` references from environment variables.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnvSecretResolver;
+
+impl SecretResolver for EnvSecretResolver {
+    fn scheme(&self) -> &str {
+        "env"
+    }
+
+    fn resolve(&self, reference: &SecretReference) -> crate::error::Result<String> {
+        if reference.key().is_some() {
+            return Err(PgBouncerError::Secret(format!("{} must not have a #key", reference)));
+        }
+
+        std::env::var(reference.path())
+            .map_err(|_| PgBouncerError::Secret(format!("environment variable {} is not set", reference.path())))
+    }
+}
+
+/// Registry of [`SecretResolver`]s keyed by scheme.
+///
+/// # Fields
+/// - resolvers: Registered resolvers; a later registration for the same
+///   scheme replaces the earlier one.
+pub struct SecretResolvers {
+    resolvers: Vec<Box<dyn SecretResolver>>,
+}
+
+impl SecretResolvers {
+    /// Creates a registry with the [`EnvSecretResolver`].
+    ///
+    /// # Returns
+    /// A registry resolving `env://` references.
+    pub fn new() -> Self {
+        Self {
+            resolvers: vec![Box::new(EnvSecretResolver)],
+        }
+    }
+
+    /// Registers a resolver for its scheme.
+    ///
+    /// # Parameters
+    /// - resolver: Resolver to add; it replaces any resolver of the same scheme.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the resolver added.
+    pub fn register(&mut self, resolver: impl SecretResolver + 'static) -> &mut Self {
+        self.resolvers.retain(|registered| registered.scheme() != resolver.scheme());
+        self.resolvers.push(Box::new(resolver));
+        self
+    }
+
+    /// Materializes a value from the definition.
+    ///
+    /// # Parameters
+    /// - value: Literal secret or secret reference.
+    ///
+    /// # Returns
+    /// The secret for a reference, or `value` itself when it is not a
+    /// reference or no resolver is registered for its scheme.
+    ///
+    /// # Errors
+    /// Returns an error if the resolver of the reference's scheme fails.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::utils::secret::SecretResolvers;
+    ///
+    /// let resolvers = SecretResolvers::new();
+    /// assert_eq!(resolvers.resolve("plain-password").unwrap(), "plain-password");
+    /// assert_eq!(resolvers.resolve("abc://xyz").unwrap(), "abc://xyz");
+    /// assert!(resolvers.resolve("env://PGBOUNCER_CONFIG_UNSET_VARIABLE").is_err());
+    /// ```
+    pub fn resolve(&self, value: &str) -> crate::error::Result<String> {
+        let resolver = SecretReference::parse(value).and_then(|reference| {
+            self.resolvers
+                .iter()
+                .find(|resolver| resolver.scheme() == reference.scheme())
+                .map(|resolver| (resolver, reference))
+        });
+
+        match resolver {
+            Some((resolver, reference)) => resolver.resolve(&reference),
+            None => Ok(value.to_string()),
+        }
+    }
+
+    /// Replaces a secret reference with its value in place.
+    ///
+    /// # Parameters
+    /// - value: Literal secret or secret reference; left unchanged when it is not a reference.
+    ///
+    /// # Errors
+    /// Returns an error if the reference cannot be resolved.
+    pub fn resolve_in_place(&self, value: &mut String) -> crate::error::Result<()> {
+        *value = self.resolve(value)?;
+
+        Ok(())
+    }
+}
+
+impl Default for SecretResolvers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StaticResolver;
+
+    impl SecretResolver for StaticResolver {
+        fn scheme(&self) -> &str {
+            "static"
+        }
+
+        fn resolve(&self, reference: &SecretReference) -> crate::error::Result<String> {
+            Ok(format!("{}/{}", reference.path(), reference.key().unwrap_or_default()))
+        }
+    }
+
+    #[test]
+    fn secret_reference_parse_and_display() {
+        let reference = SecretReference::parse("Vault://kv/app/db#password").expect("reference");
+        assert_eq!(reference.scheme(), "vault");
+        assert_eq!(reference.path(), "kv/app/db");
+        assert_eq!(reference.key(), Some("password"));
+        assert_eq!(reference.to_string(), "vault://kv/app/db#password");
+
+        assert!(SecretReference::parse("p@ss://word").is_none());
+        assert!(SecretReference::parse("env://").is_none());
+        assert!(SecretReference::parse("md5abcdef").is_none());
+    }
+
+    #[test]
+    fn secret_resolvers_dispatch_by_scheme() {
+        let mut resolvers = SecretResolvers::new();
+        resolvers.register(StaticResolver);
+        assert_eq!(resolvers.resolve("env://PATH").unwrap(), std::env::var("PATH").unwrap());
+        assert_eq!(resolvers.resolve("static://a/b#c").unwrap(), "a/b/c");
+        assert_eq!(resolvers.resolve("literal").unwrap(), "literal");
+        assert!(matches!(resolvers.resolve("env://PGBOUNCER_CONFIG_SECRET_UNSET"), Err(PgBouncerError::Secret(_))));
+
+        let mut value = "static://x#y".to_string();
+        resolvers.resolve_in_place(&mut value).unwrap();
+        assert_eq!(value, "x/y");
+    }
+
+    #[test]
+    fn secret_resolvers_keep_literals_with_unregistered_schemes() {
+        let mut resolvers = SecretResolvers::new();
+        for literal in ["abc://xyz", "vault://secret/app#password", "static://a/b#c"] {
+            assert_eq!(resolvers.resolve(literal).unwrap(), literal);
+        }
+
+        resolvers.register(StaticResolver);
+        assert_eq!(resolvers.resolve("abc://xyz").unwrap(), "abc://xyz");
+        assert_eq!(resolvers.resolve("static://a/b#c").unwrap(), "a/b/c");
+    }
+}
@@ -0,0 +1,164 @@
+use std::path::PathBuf;
+
+/// Settings an OpenSSH client config (`~/.ssh/config`) gives a host alias.
+///
+/// Only the keys used by SSH tunnels are read; `Match` blocks and `Include`
+/// directives are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct SshConfigEntry {
+    pub(crate) host_name: Option<String>,
+    pub(crate) user: Option<String>,
+    pub(crate) port: Option<u16>,
+    pub(crate) identity_files: Vec<PathBuf>,
+    pub(crate) proxy_jump: Option<String>,
+}
+
+/// Collects the settings of `alias` from the text of an ssh config.
+///
+/// Like ssh, the first value obtained for a key wins, except `IdentityFile`
+/// whose values accumulate in order.
+pub(crate) fn lookup(config: &str, alias: &str) -> SshConfigEntry {
+    let mut entry = SshConfigEntry::default();
+    // Settings before the first Host line apply to every host.
+    let mut matching = true;
+
+    for line in config.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = split_key_value(line) else {
+            continue;
+        };
+
+        match key.to_ascii_lowercase().as_str() {
+            "host" => matching = host_matches(value, alias),
+            "match" => matching = false,
+            _ if !matching => {},
+            "hostname" => {
+                entry.host_name.get_or_insert_with(|| value.replace("%h", alias));
+            },
+            "user" => {
+                entry.user.get_or_insert_with(|| value.to_string());
+            },
+            "port" if entry.port.is_none() => entry.port = value.parse().ok(),
+            "identityfile" => entry.identity_files.push(expand_home(value)),
+            "proxyjump" => {
+                entry.proxy_jump.get_or_insert_with(|| value.to_string());
+            },
+            _ => {},
+        }
+    }
+
+    entry
+}
+
+/// Path of the user's ssh config, `~/.ssh/config`.
+pub(crate) fn default_path() -> Option<PathBuf> {
+    home_dir().map(|home| home.join(".ssh").join("config"))
+}
+
+/// Name of the local user, which ssh uses when no `User` is configured.
+pub(crate) fn local_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Splits `Key value` or `Key=value`, unquoting the value.
+fn split_key_value(line: &str) -> Option<(&str, &str)> {
+    let end = line.find(|c: char| c.is_whitespace() || c == '=')?;
+    let (key, rest) = line.split_at(end);
+    let value = rest.trim_start().strip_prefix('=').unwrap_or(rest).trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value);
+
+    Some((key, value))
+}
+
+/// Whether a `Host` line applies to `alias`: one of its patterns matches and
+/// none of its negated (`!`) patterns does.
+fn host_matches(patterns: &str, alias: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns.split_whitespace() {
+        match pattern.strip_prefix('!') {
+            Some(negated) if wildcard_match(negated, alias) => return false,
+            Some(_) => {},
+            None => matched |= wildcard_match(pattern, alias),
+        }
+    }
+
+    matched
+}
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    fn matches(pattern: &[char], text: &[char]) -> bool {
+        match pattern.split_first() {
+            None => text.is_empty(),
+            Some(('*', rest)) => (0..=text.len()).any(|skip| matches(rest, &text[skip..])),
+            Some(('?', rest)) => !text.is_empty() && matches(rest, &text[1..]),
+            Some((c, rest)) => text.first() == Some(c) && matches(rest, &text[1..]),
+        }
+    }
+
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    matches(&pattern, &text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+# Defaults for every host
+ServerAliveInterval 30
+
+Host bastion-prod
+    HostName 203.0.113.10
+    User ops
+    Port=2222
+    IdentityFile /keys/prod_ed25519
+    IdentityFile "/keys/fallback"
+    ProxyJump jump@edge.example.com:22
+
+Host *.internal !legacy.internal
+    User internal
+
+Host *
+    User fallback
+    IdentityFile /keys/default
+"#;
+
+    #[test]
+    fn lookup_takes_the_first_value_and_accumulates_identity_files() {
+        let entry = lookup(CONFIG, "bastion-prod");
+        assert_eq!(entry.host_name.as_deref(), Some("203.0.113.10"));
+        assert_eq!(entry.user.as_deref(), Some("ops"));
+        assert_eq!(entry.port, Some(2222));
+        assert_eq!(entry.identity_files, [PathBuf::from("/keys/prod_ed25519"), PathBuf::from("/keys/fallback"), PathBuf::from("/keys/default")]);
+        assert_eq!(entry.proxy_jump.as_deref(), Some("jump@edge.example.com:22"));
+    }
+
+    #[test]
+    fn lookup_honors_wildcards_and_negations() {
+        assert_eq!(lookup(CONFIG, "db.internal").user.as_deref(), Some("internal"));
+        assert_eq!(lookup(CONFIG, "legacy.internal").user.as_deref(), Some("fallback"));
+        assert_eq!(lookup(CONFIG, "other").host_name, None);
+    }
+}
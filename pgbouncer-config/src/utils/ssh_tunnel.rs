@@ -1,11 +1,41 @@
+//! Local port forwarding through an SSH bastion.
+//!
+//! [`SSHTunnel`] listens on a local port and forwards every accepted
+//! connection to a remote host through a direct-tcpip channel of the bastion,
+//! like `ssh -L`. Imports use it to reach Postgres, but it works for any TCP
+//! service behind the bastion, e.g. the PgBouncer admin console.
+//!
+//! The lifecycle is: configure an [`SSHTunnel`], call [`SSHTunnel::run`] (or
+//! [`SSHTunnel::forward`]) to connect and start listening, connect to
+//! [`SSHTunnelHandler::local_addr`], and finally call
+//! [`SSHTunnelHandler::shutdown`] to close the SSH session.
+//!
+//! ```rust,no_run
+//! use pgbouncer_config::pgbouncer_config::databases_setting::SSHAuth;
+//! use pgbouncer_config::utils::ssh_tunnel::SSHTunnel;
+//!
+//! # async fn example() -> pgbouncer_config::error::Result<()> {
+//! let tunnel = SSHTunnel::new("bastion.example.com", 22, "ubuntu", SSHAuth::Agent { socket: None }, 0, None, 5432);
+//! let handler = tunnel.forward(0, "pgbouncer.internal", 6432).await?;
+//! println!("admin console at {}", handler.local_addr());
+//! handler.shutdown().await?;
+//! # Ok(())
+//! # }
+//! ```
+
 use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::Path;
 use std::sync::Arc;
 use russh::client;
 use russh::keys::{decode_secret_key, load_secret_key, HashAlg, PrivateKeyWithHashAlg, PublicKey};
+#[cfg(unix)]
+use russh::keys::agent::client::AgentClient;
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::watch;
+use tokio::task::JoinHandle;
 use crate::error::PgBouncerError;
-use crate::pgbouncer_config::databases_setting::{SSHAuth, SSHTunnelBuilder};
+use crate::pgbouncer_config::databases_setting::{SSHAuth, SSHHop, SSHTunnelBuilder};
 
 struct ClientHandler;
 
@@ -17,33 +47,73 @@ impl client::Handler for ClientHandler {
     }
 }
 
+/// Forwards a local port to a remote host through an SSH bastion.
+///
+/// # Fields
+/// - bastion_host: Bastion hostname or IP address.
+/// - bastion_port: Bastion SSH port.
+/// - bastion_user: Username used on the bastion.
+/// - bastion_auth: Authentication method for the bastion.
+/// - jump_hosts: Hosts passed through, in order, before the bastion.
+/// - local_port: Local port to listen on; 0 picks a free port.
+/// - pg_host: Remote host the connections are forwarded to, as seen from the bastion.
+/// - pg_port: Remote port the connections are forwarded to.
 #[derive(Clone)]
 pub struct SSHTunnel {
     bastion_host: String,
     bastion_port: u16,
     bastion_user: String,
     bastion_auth: SSHAuth,
+    jump_hosts: Vec<SSHHop>,
     local_port: u16,
     pg_host: Option<String>,
     pg_port: u16,
 }
 
+/// Running tunnel returned by [`SSHTunnel::run`].
+///
+/// Dropping the handler stops accepting connections and closes the SSH
+/// session in the background; [`SSHTunnelHandler::shutdown`] does the same and
+/// waits for it.
 pub struct SSHTunnelHandler {
     shutdown_tx: watch::Sender<()>,
     local_addr: SocketAddr,
+    task: JoinHandle<crate::error::Result<()>>,
 }
 
 impl SSHTunnelHandler {
-    pub async fn shutdown(self) {
+    /// Stops accepting connections and disconnects from the bastion and the jump hosts.
+    ///
+    /// # Returns
+    /// Unit once the SSH sessions are closed.
+    ///
+    /// # Errors
+    /// Returns an error if disconnecting fails or the tunnel task panicked.
+    pub async fn shutdown(self) -> crate::error::Result<()> {
         drop(self.shutdown_tx);
+        self.task.await?
     }
 
+    /// Returns the local address the tunnel listens on.
     pub fn local_addr(&self) -> SocketAddr {
         self.local_addr
     }
 }
 
 impl SSHTunnel {
+    /// Creates a tunnel configuration; nothing is connected until [`SSHTunnel::run`].
+    ///
+    /// # Parameters
+    /// - bastion_host: Bastion hostname or IP address.
+    /// - bastion_port: Bastion SSH port.
+    /// - bastion_user: Username used on the bastion.
+    /// - bastion_auth: Authentication method for the bastion.
+    /// - local_port: Local port to listen on; 0 picks a free port.
+    /// - pg_host: Remote host to forward to, as seen from the bastion.
+    /// - pg_port: Remote port to forward to.
+    ///
+    /// # Returns
+    /// The tunnel configuration.
     pub fn new(
         bastion_host: &str,
         bastion_port: u16,
@@ -58,58 +128,68 @@ impl SSHTunnel {
             bastion_port,
             bastion_user: bastion_user.to_string(),
             bastion_auth,
+            jump_hosts: vec![],
             local_port,
             pg_host: pg_host.map(ToString::to_string),
             pg_port,
         }
     }
     
-    pub fn set_pg_host(&mut self, pg_host: &str) -> Self {
+    /// Sets the remote host the connections are forwarded to.
+    ///
+    /// # Parameters
+    /// - pg_host: Remote host, as seen from the bastion.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated field(s).
+    pub fn set_pg_host(&mut self, pg_host: &str) -> &mut Self {
         self.pg_host = Some(pg_host.to_string());
-        self.clone()
+        self
     }
 
-    pub async fn run(&self) -> crate::error::Result<SSHTunnelHandler> {
-        let (shutdown_tx, mut shutdown_rx) = watch::channel(());
+    /// Sets the remote port the connections are forwarded to.
+    ///
+    /// # Parameters
+    /// - pg_port: Remote port.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated field(s).
+    pub fn set_pg_port(&mut self, pg_port: u16) -> &mut Self {
+        self.pg_port = pg_port;
+        self
+    }
 
-        let config = Arc::new(client::Config::default());
-        let client_handler = ClientHandler;
-        let mut session = client::connect(
-            config, (self.bastion_host(), self.bastion_port), client_handler).await?;
+    /// Sets the local port the tunnel listens on.
+    ///
+    /// # Parameters
+    /// - local_port: Local port; 0 picks a free port.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated field(s).
+    pub fn set_local_port(&mut self, local_port: u16) -> &mut Self {
+        self.local_port = local_port;
+        self
+    }
 
-        let auth_success = match self.bastion_auth() {
-            SSHAuth::Password{ password} => {
-                session.authenticate_password(self.bastion_user(), password).await?
-            },
-            SSHAuth::SSHKey {
-                key, pass_phrase
-            } => {
-                let key_pair = decode_secret_key(&key, pass_phrase.as_deref())?;
-                session.authenticate_publickey(
-                    self.bastion_user(),
-                    PrivateKeyWithHashAlg::new(
-                        Arc::new(key_pair),
-                        None
-                    )
-                ).await?
-            },
-            SSHAuth::LocalSSHKeyFile {
-                path, pass_phrase
-            } => {
-                let key_pair = load_secret_key(path.as_path(), pass_phrase.as_deref())?;
-                session.authenticate_publickey(
-                    self.bastion_user(),
-                    PrivateKeyWithHashAlg::new(
-                        Arc::new(key_pair),
-                        Some(HashAlg::Sha256)
-                    )
-                ).await?
-            }
-        };
+    /// Appends a jump host passed through, in order, before the bastion.
+    pub fn add_jump_host(&mut self, hop: SSHHop) -> &mut Self {
+        self.jump_hosts.push(hop);
+        self
+    }
 
-        if !auth_success.success() {
-            return Err(PgBouncerError::Connection(format!("Authentication failed for user {}", self.bastion_user())));
-        }
+    /// Connects to the bastion, through the jump hosts, and listens on
+    /// `127.0.0.1:<local_port>`, forwarding each connection to the remote host.
+    ///
+    /// # Returns
+    /// The handler of the running tunnel.
+    ///
+    /// # Errors
+    /// Returns an error if no remote host is set, connecting or authenticating
+    /// to a host fails, or the local port cannot be bound.
+    pub async fn run(&self) -> crate::error::Result<SSHTunnelHandler> {
+        let (shutdown_tx, mut shutdown_rx) = watch::channel(());
+
+        let (session, jump_sessions) = self.connect_hops().await?;
 
         let listener = TcpListener::bind(("127.0.0.1", self.local_port)).await?;
         let local_addr = listener.local_addr()?;
@@ -122,7 +202,7 @@ impl SSHTunnel {
             return Err(PgBouncerError::Connection("Postgres hostname is required but isn't given".to_string()));
         };
         let pg_port = self.pg_port;
-        tokio::spawn(async move {
+        let task = tokio::spawn(async move {
             loop {
                 let session_handle = session_arc_clone.clone();
                 let pg_host = pg_host.clone();
@@ -157,12 +237,173 @@ impl SSHTunnel {
             if let Err(e) = session_arc.disconnect(russh::Disconnect::ByApplication, "Shutdown", "en").await {
                 return Err(PgBouncerError::Connection(format!("Disconnect error: {}", e)));
             }
+            for jump_session in jump_sessions.iter().rev() {
+                if let Err(e) = jump_session.disconnect(russh::Disconnect::ByApplication, "Shutdown", "en").await {
+                    return Err(PgBouncerError::Connection(format!("Disconnect error: {}", e)));
+                }
+            }
 
             Ok(())
         });
 
 
-        Ok(SSHTunnelHandler { shutdown_tx, local_addr })
+        Ok(SSHTunnelHandler { shutdown_tx, local_addr, task })
+    }
+
+    /// Runs this tunnel forwarding `127.0.0.1:<local_port>` to `remote_host:remote_port`,
+    /// like `ssh -L local_port:remote_host:remote_port`.
+    ///
+    /// The configured local and remote endpoints are left unchanged, so one
+    /// configuration can open several forwards.
+    ///
+    /// # Parameters
+    /// - local_port: Local port to listen on; 0 picks a free port.
+    /// - remote_host: Remote host, as seen from the bastion.
+    /// - remote_port: Remote port.
+    ///
+    /// # Returns
+    /// The handler of the running tunnel.
+    ///
+    /// # Errors
+    /// Same as [`SSHTunnel::run`].
+    pub async fn forward(
+        &self,
+        local_port: u16,
+        remote_host: &str,
+        remote_port: u16,
+    ) -> crate::error::Result<SSHTunnelHandler> {
+        let mut tunnel = self.clone();
+        tunnel
+            .set_local_port(local_port)
+            .set_pg_host(remote_host)
+            .set_pg_port(remote_port);
+        tunnel.run().await
+    }
+
+    /// Connects to the jump hosts in order and then to the bastion, each one
+    /// through a direct-tcpip channel of the previous session.
+    ///
+    /// Returns the session of the bastion and the sessions of the jump hosts,
+    /// which must stay open as long as the bastion session is used.
+    async fn connect_hops(&self) -> crate::error::Result<(client::Handle<ClientHandler>, Vec<client::Handle<ClientHandler>>)> {
+        let config = Arc::new(client::Config::default());
+        let mut hops = self.jump_hosts
+            .iter()
+            .map(|hop| (hop.host(), hop.port(), hop.user(), hop.auth()))
+            .collect::<Vec<_>>();
+        hops.push((self.bastion_host(), self.bastion_port, self.bastion_user(), self.bastion_auth()));
+
+        let mut sessions: Vec<client::Handle<ClientHandler>> = Vec::with_capacity(hops.len());
+        for (host, port, user, auth) in hops {
+            let mut session = match sessions.last() {
+                None => client::connect(config.clone(), (host, port), ClientHandler).await?,
+                Some(previous) => {
+                    let channel = previous
+                        .channel_open_direct_tcpip(host, port as u32, "127.0.0.1", 0)
+                        .await
+                        .map_err(|e| PgBouncerError::Connection(format!("Failed to reach {}:{}: {}", host, port, e)))?;
+                    client::connect_stream(config.clone(), channel.into_stream(), ClientHandler).await?
+                },
+            };
+            Self::authenticate(&mut session, user, auth).await?;
+            sessions.push(session);
+        }
+
+        // SAFETY: the bastion is always the last hop
+        let session = sessions.pop().unwrap();
+        Ok((session, sessions))
+    }
+
+    async fn authenticate(
+        session: &mut client::Handle<ClientHandler>,
+        user: &str,
+        auth: &SSHAuth,
+    ) -> crate::error::Result<()> {
+        let auth_success = match auth {
+            SSHAuth::Password{ password} => {
+                session.authenticate_password(user, password).await?
+            },
+            SSHAuth::SSHKey {
+                key, pass_phrase
+            } => {
+                let key_pair = decode_secret_key(key, pass_phrase.as_deref())?;
+                session.authenticate_publickey(
+                    user,
+                    PrivateKeyWithHashAlg::new(
+                        Arc::new(key_pair),
+                        None
+                    )
+                ).await?
+            },
+            SSHAuth::LocalSSHKeyFile {
+                path, pass_phrase
+            } => {
+                let key_pair = load_secret_key(path.as_path(), pass_phrase.as_deref())?;
+                session.authenticate_publickey(
+                    user,
+                    PrivateKeyWithHashAlg::new(
+                        Arc::new(key_pair),
+                        Some(HashAlg::Sha256)
+                    )
+                ).await?
+            },
+            SSHAuth::Agent { socket } => {
+                Self::authenticate_with_agent(session, user, socket.as_deref()).await?
+            },
+            SSHAuth::SshConfig => {
+                return Err(PgBouncerError::SshAuth(format!(
+                    "The ssh config authentication of user {} must be resolved before connecting", user
+                )));
+            },
+        };
+
+        if !auth_success.success() {
+            return Err(PgBouncerError::Connection(format!("Authentication failed for user {}", user)));
+        }
+
+        Ok(())
+    }
+
+    /// Tries the keys of the ssh-agent one by one until the host accepts one.
+    #[cfg(unix)]
+    async fn authenticate_with_agent(
+        session: &mut client::Handle<ClientHandler>,
+        user: &str,
+        socket: Option<&Path>,
+    ) -> crate::error::Result<client::AuthResult> {
+        let mut agent = match socket {
+            Some(socket) => AgentClient::connect_uds(socket).await,
+            None => AgentClient::connect_env().await,
+        }.map_err(|e| PgBouncerError::SshAuth(format!("Failed to connect to the ssh-agent: {}", e)))?;
+
+        let identities = agent.request_identities().await?;
+        if identities.is_empty() {
+            return Err(PgBouncerError::SshAuth("The ssh-agent holds no keys".to_string()));
+        }
+
+        let hash_alg = session.best_supported_rsa_hash().await?.flatten();
+        for key in identities {
+            let result = session
+                .authenticate_publickey_with(user, key, hash_alg, &mut agent)
+                .await
+                .map_err(|e| PgBouncerError::SshAuth(e.to_string()))?;
+            if result.success() {
+                return Ok(result);
+            }
+        }
+
+        Err(PgBouncerError::SshAuth(format!(
+            "None of the keys of the ssh-agent was accepted for user {}", user
+        )))
+    }
+
+    #[cfg(not(unix))]
+    async fn authenticate_with_agent(
+        _session: &mut client::Handle<ClientHandler>,
+        _user: &str,
+        _socket: Option<&std::path::Path>,
+    ) -> crate::error::Result<client::AuthResult> {
+        Err(PgBouncerError::SshAuth("ssh-agent authentication is only supported on Unix".to_string()))
     }
 
     fn bastion_host(&self) -> &str {
@@ -216,6 +457,7 @@ impl SSHTunnel {
     }
 }
 
+/// Builds a tunnel from a definition; the remote host is set with [`SSHTunnel::set_pg_host`].
 impl From<SSHTunnelBuilder> for SSHTunnel {
     fn from(value: SSHTunnelBuilder) -> Self {
         let bastion_port = value.port.unwrap_or(22);
@@ -228,9 +470,124 @@ impl From<SSHTunnelBuilder> for SSHTunnel {
             bastion_port,
             bastion_user: value.user,
             bastion_auth: value.auth,
+            jump_hosts: value.jump_hosts,
             local_port,
             pg_host: None,
             pg_port,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use russh::keys::ssh_key::rand_core::OsRng;
+    use russh::keys::{Algorithm, PrivateKey};
+    use russh::server::{self, Auth, Msg, Session};
+    use russh::Channel;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use super::*;
+
+    /// Bastion accepting `tunnel`/`secret` that opens direct-tcpip channels to the requested host.
+    struct Bastion;
+
+    impl server::Handler for Bastion {
+        type Error = russh::Error;
+
+        async fn auth_password(&mut self, user: &str, password: &str) -> Result<Auth, Self::Error> {
+            match (user, password) {
+                ("tunnel", "secret") => Ok(Auth::Accept),
+                _ => Ok(Auth::reject()),
+            }
+        }
+
+        async fn channel_open_direct_tcpip(
+            &mut self,
+            channel: Channel<Msg>,
+            host_to_connect: &str,
+            port_to_connect: u32,
+            _originator_address: &str,
+            _originator_port: u32,
+            _session: &mut Session,
+        ) -> Result<bool, Self::Error> {
+            let mut remote = TcpStream::connect((host_to_connect, port_to_connect as u16)).await?;
+            tokio::spawn(async move {
+                let _ = tokio::io::copy_bidirectional(&mut channel.into_stream(), &mut remote).await;
+            });
+            Ok(true)
+        }
+    }
+
+    /// Starts a bastion on a free local port and returns the port.
+    async fn start_bastion() -> u16 {
+        let config = Arc::new(server::Config {
+            keys: vec![PrivateKey::random(&mut OsRng, Algorithm::Ed25519).unwrap()],
+            ..Default::default()
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let session = server::run_stream(config.clone(), socket, Bastion).await.unwrap();
+                tokio::spawn(session);
+            }
+        });
+
+        port
+    }
+
+    /// Starts a TCP echo server on a free local port and returns the port.
+    async fn start_echo() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let (mut reader, mut writer) = socket.split();
+                    let _ = tokio::io::copy(&mut reader, &mut writer).await;
+                });
+            }
+        });
+
+        port
+    }
+
+    fn tunnel(bastion_port: u16, password: &str, pg_host: Option<&str>) -> SSHTunnel {
+        let auth = SSHAuth::Password { password: password.to_string() };
+        SSHTunnel::new("127.0.0.1", bastion_port, "tunnel", auth, 0, pg_host, 5432)
+    }
+
+    #[tokio::test]
+    async fn forward_relays_connections_to_the_remote_host() {
+        let bastion_port = start_bastion().await;
+        let echo_port = start_echo().await;
+        let tunnel = tunnel(bastion_port, "secret", Some("db.internal"));
+
+        let handler = tunnel.forward(0, "127.0.0.1", echo_port).await.unwrap();
+        assert!(handler.local_addr().ip().is_loopback());
+        assert_ne!(handler.local_addr().port(), 0);
+
+        let mut stream = TcpStream::connect(handler.local_addr()).await.unwrap();
+        stream.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+
+        // forward leaves the configured endpoints alone
+        assert_eq!(tunnel.pg_host.as_deref(), Some("db.internal"));
+        assert_eq!((tunnel.local_port, tunnel.pg_port), (0, 5432));
+
+        drop(stream);
+        handler.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn run_fails_without_a_remote_host_or_with_a_wrong_password() {
+        let bastion_port = start_bastion().await;
+
+        let err = tunnel(bastion_port, "secret", None).run().await.err().unwrap();
+        assert!(matches!(err, PgBouncerError::Connection(ref message) if message.contains("hostname is required")), "{}", err);
+
+        let err = tunnel(bastion_port, "wrong", Some("127.0.0.1")).run().await.err().unwrap();
+        assert!(matches!(err, PgBouncerError::Connection(ref message) if message.contains("Authentication failed")), "{}", err);
+    }
+}
@@ -0,0 +1,230 @@
+//! HashiCorp Vault KV backend for secret references.
+//!
+//! Enabled with the `vault` feature. [`VaultSecretResolver`] materializes
+//! `vault://<mount>/<path>#<key>` references by reading the KV secret at
+//! `<mount>/<path>` and returning its `<key>` field, e.g.
+//! `vault://secret/pgbouncer/app#password` reads the `password` field of
+//! `pgbouncer/app` in the `secret` mount.
+//!
+//! HTTPS connections trust the platform's root certificates plus the CA
+//! bundle given with [`VaultSecretResolver::set_ca_cert`] (`VAULT_CACERT`).
+
+use std::path::Path;
+use ureq::Agent;
+use ureq::tls::{parse_pem, PemItem};
+use crate::error::PgBouncerError;
+use crate::utils::http::build_agent;
+use crate::utils::secret::{SecretReference, SecretResolver};
+
+/// Version of the KV secrets engine mounted at the referenced path.
+///
+/// # Variants
+/// - V1: KV version 1; secrets are read from `/v1/<mount>/<path>`.
+/// - V2: KV version 2 (the default); secrets are read from `/v1/<mount>/data/<path>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KvVersion {
+    V1,
+    #[default]
+    V2,
+}
+
+/// Resolves `vault://` references from a Vault KV secrets engine.
+///
+/// # Fields
+/// - address: Base URL of the Vault server, e.g. `https://vault.example.com:8200`.
+/// - token: Token sent in the `X-Vault-Token` header.
+/// - namespace: Optional Vault Enterprise namespace sent in `X-Vault-Namespace`.
+/// - kv_version: Version of the KV engine the references point to.
+/// - agent: HTTP client configured with the trusted root certificates.
+pub struct VaultSecretResolver {
+    address: String,
+    token: String,
+    namespace: Option<String>,
+    kv_version: KvVersion,
+    agent: Agent,
+}
+
+impl VaultSecretResolver {
+    /// Creates a resolver for a Vault server.
+    ///
+    /// # Parameters
+    /// - address: Base URL of the Vault server.
+    /// - token: Vault token with read access to the referenced secrets.
+    ///
+    /// # Returns
+    /// A resolver for KV version 2 without a namespace.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use pgbouncer_config::utils::secret::SecretResolvers;
+    /// use pgbouncer_config::utils::vault::{KvVersion, VaultSecretResolver};
+    ///
+    /// let mut vault = VaultSecretResolver::new("http://127.0.0.1:8200", "dev-token");
+    /// vault.set_kv_version(KvVersion::V1);
+    /// let mut resolvers = SecretResolvers::new();
+    /// resolvers.register(vault);
+    /// ```
+    pub fn new(address: &str, token: &str) -> Self {
+        Self {
+            address: address.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            namespace: None,
+            kv_version: KvVersion::default(),
+            agent: build_agent(Vec::new()),
+        }
+    }
+
+    /// Creates a resolver from the environment variables used by the Vault CLI.
+    ///
+    /// `VAULT_ADDR` and `VAULT_TOKEN` are required; `VAULT_NAMESPACE` and
+    /// `VAULT_CACERT` are applied when set.
+    ///
+    /// # Returns
+    /// A resolver for KV version 2.
+    ///
+    /// # Errors
+    /// Returns an error if `VAULT_ADDR` or `VAULT_TOKEN` is not set or the CA
+    /// bundle cannot be read.
+    pub fn from_env() -> crate::error::Result<Self> {
+        let variable = |name: &str| {
+            std::env::var(name)
+                .map_err(|_| PgBouncerError::Secret(format!("{} must be set to resolve vault:// references", name)))
+        };
+
+        let mut resolver = Self::new(&variable("VAULT_ADDR")?, &variable("VAULT_TOKEN")?);
+        if let Ok(namespace) = std::env::var("VAULT_NAMESPACE") {
+            resolver.set_namespace(Some(&namespace));
+        }
+        if let Ok(ca_cert) = std::env::var("VAULT_CACERT") {
+            resolver.set_ca_cert(Path::new(&ca_cert))?;
+        }
+
+        Ok(resolver)
+    }
+
+    /// Sets the Vault Enterprise namespace.
+    ///
+    /// # Parameters
+    /// - namespace: Namespace sent with every request, or `None` for the root namespace.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated namespace.
+    pub fn set_namespace(&mut self, namespace: Option<&str>) -> &mut Self {
+        self.namespace = namespace.map(ToString::to_string);
+        self
+    }
+
+    /// Sets the version of the KV secrets engine.
+    ///
+    /// # Parameters
+    /// - kv_version: Version of the engine mounted at the referenced paths.
+    ///
+    /// # Returns
+    /// A mutable reference to self with the updated KV version.
+    pub fn set_kv_version(&mut self, kv_version: KvVersion) -> &mut Self {
+        self.kv_version = kv_version;
+        self
+    }
+
+    /// Trusts the certificates of a PEM CA bundle in addition to the platform roots.
+    ///
+    /// # Parameters
+    /// - path: Path of the PEM file, e.g. the CA that signed the Vault server certificate.
+    ///
+    /// # Returns
+    /// If successful, a mutable reference to self trusting the bundle.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or contains an invalid PEM item.
+    pub fn set_ca_cert(&mut self, path: &Path) -> crate::error::Result<&mut Self> {
+        let pem = std::fs::read(path)?;
+        let mut certs = Vec::new();
+        for item in parse_pem(&pem) {
+            let item = item.map_err(|e| PgBouncerError::Secret(format!("invalid CA bundle {}: {}", path.display(), e)))?;
+            if let PemItem::Certificate(cert) = item {
+                certs.push(cert);
+            }
+        }
+
+        self.agent = build_agent(certs);
+        Ok(self)
+    }
+
+    fn secret_url(&self, reference: &SecretReference) -> String {
+        let path = reference.path().trim_matches('/');
+        match self.kv_version {
+            KvVersion::V1 => format!("{}/v1/{}", self.address, path),
+            KvVersion::V2 => {
+                let (mount, rest) = path.split_once('/').unwrap_or((path, ""));
+                format!("{}/v1/{}/data/{}", self.address, mount, rest)
+            },
+        }
+    }
+}
+
+impl SecretResolver for VaultSecretResolver {
+    fn scheme(&self) -> &str {
+        "vault"
+    }
+
+    fn resolve(&self, reference: &SecretReference) -> crate::error::Result<String> {
+        let key = reference.key()
+            .ok_or_else(|| PgBouncerError::Secret(format!("{} needs a #key naming the secret field", reference)))?;
+
+        let mut request = self.agent
+            .get(&self.secret_url(reference))
+            .header("X-Vault-Token", &self.token);
+        if let Some(namespace) = &self.namespace {
+            request = request.header("X-Vault-Namespace", namespace);
+        }
+
+        let body = request
+            .call()
+            .and_then(|mut response| response.body_mut().read_to_string())
+            .map_err(|e| PgBouncerError::Secret(format!("failed to read {} from Vault: {}", reference, e)))?;
+        let body: serde_json::Value = serde_json::from_str(&body)?;
+
+        secret_field(&body, self.kv_version, key)
+            .ok_or_else(|| PgBouncerError::Secret(format!("{} does not exist in Vault", reference)))
+    }
+}
+
+fn secret_field(body: &serde_json::Value, kv_version: KvVersion, key: &str) -> Option<String> {
+    let data = match kv_version {
+        KvVersion::V1 => &body["data"],
+        KvVersion::V2 => &body["data"]["data"],
+    };
+
+    match &data[key] {
+        serde_json::Value::String(value) => Some(value.clone()),
+        serde_json::Value::Null => None,
+        value => Some(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secret_url_follows_kv_version() {
+        let reference = SecretReference::parse("vault://secret/pgbouncer/app#password").unwrap();
+
+        let mut resolver = VaultSecretResolver::new("https://vault.example.com:8200/", "token");
+        assert_eq!(resolver.secret_url(&reference), "https://vault.example.com:8200/v1/secret/data/pgbouncer/app");
+
+        resolver.set_kv_version(KvVersion::V1);
+        assert_eq!(resolver.secret_url(&reference), "https://vault.example.com:8200/v1/secret/pgbouncer/app");
+    }
+
+    #[test]
+    fn secret_field_reads_kv_payloads() {
+        let v2 = serde_json::json!({"data": {"data": {"password": "s3cret", "port": 5432}, "metadata": {}}});
+        assert_eq!(secret_field(&v2, KvVersion::V2, "password").as_deref(), Some("s3cret"));
+        assert_eq!(secret_field(&v2, KvVersion::V2, "port").as_deref(), Some("5432"));
+        assert_eq!(secret_field(&v2, KvVersion::V2, "missing"), None);
+
+        let v1 = serde_json::json!({"data": {"password": "s3cret"}});
+        assert_eq!(secret_field(&v1, KvVersion::V1, "password").as_deref(), Some("s3cret"));
+    }
+}
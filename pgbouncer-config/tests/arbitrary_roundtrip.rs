@@ -0,0 +1,54 @@
+#[cfg(all(feature = "arbitrary", feature = "io"))]
+mod tests {
+    use std::collections::BTreeSet;
+    use proptest::prelude::*;
+    use pgbouncer_config::builder::PgBouncerConfigBuilder;
+    use pgbouncer_config::pgbouncer_config::PgBouncerConfig;
+    use pgbouncer_config::pgbouncer_config::databases_setting::{Database, DatabasesSetting};
+    use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+    use pgbouncer_config::utils::parser::ParserIniFromStr;
+
+    fn exposed_database_names(ini: &str) -> BTreeSet<String> {
+        ini.split("[databases]")
+            .nth(1)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.split_once(" = ").map(|(name, _)| name.to_string()))
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn pgbouncer_setting_render_parse_roundtrip(setting in any::<PgBouncerSetting>()) {
+            let cfg = PgBouncerConfigBuilder::new(setting.clone(), DatabasesSetting::new())
+                .unwrap()
+                .build();
+            let ini = cfg.expr().unwrap();
+
+            let parsed = PgBouncerConfig::parse_from_str(&ini).unwrap();
+            prop_assert_eq!(parsed.get_config::<PgBouncerSetting>().unwrap(), &setting);
+        }
+
+        #[test]
+        fn database_render_parse_roundtrip(database in any::<Database>()) {
+            let rendered = database.expr();
+
+            let reparsed = rendered
+                .lines()
+                .map(|line| Database::parse_from_str(line).unwrap().expr())
+                .collect::<String>();
+            prop_assert_eq!(reparsed, rendered);
+        }
+
+        #[test]
+        fn config_roundtrip_keeps_every_database_route(cfg in any::<PgBouncerConfig>()) {
+            let ini = cfg.expr().unwrap();
+            let parsed = PgBouncerConfig::parse_from_str(&ini).unwrap();
+
+            prop_assert_eq!(
+                exposed_database_names(&parsed.expr().unwrap()),
+                exposed_database_names(&ini)
+            );
+        }
+    }
+}
@@ -0,0 +1,137 @@
+#[cfg(feature = "test-support")]
+mod tests {
+    use pgbouncer_config::pgbouncer_config::databases_setting::{Database, DatabasesSetting};
+    use pgbouncer_config::pgbouncer_config::Expression;
+    use pgbouncer_config::test_support::PostgresFixture;
+
+    #[tokio::test]
+    #[ignore = "requires a Docker daemon"]
+    async fn add_database_from_hosts_imports_seeded_databases() {
+        let fixture = PostgresFixture::start().await.unwrap();
+        fixture.seed(&["app", "reporting"], &[]).await.unwrap();
+
+        let mut setting = DatabasesSetting::new();
+        setting.add_database(fixture.database(None));
+        setting.add_database_from_hosts(None).await.unwrap();
+
+        let text = setting.expr().unwrap();
+        assert!(text.contains("app = dbname=app"), "{}", text);
+        assert!(text.contains("reporting = dbname=reporting"), "{}", text);
+        assert!(text.contains(&format!("port={}", fixture.port())), "{}", text);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Docker daemon"]
+    async fn add_database_from_hosts_skips_non_target_hosts() {
+        let fixture = PostgresFixture::start().await.unwrap();
+        fixture.seed(&["app"], &[]).await.unwrap();
+
+        let mut setting = DatabasesSetting::new();
+        setting.add_database(fixture.database(None));
+        setting.add_database_from_hosts(Some(&["unrelated.example"])).await.unwrap();
+
+        assert!(!setting.expr().unwrap().contains("app = dbname=app"));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Docker daemon"]
+    async fn import_works_with_a_seeded_login_role() {
+        let fixture = PostgresFixture::start().await.unwrap();
+        fixture.seed(&["app"], &[("importer", "importer-pass")]).await.unwrap();
+
+        let mut database = Database::new(fixture.host(), fixture.port(), "importer", "importer-pass", None);
+        database.get_databases_from_host(None).await.unwrap();
+
+        assert!(database.expr().contains("app = dbname=app"));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Docker daemon"]
+    async fn import_persists_into_the_definition_file() {
+        use pgbouncer_config::builder::PgBouncerConfigBuilder;
+        use pgbouncer_config::io::ConfigFileFormat;
+        use pgbouncer_config::io::read::{Reader, Readers};
+        use pgbouncer_config::io::write::{Writer, Writers};
+        use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+
+        let fixture = PostgresFixture::start().await.unwrap();
+        fixture.seed(&["app", "reporting"], &[]).await.unwrap();
+
+        let mut databases = DatabasesSetting::new();
+        databases.add_database(Database::new("unrelated.example", 5432, "postgres", "postgres", Some(&["legacy"])));
+        databases.add_database(fixture.database(None));
+        let config = PgBouncerConfigBuilder::new(PgBouncerSetting::default(), databases).unwrap().build();
+        let path = std::env::temp_dir().join(format!("pgbouncer_import_{}.toml", fixture.port()));
+        Writer::try_from(Writers::File(&path)).unwrap().write_config(&config, ConfigFileFormat::TOML).unwrap();
+
+        // Same steps as the `import` command: read, import the target host, write back
+        let mut config = Reader::try_from(Readers::File(&path)).unwrap().read_config(ConfigFileFormat::TOML).unwrap();
+        config
+            .get_config_mut::<DatabasesSetting>()
+            .unwrap()
+            .add_database_from_hosts(Some(&[fixture.host()]))
+            .await
+            .unwrap();
+        Writer::try_from(Writers::File(&path)).unwrap().write_config(&config, ConfigFileFormat::TOML).unwrap();
+
+        let reread = Reader::try_from(Readers::File(&path)).unwrap().read_config(ConfigFileFormat::TOML).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let databases = reread.get_config::<DatabasesSetting>().unwrap();
+        assert_eq!(databases.len(), 2);
+        assert_eq!(databases[0].databases(), ["legacy"]);
+        assert!(databases[1].databases().contains(&"app".to_string()));
+        assert!(databases[1].databases().contains(&"reporting".to_string()));
+        assert!(!databases[1].databases().contains(&"postgres".to_string()));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Docker daemon"]
+    async fn pg_client_runs_discovery_queries() {
+        use pgbouncer_config::pg_client::PgClient;
+        use pgbouncer_config::pgbouncer_config::databases_setting::ConnectionOptions;
+        use pgbouncer_config::test_support::{FIXTURE_PASSWORD, FIXTURE_USER};
+
+        let fixture = PostgresFixture::start().await.unwrap();
+        fixture.seed(&["app"], &[]).await.unwrap();
+
+        let client = PgClient::new(fixture.host(), fixture.port(), FIXTURE_USER, FIXTURE_PASSWORD, "app", None, &ConnectionOptions::new())
+            .await
+            .unwrap();
+        let schemas: Vec<String> = client
+            .query_scalar("SELECT nspname::text FROM pg_namespace WHERE nspname !~ '^pg_' ORDER BY nspname")
+            .await
+            .unwrap();
+        assert!(schemas.contains(&"public".to_string()), "{:?}", schemas);
+        let names = client.get_databases(false).await.unwrap().into_iter().map(|database| database.name).collect::<Vec<_>>();
+        assert!(names.contains(&"app".to_string()), "{:?}", names);
+        client.close().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Docker daemon"]
+    async fn import_reads_databases_and_roles_in_one_pass() {
+        use pgbouncer_config::pgbouncer_config::auth_file::RoleFilter;
+        use pgbouncer_config::pgbouncer_config::databases_setting::{ImportEvent, ImportOptions};
+
+        let fixture = PostgresFixture::start().await.unwrap();
+        fixture.seed(&["app"], &[("app", "secret")]).await.unwrap();
+
+        let mut setting = DatabasesSetting::new();
+        setting.add_database(fixture.database(None));
+        let mut roles = RoleFilter::new();
+        roles.set_include(Some("^app$")).unwrap();
+        let mut options = ImportOptions::new();
+        options.set_roles(Some(roles));
+        let events = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let recorded = events.clone();
+        let auth_file = setting
+            .add_database_and_roles_from_hosts_with_progress(None, &options, move |event| recorded.lock().unwrap().push(event))
+            .await
+            .unwrap();
+
+        assert!(setting.expr().unwrap().contains("app = dbname=app"));
+        assert_eq!(auth_file.users().keys().collect::<Vec<_>>(), ["app"]);
+        assert!(auth_file.password("app").unwrap().starts_with("SCRAM-SHA-256$"));
+        assert!(events.lock().unwrap().iter().any(|event| matches!(event, ImportEvent::RolesFound { roles, .. } if roles == &["app"])));
+    }
+}
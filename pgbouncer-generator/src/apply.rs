@@ -0,0 +1,179 @@
+use std::path::{Path, PathBuf};
+use clap::{Args, ValueEnum};
+use pgbouncer_config::admin::AdminClient;
+use pgbouncer_config::io::compose::listens_on_all_interfaces;
+use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+
+/// How a running PgBouncer is told to reload pgbouncer.ini.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ReloadMethod {
+    /// Send SIGHUP to the process whose PID is in the pidfile.
+    Sighup,
+    /// Run `RELOAD` on the admin console.
+    Admin,
+    /// Only write the file.
+    None,
+}
+
+/// Connection settings of the admin console used by [`ReloadMethod::Admin`].
+#[derive(Debug, Clone)]
+pub(crate) struct AdminTarget {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) user: String,
+    pub(crate) password: String,
+}
+
+/// Options selecting how PgBouncer is reloaded, shared by `apply` and `generate --watch`.
+#[derive(Args, Debug, Clone)]
+pub(crate) struct ReloadArgs {
+    #[clap(
+        help = "How to reload PgBouncer after writing the file (apply defaults to sighup, generate --watch to none)",
+        short,
+        long,
+        value_enum,
+    )]
+    pub(crate) reload: Option<ReloadMethod>,
+    #[clap(
+        help = "The pidfile of PgBouncer for --reload sighup (defaults to pidfile of the definition)",
+        long,
+    )]
+    pub(crate) pidfile: Option<String>,
+    #[clap(
+        help = "The host of the admin console for --reload admin (defaults to listen_addr, or 127.0.0.1 when it is a wildcard)",
+        long,
+    )]
+    pub(crate) admin_host: Option<String>,
+    #[clap(
+        help = "The port of the admin console for --reload admin (defaults to listen_port)",
+        long,
+    )]
+    pub(crate) admin_port: Option<u16>,
+    #[clap(
+        help = "A user listed in admin_users for --reload admin",
+        long,
+        default_value = "pgbouncer",
+    )]
+    pub(crate) admin_user: String,
+    #[clap(
+        help = "The password of --admin-user",
+        long,
+        default_value = "",
+    )]
+    pub(crate) admin_password: String,
+}
+
+impl ReloadArgs {
+    /// Resolves where the reload goes, falling back to the settings of the definition.
+    ///
+    /// Called before anything is written, so a missing pidfile does not leave a new file unapplied.
+    pub(crate) fn resolve(&self, default: ReloadMethod, setting: &PgBouncerSetting) -> anyhow::Result<Reloader> {
+        let reloader = match self.reload.unwrap_or(default) {
+            ReloadMethod::Sighup => Reloader::Sighup(
+                self.pidfile
+                    .clone()
+                    .or_else(|| setting.pidfile().map(ToString::to_string))
+                    .ok_or_else(|| anyhow::anyhow!("No pidfile is set in the definition, please use the --pidfile option"))?
+                    .into(),
+            ),
+            ReloadMethod::Admin => Reloader::Admin(AdminTarget {
+                host: self.admin_host.clone().unwrap_or_else(|| {
+                    if listens_on_all_interfaces(setting) {
+                        "127.0.0.1".to_string()
+                    } else {
+                        // SAFETY: split always yields at least one item
+                        setting.listen_addr().split(',').next().unwrap().trim().to_string()
+                    }
+                }),
+                port: self.admin_port.unwrap_or(setting.listen_port()),
+                user: self.admin_user.clone(),
+                password: self.admin_password.clone(),
+            }),
+            ReloadMethod::None => Reloader::None,
+        };
+
+        Ok(reloader)
+    }
+}
+
+/// A resolved reload target.
+#[derive(Debug, Clone)]
+pub(crate) enum Reloader {
+    /// Send SIGHUP to the PID in this pidfile.
+    Sighup(PathBuf),
+    /// Run `RELOAD` on this admin console.
+    Admin(AdminTarget),
+    /// Do not reload.
+    None,
+}
+
+impl Reloader {
+    /// Reloads PgBouncer; returns whether a reload was requested.
+    pub(crate) async fn reload(&self) -> anyhow::Result<bool> {
+        match self {
+            Reloader::Sighup(pidfile) => reload_with_sighup(pidfile)?,
+            Reloader::Admin(target) => reload_with_admin(target).await?,
+            Reloader::None => return Ok(false),
+        }
+
+        Ok(true)
+    }
+}
+
+/// Replaces `path` with `contents` so that PgBouncer never reads a partially written file.
+///
+/// The text is written to a temporary file in the same directory and renamed over
+/// `path`, which is atomic as long as both are on the same file system.
+pub(crate) fn write_atomically(path: &Path, contents: &str) -> anyhow::Result<()> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(dir)?;
+
+    let temp_path = temp_path(path);
+    std::fs::write(&temp_path, contents)?;
+    if let Err(e) = std::fs::rename(&temp_path, path) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+
+    Ok(())
+}
+
+/// Sends SIGHUP to the PgBouncer process recorded in `pidfile`.
+fn reload_with_sighup(pidfile: &Path) -> anyhow::Result<()> {
+    let pid = std::fs::read_to_string(pidfile)
+        .map_err(|e| anyhow::anyhow!("Failed to read the pidfile {}: {}", pidfile.display(), e))?;
+    let pid = pid
+        .trim()
+        .parse::<u32>()
+        .map_err(|_| anyhow::anyhow!("The pidfile {} does not contain a PID", pidfile.display()))?;
+
+    let status = std::process::Command::new("kill")
+        .args(["-HUP", &pid.to_string()])
+        .status()?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("Failed to send SIGHUP to PgBouncer (PID {}): kill exited with {}", pid, status));
+    }
+
+    Ok(())
+}
+
+/// Runs `RELOAD` on the admin console.
+async fn reload_with_admin(target: &AdminTarget) -> anyhow::Result<()> {
+    let mut client = AdminClient::connect(&target.host, target.port, &target.user, &target.password).await?;
+    client.reload().await?;
+    client.close().await?;
+
+    Ok(())
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "pgbouncer.ini".to_string());
+
+    path.with_file_name(format!(".{}.tmp", file_name))
+}
@@ -0,0 +1,52 @@
+use std::path::Path;
+use miette::{Diagnostic, GraphicalReportHandler};
+use pgbouncer_config::diagnostic::ConfigDiagnostic;
+use pgbouncer_config::error::PgBouncerError;
+use pgbouncer_config::pgbouncer_config::PgBouncerConfig;
+
+/// Renders a diagnostic with the snippet of its source, as miette prints reports.
+pub(crate) fn render(diagnostic: &dyn Diagnostic) -> String {
+    let mut out = String::new();
+    // Writing into a String cannot fail
+    let _ = GraphicalReportHandler::new().render_report(&mut out, diagnostic);
+    out
+}
+
+/// Formats a diagnostic on one line, with its help in parentheses.
+pub(crate) fn format_plain(diagnostic: &dyn Diagnostic) -> String {
+    let severity = match diagnostic.severity() {
+        Some(miette::Severity::Error) | None => "error",
+        Some(miette::Severity::Warning) => "warning",
+        Some(miette::Severity::Advice) => "advice",
+    };
+
+    match diagnostic.help() {
+        Some(help) => format!("{}: {} ({})", severity, diagnostic, help),
+        None => format!("{}: {}", severity, diagnostic),
+    }
+}
+
+/// Loads a config with `load`, printing a parse failure against the text of
+/// the file before returning it.
+pub(crate) fn load_or_report(
+    path: &str,
+    decorated: bool,
+    load: impl FnOnce(&Path) -> anyhow::Result<PgBouncerConfig>,
+) -> anyhow::Result<PgBouncerConfig> {
+    let error = match load(path.as_ref()) {
+        Ok(config) => return Ok(config),
+        Err(error) => error,
+    };
+
+    match (error.downcast_ref::<PgBouncerError>(), std::fs::read_to_string(path)) {
+        (Some(parse_error @ PgBouncerError::ParseError { .. }), Ok(text)) => {
+            let diagnostic = ConfigDiagnostic::from_error(parse_error, path, &text);
+            match decorated {
+                true => eprint!("{}", render(&diagnostic)),
+                false => eprintln!("{}: {}", path, format_plain(&diagnostic)),
+            }
+            Err(anyhow::anyhow!("Failed to parse {}", path))
+        },
+        _ => Err(error),
+    }
+}
@@ -0,0 +1,174 @@
+use std::io::{BufRead, Cursor, Write};
+use std::path::Path;
+use std::process::Command;
+use tempfile::NamedTempFile;
+use pgbouncer_config::io::ConfigFileFormat;
+use pgbouncer_config::io::read::Reader;
+use pgbouncer_config::pgbouncer_config::PgBouncerConfig;
+use pgbouncer_config::pgbouncer_config::validation::TargetVersion;
+
+/// Outcome of an interactive edit session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EditOutcome {
+    /// The edited definition was valid and has been written back.
+    Saved,
+    /// The user gave up after a validation failure; nothing was written.
+    Aborted,
+    /// The definition was not modified in the editor.
+    Unchanged,
+}
+
+/// Opens the definition file in the user's editor and persists it only after it validates.
+///
+/// The definition is copied to a temporary file, the editor is launched on the copy and,
+/// once the editor exits, the content is re-parsed and rendered. When it fails the errors
+/// are printed and the user is asked whether to re-open the editor; the original file is
+/// only replaced when the edited content is valid.
+///
+/// The copy holds the same credentials as the definition, so it is created readable by
+/// the owner only and removed on every return path.
+pub(crate) fn edit_definition(path: &Path, editor: Option<&str>) -> anyhow::Result<EditOutcome> {
+    let original = std::fs::read_to_string(path)?;
+    let editor = resolve_editor(editor);
+    let mut temp_file = temp_definition_file(path)?;
+    temp_file.write_all(original.as_bytes())?;
+    temp_file.flush()?;
+
+    loop {
+        launch_editor(&editor, temp_file.path())?;
+
+        let edited = std::fs::read_to_string(temp_file.path())?;
+        if edited == original {
+            return Ok(EditOutcome::Unchanged);
+        }
+
+        match validate_definition(&edited) {
+            Ok(_) => {
+                save_atomically(path, &edited)?;
+                return Ok(EditOutcome::Saved);
+            },
+            Err(e) => {
+                eprintln!("The edited definition is invalid: {}", e);
+                if !confirm("Re-open the editor to fix it?")? {
+                    return Ok(EditOutcome::Aborted);
+                }
+            }
+        }
+    }
+}
+
+/// Parses the definition text and renders it to make sure it can produce a pgbouncer.ini.
+///
+/// The semantic rules of [`PgBouncerConfig::validate`] are checked as well; see [`check_issues`].
+pub(crate) fn validate_definition(text: &str) -> anyhow::Result<PgBouncerConfig> {
+    let config = Reader::new(Cursor::new(text.as_bytes())).read_config(ConfigFileFormat::Auto)?;
+    config.expr()?;
+    check_issues(&config, None)?;
+
+    Ok(config)
+}
+
+/// Prints the warnings of [`PgBouncerConfig::validate`] and fails when any error is found.
+///
+/// With a target version, keys that the version does not support are errors as well
+/// (see [`PgBouncerConfig::validate_for`]).
+pub(crate) fn check_issues(config: &PgBouncerConfig, target_version: Option<TargetVersion>) -> anyhow::Result<()> {
+    let issues = match target_version {
+        Some(target_version) => config.validate_for(target_version),
+        None => config.validate(),
+    };
+    let (errors, warnings): (Vec<_>, Vec<_>) = issues
+        .into_iter()
+        .partition(|issue| issue.is_error());
+
+    for warning in &warnings {
+        eprintln!("{}", warning);
+    }
+    if !errors.is_empty() {
+        let errors = errors.iter().map(|issue| issue.to_string()).collect::<Vec<_>>();
+        return Err(anyhow::anyhow!("The definition is invalid:\n{}", errors.join("\n")));
+    }
+
+    Ok(())
+}
+
+fn resolve_editor(editor: Option<&str>) -> String {
+    if let Some(editor) = editor {
+        return editor.to_string();
+    }
+
+    std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string())
+}
+
+/// Creates the owner-only temporary copy edited in place of the definition; it is removed when dropped.
+fn temp_definition_file(path: &Path) -> anyhow::Result<NamedTempFile> {
+    let file_name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "pgbouncer_definition".to_string());
+
+    // Keep the extension so the editor highlights the format it is written in.
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_string())
+        .unwrap_or_else(|| "toml".to_string());
+
+    Ok(tempfile::Builder::new()
+        .prefix(&format!("{}.", file_name))
+        .suffix(&format!(".edit.{}", extension))
+        .tempfile()?)
+}
+
+/// Replaces the definition through a temporary file in the same directory renamed over it,
+/// so it is never left half written. The permissions of the definition are kept.
+fn save_atomically(path: &Path, contents: &str) -> anyhow::Result<()> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let mut temp_file = NamedTempFile::new_in(dir)?;
+    temp_file.write_all(contents.as_bytes())?;
+    temp_file.as_file().sync_all()?;
+    std::fs::set_permissions(temp_file.path(), std::fs::metadata(path)?.permissions())?;
+    temp_file.persist(path)?;
+
+    Ok(())
+}
+
+fn launch_editor(editor: &str, path: &Path) -> anyhow::Result<()> {
+    // The editor may carry its own arguments (e.g. `code --wait`).
+    let mut parts = editor.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("The editor command is empty"))?;
+
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()?;
+
+    if !status.success() {
+        return Err(anyhow::anyhow!("The editor exited with {}", status));
+    }
+
+    Ok(())
+}
+
+/// Asks a yes/no question; an empty answer is yes, but the end of the input is no
+/// so a closed stdin cannot re-open the editor forever.
+fn confirm(question: &str) -> anyhow::Result<bool> {
+    print!("{} [Y/n] ", question);
+    std::io::stdout().flush()?;
+
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer)? == 0 {
+        println!();
+        return Ok(false);
+    }
+    let answer = answer.trim().to_lowercase();
+
+    Ok(answer.is_empty() || answer == "y" || answer == "yes")
+}
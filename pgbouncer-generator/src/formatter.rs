@@ -1,7 +1,34 @@
 use std::fmt::Write;
 
+use clap::ValueEnum;
 use colored::Colorize;
-use pgbouncer_config::utils::diff::Diff;
+use pgbouncer_config::utils::diff::{Diff, SettingChange};
+
+/// How the diff command prints the diff.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DiffOutput {
+    /// Indented tree, optionally colored.
+    Text,
+    /// The diff serialized as JSON.
+    Json,
+}
+
+/// Result of the diff command.
+pub(crate) enum DiffReport {
+    /// Structured diff of the serialized configurations or auth files.
+    Tree(Diff),
+    /// Changes of the rendered pgbouncer.ini, setting by setting.
+    Settings(Vec<SettingChange>),
+}
+
+impl DiffReport {
+    pub(crate) fn has_changes(&self) -> bool {
+        match self {
+            DiffReport::Tree(diff) => diff.has_changes(),
+            DiffReport::Settings(changes) => !changes.is_empty(),
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct DisplayOptions {
@@ -26,6 +53,24 @@ pub(crate) fn format_diff(diff: &Diff, opt: DisplayOptions) -> String {
     output
 }
 
+/// Formats setting-by-setting changes as one `[section] key: old → new` line each.
+///
+/// Added settings are prefixed with `+`, removed ones with `-` and changed ones with `~`.
+pub(crate) fn format_setting_changes(changes: &[SettingChange], decoration: bool) -> String {
+    let mut output = String::new();
+    for change in changes {
+        let prefix = match (change.old_value(), change.new_value()) {
+            (None, _) => "+".green(),
+            (_, None) => "-".red(),
+            _ => "~".yellow(),
+        };
+        let prefix = if decoration { prefix.to_string() } else { prefix.clear().to_string() };
+        let _ = writeln!(output, "{}{}", prefix, change);
+    }
+
+    output
+}
+
 fn format_diff_inner(
     diff: &Diff,
     opt: DisplayOptions,
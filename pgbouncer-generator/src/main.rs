@@ -1,15 +1,43 @@
+mod apply;
+mod diagnostics;
 mod formatter;
+mod editor;
+mod profiles;
+mod schema;
+mod watch;
+mod wizard;
 
 use std::path::Path;
+use std::time::Duration;
 use clap::{Parser, Subcommand};
+use colored::Colorize;
 use pgbouncer_config::builder::PgBouncerConfigBuilder;
-use pgbouncer_config::io::ConfigFileFormat::TOML;
-use pgbouncer_config::io::read::{Reader, Readers};
-use pgbouncer_config::io::write::{Writer, Writers};
-use pgbouncer_config::pgbouncer_config::databases_setting::{Database, DatabasesSetting};
-use pgbouncer_config::pgbouncer_config::pgbouncer_setting::PgBouncerSetting;
+use pgbouncer_config::diagnostic::{deprecations, unknown_settings, ConfigDiagnostic};
+use pgbouncer_config::fleet::Fleet;
+use pgbouncer_config::io::ConfigFileFormat;
+use pgbouncer_config::io::compose::{listens_on_all_interfaces, ComposeService};
+use pgbouncer_config::io::helm::HelmValues;
+use pgbouncer_config::error::PgBouncerError;
+use pgbouncer_config::io::k8s::KubernetesManifests;
+use pgbouncer_config::io::read::{is_url, Reader, Readers};
+use pgbouncer_config::io::write::{rotate_backups, Writer, Writers};
+use pgbouncer_config::pgbouncer_config::auth_file::{AuthFile, RoleFilter};
+use pgbouncer_config::pgbouncer_config::document::Document;
+use pgbouncer_config::pgbouncer_config::databases_setting::{ClientTls, ConnectionOptions, Database, DatabaseFilter, DatabasesSetting, ImportEvent, ImportOptions, TunnelKind};
+use pgbouncer_config::pgbouncer_config::pgbouncer_setting::{auth_query_lookup, AuthType, PgBouncerSetting, SslMode};
 use pgbouncer_config::pgbouncer_config::PgBouncerConfig;
-use pgbouncer_config::utils::diff::{compute_diff_pg_config};
+use pgbouncer_config::pgbouncer_config::checksum::{embed_checksum, verify_checksum, ChecksumStatus, CHECKSUM_MARKER};
+use pgbouncer_config::pgbouncer_config::render::{Provenance, RenderOptions};
+use pgbouncer_config::utils::parser::{ParserIniFromStr, INCLUDE_DIRECTIVE};
+use pgbouncer_config::utils::retry::RetryPolicy;
+use pgbouncer_config::pgbouncer_config::settings_catalog::{find_setting, settings, SettingInfo};
+use pgbouncer_config::pgbouncer_config::lint::{LintRule, Linter};
+use pgbouncer_config::pgbouncer_config::profile::Profile;
+use pgbouncer_config::pgbouncer_config::validation::{Severity, TargetVersion, ValidationIssue};
+use pgbouncer_config::utils::diff::{compute_diff_auth_file, compute_diff_pg_config, compute_diff_pg_config_with_options, compute_ini_diff_with_options, DiffOptions};
+use pgbouncer_config::utils::git::read_config_at_revision;
+use pgbouncer_config::utils::secret::{SecretReference, SecretResolver, SecretResolvers};
+use pgbouncer_config::utils::vault::VaultSecretResolver;
 
 #[derive(Parser, Debug)]
 struct Cli {
@@ -42,6 +70,20 @@ enum Commands {
             default_value = "false",
         )]
         force_overwrite: bool,
+        #[clap(
+            help = "Ask for the listen address, port, auth type, pool mode and Postgres hosts interactively",
+            short,
+            long,
+            default_value = "false",
+            conflicts_with = "enable_ssh_tunnel",
+        )]
+        wizard: bool,
+        #[clap(
+            help = "Start from a pre-tuned [pgbouncer] preset (transaction-heavy, session-compat, rds-behind-nlb)",
+            long,
+            conflicts_with = "wizard",
+        )]
+        profile: Option<String>,
     },
     #[command(about = "Add a new postgres template to the definition file")]
     AddEmptyPgTemplate {
@@ -127,6 +169,26 @@ enum Commands {
             default_value = "false",
         )]
         is_output_credentials_to_config: bool,
+        #[clap(
+            help = "The sslmode used to connect to the Postgres when import (disable, allow, prefer, require, verify-ca or verify-full)",
+            long,
+        )]
+        sslmode: Option<String>,
+        #[clap(
+            help = "The root certificate file used to verify the Postgres server certificate",
+            long,
+        )]
+        sslrootcert: Option<String>,
+        #[clap(
+            help = "The client certificate file presented to the Postgres",
+            long,
+        )]
+        sslcert: Option<String>,
+        #[clap(
+            help = "The private key file of the client certificate",
+            long,
+        )]
+        sslkey: Option<String>,
         #[clap(
             help = "Allow to create a new definition file if the definition file does not exist when add Postgres",
             short,
@@ -135,6 +197,43 @@ enum Commands {
         )]
         allow_not_exist: bool,
     },
+    #[command(about = "List the Postgres entries of the definition file")]
+    ListPg {
+        #[clap(
+            help = "The path of the intermediate definition file",
+            short,
+            long,
+            default_value = "./generated/pgbouncer_definition.toml",
+        )]
+        path_def_file: String,
+    },
+    #[command(about = "Remove the Postgres entries matching the host, port and user from the definition file")]
+    RemovePg {
+        #[clap(
+            help = "The path of the intermediate definition file",
+            short,
+            long,
+            default_value = "./generated/pgbouncer_definition.toml",
+        )]
+        path_def_file: String,
+        #[clap(
+            help = "The host of the Postgres entry to remove",
+            long,
+        )]
+        host: String,
+        #[clap(
+            help = "The port of the Postgres entry to remove",
+            long,
+            default_value = "5432",
+        )]
+        port: u16,
+        #[clap(
+            help = "The user of the Postgres entry to remove",
+            short,
+            long,
+        )]
+        user: String,
+    },
     #[command(about = "Import databases from the Postgres host")]
     Import {
         #[clap(
@@ -153,8 +252,151 @@ enum Commands {
             num_args = 1..,
         )]
         target_postgres_host: Vec<String>,
+        #[clap(
+            help = "The maximum number of connection attempts per host",
+            long,
+            default_value = "3",
+        )]
+        retry_attempts: u32,
+        #[clap(
+            help = "The delay in milliseconds before the first retry, doubled for every further retry",
+            long,
+            default_value = "500",
+        )]
+        retry_delay_ms: u64,
+        #[clap(
+            help = "Only import databases whose name matches this regular expression",
+            long,
+        )]
+        include_databases: Option<String>,
+        #[clap(
+            help = "Skip databases whose name matches this regular expression (e.g. '^(postgres|rdsadmin)$')",
+            long,
+        )]
+        exclude_databases: Option<String>,
+        #[clap(
+            help = "Only import databases owned by these roles",
+            long,
+            value_parser,
+            value_delimiter = ' ',
+            num_args = 1..,
+        )]
+        owners: Vec<String>,
+        #[clap(
+            help = "Record the owner, encoding and size of the imported databases as comments in pgbouncer.ini",
+            long,
+            default_value = "false",
+        )]
+        with_metadata: bool,
+        #[clap(
+            help = "The maximum number of hosts to import from at the same time",
+            long,
+            default_value = "8",
+        )]
+        concurrency: usize,
+        #[clap(
+            help = "Give up on the hosts not done after this many seconds",
+            long,
+        )]
+        timeout_secs: Option<u64>,
+        #[clap(
+            help = "The application_name reported to the Postgres hosts (shown in pg_stat_activity)",
+            long,
+            default_value = "pgbouncer-config",
+        )]
+        application_name: String,
+        #[clap(
+            help = "Give up connecting to a host after this many seconds",
+            long,
+            default_value = "10",
+        )]
+        connect_timeout_secs: u64,
+        #[clap(
+            help = "The statement_timeout in milliseconds of the sessions on the Postgres hosts",
+            long,
+        )]
+        statement_timeout_ms: Option<u64>,
+        #[clap(
+            help = "Also import the login roles and their password hashes into userlist.txt over the same connections",
+            long,
+            default_value = "false",
+        )]
+        with_users: bool,
+        #[clap(
+            help = "The path of the userlist.txt to write with --with-users (defaults to auth_file of the definition)",
+            long,
+        )]
+        userlist: Option<String>,
+        #[clap(
+            help = "Only import roles whose name matches this regular expression (with --with-users)",
+            long,
+        )]
+        include_roles: Option<String>,
+        #[clap(
+            help = "Skip roles whose name matches this regular expression (with --with-users)",
+            long,
+        )]
+        exclude_roles: Option<String>,
+    },
+    #[command(about = "Import login roles and their password hashes from the Postgres hosts into userlist.txt")]
+    ImportUsers {
+        #[clap(
+            help = "The path of the intermediate definition file",
+            short,
+            long,
+            default_value = "./generated/pgbouncer_definition.toml",
+        )]
+        path_def_file: String,
+        #[clap(
+            help = "The path of the userlist.txt to write (defaults to auth_file of the definition)",
+            short,
+            long,
+        )]
+        output: Option<String>,
+        #[clap(
+            help = "Only import roles whose name matches this regular expression",
+            long,
+        )]
+        include_roles: Option<String>,
+        #[clap(
+            help = "Skip roles whose name matches this regular expression (e.g. '^(postgres|rds.*)$')",
+            long,
+        )]
+        exclude_roles: Option<String>,
+        #[clap(
+            help = "Replace the existing userlist.txt instead of updating the imported users in it",
+            long,
+            default_value = "false",
+        )]
+        replace: bool,
+    },
+    #[command(about = "Connect to the backend of every [databases] line and report unreachable hosts and authentication problems")]
+    Check {
+        #[clap(
+            help = "The path of the intermediate definition file",
+            short,
+            long,
+            default_value = "./generated/pgbouncer_definition.toml",
+        )]
+        path_def_file: String,
+        #[clap(
+            help = "Connect through the SSH tunnels of the definition (PgBouncer itself connects to the hosts directly)",
+            long,
+            default_value = "false",
+        )]
+        ssh_tunnel: bool,
+        #[clap(
+            help = "Flag if decorate the output or not",
+            short,
+            long,
+            default_value = "false",
+        )]
+        disable_decorated_output: bool,
     },
-    #[command(about = "Display the difference between definition file and current pgbouncer.ini file")]
+    #[command(
+        about = "Display the difference between definition file and current pgbouncer.ini file",
+        long_about = "Display the difference between definition file and current pgbouncer.ini file.\n\nExits with 0 when there is no difference, 1 when differences exist and 2 on errors.",
+    )]
     Diff {
         #[clap(
             help = "The path of the intermediate definition file",
@@ -191,6 +433,62 @@ enum Commands {
             default_value = "false",
         )]
         show_same: bool,
+        #[clap(
+            help = "Compare the definition at this git revision (e.g. HEAD~1) with the working copy instead of the pgbouncer.ini file",
+            long,
+        )]
+        against_git: Option<String>,
+        #[clap(
+            help = "Compare the userlist.txt at this path with the users the definition would write, instead of the pgbouncer.ini file",
+            long,
+            conflicts_with = "against_git",
+        )]
+        auth_file: Option<String>,
+        #[clap(
+            help = "The format of the diff output",
+            short,
+            long,
+            value_enum,
+            default_value = "text",
+        )]
+        output: formatter::DiffOutput,
+        #[clap(
+            help = "Compare the rendered pgbouncer.ini setting by setting ([databases] app.host: old → new) instead of the structured diff",
+            long,
+            default_value = "false",
+            conflicts_with = "auth_file",
+        )]
+        by_setting: bool,
+        #[clap(
+            help = "Replace the values of fields named password, auth or key with *** (changes are still reported)",
+            long,
+            default_value = "false",
+        )]
+        mask_secrets: bool,
+        #[clap(
+            help = "Only show entries whose dotted path matches one of these patterns (* matches anything, e.g. pgbouncer.*)",
+            long,
+            value_parser,
+            value_delimiter = ' ',
+            num_args = 1..,
+        )]
+        include: Vec<String>,
+        #[clap(
+            help = "Ignore entries whose dotted path matches one of these patterns (* matches anything, e.g. *.password)",
+            long,
+            value_parser,
+            value_delimiter = ' ',
+            num_args = 1..,
+        )]
+        exclude: Vec<String>,
+        #[clap(
+            help = "Partial definitions merged on top of the definition file in order (e.g. prod.toml), later layers override earlier ones",
+            long,
+            value_parser,
+            value_delimiter = ' ',
+            num_args = 1..,
+        )]
+        overlay: Vec<String>,
     },
     #[command(about = "Generate pgbouncer.ini file from the definition file")]
     Generate {
@@ -215,41 +513,480 @@ enum Commands {
             default_value = "false",
         )]
         disallow_overwrite: bool,
+        #[clap(
+            help = "Generate one pgbouncer.ini per profile definition found in --profiles-dir",
+            long,
+            default_value = "false",
+        )]
+        all_profiles: bool,
+        #[clap(
+            help = "The directory containing one definition file (<profile>.toml) per environment profile",
+            long,
+            default_value = "./generated/profiles",
+        )]
+        profiles_dir: String,
+        #[clap(
+            help = "The directory to write <profile>/pgbouncer.ini files and the manifest into",
+            long,
+            default_value = "./generated",
+        )]
+        out_dir: String,
+        #[clap(
+            help = "Sections to write into <section>.ini next to the pgbouncer.ini file, referenced with %include",
+            long,
+            value_parser,
+            value_delimiter = ' ',
+            num_args = 1..,
+            conflicts_with = "all_profiles",
+        )]
+        include_sections: Vec<String>,
+        #[clap(
+            help = "Update an existing pgbouncer.ini in place, keeping comments, blank lines and key order",
+            long,
+            default_value = "false",
+            conflicts_with_all = ["include_sections", "all_profiles"],
+        )]
+        lossless: bool,
+        #[clap(
+            help = "Reject keys not supported by this PgBouncer version (e.g. 1.18)",
+            long,
+        )]
+        target_version: Option<String>,
+        #[clap(
+            help = "Also write a userlist.txt with the credentials of the database entries to this path, hashed for auth_type",
+            long,
+            conflicts_with = "all_profiles",
+        )]
+        auth_file: Option<String>,
+        #[clap(
+            help = "Keep running and regenerate pgbouncer.ini every time the definition file changes",
+            short,
+            long,
+            default_value = "false",
+            conflicts_with = "all_profiles",
+        )]
+        watch: bool,
+        #[clap(
+            help = "Keep this many previous pgbouncer.ini files as pgbouncer.ini.bak.N (1 is the most recent) before overwriting",
+            long,
+            default_value = "0",
+            conflicts_with = "all_profiles",
+        )]
+        backups: usize,
+        #[clap(
+            help = "Start pgbouncer.ini with a comment recording the generator version, the generation time and the definition path and SHA-256",
+            long,
+            default_value = "false",
+            conflicts_with_all = ["include_sections", "lossless", "all_profiles"],
+        )]
+        provenance: bool,
+        #[clap(
+            help = "Start pgbouncer.ini with a SHA-256 checksum comment so manual edits can be detected with the verify subcommand",
+            long,
+            default_value = "false",
+            conflicts_with_all = ["include_sections", "all_profiles"],
+        )]
+        checksum: bool,
+        #[clap(
+            help = "Partial definitions merged on top of the definition file in order (e.g. prod.toml), later layers override earlier ones",
+            long,
+            value_parser,
+            value_delimiter = ' ',
+            num_args = 1..,
+            conflicts_with = "all_profiles",
+        )]
+        overlay: Vec<String>,
+        #[command(flatten)]
+        reload: apply::ReloadArgs,
     },
-}
-
-
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    let args = Cli::parse();
-
-    match args.command {
-        Commands::Init { path_def_file, enable_ssh_tunnel, force_overwrite } => {
-            let path: &Path = path_def_file.as_str().as_ref();
-            if path.exists() && !force_overwrite {
-                return Err(anyhow::anyhow!("The definition file already exists"));
-            }
-
-            let pgbouncer_setting = PgBouncerSetting::default();
-            let mut db_setting = DatabasesSetting::new();
-            if enable_ssh_tunnel {
-                db_setting.add_empty_database_with_tunnel();
-            } else {
-                db_setting.add_empty_database();
-            }
-
-            let pgbouncer_config = PgBouncerConfigBuilder::builder()
-                .set_pgbouncer_setting(pgbouncer_setting)?
-                .set_databases_setting(db_setting)?
-                .build();
-
-            let mut writer = Writer::try_from(Writers::File(path))?;
-            writer.write_config(&pgbouncer_config, TOML)?;
-
-            Ok(())
-        },
-        Commands::AddEmptyPgTemplate { path_def_file, enable_ssh_tunnel, allow_not_exist } => {
-            let path: &Path = path_def_file.as_str().as_ref();
+    #[command(about = "Write pgbouncer.ini atomically from the definition file and reload the running PgBouncer")]
+    Apply {
+        #[clap(
+            help = "The path of the intermediate definition file",
+            short = 'd',
+            long,
+            default_value = "./generated/pgbouncer_definition.toml",
+        )]
+        path_def_file: String,
+        #[clap(
+            help = "The path of the pgbouncer.ini file read by the running PgBouncer",
+            short = 'c',
+            long,
+            default_value = "./generated/pgbouncer.ini",
+        )]
+        path_pgbouncer_ini: String,
+        #[command(flatten)]
+        reload: apply::ReloadArgs,
+        #[clap(
+            help = "Print the difference with the current pgbouncer.ini instead of writing and reloading",
+            long,
+            default_value = "false",
+        )]
+        dry_run: bool,
+        #[clap(
+            help = "Flag if decorate the diff output or not",
+            long,
+            default_value = "false",
+        )]
+        disable_decorated_output: bool,
+    },
+    #[command(about = "Edit the definition file in $EDITOR and save it only if it is valid")]
+    Edit {
+        #[clap(
+            help = "The path of the intermediate definition file",
+            short,
+            long,
+            default_value = "./generated/pgbouncer_definition.toml",
+        )]
+        path_def_file: String,
+        #[clap(
+            help = "The editor command to use instead of $VISUAL/$EDITOR",
+            short,
+            long,
+        )]
+        editor: Option<String>,
+    },
+    #[command(about = "Explain a PgBouncer setting (description, type, default and version availability)")]
+    Explain {
+        #[clap(
+            help = "The name of the setting to explain (e.g. pool_mode)",
+            required_unless_present = "all",
+        )]
+        setting: Option<String>,
+        #[clap(
+            help = "Explain all settings known to the catalog",
+            short,
+            long,
+            default_value = "false",
+        )]
+        all: bool,
+    },
+    #[command(about = "Print the schema of the intermediate definition file (JSON Schema or a key table)")]
+    PrintSchema {
+        #[clap(
+            help = "The output format of the schema",
+            short,
+            long,
+            value_enum,
+            default_value = "json",
+        )]
+        format: schema::SchemaOutput,
+    },
+    #[command(about = "Print the SQL creating the SECURITY DEFINER lookup function for auth_query")]
+    AuthQuerySql {
+        #[clap(
+            help = "The path of the intermediate definition file",
+            short,
+            long,
+            default_value = "./generated/pgbouncer_definition.toml",
+        )]
+        path_def_file: String,
+        #[clap(
+            help = "The schema that holds the lookup function",
+            short,
+            long,
+            default_value = "pgbouncer",
+        )]
+        schema: String,
+    },
+    #[command(about = "Generate Kubernetes ConfigMap/Secret manifests holding pgbouncer.ini and userlist.txt")]
+    Kubernetes {
+        #[clap(
+            help = "The path of the intermediate definition file",
+            short,
+            long,
+            default_value = "./generated/pgbouncer_definition.toml",
+        )]
+        path_def_file: String,
+        #[clap(
+            help = "The application name; objects are named <name>-config and <name>-userlist",
+            long,
+            default_value = "pgbouncer",
+        )]
+        name: String,
+        #[clap(
+            help = "The namespace of the objects (defaults to the namespace of the kubectl context)",
+            short,
+            long,
+        )]
+        namespace: Option<String>,
+        #[clap(
+            help = "Also generate a Secret with the userlist.txt of the database entries, hashed for auth_type",
+            long,
+            default_value = "false",
+        )]
+        with_auth_file: bool,
+        #[clap(
+            help = "The path of the manifest file to write (defaults to stdout)",
+            short,
+            long,
+        )]
+        output: Option<String>,
+    },
+    #[command(about = "Generate values.yaml for the Bitnami pgbouncer Helm chart")]
+    HelmValues {
+        #[clap(
+            help = "The path of the intermediate definition file",
+            short,
+            long,
+            default_value = "./generated/pgbouncer_definition.toml",
+        )]
+        path_def_file: String,
+        #[clap(
+            help = "The pgbouncer image tag (defaults to the chart default)",
+            long,
+        )]
+        image_tag: Option<String>,
+        #[clap(
+            help = "An existing Secret whose keys are added as environment variables (e.g. POSTGRESQL_PASSWORD)",
+            long,
+        )]
+        extra_env_vars_secret: Option<String>,
+        #[clap(
+            help = "The path of the values file to write (defaults to stdout)",
+            short,
+            long,
+        )]
+        output: Option<String>,
+    },
+    #[command(about = "Generate a docker-compose service (or Dockerfile) running PgBouncer with the generated files")]
+    Compose {
+        #[clap(
+            help = "The path of the intermediate definition file",
+            short,
+            long,
+            default_value = "./generated/pgbouncer_definition.toml",
+        )]
+        path_def_file: String,
+        #[clap(
+            help = "The name of the compose service",
+            long,
+            default_value = "pgbouncer",
+        )]
+        service_name: String,
+        #[clap(
+            help = "The PgBouncer image reading /etc/pgbouncer/pgbouncer.ini",
+            long,
+            default_value = "edoburu/pgbouncer:latest",
+        )]
+        image: String,
+        #[clap(
+            help = "The path of the generated pgbouncer.ini, relative to the compose file",
+            long,
+            default_value = "./pgbouncer.ini",
+        )]
+        config_source: String,
+        #[clap(
+            help = "The path of the generated userlist.txt, relative to the compose file, mounted at auth_file",
+            long,
+        )]
+        auth_file_source: Option<String>,
+        #[clap(
+            help = "Print a Dockerfile copying the files into the image instead of a compose service",
+            long,
+            default_value = "false",
+        )]
+        dockerfile: bool,
+        #[clap(
+            help = "The path of the file to write (defaults to stdout)",
+            short,
+            long,
+        )]
+        output: Option<String>,
+    },
+    #[command(about = "Validate the definition file (and optionally a pgbouncer.ini file) and fail if any error is found")]
+    Validate {
+        #[clap(
+            help = "The path of the intermediate definition file",
+            short,
+            long,
+            default_value = "./generated/pgbouncer_definition.toml",
+        )]
+        path_def_file: String,
+        #[clap(
+            help = "The path of a pgbouncer.ini file to validate as well",
+            short = 'c',
+            long,
+        )]
+        path_pgbouncer_ini: Option<String>,
+        #[clap(
+            help = "Also report keys not supported by this PgBouncer version (e.g. 1.18) as errors",
+            long,
+        )]
+        target_version: Option<String>,
+        #[clap(
+            help = "Flag if decorate the output or not",
+            short,
+            long,
+            default_value = "false",
+        )]
+        disable_decorated_output: bool,
+    },
+    #[command(about = "Check the definition file (and optionally a pgbouncer.ini file) against best-practice rules")]
+    Lint {
+        #[clap(
+            help = "The path of the intermediate definition file",
+            short,
+            long,
+            default_value = "./generated/pgbouncer_definition.toml",
+        )]
+        path_def_file: String,
+        #[clap(
+            help = "The path of a pgbouncer.ini file to lint as well",
+            short = 'c',
+            long,
+        )]
+        path_pgbouncer_ini: Option<String>,
+        #[clap(
+            help = "The PgBouncer version (e.g. 1.20) version-dependent rules check against, the latest by default",
+            long,
+        )]
+        target_version: Option<String>,
+        #[clap(
+            help = "Only check these rules (e.g. plaintext-credentials trust-on-network)",
+            long,
+            value_parser,
+            value_delimiter = ' ',
+            num_args = 1..,
+        )]
+        enable: Vec<String>,
+        #[clap(
+            help = "Skip these rules (e.g. missing-admin-users)",
+            long,
+            value_parser,
+            value_delimiter = ' ',
+            num_args = 1..,
+        )]
+        disable: Vec<String>,
+        #[clap(
+            help = "List the rules with their descriptions and exit",
+            long,
+            default_value = "false",
+        )]
+        list_rules: bool,
+        #[clap(
+            help = "Flag if decorate the output or not",
+            short,
+            long,
+            default_value = "false",
+        )]
+        disable_decorated_output: bool,
+    },
+    #[command(about = "Rewrite an existing pgbouncer.ini in canonical key order and spacing (comments are dropped)")]
+    Fmt {
+        #[clap(
+            help = "The path of the pgbouncer.ini file",
+            short = 'c',
+            long,
+            default_value = "./generated/pgbouncer.ini",
+        )]
+        path_pgbouncer_ini: String,
+        #[clap(
+            help = "Overwrite the pgbouncer.ini file instead of printing the formatted text",
+            short,
+            long,
+            default_value = "false",
+        )]
+        in_place: bool,
+        #[clap(
+            help = "Write nothing and fail if the pgbouncer.ini file is not formatted",
+            long,
+            default_value = "false",
+            conflicts_with = "in_place",
+        )]
+        check: bool,
+    },
+    #[command(about = "Check that a pgbouncer.ini generated with --checksum was not edited since generation")]
+    Verify {
+        #[clap(
+            help = "The path of the pgbouncer.ini file",
+            short = 'c',
+            long,
+            default_value = "./generated/pgbouncer.ini",
+        )]
+        path_pgbouncer_ini: String,
+    },
+    #[command(about = "Generate pgbouncer.ini for every instance of a fleet inventory and report their differences")]
+    Fleet {
+        #[clap(
+            help = "The path of the fleet inventory file",
+            short,
+            long,
+            default_value = "./generated/inventory.toml",
+        )]
+        inventory: String,
+        #[clap(
+            help = "The directory to write <instance>/pgbouncer.ini files to",
+            short,
+            long,
+            default_value = "./generated/fleet",
+        )]
+        out_dir: String,
+        #[clap(
+            help = "The instance the others are compared with (defaults to the first instance)",
+            long,
+        )]
+        diff_against: Option<String>,
+        #[clap(
+            help = "Validate and report the differences without writing any file",
+            long,
+            default_value = "false",
+        )]
+        dry_run: bool,
+        #[clap(
+            help = "Flag if decorate the diff output or not",
+            short,
+            long,
+            default_value = "false",
+        )]
+        disable_decorated_output: bool,
+    },
+}
+
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Cli::parse();
+
+    match args.command {
+        Commands::Init { path_def_file, enable_ssh_tunnel, force_overwrite, wizard, profile } => {
+            let path: &Path = path_def_file.as_str().as_ref();
+            if path.exists() && !force_overwrite {
+                return Err(anyhow::anyhow!("The definition file already exists"));
+            }
+
+            if wizard {
+                let pgbouncer_config = wizard::run_wizard(&mut std::io::stdin().lock(), &mut std::io::stdout())?;
+                let mut writer = Writer::try_from(Writers::File(path))?;
+                writer.write_config(&pgbouncer_config, definition_format(path)?)?;
+                println!("\nWrote {}", path.display());
+
+                return Ok(());
+            }
+
+            let pgbouncer_setting = match profile {
+                Some(profile) => Profile::try_from(profile.as_str())?.setting(),
+                None => PgBouncerSetting::default(),
+            };
+            let mut db_setting = DatabasesSetting::new();
+            if enable_ssh_tunnel {
+                db_setting.add_empty_database_with_tunnel();
+            } else {
+                db_setting.add_empty_database();
+            }
+
+            let pgbouncer_config = PgBouncerConfigBuilder::builder()
+                .set_pgbouncer_setting(pgbouncer_setting)?
+                .set_databases_setting(db_setting)?
+                .build();
+
+            let mut writer = Writer::try_from(Writers::File(path))?;
+            writer.write_config(&pgbouncer_config, definition_format(path)?)?;
+
+            Ok(())
+        },
+        Commands::AddEmptyPgTemplate { path_def_file, enable_ssh_tunnel, allow_not_exist } => {
+            let path: &Path = path_def_file.as_str().as_ref();
             let mut current_setting = load_config_from_definition(path, allow_not_exist)?;
 
             let db_setting = current_setting.get_config_mut::<DatabasesSetting>()?;
@@ -259,7 +996,7 @@ async fn main() -> anyhow::Result<()> {
                 db_setting.add_empty_database();
             }
             let mut writer = Writer::try_from(Writers::File(path))?;
-            writer.write_config(&current_setting, TOML)?;
+            writer.write_config(&current_setting, definition_format(path)?)?;
 
             Ok(())
         },
@@ -272,6 +1009,10 @@ async fn main() -> anyhow::Result<()> {
             databases,
             ignore_databases,
             is_output_credentials_to_config,
+            sslmode,
+            sslrootcert,
+            sslcert,
+            sslkey,
             allow_not_exist
         } => {
             let path: &Path = path_def_file.as_str().as_ref();
@@ -282,25 +1023,188 @@ async fn main() -> anyhow::Result<()> {
                 database.add_ignore_database(ignore_database.as_str());
             }
             database.set_is_output_credentials_to_config(is_output_credentials_to_config);
+            if sslmode.is_some() || sslrootcert.is_some() || sslcert.is_some() || sslkey.is_some() {
+                let sslmode = match sslmode {
+                    Some(sslmode) => SslMode::try_from(sslmode.as_str())?,
+                    None => SslMode::Prefer,
+                };
+                let mut tls = ClientTls::new(sslmode);
+                tls.set_root_cert(sslrootcert)
+                    .set_client_cert(sslcert)
+                    .set_client_key(sslkey);
+                database.set_tls(Some(tls));
+            }
 
             let db_setting = current_setting.get_config_mut::<DatabasesSetting>()?;
             db_setting.add_database(database);
 
             let mut writer = Writer::try_from(Writers::File(path))?;
-            writer.write_config(&current_setting, TOML)?;
+            writer.write_config(&current_setting, definition_format(path)?)?;
+
+            Ok(())
+        },
+        Commands::ListPg { path_def_file } => {
+            let definition = load_config_from_definition(path_def_file.as_ref(), false)?;
+            let db_setting = definition.get_config::<DatabasesSetting>()?;
+
+            for database in db_setting.iter() {
+                let databases = if database.databases().is_empty() {
+                    "(none, run import)".to_string()
+                } else {
+                    database.databases().join(" ")
+                };
+                let hosts = database.hosts().iter().map(|host| host.as_str()).collect::<Vec<_>>();
+                let mut line = format!("{}:{} user={} databases={}", hosts.join(","), database.port(), database.user(), databases);
+                if !database.ignore_databases().is_empty() {
+                    line.push_str(&format!(" ignored={}", database.ignore_databases().join(" ")));
+                }
+                match database.tunnel() {
+                    Some(TunnelKind::Ssh(_)) => line.push_str(" (via SSH tunnel)"),
+                    Some(TunnelKind::Proxy(proxy)) => line.push_str(&format!(" (via proxy {}:{})", proxy.host(), proxy.port())),
+                    None => {},
+                }
+                println!("{}", line);
+            }
+            if let Some(fallback) = db_setting.fallback() {
+                println!("* -> {}:{}", fallback.host(), fallback.port());
+            }
 
             Ok(())
         },
-        Commands::Import { path_def_file, target_postgres_host } => {
+        Commands::RemovePg { path_def_file, host, port, user } => {
             let path: &Path = path_def_file.as_str().as_ref();
             let mut current_setting = load_config_from_definition(path, false)?;
 
             let db_setting = current_setting.get_config_mut::<DatabasesSetting>()?;
+            let removed = db_setting.remove_database(&host, port, &user);
+            if removed.is_empty() {
+                return Err(anyhow::anyhow!("No Postgres entry matches {}:{} user={}", host, port, user));
+            }
 
-            db_setting.add_database_from_hosts(get_option_vec_str(&target_postgres_host).as_deref()).await?;
+            let mut writer = Writer::try_from(Writers::File(path))?;
+            writer.write_config(&current_setting, definition_format(path)?)?;
+            println!("Removed {} entry(ies) for {}:{} user={}", removed.len(), host, port, user);
 
+            Ok(())
+        },
+        Commands::Import {
+            path_def_file,
+            target_postgres_host,
+            retry_attempts,
+            retry_delay_ms,
+            include_databases,
+            exclude_databases,
+            owners,
+            with_metadata,
+            concurrency,
+            timeout_secs,
+            application_name,
+            connect_timeout_secs,
+            statement_timeout_ms,
+            with_users,
+            userlist,
+            include_roles,
+            exclude_roles,
+        } => {
+            let path: &Path = path_def_file.as_str().as_ref();
+            let mut definition = load_config_from_definition(path, false)?;
+            // The connections need the secrets, but the definition keeps their references
+            let mut current_setting = definition.clone();
+            current_setting.resolve_secrets(&secret_resolvers()?)?;
+            let pgbouncer_setting = current_setting.get_config::<PgBouncerSetting>()?;
+            let auth_type = pgbouncer_setting.auth_type();
+            let (userlist, roles) = if with_users {
+                let mut roles = RoleFilter::new();
+                roles
+                    .set_include(include_roles.as_deref())?
+                    .set_exclude(exclude_roles.as_deref())?;
+                (Some(userlist_path(pgbouncer_setting, userlist, "userlist")?), Some(roles))
+            } else {
+                (None, None)
+            };
+
+            let db_setting = current_setting.get_config_mut::<DatabasesSetting>()?;
+            if with_metadata {
+                db_setting.set_import_metadata(true);
+            }
+            let mut retry = RetryPolicy::new();
+            retry
+                .set_attempts(retry_attempts)
+                .set_initial_delay(Duration::from_millis(retry_delay_ms));
+            let mut filter = DatabaseFilter::new();
+            filter
+                .set_include(include_databases.as_deref())?
+                .set_exclude(exclude_databases.as_deref())?
+                .set_owners(&get_option_vec_str(&owners).unwrap_or_default());
+            let mut connection = ConnectionOptions::new();
+            connection
+                .set_application_name(&application_name)
+                .set_connect_timeout(Duration::from_secs(connect_timeout_secs))
+                .set_statement_timeout(statement_timeout_ms.map(Duration::from_millis));
+            let mut options = ImportOptions::new();
+            options
+                .set_retry(retry)
+                .set_filter(filter)
+                .set_concurrency(concurrency)
+                .set_timeout(timeout_secs.map(Duration::from_secs))
+                .set_connection(connection)
+                .set_roles(roles);
+
+            // The databases found on the reachable hosts are written even if some hosts failed
+            let result = db_setting
+                .add_database_and_roles_from_hosts_with_progress(get_option_vec_str(&target_postgres_host).as_deref(), &options, |event| {
+                    match event {
+                        ImportEvent::HostFailed { .. } => eprintln!("{}", event.to_string().red()),
+                        ImportEvent::Retrying { .. } => eprintln!("{}", event.to_string().yellow()),
+                        event => eprintln!("{}", event),
+                    }
+                })
+                .await;
+
+            merge_imported_databases(&mut definition, &current_setting)?;
             let mut writer = Writer::try_from(Writers::File(path))?;
-            writer.write_config(&current_setting, TOML)?;
+            writer.write_config(&definition, definition_format(path)?)?;
+
+            let imported = result?;
+            if let Some(userlist) = userlist {
+                write_imported_users(userlist.as_ref(), &imported, auth_type, false)?;
+            }
+
+            Ok(())
+        },
+        Commands::ImportUsers { path_def_file, output, include_roles, exclude_roles, replace } => {
+            let definition = load_resolved_definition(path_def_file.as_ref())?;
+            let pgbouncer_setting = definition.get_config::<PgBouncerSetting>()?;
+            let output = userlist_path(pgbouncer_setting, output, "output")?;
+
+            let mut filter = RoleFilter::new();
+            filter
+                .set_include(include_roles.as_deref())?
+                .set_exclude(exclude_roles.as_deref())?;
+            let imported = AuthFile::import_roles(definition.get_config::<DatabasesSetting>()?, &filter).await?;
+            write_imported_users(output.as_ref(), &imported, pgbouncer_setting.auth_type(), replace)?;
+
+            Ok(())
+        },
+        Commands::Check { path_def_file, ssh_tunnel, disable_decorated_output } => {
+            let definition = load_resolved_definition(path_def_file.as_ref())?;
+            let checks = definition.get_config::<DatabasesSetting>()?.check_connectivity(ssh_tunnel).await;
+
+            for check in &checks {
+                let mark = match (check.is_ok(), disable_decorated_output) {
+                    (true, true) => "OK".to_string(),
+                    (false, true) => "NG".to_string(),
+                    (true, false) => "OK".green().bold().to_string(),
+                    (false, false) => "NG".red().bold().to_string(),
+                };
+                println!("{} {}", mark, check);
+            }
+
+            let failures = checks.iter().filter(|check| !check.is_ok()).count();
+            println!("{} route(s) checked, {} failed", checks.len(), failures);
+            if failures > 0 {
+                return Err(anyhow::anyhow!("{} route(s) cannot be connected", failures));
+            }
 
             Ok(())
         },
@@ -309,49 +1213,639 @@ async fn main() -> anyhow::Result<()> {
             path_pgbouncer_ini,
             disable_decorated_output,
             max_diff_depth,
-            show_same
+            show_same,
+            against_git,
+            auth_file,
+            output,
+            by_setting,
+            mask_secrets,
+            include,
+            exclude,
+            overlay,
         } => {
             let path: &Path = path_def_file.as_str().as_ref();
             let path_pgbouncer_ini: &Path = path_pgbouncer_ini.as_str().as_ref();
 
-            let definition = load_config_from_definition(path, false)?;
-            let current_ini = load_config_from_ini(path_pgbouncer_ini)?;
+            let include = include.iter().map(String::as_str).collect::<Vec<_>>();
+            let exclude = exclude.iter().map(String::as_str).collect::<Vec<_>>();
+            let mut options = DiffOptions::new();
+            options
+                .set_mask_secrets(mask_secrets)
+                .set_include(&include)
+                .set_exclude(&exclude);
+            let report = (|| -> anyhow::Result<_> {
+                if let Some(auth_file) = auth_file {
+                    let definition = load_layered_definition(path, &overlay)?;
+                    let current = load_auth_file(auth_file.as_ref())?;
+                    let diff = compute_diff_auth_file(&current, &desired_auth_file(&definition, &current)?);
+                    return Ok(formatter::DiffReport::Tree(diff.with_options(&options)));
+                }
 
-            let diff = compute_diff_pg_config(&current_ini, &definition)?;
+                let (old, new) = if let Some(revision) = against_git {
+                    // The overlays of the working copy are applied to both sides
+                    let mut committed = read_config_at_revision(path, &revision, definition_format(path)?)?;
+                    apply_overlays(&mut committed, &overlay)?;
+                    let mut current = load_config_from_definition(path, false)?;
+                    apply_overlays(&mut current, &overlay)?;
+                    (committed, current)
+                } else {
+                    (load_config_from_ini(path_pgbouncer_ini)?, load_layered_definition(path, &overlay)?)
+                };
 
-            let opts = formatter::DisplayOptions::new(
-                !disable_decorated_output,
-                show_same,
-                max_diff_depth
-            );
-            let formatted_diff = formatter::format_diff(&diff, opts);
-            println!("{}", formatted_diff);
+                if by_setting {
+                    Ok(formatter::DiffReport::Settings(compute_ini_diff_with_options(&old, &new, &options)?))
+                } else {
+                    Ok(formatter::DiffReport::Tree(compute_diff_pg_config_with_options(&old, &new, &options)?))
+                }
+            })();
+            // Follow diff(1): 0 when identical, 1 when different and 2 on errors
+            let report = match report {
+                Ok(report) => report,
+                Err(e) => {
+                    eprintln!("Error: {:?}", e);
+                    std::process::exit(2);
+                },
+            };
+
+            match (output, &report) {
+                (formatter::DiffOutput::Text, formatter::DiffReport::Tree(diff)) => {
+                    let opts = formatter::DisplayOptions::new(
+                        !disable_decorated_output,
+                        show_same,
+                        max_diff_depth
+                    );
+                    println!("{}", formatter::format_diff(diff, opts));
+                },
+                (formatter::DiffOutput::Text, formatter::DiffReport::Settings(changes)) => {
+                    print!("{}", formatter::format_setting_changes(changes, !disable_decorated_output));
+                },
+                (formatter::DiffOutput::Json, formatter::DiffReport::Tree(diff)) => println!("{}", serde_json::to_string_pretty(diff)?),
+                (formatter::DiffOutput::Json, formatter::DiffReport::Settings(changes)) => println!("{}", serde_json::to_string_pretty(changes)?),
+            }
+
+            if report.has_changes() {
+                std::process::exit(1);
+            }
 
             Ok(())
         },
-        Commands::Generate { path_def_file, path_pgbouncer_ini, disallow_overwrite } => {
+        Commands::Generate {
+            path_def_file,
+            path_pgbouncer_ini,
+            disallow_overwrite,
+            all_profiles,
+            profiles_dir,
+            out_dir,
+            include_sections,
+            lossless,
+            target_version,
+            auth_file,
+            watch,
+            backups,
+            provenance,
+            checksum,
+            overlay,
+            reload,
+        } => {
+            if reload.reload.is_some() && !watch {
+                return Err(anyhow::anyhow!("--reload is only used with --watch, use the apply subcommand to reload after a single generation"));
+            }
+
+            let target_version = target_version
+                .as_deref()
+                .map(TargetVersion::try_from)
+                .transpose()?;
+
+            if all_profiles {
+                let profiles = profiles::discover_profiles(profiles_dir.as_ref())?;
+                if profiles.is_empty() {
+                    return Err(anyhow::anyhow!("No profile definitions found in {}", profiles_dir));
+                }
+
+                let generated = profiles::generate_profiles(&profiles, out_dir.as_ref(), disallow_overwrite, target_version)?;
+                for g in &generated {
+                    println!("{}: {}", g.profile.name, g.output.display());
+                }
+                println!("Manifest: {}", Path::new(&out_dir).join(profiles::MANIFEST_FILE_NAME).display());
+
+                return Ok(());
+            }
+
             let path: &Path = path_def_file.as_str().as_ref();
             let path_pgbouncer_ini: &Path = path_pgbouncer_ini.as_str().as_ref();
             if path_pgbouncer_ini.exists() && disallow_overwrite {
                 return Err(anyhow::anyhow!("The pgbouncer.ini file already exists, if you want to overwrite it, please use the --allow-overwrite option"));
             }
 
-            let definition = load_config_from_definition(path, false)?;
-            let mut writer = Writer::try_from(Writers::File(path_pgbouncer_ini))?;
-            writer.write(&definition)?;
+            let auth_file = auth_file.as_deref().map(Path::new);
+            let generate = || generate_ini(path, &overlay, path_pgbouncer_ini, &include_sections, lossless, target_version, auth_file, backups, provenance, checksum);
+            generate()?;
+            if !watch {
+                return Ok(());
+            }
+
+            let mut definition = load_config_from_definition(path, false)?;
+            apply_overlays(&mut definition, &overlay)?;
+            let reloader = reload.resolve(apply::ReloadMethod::None, definition.get_config::<PgBouncerSetting>()?)?;
+            let mut watched = vec![path];
+            watched.extend(overlay.iter().map(Path::new));
+            let mut watcher = watch::DefinitionWatcher::new(&watched)?;
+            let watched = watched.iter().map(|path| path.display().to_string()).collect::<Vec<_>>();
+            println!("Watching {} for changes (press Ctrl+C to stop)", watched.join(", "));
+            loop {
+                watcher.changed().await?;
+
+                // A broken definition is reported and the last good pgbouncer.ini is kept until the next save
+                let before = std::fs::read_to_string(path_pgbouncer_ini).ok();
+                if let Err(e) = generate() {
+                    eprintln!("{} {:?}", "Failed to regenerate:".red().bold(), e);
+                    continue;
+                }
+                let after = std::fs::read_to_string(path_pgbouncer_ini).ok();
+                if after.as_deref().map(without_generated_header) == before.as_deref().map(without_generated_header) {
+                    println!("{} is up to date", path_pgbouncer_ini.display());
+                    continue;
+                }
+                println!("Regenerated {}", path_pgbouncer_ini.display());
+
+                match reloader.reload().await {
+                    Ok(true) => println!("Reloaded PgBouncer"),
+                    Ok(false) => (),
+                    Err(e) => eprintln!("{} {:?}", "Failed to reload PgBouncer:".red().bold(), e),
+                }
+            }
+        },
+        Commands::Apply {
+            path_def_file,
+            path_pgbouncer_ini,
+            reload,
+            dry_run,
+            disable_decorated_output,
+        } => {
+            let path_pgbouncer_ini: &Path = path_pgbouncer_ini.as_str().as_ref();
+            let definition = load_resolved_definition(path_def_file.as_ref())?;
+            editor::check_issues(&definition, None)?;
+
+            if dry_run {
+                if !path_pgbouncer_ini.exists() {
+                    println!("{} does not exist and would be created", path_pgbouncer_ini.display());
+                    return Ok(());
+                }
+
+                let diff = compute_diff_pg_config(&load_config_from_ini(path_pgbouncer_ini)?, &definition)?;
+                let opts = formatter::DisplayOptions::new(!disable_decorated_output, false, 0);
+                println!("{}", formatter::format_diff(&diff, opts));
+                return Ok(());
+            }
+
+            let reloader = reload.resolve(apply::ReloadMethod::Sighup, definition.get_config::<PgBouncerSetting>()?)?;
+
+            let mut rendered = Vec::new();
+            Writer::new(&mut rendered).write(&definition)?;
+            let rendered = String::from_utf8(rendered)?;
+            if std::fs::read_to_string(path_pgbouncer_ini).is_ok_and(|current| current == rendered) {
+                println!("{} is up to date, PgBouncer was not reloaded", path_pgbouncer_ini.display());
+                return Ok(());
+            }
+            apply::write_atomically(path_pgbouncer_ini, &rendered)?;
+            println!("Wrote {}", path_pgbouncer_ini.display());
+
+            if reloader.reload().await? {
+                println!("Reloaded PgBouncer");
+            }
+
+            Ok(())
+        },
+        Commands::Edit { path_def_file, editor } => {
+            let path: &Path = path_def_file.as_str().as_ref();
+            if !path.exists() {
+                return Err(anyhow::anyhow!("The definition file does not exist"));
+            }
+
+            match editor::edit_definition(path, editor.as_deref())? {
+                editor::EditOutcome::Saved => println!("Saved {}", path.display()),
+                editor::EditOutcome::Unchanged => println!("No changes made to {}", path.display()),
+                editor::EditOutcome::Aborted => {
+                    return Err(anyhow::anyhow!("Edit aborted, the definition file was not modified"));
+                }
+            }
+
+            Ok(())
+        },
+        Commands::Explain { setting, all } => {
+            if all {
+                let explained = settings()
+                    .iter()
+                    .map(format_setting_info)
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                println!("{}", explained);
+                return Ok(());
+            }
+
+            // SAFETY: clap requires `setting` unless `--all` is given
+            let setting = setting.unwrap_or_default();
+            let info = find_setting(&setting)
+                .ok_or_else(|| anyhow::anyhow!("Unknown setting: {}", setting))?;
+            println!("{}", format_setting_info(info));
+
+            Ok(())
+        },
+        Commands::PrintSchema { format } => {
+            let definition_schema = PgBouncerConfig::schema();
+            match format {
+                schema::SchemaOutput::Json => println!("{}", serde_json::to_string_pretty(&definition_schema)?),
+                schema::SchemaOutput::Table => println!("{}", schema::key_table(&definition_schema)),
+            }
+
+            Ok(())
+        },
+        Commands::AuthQuerySql { path_def_file, schema } => {
+            let definition = load_config_from_definition(path_def_file.as_ref(), false)?;
+            let pgbouncer_setting = definition.get_config::<PgBouncerSetting>()?;
+            print!("{}", pgbouncer_setting.auth_query_sql(&schema)?);
+            if pgbouncer_setting.auth_query() != Some(auth_query_lookup(&schema).as_str()) {
+                eprintln!("Set auth_query = {} to use this function", auth_query_lookup(&schema));
+            }
+
+            Ok(())
+        },
+        Commands::Kubernetes { path_def_file, name, namespace, with_auth_file, output } => {
+            let definition = load_resolved_definition(path_def_file.as_ref())?;
+            let auth_file = if with_auth_file {
+                Some(desired_auth_file(&definition, &AuthFile::new())?)
+            } else {
+                None
+            };
+            let mut manifests = KubernetesManifests::new(&name);
+            manifests.set_namespace(namespace.as_deref());
+
+            let mut writer = match &output {
+                Some(output) => Writer::try_from(Writers::File(output.as_ref()))?,
+                None => Writer::try_from(Writers::Stdout)?,
+            };
+            writer.write_kubernetes(&definition, auth_file.as_ref(), &manifests)?;
+
+            Ok(())
+        },
+        Commands::HelmValues { path_def_file, image_tag, extra_env_vars_secret, output } => {
+            let definition = load_resolved_definition(path_def_file.as_ref())?;
+            let mut helm_values = HelmValues::new();
+            helm_values
+                .set_image_tag(image_tag.as_deref())
+                .set_extra_env_vars_secret(extra_env_vars_secret.as_deref());
+
+            let values = helm_values.render(&definition)?;
+            match &output {
+                Some(output) => {
+                    if let Some(parent) = Path::new(output).parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(output, values)?;
+                },
+                None => print!("{}", values),
+            }
+            for key in helm_values.unmapped_keys(&definition) {
+                eprintln!("{} has no chart value and is not exported", key);
+            }
+
+            Ok(())
+        },
+        Commands::Compose {
+            path_def_file,
+            service_name,
+            image,
+            config_source,
+            auth_file_source,
+            dockerfile,
+            output,
+        } => {
+            let definition = load_config_from_definition(path_def_file.as_ref(), false)?;
+            let mut service = ComposeService::new(&service_name);
+            service
+                .set_image(&image)
+                .set_config_source(&config_source)
+                .set_auth_file_source(auth_file_source.as_deref());
+
+            let snippet = if dockerfile {
+                service.dockerfile(&definition)?
+            } else {
+                service.render(&definition)?
+            };
+            match &output {
+                Some(output) => {
+                    if let Some(parent) = Path::new(output).parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(output, snippet)?;
+                },
+                None => print!("{}", snippet),
+            }
+            if !listens_on_all_interfaces(definition.get_config::<PgBouncerSetting>()?) {
+                eprintln!("listen_addr is not * or 0.0.0.0, so the published port cannot reach PgBouncer inside the container");
+            }
+
+            Ok(())
+        },
+        Commands::Validate { path_def_file, path_pgbouncer_ini, target_version, disable_decorated_output } => {
+            let target_version = target_version
+                .as_deref()
+                .map(TargetVersion::try_from)
+                .transpose()?;
+
+            let decorated = !disable_decorated_output;
+            let definition = diagnostics::load_or_report(&path_def_file, decorated, |path| load_config_from_definition(path, false))?;
+            let mut targets = vec![(path_def_file.clone(), definition)];
+            if let Some(path_pgbouncer_ini) = path_pgbouncer_ini {
+                let config = diagnostics::load_or_report(&path_pgbouncer_ini, decorated, load_config_from_ini)?;
+                targets.push((path_pgbouncer_ini, config));
+            }
+
+            let mut error_count = 0;
+            let mut warning_count = 0;
+            for (index, (path, config)) in targets.iter().enumerate() {
+                let issues = match target_version {
+                    Some(target_version) => config.validate_for(target_version),
+                    None => config.validate(),
+                };
+                // Snippets need the text of the file, which URLs do not keep
+                let text = std::fs::read_to_string(path).ok();
+                for issue in &issues {
+                    match (&text, decorated) {
+                        (Some(text), true) => print!("{}", diagnostics::render(&ConfigDiagnostic::from_issue(issue, path, text))),
+                        _ => println!("{}: {}", path, format_issue(issue, decorated)),
+                    }
+                }
+                error_count += issues.iter().filter(|issue| issue.is_error()).count();
+                warning_count += issues.iter().filter(|issue| !issue.is_error()).count();
+
+                // Only pgbouncer.ini keeps unknown and deprecated keys as they are written
+                if let (Some(text), true) = (&text, index > 0) {
+                    for diagnostic in deprecations(path, text).into_iter().chain(unknown_settings(path, text)) {
+                        match decorated {
+                            true => print!("{}", diagnostics::render(&diagnostic)),
+                            false => println!("{}: {}", path, diagnostics::format_plain(&diagnostic)),
+                        }
+                        warning_count += 1;
+                    }
+                }
+            }
+
+            println!("{} error(s), {} warning(s)", error_count, warning_count);
+            if error_count > 0 {
+                return Err(anyhow::anyhow!("Validation failed with {} error(s)", error_count));
+            }
+
+            Ok(())
+        },
+        Commands::Lint { path_def_file, path_pgbouncer_ini, target_version, enable, disable, list_rules, disable_decorated_output } => {
+            if list_rules {
+                for rule in LintRule::all() {
+                    println!("{:<24}{}", rule.name(), rule.description());
+                }
+                return Ok(());
+            }
+
+            let target_version = target_version
+                .as_deref()
+                .map(TargetVersion::try_from)
+                .transpose()?;
+
+            let mut linter = Linter::new();
+            linter.set_target_version(target_version);
+            if !enable.is_empty() {
+                for rule in LintRule::all() {
+                    linter.disable(*rule);
+                }
+                for rule in &enable {
+                    linter.enable(LintRule::try_from(rule.as_str())?);
+                }
+            }
+            for rule in &disable {
+                linter.disable(LintRule::try_from(rule.as_str())?);
+            }
+
+            let decorated = !disable_decorated_output;
+            let definition = diagnostics::load_or_report(&path_def_file, decorated, |path| load_config_from_definition(path, false))?;
+            let mut targets = vec![(path_def_file.clone(), definition)];
+            if let Some(path_pgbouncer_ini) = path_pgbouncer_ini {
+                let config = diagnostics::load_or_report(&path_pgbouncer_ini, decorated, load_config_from_ini)?;
+                targets.push((path_pgbouncer_ini, config));
+            }
+
+            let mut finding_count = 0;
+            for (path, config) in &targets {
+                let findings = linter.lint(config);
+                let text = std::fs::read_to_string(path).ok();
+                for finding in &findings {
+                    match (&text, decorated) {
+                        (Some(text), true) => print!("{}", diagnostics::render(&ConfigDiagnostic::from_finding(finding, path, text))),
+                        _ => println!("{}: {} ({})", path, format_issue(finding.issue(), decorated), finding.rule()),
+                    }
+                }
+                finding_count += findings.len();
+            }
+
+            println!("{} finding(s)", finding_count);
+            if finding_count > 0 {
+                return Err(anyhow::anyhow!("Lint failed with {} finding(s)", finding_count));
+            }
+
+            Ok(())
+        },
+        Commands::Fmt { path_pgbouncer_ini, in_place, check } => {
+            let path: &Path = path_pgbouncer_ini.as_str().as_ref();
+            if !path.exists() {
+                return Err(anyhow::anyhow!("The pgbouncer.ini file does not exist"));
+            }
+
+            let current = std::fs::read_to_string(path)?;
+            if current.lines().any(|line| line.trim_start().starts_with(INCLUDE_DIRECTIVE)) {
+                return Err(anyhow::anyhow!(
+                    "{} contains {} directives, which formatting would inline; format the included files instead",
+                    path.display(),
+                    INCLUDE_DIRECTIVE,
+                ));
+            }
+
+            let mut formatted = Vec::new();
+            Writer::new(&mut formatted).write(&load_config_from_ini(path)?)?;
+            let formatted = String::from_utf8(formatted)?;
+
+            if check {
+                if formatted != current {
+                    return Err(anyhow::anyhow!("{} is not formatted, run fmt --in-place to fix it", path.display()));
+                }
+                println!("{} is formatted", path.display());
+            } else if in_place {
+                if formatted != current {
+                    std::fs::write(path, formatted)?;
+                }
+            } else {
+                print!("{}", formatted);
+            }
+
+            Ok(())
+        },
+        Commands::Verify { path_pgbouncer_ini } => {
+            let path: &Path = path_pgbouncer_ini.as_str().as_ref();
+            if !path.exists() {
+                return Err(anyhow::anyhow!("The pgbouncer.ini file does not exist"));
+            }
+
+            match verify_checksum(&std::fs::read_to_string(path)?) {
+                ChecksumStatus::Verified => {
+                    println!("{} matches its checksum", path.display());
+                    Ok(())
+                },
+                ChecksumStatus::Modified { expected, actual } => Err(anyhow::anyhow!(
+                    "{} was edited since generation (expected sha256 {}, found {})",
+                    path.display(),
+                    expected,
+                    actual,
+                )),
+                ChecksumStatus::Missing => Err(anyhow::anyhow!(
+                    "{} has no checksum comment, generate it with --checksum",
+                    path.display(),
+                )),
+            }
+        },
+        Commands::Fleet {
+            inventory,
+            out_dir,
+            diff_against,
+            dry_run,
+            disable_decorated_output
+        } => {
+            let fleet = Fleet::from_inventory_file(inventory.as_ref())?;
+            if fleet.instances().is_empty() {
+                return Err(anyhow::anyhow!("No instances found in {}", inventory));
+            }
+
+            if dry_run {
+                fleet.validate()?;
+            } else {
+                for rendered in fleet.render_to(out_dir.as_ref())? {
+                    println!("Generated {} -> {}", rendered.name, rendered.output.display());
+                }
+            }
+
+            // SAFETY: the fleet has at least one instance (checked above)
+            let reference = diff_against.unwrap_or_else(|| fleet.instances()[0].name.clone());
+            let opts = formatter::DisplayOptions::new(!disable_decorated_output, false, 0);
+            for (name, diff) in fleet.diff_against(&reference)? {
+                println!("\n{} -> {}", reference, name);
+                println!("{}", formatter::format_diff(&diff, opts));
+            }
 
             Ok(())
         }
     }
 }
 
+/// Renders the definition, with `overlays` merged on top, into pgbouncer.ini (and userlist.txt when `auth_file` is set).
+///
+/// The previous pgbouncer.ini is kept as `pgbouncer.ini.bak.1` when `backups` is not 0.
+#[allow(clippy::too_many_arguments)]
+fn generate_ini(
+    path: &Path,
+    overlays: &[String],
+    path_pgbouncer_ini: &Path,
+    include_sections: &[String],
+    lossless: bool,
+    target_version: Option<TargetVersion>,
+    auth_file: Option<&Path>,
+    backups: usize,
+    provenance: bool,
+    checksum: bool,
+) -> anyhow::Result<()> {
+    let definition = load_layered_definition(path, overlays)?;
+    editor::check_issues(&definition, target_version)?;
+    if let Some(auth_file) = auth_file {
+        let desired = desired_auth_file(&definition, &load_auth_file(auth_file)?)?;
+        let mut writer = Writer::try_from(Writers::File(auth_file))?;
+        writer.write_auth_file(&desired)?;
+    }
+    if lossless && path_pgbouncer_ini.exists() {
+        let mut document = Document::parse(&std::fs::read_to_string(path_pgbouncer_ini)?)?;
+        document.apply_config(&definition)?;
+        let text = match checksum {
+            true => embed_checksum(&document.to_string()),
+            false => document.to_string(),
+        };
+        rotate_backups(path_pgbouncer_ini, backups)?;
+        std::fs::write(path_pgbouncer_ini, text)?;
+
+        return Ok(());
+    }
+
+    let mut writer = Writer::try_from(Writers::FileWithBackups(path_pgbouncer_ini, backups))?;
+    if provenance || checksum {
+        let mut options = RenderOptions::new();
+        options.set_checksum(checksum);
+        if provenance {
+            options.set_provenance(Some(definition_provenance(path)));
+        }
+        writer.write_with_options(&definition, &options)?;
+    } else if include_sections.is_empty() {
+        writer.write(&definition)?;
+    } else {
+        let ini_dir = path_pgbouncer_ini.parent().unwrap_or(Path::new("."));
+        let include_paths = include_sections
+            .iter()
+            .map(|section| ini_dir.join(format!("{}.ini", section)))
+            .collect::<Vec<_>>();
+        let includes = include_sections
+            .iter()
+            .zip(&include_paths)
+            .map(|(section, path)| (section.as_str(), path.as_path()))
+            .collect::<Vec<_>>();
+        writer.write_with_includes(&definition, &includes)?;
+    }
+
+    Ok(())
+}
+
+/// Strips the checksum and provenance comments written at the top of a generated pgbouncer.ini.
+///
+/// They change on every generation (generation time, definition hash), so comparing
+/// the rest tells whether the settings PgBouncer reads have changed.
+fn without_generated_header(text: &str) -> &str {
+    let text = match text.strip_prefix("; ").filter(|rest| rest.starts_with(CHECKSUM_MARKER)) {
+        Some(rest) => rest.split_once('\n').map_or("", |(_, body)| body),
+        None => text,
+    };
+    match text.starts_with("; Generated by ") {
+        true => text.split_once("\n\n").map_or("", |(_, body)| body),
+        false => text,
+    }
+}
+
+/// Provenance header naming this generator and the definition file, hashed when it is local.
+fn definition_provenance(path: &Path) -> Provenance {
+    let location = path.display().to_string();
+    let content = match is_url(&location) {
+        true => None,
+        false => std::fs::read(path).ok(),
+    };
+
+    let mut provenance = Provenance::new();
+    provenance
+        .set_tool(&format!("pgbouncer-generator {}", env!("CARGO_PKG_VERSION")))
+        .set_source(&location, content.as_deref());
+    provenance
+}
+
 fn load_config_from_definition(path: &Path, allow_not_exist: bool) -> anyhow::Result<PgBouncerConfig> {
+    if let Some(url) = path.to_str().filter(|location| is_url(location)) {
+        return Ok(Reader::try_from(Readers::Url(url))?.read_config(ConfigFileFormat::from_path(path)?)?);
+    }
     if !path.exists() && !allow_not_exist {
         return Err(anyhow::anyhow!("The definition file does not exist and allow_not_exist is false"));
     }
 
     let current_setting = if path.exists() {
-        Reader::try_from(Readers::File(path))?.read_config(TOML)?
+        Reader::try_from(Readers::File(path))?.read_config(definition_format(path)?)?
     } else {
         let pgbouncer_setting = PgBouncerSetting::default();
         let db_setting = DatabasesSetting::new();
@@ -364,21 +1858,184 @@ fn load_config_from_definition(path: &Path, allow_not_exist: bool) -> anyhow::Re
     Ok(current_setting)
 }
 
+/// Loads the definition and replaces its `vault://` and `env://` secret references with their values.
+///
+/// The result is only meant for rendering; it must never be written back to the definition.
+fn load_resolved_definition(path: &Path) -> anyhow::Result<PgBouncerConfig> {
+    load_layered_definition(path, &[])
+}
+
+/// Loads the definition, merges the overlay files on top in order and resolves its secret references.
+///
+/// Like [`load_resolved_definition`], the result is only meant for rendering.
+fn load_layered_definition(path: &Path, overlays: &[String]) -> anyhow::Result<PgBouncerConfig> {
+    let mut definition = load_config_from_definition(path, false)?;
+    apply_overlays(&mut definition, overlays)?;
+    definition.resolve_secrets(&secret_resolvers()?)?;
+
+    Ok(definition)
+}
+
+/// Adds the databases and metadata imported into `imported` to the entries of `definition`.
+///
+/// The import runs on a copy with resolved secrets; it keeps the entries in
+/// order, so they are matched by position.
+fn merge_imported_databases(definition: &mut PgBouncerConfig, imported: &PgBouncerConfig) -> anyhow::Result<()> {
+    let imported = imported.get_config::<DatabasesSetting>()?;
+    let db_setting = definition.get_config_mut::<DatabasesSetting>()?;
+    db_setting.set_import_metadata(imported.import_metadata());
+    for (database, imported) in db_setting.iter_mut().zip(imported.iter()) {
+        database.push_databases(imported.databases());
+        for name in imported.databases() {
+            if let Some(metadata) = imported.metadata(name) {
+                database.set_metadata(name, Some(metadata.clone()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Merges the overlay files on top of the definition, later files overriding earlier ones.
+fn apply_overlays(definition: &mut PgBouncerConfig, overlays: &[String]) -> anyhow::Result<()> {
+    for overlay in overlays {
+        let path = Path::new(overlay);
+        if !path.exists() {
+            return Err(anyhow::anyhow!("The overlay file {} does not exist", overlay));
+        }
+
+        definition
+            .overlay(&std::fs::read_to_string(path)?, definition_format(path)?)
+            .map_err(|e| anyhow::anyhow!("Failed to apply the overlay {}: {}", overlay, e))?;
+    }
+
+    Ok(())
+}
+
+/// Resolvers for secret references; `vault://` is available when `VAULT_ADDR` is set.
+fn secret_resolvers() -> anyhow::Result<SecretResolvers> {
+    let mut resolvers = SecretResolvers::new();
+    match std::env::var_os("VAULT_ADDR") {
+        Some(_) => resolvers.register(VaultSecretResolver::from_env()?),
+        None => resolvers.register(VaultNotConfigured),
+    };
+
+    Ok(resolvers)
+}
+
+/// Stands in for Vault without `VAULT_ADDR`, so `vault://` references fail
+/// instead of being written as literal passwords.
+struct VaultNotConfigured;
+
+impl SecretResolver for VaultNotConfigured {
+    fn scheme(&self) -> &str {
+        "vault"
+    }
+
+    fn resolve(&self, reference: &SecretReference) -> pgbouncer_config::error::Result<String> {
+        Err(PgBouncerError::Secret(format!("set VAULT_ADDR to resolve {}", reference)))
+    }
+}
+
+/// Picks the definition format from the extension, falling back to the content of an existing file.
+fn definition_format(path: &Path) -> anyhow::Result<ConfigFileFormat> {
+    let format = ConfigFileFormat::from_path(path)?;
+    if format == ConfigFileFormat::Auto && path.exists() {
+        return Ok(ConfigFileFormat::detect(&std::fs::read_to_string(path)?));
+    }
+
+    Ok(format)
+}
+
 fn load_config_from_ini(path: &Path) -> anyhow::Result<PgBouncerConfig> {
+    if let Some(url) = path.to_str().filter(|location| is_url(location)) {
+        return Ok(Reader::try_from(Readers::Url(url))?.read()?);
+    }
     if !path.exists() {
         return Err(anyhow::anyhow!("The pgbouncer.ini file does not exist"));
     }
 
     let mut reader = Reader::try_from(Readers::File(path))?;
-    let pgbouncer_ini = reader.read()?;
+    let pgbouncer_ini = reader.read_with_base_dir(path.parent().unwrap_or(Path::new(".")))?;
 
     Ok(pgbouncer_ini)
 }
 
+fn load_auth_file(path: &Path) -> anyhow::Result<AuthFile> {
+    if !path.exists() {
+        return Ok(AuthFile::new());
+    }
+
+    Ok(AuthFile::parse_from_str(&std::fs::read_to_string(path)?)?)
+}
+
+fn desired_auth_file(definition: &PgBouncerConfig, current: &AuthFile) -> anyhow::Result<AuthFile> {
+    let databases_setting = definition.get_config::<DatabasesSetting>()?;
+    let auth_type = definition.get_config::<PgBouncerSetting>()?.auth_type();
+
+    Ok(AuthFile::from_databases(databases_setting)?.reuse_verifiers(current).with_verifiers(auth_type))
+}
+
+fn userlist_path(pgbouncer_setting: &PgBouncerSetting, output: Option<String>, option: &str) -> anyhow::Result<String> {
+    output
+        .or_else(|| pgbouncer_setting.auth_file().map(ToString::to_string))
+        .ok_or_else(|| anyhow::anyhow!("No auth_file is set in the definition, please use the --{} option", option))
+}
+
+fn write_imported_users(output: &Path, imported: &AuthFile, auth_type: AuthType, replace: bool) -> anyhow::Result<()> {
+    if auth_type == AuthType::ScramSha256 {
+        for (user, password) in imported.users() {
+            if password.starts_with("md5") {
+                eprintln!("{} has an md5 password, which cannot be used with auth_type = scram-sha-256", user);
+            }
+        }
+    }
+
+    let mut auth_file = if replace { AuthFile::new() } else { load_auth_file(output)? };
+    for (user, password) in imported.users() {
+        auth_file.add_user(user, password);
+    }
+    let mut writer = Writer::try_from(Writers::File(output))?;
+    writer.write_auth_file(&auth_file)?;
+    println!("Imported {} role(s) into {}", imported.users().len(), output.display());
+
+    Ok(())
+}
+
 fn get_option_vec_str(value: &[String]) -> Option<Vec<&str>> {
     if value.is_empty() {
         None
     } else {
         Some(value.iter().map(|s| s.as_str()).collect::<Vec<_>>())
     }
-}
\ No newline at end of file
+}
+
+fn format_issue(issue: &ValidationIssue, decorated: bool) -> String {
+    let line = issue.to_string();
+    if !decorated {
+        return line;
+    }
+
+    // `ValidationIssue` is displayed as "<severity>: ...", so only the prefix is colored
+    let severity = issue.severity().to_string();
+    let rest = line.strip_prefix(&severity).unwrap_or_default();
+    let severity = match issue.severity() {
+        Severity::Error => severity.red().bold(),
+        Severity::Warning => severity.yellow().bold(),
+    };
+    format!("{}{}", severity, rest)
+}
+
+fn format_setting_info(info: &SettingInfo) -> String {
+    let default = if info.default.is_empty() { "(not set)" } else { info.default };
+
+    format!(
+        "{} [{}]\n  {}\n  type:    {}\n  default: {}\n  since:   PgBouncer {}\n",
+        info.name.bold(),
+        info.section,
+        info.description,
+        info.value_type,
+        default,
+        info.since,
+    )
+}
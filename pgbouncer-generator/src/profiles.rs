@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+use pgbouncer_config::io::write::Writer;
+use pgbouncer_config::pgbouncer_config::validation::TargetVersion;
+use crate::{apply, editor, load_resolved_definition};
+
+/// File name of the manifest written next to the generated profiles.
+pub(crate) const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// A definition file representing one environment profile.
+#[derive(Debug, Clone)]
+pub(crate) struct Profile {
+    pub(crate) name: String,
+    pub(crate) definition: PathBuf,
+}
+
+/// A pgbouncer.ini rendered for a profile.
+#[derive(Debug, Clone)]
+pub(crate) struct GeneratedProfile {
+    pub(crate) profile: Profile,
+    pub(crate) output: PathBuf,
+}
+
+/// Lists the profiles in `profiles_dir`.
+///
+/// Every `*.toml` file in the directory is a profile whose name is the file stem
+/// (e.g. `prod.toml` -> `prod`). Profiles are returned sorted by name.
+pub(crate) fn discover_profiles(profiles_dir: &Path) -> anyhow::Result<Vec<Profile>> {
+    if !profiles_dir.is_dir() {
+        return Err(anyhow::anyhow!("The profiles directory does not exist: {}", profiles_dir.display()));
+    }
+
+    let mut profiles = Vec::new();
+    for entry in std::fs::read_dir(profiles_dir)? {
+        let path = entry?.path();
+        if !path.is_file() || path.extension().is_none_or(|ext| ext != "toml") {
+            continue;
+        }
+
+        if let Some(name) = path.file_stem() {
+            profiles.push(Profile {
+                name: name.to_string_lossy().to_string(),
+                definition: path.clone(),
+            });
+        }
+    }
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(profiles)
+}
+
+/// Renders one pgbouncer.ini per profile into `out_dir/<profile>/pgbouncer.ini`
+/// and writes a manifest of the outputs to `out_dir/manifest.json`.
+///
+/// Every profile is validated with [`editor::check_issues`] (against `target_version` when set)
+/// before any file is written, and each file is replaced atomically.
+pub(crate) fn generate_profiles(
+    profiles: &[Profile],
+    out_dir: &Path,
+    disallow_overwrite: bool,
+    target_version: Option<TargetVersion>,
+) -> anyhow::Result<Vec<GeneratedProfile>> {
+    // Load and render everything first so a broken profile does not leave a half-written tree.
+    let mut rendered = Vec::new();
+    for profile in profiles {
+        let config = load_resolved_definition(&profile.definition)
+            .map_err(|e| anyhow::anyhow!("Failed to load profile '{}': {}", profile.name, e))?;
+        editor::check_issues(&config, target_version)
+            .map_err(|e| anyhow::anyhow!("Profile '{}': {}", profile.name, e))?;
+        let output = out_dir.join(&profile.name).join("pgbouncer.ini");
+        if output.exists() && disallow_overwrite {
+            return Err(anyhow::anyhow!("The pgbouncer.ini file already exists: {}", output.display()));
+        }
+
+        let mut text = Vec::new();
+        Writer::new(&mut text).write(&config)?;
+        rendered.push((profile, String::from_utf8(text)?, output));
+    }
+
+    let mut generated = Vec::new();
+    for (profile, text, output) in rendered {
+        apply::write_atomically(&output, &text)?;
+        generated.push(GeneratedProfile { profile: profile.clone(), output });
+    }
+
+    write_manifest(out_dir, &generated)?;
+
+    Ok(generated)
+}
+
+fn write_manifest(out_dir: &Path, generated: &[GeneratedProfile]) -> anyhow::Result<()> {
+    let outputs = generated
+        .iter()
+        .map(|g| serde_json::json!({
+            "profile": g.profile.name,
+            "definition": g.profile.definition.display().to_string(),
+            "output": g.output.display().to_string(),
+        }))
+        .collect::<Vec<_>>();
+    let manifest = serde_json::json!({ "outputs": outputs });
+
+    apply::write_atomically(&out_dir.join(MANIFEST_FILE_NAME), &serde_json::to_string_pretty(&manifest)?)?;
+
+    Ok(())
+}
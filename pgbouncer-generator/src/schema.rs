@@ -0,0 +1,195 @@
+use clap::ValueEnum;
+use serde_json::Value;
+
+/// How the print-schema command prints the definition schema.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SchemaOutput {
+    /// The JSON Schema, for editors and validators.
+    Json,
+    /// One line per key with its type and description.
+    Table,
+}
+
+/// A key of the definition format, as listed by the table output.
+struct KeyRow {
+    path: String,
+    value_type: String,
+    description: String,
+}
+
+/// Lists every key of the definition schema with its type and description.
+///
+/// Keys are written as their path in the definition, e.g.
+/// `databases.DatabasesSetting.databases[].host`.
+pub(crate) fn key_table(schema: &Value) -> String {
+    let mut rows = Vec::new();
+    collect_rows(schema, schema, "", &mut Vec::new(), &mut rows);
+
+    let width = rows.iter().map(|row| row.path.len()).max().unwrap_or(0);
+    let type_width = rows.iter().map(|row| row.value_type.len()).max().unwrap_or(0);
+    rows.iter()
+        .map(|row| {
+            format!("{:<width$}  {:<type_width$}  {}", row.path, row.value_type, row.description)
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn collect_rows(root: &Value, schema: &Value, path: &str, refs: &mut Vec<String>, rows: &mut Vec<KeyRow>) {
+    for (name, property) in properties(root, schema) {
+        let key = match path.is_empty() {
+            true => name.clone(),
+            false => format!("{}.{}", path, name),
+        };
+        let target = resolve(root, property);
+        // Only lists of objects get a `[]` segment; lists of values are leaves
+        let (key, target) = match target.get("items").map(|items| resolve(root, items)) {
+            Some(items) if !properties(root, items).is_empty() => (format!("{}[]", key), items),
+            _ => (key, target),
+        };
+
+        let reference = reference_name(property).or_else(|| property.get("items").and_then(reference_name));
+        if let Some(reference) = &reference {
+            if refs.contains(reference) {
+                continue;
+            }
+            refs.push(reference.clone());
+        }
+        if properties(root, target).is_empty() {
+            rows.push(KeyRow {
+                path: key,
+                value_type: type_name(root, property),
+                description: description(property).or_else(|| description(target)).unwrap_or_default(),
+            });
+        } else {
+            collect_rows(root, target, &key, refs, rows);
+        }
+        if reference.is_some() {
+            refs.pop();
+        }
+    }
+}
+
+/// Properties of an object schema, including those of its `allOf`/`anyOf`/`oneOf`
+/// branches (flattened and untagged types), without duplicates.
+fn properties<'a>(root: &'a Value, schema: &'a Value) -> Vec<(&'a String, &'a Value)> {
+    let schema = resolve(root, schema);
+    let mut found: Vec<(&String, &Value)> = schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .map(|properties| properties.iter().collect())
+        .unwrap_or_default();
+
+    for keyword in ["allOf", "anyOf", "oneOf"] {
+        for branch in schema.get(keyword).and_then(Value::as_array).into_iter().flatten() {
+            for (name, property) in properties(root, branch) {
+                if !found.iter().any(|(found_name, _)| *found_name == name) {
+                    found.push((name, property));
+                }
+            }
+        }
+    }
+
+    found
+}
+
+/// Follows a local `$ref` (`#/$defs/Name`), or an `Option` wrapper around one.
+fn resolve<'a>(root: &'a Value, schema: &'a Value) -> &'a Value {
+    if let Some(reference) = reference_name(schema) {
+        return root
+            .get("$defs")
+            .and_then(|defs| defs.get(&reference))
+            .map(|target| resolve(root, target))
+            .unwrap_or(schema);
+    }
+
+    let branches = schema.get("anyOf").or_else(|| schema.get("oneOf")).and_then(Value::as_array);
+    match branches.map(|branches| branches.iter().filter(|b| !is_null(b)).collect::<Vec<_>>()) {
+        Some(non_null) if non_null.len() == 1 && reference_name(non_null[0]).is_some() => resolve(root, non_null[0]),
+        _ => schema,
+    }
+}
+
+fn reference_name(schema: &Value) -> Option<String> {
+    schema
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|reference| reference.strip_prefix("#/$defs/"))
+        .map(str::to_string)
+}
+
+fn is_null(schema: &Value) -> bool {
+    schema.get("type").and_then(Value::as_str) == Some("null")
+}
+
+fn description(schema: &Value) -> Option<String> {
+    schema
+        .get("description")
+        .and_then(Value::as_str)
+        .map(|description| description.lines().next().unwrap_or_default().to_string())
+}
+
+/// Readable type of a property: its JSON type, its enum values, or the
+/// types of its branches joined with `|`.
+fn type_name(root: &Value, schema: &Value) -> String {
+    if let Some(reference) = reference_name(schema) {
+        let target = resolve(root, schema);
+        let is_union = ["enum", "oneOf", "anyOf"].iter().any(|keyword| target.get(keyword).is_some());
+        return match is_union && properties(root, target).is_empty() {
+            true => type_name(root, target),
+            false => reference,
+        };
+    }
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        let values = values.iter().filter_map(Value::as_str).collect::<Vec<_>>();
+        return format!("enum ({})", values.join(", "));
+    }
+    if let Some(constant) = schema.get("const").and_then(Value::as_str) {
+        return format!("\"{}\"", constant);
+    }
+    if let Some(items) = schema.get("items") {
+        return format!("list of {}", type_name(root, items));
+    }
+
+    match schema.get("type") {
+        Some(Value::String(value_type)) => value_type.clone(),
+        Some(Value::Array(types)) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .filter(|value_type| *value_type != "null")
+            .collect::<Vec<_>>()
+            .join("|"),
+        _ => schema
+            .get("anyOf")
+            .or_else(|| schema.get("oneOf"))
+            .and_then(Value::as_array)
+            .map(|branches| {
+                branches
+                    .iter()
+                    .filter(|branch| !is_null(branch))
+                    .map(|branch| type_name(root, branch))
+                    .collect::<Vec<_>>()
+                    .join("|")
+            })
+            .unwrap_or_else(|| "any".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pgbouncer_config::pgbouncer_config::PgBouncerConfig;
+
+    #[test]
+    fn key_table_lists_nested_keys_with_their_types() {
+        let table = key_table(&PgBouncerConfig::schema());
+        let row = |key: &str| table.lines().find(|line| line.starts_with(&format!("{} ", key))).map(str::to_string);
+
+        assert!(row("pgbouncer.PgBouncerSetting.listen_port").unwrap().contains("integer"));
+        assert!(row("pgbouncer.PgBouncerSetting.pool_mode").unwrap().contains("enum ("));
+        assert!(row("databases.DatabasesSetting.databases[].host").is_some());
+        assert!(row("databases.DatabasesSetting.databases[].proxy_type").is_some());
+    }
+}
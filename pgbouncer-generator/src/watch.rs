@@ -0,0 +1,75 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+/// Time to wait for further events after a change, so one save is reported once.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Watches definition files (a base definition and its overlays) for changes.
+///
+/// The parent directories are watched rather than the files themselves, because
+/// many editors save by writing a new file and renaming it over the old one.
+pub(crate) struct DefinitionWatcher {
+    // Dropping the watcher stops the notifications
+    _watcher: RecommendedWatcher,
+    events: UnboundedReceiver<notify::Result<Event>>,
+    file_names: Vec<OsString>,
+}
+
+impl DefinitionWatcher {
+    pub(crate) fn new(paths: &[&Path]) -> anyhow::Result<Self> {
+        let (tx, events) = unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })?;
+
+        let mut file_names = Vec::new();
+        let mut dirs = Vec::new();
+        for path in paths {
+            let file_name = path
+                .file_name()
+                .ok_or_else(|| anyhow::anyhow!("{} is not a file path", path.display()))?
+                .to_os_string();
+            let dir = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+                _ => PathBuf::from("."),
+            };
+            if !dirs.contains(&dir) {
+                watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+                dirs.push(dir);
+            }
+            file_names.push(file_name);
+        }
+
+        Ok(Self { _watcher: watcher, events, file_names })
+    }
+
+    /// Waits until one of the files is created, written or replaced.
+    pub(crate) async fn changed(&mut self) -> anyhow::Result<()> {
+        loop {
+            let event = self
+                .events
+                .recv()
+                .await
+                .ok_or_else(|| anyhow::anyhow!("The file watcher stopped"))??;
+            if self.concerns_files(&event) {
+                break;
+            }
+        }
+
+        // Drain the rest of the burst (e.g. truncate + write + chmod of one save)
+        tokio::time::sleep(DEBOUNCE).await;
+        while self.events.try_recv().is_ok() {}
+
+        Ok(())
+    }
+
+    fn concerns_files(&self, event: &Event) -> bool {
+        matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+            && event.paths.iter().any(|path| {
+                path.file_name().is_some_and(|name| self.file_names.iter().any(|file_name| file_name == name))
+            })
+    }
+}
@@ -0,0 +1,120 @@
+use std::io::{BufRead, Write};
+use pgbouncer_config::builder::PgBouncerConfigBuilder;
+use pgbouncer_config::pgbouncer_config::PgBouncerConfig;
+use pgbouncer_config::pgbouncer_config::databases_setting::{Database, DatabasesSetting};
+use pgbouncer_config::pgbouncer_config::pgbouncer_setting::{AuthType, PgBouncerSetting, PoolMode};
+
+const AUTH_TYPES: [&str; 8] = ["md5", "scram-sha-256", "cert", "plain", "trust", "any", "hba", "pam"];
+const POOL_MODES: [&str; 3] = ["session", "transaction", "statement"];
+
+/// Asks for the basic settings and the Postgres hosts and builds a definition from the answers.
+///
+/// Every question shows its default in brackets; an empty answer accepts it.
+/// Invalid answers are asked again.
+pub(crate) fn run_wizard<R: BufRead, W: Write>(input: &mut R, output: &mut W) -> anyhow::Result<PgBouncerConfig> {
+    let mut prompter = Prompter { input, output };
+    let mut pgbouncer_setting = PgBouncerSetting::default();
+
+    writeln!(prompter.output, "PgBouncer settings")?;
+    let listen_addr = prompter.ask("Listen address (* for every interface)", Some(pgbouncer_setting.listen_addr()))?;
+    pgbouncer_setting.set_listen_addr(&listen_addr);
+    let listen_port = prompter.ask_parsed("Listen port", pgbouncer_setting.listen_port())?;
+    pgbouncer_setting.set_listen_port(listen_port);
+    let auth_type = prompter.ask_choice("Auth type", &AUTH_TYPES, &pgbouncer_setting.auth_type().to_string())?;
+    pgbouncer_setting.set_auth_type(AuthType::try_from(auth_type.as_str())?);
+    let pool_mode = prompter.ask_choice("Pool mode", &POOL_MODES, &pgbouncer_setting.pool_mode().to_string())?;
+    pgbouncer_setting.set_pool_mode(parse_pool_mode(&pool_mode));
+
+    let mut db_setting = DatabasesSetting::new();
+    for number in 1.. {
+        writeln!(prompter.output, "\nPostgres host #{}", number)?;
+        let host = prompter.ask("Host", None)?;
+        let port = prompter.ask_parsed("Port", 5432u16)?;
+        let user = prompter.ask("User", Some("postgres"))?;
+        let password = prompter.ask("Password", Some(""))?;
+        let databases = prompter.ask("Databases (space separated, empty to fetch them later with import)", Some(""))?;
+        let databases = databases.split_whitespace().collect::<Vec<_>>();
+        let databases = if databases.is_empty() { None } else { Some(databases.as_slice()) };
+
+        db_setting.add_database(Database::new(&host, port, &user, &password, databases));
+
+        if !prompter.confirm("Add another Postgres host?", false)? {
+            break;
+        }
+    }
+
+    Ok(PgBouncerConfigBuilder::builder()
+        .set_pgbouncer_setting(pgbouncer_setting)?
+        .set_databases_setting(db_setting)?
+        .build())
+}
+
+fn parse_pool_mode(value: &str) -> PoolMode {
+    match value {
+        "transaction" => PoolMode::Transaction,
+        "statement" => PoolMode::Statement,
+        _ => PoolMode::Session,
+    }
+}
+
+struct Prompter<'a, R: BufRead, W: Write> {
+    input: &'a mut R,
+    output: &'a mut W,
+}
+
+impl<R: BufRead, W: Write> Prompter<'_, R, W> {
+    /// Asks a question until it gets an answer; `default` is used for an empty answer.
+    fn ask(&mut self, question: &str, default: Option<&str>) -> anyhow::Result<String> {
+        loop {
+            match default {
+                Some(default) if !default.is_empty() => write!(self.output, "{} [{}]: ", question, default)?,
+                _ => write!(self.output, "{}: ", question)?,
+            }
+            self.output.flush()?;
+
+            let mut answer = String::new();
+            if self.input.read_line(&mut answer)? == 0 {
+                return Err(anyhow::anyhow!("The wizard was aborted before it was completed"));
+            }
+
+            let answer = answer.trim();
+            match (answer.is_empty(), default) {
+                (false, _) => return Ok(answer.to_string()),
+                (true, Some(default)) => return Ok(default.to_string()),
+                (true, None) => writeln!(self.output, "An answer is required")?,
+            }
+        }
+    }
+
+    fn ask_parsed<T: std::str::FromStr + ToString>(&mut self, question: &str, default: T) -> anyhow::Result<T> {
+        loop {
+            let answer = self.ask(question, Some(&default.to_string()))?;
+            match answer.parse::<T>() {
+                Ok(value) => return Ok(value),
+                Err(_) => writeln!(self.output, "{} is not a valid value", answer)?,
+            }
+        }
+    }
+
+    fn ask_choice(&mut self, question: &str, choices: &[&str], default: &str) -> anyhow::Result<String> {
+        let question = format!("{} ({})", question, choices.join(", "));
+        loop {
+            let answer = self.ask(&question, Some(default))?.to_lowercase();
+            if choices.contains(&answer.as_str()) {
+                return Ok(answer);
+            }
+            writeln!(self.output, "Choose one of {}", choices.join(", "))?;
+        }
+    }
+
+    fn confirm(&mut self, question: &str, default: bool) -> anyhow::Result<bool> {
+        let default = if default { "y" } else { "n" };
+        loop {
+            match self.ask(&format!("{} (y/n)", question), Some(default))?.to_lowercase().as_str() {
+                "y" | "yes" => return Ok(true),
+                "n" | "no" => return Ok(false),
+                _ => writeln!(self.output, "Answer y or n")?,
+            }
+        }
+    }
+}
@@ -0,0 +1,639 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn generator(dir: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_pgbouncer-generator"));
+    command
+        .current_dir(dir)
+        .env("RUST_BACKTRACE", "0")
+        .env_remove("VISUAL")
+        .env_remove("EDITOR");
+    command
+}
+
+fn run(command: &mut Command, stdin: &str) -> Output {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+/// Writes the default definition file into `dir`.
+fn init(dir: &Path) -> PathBuf {
+    let path = dir.join("pgbouncer_definition.toml");
+    let output = run(generator(dir).args(["init", "-p"]).arg(&path), "");
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    path
+}
+
+/// Writes an executable shell script used as `$EDITOR`; it receives the edited file as `$1`.
+fn editor_script(dir: &Path, body: &str) -> PathBuf {
+    let path = dir.join("editor.sh");
+    std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    path
+}
+
+fn edit(dir: &Path, definition: &Path, editor: &Path, temp_dir: &Path, stdin: &str) -> Output {
+    run(
+        generator(dir)
+            .args(["edit", "-p"])
+            .arg(definition)
+            .env("EDITOR", editor)
+            .env("TMPDIR", temp_dir),
+        stdin,
+    )
+}
+
+#[test]
+fn edit_saves_a_valid_definition_and_removes_the_private_copy() {
+    let dir = TempDir::new().unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let mode = dir.path().join("mode");
+    let editor = editor_script(
+        dir.path(),
+        &format!("stat -c %a \"$1\" > {}\nsed -i 's/listen_port = 6432/listen_port = 7432/' \"$1\"", mode.display()),
+    );
+
+    let output = edit(dir.path(), &definition, &editor, temp_dir.path(), "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Saved"), "{}", stdout(&output));
+    assert!(std::fs::read_to_string(&definition).unwrap().contains("listen_port = 7432"));
+    assert_eq!(std::fs::read_to_string(&mode).unwrap().trim(), "600");
+    assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn edit_reports_an_unchanged_definition() {
+    let dir = TempDir::new().unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let original = std::fs::read_to_string(&definition).unwrap();
+    let editor = editor_script(dir.path(), "true");
+
+    let output = edit(dir.path(), &definition, &editor, temp_dir.path(), "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("No changes made"), "{}", stdout(&output));
+    assert_eq!(std::fs::read_to_string(&definition).unwrap(), original);
+    assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn edit_aborted_after_an_invalid_edit_keeps_the_definition() {
+    let dir = TempDir::new().unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let original = std::fs::read_to_string(&definition).unwrap();
+    let editor = editor_script(dir.path(), "echo 'listen_port = [' > \"$1\"");
+
+    let output = edit(dir.path(), &definition, &editor, temp_dir.path(), "n\n");
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("The edited definition is invalid"), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Edit aborted"), "{}", stderr(&output));
+    assert_eq!(std::fs::read_to_string(&definition).unwrap(), original);
+    assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn edit_aborts_an_invalid_edit_when_stdin_is_closed() {
+    let dir = TempDir::new().unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let original = std::fs::read_to_string(&definition).unwrap();
+    let count = dir.path().join("count");
+    let editor = editor_script(dir.path(), &format!("echo x >> {}\necho 'listen_port = [' > \"$1\"", count.display()));
+
+    let output = edit(dir.path(), &definition, &editor, temp_dir.path(), "");
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("Edit aborted"), "{}", stderr(&output));
+    assert_eq!(std::fs::read_to_string(&count).unwrap().lines().count(), 1);
+    assert_eq!(std::fs::read_to_string(&definition).unwrap(), original);
+    assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn edit_removes_the_private_copy_when_the_editor_fails() {
+    let dir = TempDir::new().unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let editor = editor_script(dir.path(), "exit 1");
+
+    let output = edit(dir.path(), &definition, &editor, temp_dir.path(), "");
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("The editor exited"), "{}", stderr(&output));
+    assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn generate_with_provenance_starts_with_the_header() {
+    let dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let ini = dir.path().join("pgbouncer.ini");
+
+    let output = run(generator(dir.path()).args(["generate", "--provenance", "-d"]).arg(&definition).arg("-c").arg(&ini), "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    let text = std::fs::read_to_string(&ini).unwrap();
+    assert!(text.starts_with("; Generated by pgbouncer-generator "), "{}", text);
+    assert!(text.contains(&format!("; source: {}\n", definition.display())), "{}", text);
+    assert!(text.contains("; source_sha256: "), "{}", text);
+    assert!(text.contains("[pgbouncer]"), "{}", text);
+}
+
+#[test]
+fn generate_rejects_provenance_with_options_that_cannot_write_it() {
+    let dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let ini = dir.path().join("pgbouncer.ini");
+
+    for args in [&["--include-sections", "databases"][..], &["--lossless"], &["--all-profiles"]] {
+        let output = run(
+            generator(dir.path()).args(["generate", "--provenance", "-d"]).arg(&definition).arg("-c").arg(&ini).args(args),
+            "",
+        );
+        assert!(!output.status.success(), "{:?}", args);
+        assert!(stderr(&output).contains("cannot be used with"), "{:?}: {}", args, stderr(&output));
+        assert!(!ini.exists(), "{:?}", args);
+    }
+}
+
+#[test]
+fn generate_with_checksum_is_verified_until_the_file_is_edited() {
+    let dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let ini = dir.path().join("pgbouncer.ini");
+
+    let output = run(generator(dir.path()).args(["generate", "--checksum", "-d"]).arg(&definition).arg("-c").arg(&ini), "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(std::fs::read_to_string(&ini).unwrap().starts_with("; pgbouncer-config sha256: "));
+
+    let output = run(generator(dir.path()).args(["verify", "-c"]).arg(&ini), "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("matches its checksum"), "{}", stdout(&output));
+
+    let edited = std::fs::read_to_string(&ini).unwrap().replace("listen_port = 6432", "listen_port = 7432");
+    std::fs::write(&ini, edited).unwrap();
+    let output = run(generator(dir.path()).args(["verify", "-c"]).arg(&ini), "");
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("was edited since generation"), "{}", stderr(&output));
+}
+
+#[test]
+fn generate_lossless_with_checksum_embeds_it_in_the_updated_file() {
+    let dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let ini = dir.path().join("pgbouncer.ini");
+    let output = run(generator(dir.path()).args(["generate", "-d"]).arg(&definition).arg("-c").arg(&ini), "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    let existing = std::fs::read_to_string(&ini).unwrap().replace("listen_port = 6432", "listen_port = 7432");
+    std::fs::write(&ini, format!("; kept comment\n{}", existing)).unwrap();
+
+    let output = run(
+        generator(dir.path()).args(["generate", "--lossless", "--checksum", "-d"]).arg(&definition).arg("-c").arg(&ini),
+        "",
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let text = std::fs::read_to_string(&ini).unwrap();
+    assert!(text.starts_with("; pgbouncer-config sha256: "), "{}", text);
+    assert!(text.contains("; kept comment"), "{}", text);
+    assert!(text.contains("listen_port = 6432"), "{}", text);
+
+    let output = run(generator(dir.path()).args(["verify", "-c"]).arg(&ini), "");
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn generate_rejects_checksum_with_options_that_cannot_write_it() {
+    let dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let ini = dir.path().join("pgbouncer.ini");
+
+    for args in [&["--include-sections", "databases"][..], &["--all-profiles"]] {
+        let output = run(
+            generator(dir.path()).args(["generate", "--checksum", "-d"]).arg(&definition).arg("-c").arg(&ini).args(args),
+            "",
+        );
+        assert!(!output.status.success(), "{:?}", args);
+        assert!(stderr(&output).contains("cannot be used with"), "{:?}: {}", args, stderr(&output));
+        assert!(!ini.exists(), "{:?}", args);
+    }
+
+    // --lossless writes a single file, so --include-sections is rejected with it as well
+    let output = run(
+        generator(dir.path()).args(["generate", "--lossless", "--include-sections", "databases", "-d"]).arg(&definition).arg("-c").arg(&ini),
+        "",
+    );
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("cannot be used with"), "{}", stderr(&output));
+}
+
+#[test]
+fn generate_all_profiles_fails_on_an_invalid_profile_without_writing_any() {
+    let dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let profiles = dir.path().join("profiles");
+    let out = dir.path().join("out");
+    std::fs::create_dir(&profiles).unwrap();
+    let text = std::fs::read_to_string(&definition).unwrap();
+    std::fs::write(profiles.join("dev.toml"), &text).unwrap();
+    std::fs::write(profiles.join("prod.toml"), text.replace("auth_type = \"md5\"", "auth_type = \"hba\"")).unwrap();
+
+    let output = run(
+        generator(dir.path()).args(["generate", "--all-profiles", "--profiles-dir"]).arg(&profiles).arg("--out-dir").arg(&out),
+        "",
+    );
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("Profile 'prod'"), "{}", stderr(&output));
+    assert!(stderr(&output).contains("auth_hba_file is required"), "{}", stderr(&output));
+    assert!(!out.exists());
+
+    std::fs::write(profiles.join("prod.toml"), &text).unwrap();
+    let output = run(
+        generator(dir.path()).args(["generate", "--all-profiles", "--profiles-dir"]).arg(&profiles).arg("--out-dir").arg(&out),
+        "",
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(out.join("dev").join("pgbouncer.ini").exists());
+    assert!(out.join("prod").join("pgbouncer.ini").exists());
+    assert!(out.join("manifest.json").exists());
+}
+
+#[test]
+fn generate_all_profiles_rejects_options_that_only_apply_to_one_file() {
+    let dir = TempDir::new().unwrap();
+    let profiles = dir.path().join("profiles");
+    let out = dir.path().join("out");
+    std::fs::create_dir(&profiles).unwrap();
+    std::fs::write(profiles.join("dev.toml"), std::fs::read_to_string(init(dir.path())).unwrap()).unwrap();
+
+    for args in [
+        &["--overlay", "prod.toml"][..],
+        &["--auth-file", "userlist.txt"],
+        &["--backups", "2"],
+        &["--lossless"],
+        &["--include-sections", "databases"],
+    ] {
+        let output = run(
+            generator(dir.path())
+                .args(["generate", "--all-profiles", "--profiles-dir"])
+                .arg(&profiles)
+                .arg("--out-dir")
+                .arg(&out)
+                .args(args),
+            "",
+        );
+        assert!(!output.status.success(), "{:?}", args);
+        assert!(stderr(&output).contains("cannot be used with"), "{:?}: {}", args, stderr(&output));
+        assert!(!out.exists(), "{:?}", args);
+    }
+}
+
+#[test]
+fn validate_succeeds_with_only_warnings() {
+    let dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let ini = dir.path().join("pgbouncer.ini");
+    let output = run(generator(dir.path()).args(["generate", "-d"]).arg(&definition).arg("-c").arg(&ini), "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    std::fs::write(&ini, format!("{}unknown_key = 1\n", std::fs::read_to_string(&ini).unwrap())).unwrap();
+
+    let output = run(generator(dir.path()).args(["validate", "-d", "-p"]).arg(&definition).arg("-c").arg(&ini), "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    let text = stdout(&output);
+    assert!(text.contains(&format!("{}: warning: unknown setting `unknown_key` in [pgbouncer]", ini.display())), "{}", text);
+    assert!(text.ends_with("0 error(s), 1 warning(s)\n"), "{}", text);
+}
+
+#[test]
+fn validate_fails_and_reports_each_severity_on_errors() {
+    let dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let edited = std::fs::read_to_string(&definition)
+        .unwrap()
+        .replace("default_pool_size = 100", "default_pool_size = 5000")
+        .lines()
+        .filter(|line| !line.starts_with("auth_file"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    std::fs::write(&definition, edited).unwrap();
+
+    let output = run(generator(dir.path()).args(["validate", "-d", "-p"]).arg(&definition), "");
+    assert!(!output.status.success());
+    let text = stdout(&output);
+    assert!(text.contains("error: [pgbouncer] auth_file: auth_file is required when auth_type is 'md5'"), "{}", text);
+    assert!(text.contains("warning: [pgbouncer] default_pool_size: default_pool_size (5000) is larger than max_client_conn (2000)"), "{}", text);
+    assert!(text.ends_with("1 error(s), 1 warning(s)\n"), "{}", text);
+    assert!(stderr(&output).contains("Validation failed with 1 error(s)"), "{}", stderr(&output));
+}
+
+/// Generates a formatted pgbouncer.ini and a copy of it with a comment and irregular spacing.
+fn formatted_and_unformatted_ini(dir: &Path) -> (PathBuf, PathBuf) {
+    let definition = init(dir);
+    let formatted = dir.join("formatted.ini");
+    let output = run(generator(dir).args(["generate", "-d"]).arg(&definition).arg("-c").arg(&formatted), "");
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let unformatted = dir.join("unformatted.ini");
+    let text = std::fs::read_to_string(&formatted).unwrap().replace("listen_port = 6432", "listen_port=6432");
+    std::fs::write(&unformatted, format!("; comment\n{}", text)).unwrap();
+
+    (formatted, unformatted)
+}
+
+#[test]
+fn fmt_prints_the_canonical_file() {
+    let dir = TempDir::new().unwrap();
+    let (formatted, unformatted) = formatted_and_unformatted_ini(dir.path());
+    let original = std::fs::read_to_string(&unformatted).unwrap();
+
+    let output = run(generator(dir.path()).args(["fmt", "-c"]).arg(&unformatted), "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), std::fs::read_to_string(&formatted).unwrap());
+    assert_eq!(std::fs::read_to_string(&unformatted).unwrap(), original);
+}
+
+#[test]
+fn fmt_in_place_rewrites_the_file() {
+    let dir = TempDir::new().unwrap();
+    let (formatted, unformatted) = formatted_and_unformatted_ini(dir.path());
+
+    let output = run(generator(dir.path()).args(["fmt", "--in-place", "-c"]).arg(&unformatted), "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+    assert_eq!(std::fs::read_to_string(&unformatted).unwrap(), std::fs::read_to_string(&formatted).unwrap());
+}
+
+#[test]
+fn fmt_check_fails_only_on_an_unformatted_file() {
+    let dir = TempDir::new().unwrap();
+    let (formatted, unformatted) = formatted_and_unformatted_ini(dir.path());
+    let original = std::fs::read_to_string(&unformatted).unwrap();
+
+    let output = run(generator(dir.path()).args(["fmt", "--check", "-c"]).arg(&formatted), "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("is formatted"), "{}", stdout(&output));
+
+    let output = run(generator(dir.path()).args(["fmt", "--check", "-c"]).arg(&unformatted), "");
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("is not formatted"), "{}", stderr(&output));
+    assert_eq!(std::fs::read_to_string(&unformatted).unwrap(), original);
+}
+
+#[test]
+fn fmt_refuses_files_with_include_directives() {
+    let dir = TempDir::new().unwrap();
+    let (formatted, _) = formatted_and_unformatted_ini(dir.path());
+    let text = format!("{}%include {}\n", std::fs::read_to_string(&formatted).unwrap(), dir.path().join("extra.ini").display());
+    std::fs::write(&formatted, &text).unwrap();
+
+    let output = run(generator(dir.path()).args(["fmt", "--in-place", "-c"]).arg(&formatted), "");
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("directives"), "{}", stderr(&output));
+    assert_eq!(std::fs::read_to_string(&formatted).unwrap(), text);
+}
+
+#[test]
+fn apply_dry_run_prints_the_diff_without_writing() {
+    let dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let ini = dir.path().join("pgbouncer.ini");
+
+    let output = run(generator(dir.path()).args(["apply", "--dry-run", "-d"]).arg(&definition).arg("-c").arg(&ini), "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("does not exist and would be created"), "{}", stdout(&output));
+    assert!(!ini.exists());
+
+    let output = run(generator(dir.path()).args(["generate", "-d"]).arg(&definition).arg("-c").arg(&ini), "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    let current = std::fs::read_to_string(&ini).unwrap().replace("listen_port = 6432", "listen_port = 7432");
+    std::fs::write(&ini, &current).unwrap();
+
+    let output = run(
+        generator(dir.path())
+            .args(["apply", "--dry-run", "--disable-decorated-output", "-d"])
+            .arg(&definition)
+            .arg("-c")
+            .arg(&ini),
+        "",
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let text = stdout(&output);
+    assert!(text.contains("-listen_port: 7432"), "{}", text);
+    assert!(text.contains("+listen_port: 6432"), "{}", text);
+    assert_eq!(std::fs::read_to_string(&ini).unwrap(), current);
+}
+
+#[test]
+fn apply_writes_the_file_and_skips_an_up_to_date_one() {
+    let dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let ini = dir.path().join("pgbouncer.ini");
+
+    let output = run(generator(dir.path()).args(["apply", "--reload", "none", "-d"]).arg(&definition).arg("-c").arg(&ini), "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Wrote"), "{}", stdout(&output));
+    assert!(std::fs::read_to_string(&ini).unwrap().contains("listen_port = 6432"));
+
+    let output = run(generator(dir.path()).args(["apply", "--reload", "none", "-d"]).arg(&definition).arg("-c").arg(&ini), "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("is up to date, PgBouncer was not reloaded"), "{}", stdout(&output));
+}
+
+#[test]
+fn apply_without_a_pidfile_fails_before_writing() {
+    let dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let ini = dir.path().join("pgbouncer.ini");
+
+    let output = run(generator(dir.path()).args(["apply", "-d"]).arg(&definition).arg("-c").arg(&ini), "");
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("--pidfile"), "{}", stderr(&output));
+    assert!(!ini.exists());
+}
+
+#[test]
+fn init_wizard_writes_the_answers_and_asks_invalid_ones_again() {
+    let dir = TempDir::new().unwrap();
+    let definition = dir.path().join("pgbouncer_definition.toml");
+    let answers = [
+        "0.0.0.0", "abc", "7000", "foo", "scram-sha-256", "transaction",
+        "", "db1.local", "", "app", "secret", "appdb other", "y",
+        "db2.local", "5433", "", "", "", "",
+    ];
+
+    let output = run(generator(dir.path()).args(["init", "--wizard", "-p"]).arg(&definition), &format!("{}\n", answers.join("\n")));
+    assert!(output.status.success(), "{}", stderr(&output));
+    let prompts = stdout(&output);
+    assert!(prompts.contains("abc is not a valid value"), "{}", prompts);
+    assert!(prompts.contains("Choose one of md5, scram-sha-256"), "{}", prompts);
+    assert!(prompts.contains("An answer is required"), "{}", prompts);
+    assert!(prompts.contains("Postgres host #2"), "{}", prompts);
+
+    let text = std::fs::read_to_string(&definition).unwrap();
+    for expected in [
+        "listen_addr = \"0.0.0.0\"",
+        "listen_port = 7000",
+        "auth_type = \"scram-sha-256\"",
+        "pool_mode = \"Transaction\"",
+        "host = \"db1.local\"\nport = 5432\nuser = \"app\"\npassword = \"secret\"",
+        "\"appdb\",\n    \"other\",",
+        "host = \"db2.local\"\nport = 5433\nuser = \"postgres\"\npassword = \"\"\ndatabases = []",
+    ] {
+        assert!(text.contains(expected), "{} not in {}", expected, text);
+    }
+}
+
+#[test]
+fn init_wizard_aborted_by_end_of_input_writes_nothing() {
+    let dir = TempDir::new().unwrap();
+    let definition = dir.path().join("pgbouncer_definition.toml");
+
+    let output = run(generator(dir.path()).args(["init", "--wizard", "-p"]).arg(&definition), "0.0.0.0\n");
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("The wizard was aborted before it was completed"), "{}", stderr(&output));
+    assert!(!definition.exists());
+}
+
+/// Sends each line written to `stream` to the returned receiver.
+fn lines<R: Read + Send + 'static>(stream: R) -> Receiver<String> {
+    let (tx, rx) = channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stream).lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    rx
+}
+
+/// Waits for a line containing `pattern`, panicking after a few seconds.
+fn wait_for_line(lines: &Receiver<String>, pattern: &str) {
+    loop {
+        match lines.recv_timeout(Duration::from_secs(10)) {
+            Ok(line) if line.contains(pattern) => return,
+            Ok(_) => continue,
+            Err(e) => panic!("no line containing {:?}: {}", pattern, e),
+        }
+    }
+}
+
+#[test]
+fn generate_watch_regenerates_on_changes_and_keeps_the_last_good_file() {
+    let dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let ini = dir.path().join("pgbouncer.ini");
+    let mut child = generator(dir.path())
+        .args(["generate", "--watch", "-d"])
+        .arg(&definition)
+        .arg("-c")
+        .arg(&ini)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let stdout = lines(child.stdout.take().unwrap());
+    let stderr = lines(child.stderr.take().unwrap());
+
+    wait_for_line(&stdout, "Watching");
+    assert!(std::fs::read_to_string(&ini).unwrap().contains("listen_port = 6432"));
+
+    let original = std::fs::read_to_string(&definition).unwrap();
+    std::fs::write(&definition, original.replace("listen_port = 6432", "listen_port = 7432")).unwrap();
+    wait_for_line(&stdout, "Regenerated");
+    let regenerated = std::fs::read_to_string(&ini).unwrap();
+    assert!(regenerated.contains("listen_port = 7432"), "{}", regenerated);
+
+    std::fs::write(&definition, "listen_port = [").unwrap();
+    wait_for_line(&stderr, "Failed to regenerate");
+    assert_eq!(std::fs::read_to_string(&ini).unwrap(), regenerated);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn generate_watch_with_provenance_only_regenerates_when_the_settings_change() {
+    let dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let ini = dir.path().join("pgbouncer.ini");
+    let mut child = generator(dir.path())
+        .args(["generate", "--watch", "--provenance", "--checksum", "-d"])
+        .arg(&definition)
+        .arg("-c")
+        .arg(&ini)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let stdout = lines(child.stdout.take().unwrap());
+
+    wait_for_line(&stdout, "Watching");
+    let original = std::fs::read_to_string(&definition).unwrap();
+    std::fs::write(&definition, format!("{}\n# only a comment\n", original)).unwrap();
+    wait_for_line(&stdout, "is up to date");
+
+    std::fs::write(&definition, original.replace("listen_port = 6432", "listen_port = 7432")).unwrap();
+    wait_for_line(&stdout, "Regenerated");
+    let regenerated = std::fs::read_to_string(&ini).unwrap();
+    assert!(regenerated.starts_with("; pgbouncer-config sha256: "), "{}", regenerated);
+    assert!(regenerated.contains("listen_port = 7432"), "{}", regenerated);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn import_resolves_secret_references_before_connecting_and_keeps_them() {
+    let dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let text = std::fs::read_to_string(&definition)
+        .unwrap()
+        .replace("password = \"postgres\"", "password = \"env://PGBOUNCER_GENERATOR_TEST_UNSET\"");
+    std::fs::write(&definition, &text).unwrap();
+
+    let output = run(generator(dir.path()).args(["import", "-p"]).arg(&definition).env_remove("PGBOUNCER_GENERATOR_TEST_UNSET"), "");
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("PGBOUNCER_GENERATOR_TEST_UNSET is not set"), "{}", stderr(&output));
+    assert_eq!(std::fs::read_to_string(&definition).unwrap(), text);
+}
+
+#[test]
+fn generate_fails_on_vault_references_without_vault_addr() {
+    let dir = TempDir::new().unwrap();
+    let definition = init(dir.path());
+    let ini = dir.path().join("pgbouncer.ini");
+    let text = std::fs::read_to_string(&definition)
+        .unwrap()
+        .replace("password = \"postgres\"", "password = \"vault://secret/app#password\"");
+    std::fs::write(&definition, text).unwrap();
+
+    let output = run(
+        generator(dir.path()).args(["generate", "-d"]).arg(&definition).arg("-c").arg(&ini).env_remove("VAULT_ADDR"),
+        "",
+    );
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("set VAULT_ADDR to resolve vault://secret/app#password"), "{}", stderr(&output));
+    assert!(!ini.exists());
+}